@@ -0,0 +1,32 @@
+//! フォントのバックエンドのフィーチャー (`gdi`、`dwrite`、`fontconfig`、`coretext`) のうち、
+//! ビルドするターゲットで使えるものを `backend = "..."` の cfg にします。
+//!
+//! 既定ではすべてのバックエンドのフィーチャーが有効で、Windows では GDI と DirectWrite、macOS では Core Text、
+//! それ以外では fontconfig が使われます。
+
+use std::env;
+
+/// バックエンドと、それが使えるターゲットかどうか
+fn backends() -> [(&'static str, bool); 4] {
+    let windows = env::var_os("CARGO_CFG_WINDOWS").is_some();
+    let macos = env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "macos");
+    [
+        ("gdi", windows),
+        ("dwrite", windows),
+        ("fontconfig", !windows && !macos),
+        ("coretext", macos),
+    ]
+}
+
+fn main() {
+    println!("cargo::rerun-if-changed=build.rs");
+    println!(
+        "cargo::rustc-check-cfg=cfg(backend, values(\"gdi\", \"dwrite\", \"fontconfig\", \"coretext\"))"
+    );
+    for (backend, available) in backends() {
+        let feature = format!("CARGO_FEATURE_{}", backend.to_uppercase());
+        if available && env::var_os(feature).is_some() {
+            println!("cargo::rustc-cfg=backend=\"{backend}\"");
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! `identify` で使う文字画像の読み込み (PNG) と比較
+//!
+//! 画像は背景からの濃さ (インク量, 0.0〜1.0) の格子として扱います。
+//! 比較では両方の画像をインクのある範囲で切り抜き、同じ高さに縮小してから重なり具合を測ります。
+
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+/// 比較するときにそろえる高さ (ピクセル)
+const COMPARE_HEIGHT: usize = 40;
+
+/// インクとみなす最小の濃さ (切り抜きの範囲の判定に使う)
+const INK_THRESHOLD: f32 = 0.5;
+
+/// 背景との差がこれより小さい画素はノイズ (JPEG 由来の縞など) として背景に含める
+const NOISE_FLOOR: f32 = 0.2;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// インク量の画像
+#[derive(Debug, Clone)]
+pub struct InkImage {
+    pub width: usize,
+    pub height: usize,
+    /// 行優先、0.0 が背景、1.0 がインク
+    pub ink: Vec<f32>,
+}
+
+impl InkImage {
+    /// 明るさ (0 = 黒, 255 = 白) の画素から作ります。
+    ///
+    /// 画像の縁の明るさの中央値を背景とし、背景との差をインク量とするため、白地に黒でも黒地に白でも扱えます。
+    pub fn from_luminance(width: usize, height: usize, luminance: &[u8]) -> Self {
+        let mut border: Vec<u8> = (0..width)
+            .flat_map(|x| [luminance[x], luminance[(height - 1) * width + x]])
+            .chain(
+                (0..height).flat_map(|y| [luminance[y * width], luminance[y * width + width - 1]]),
+            )
+            .collect();
+        border.sort_unstable();
+        let background = f32::from(border.get(border.len() / 2).copied().unwrap_or(255));
+        let contrast = luminance
+            .iter()
+            .map(|&l| (f32::from(l) - background).abs())
+            .fold(0.0, f32::max);
+        let ink = luminance
+            .iter()
+            .map(|&l| {
+                if contrast == 0.0 {
+                    return 0.0;
+                }
+                let ink = (f32::from(l) - background).abs() / contrast;
+                if ink < NOISE_FLOOR { 0.0 } else { ink }
+            })
+            .collect();
+        Self { width, height, ink }
+    }
+
+    /// インクのある範囲に切り抜きます。インクが無ければ `None` です。
+    pub fn crop_to_ink(&self) -> Option<Self> {
+        let inked = |x: usize, y: usize| self.ink[y * self.width + x] >= INK_THRESHOLD;
+        let rows: Vec<usize> = (0..self.height)
+            .filter(|&y| (0..self.width).any(|x| inked(x, y)))
+            .collect();
+        let columns: Vec<usize> = (0..self.width)
+            .filter(|&x| (0..self.height).any(|y| inked(x, y)))
+            .collect();
+        let (&top, &bottom) = (rows.first()?, rows.last()?);
+        let (&left, &right) = (columns.first()?, columns.last()?);
+        let width = right - left + 1;
+        let height = bottom - top + 1;
+        let ink = (top..=bottom)
+            .flat_map(|y| self.ink[y * self.width + left..y * self.width + left + width].to_vec())
+            .collect();
+        Some(Self { width, height, ink })
+    }
+
+    /// 面積平均で `width` x `height` に拡大・縮小します。
+    fn resample(&self, width: usize, height: usize) -> Self {
+        let sx = self.width as f32 / width as f32;
+        let sy = self.height as f32 / height as f32;
+        let mut ink = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let (y0, y1) = (y as f32 * sy, (y + 1) as f32 * sy);
+            for x in 0..width {
+                let (x0, x1) = (x as f32 * sx, (x + 1) as f32 * sx);
+                let mut sum = 0.0;
+                let mut area = 0.0;
+                for sy_pixel in y0.floor() as usize..(y1.ceil() as usize).min(self.height) {
+                    let h = (y1.min(sy_pixel as f32 + 1.0) - y0.max(sy_pixel as f32)).max(0.0);
+                    for sx_pixel in x0.floor() as usize..(x1.ceil() as usize).min(self.width) {
+                        let w = (x1.min(sx_pixel as f32 + 1.0) - x0.max(sx_pixel as f32)).max(0.0);
+                        sum += self.ink[sy_pixel * self.width + sx_pixel] * w * h;
+                        area += w * h;
+                    }
+                }
+                ink.push(if area > 0.0 { sum / area } else { 0.0 });
+            }
+        }
+        Self { width, height, ink }
+    }
+
+    /// 2 つの文字画像の似ている度合い (0.0〜1.0) を返します。
+    ///
+    /// 両方をインクのある範囲で切り抜き、高さをそろえて `self` の幅に合わせたうえで、
+    /// インク量の重なり (重み付きの Jaccard 係数) に縦横比の近さを掛けたものです。
+    pub fn similarity(&self, other: &Self) -> f64 {
+        let (Some(a), Some(b)) = (self.crop_to_ink(), other.crop_to_ink()) else {
+            return 0.0;
+        };
+        let aspect = |image: &Self| image.width as f64 / image.height as f64;
+        let (aspect_a, aspect_b) = (aspect(&a), aspect(&b));
+        let width = ((aspect_a * COMPARE_HEIGHT as f64).round() as usize).max(1);
+        let a = a.resample(width, COMPARE_HEIGHT);
+        let b = b.resample(width, COMPARE_HEIGHT);
+
+        let (mut overlap, mut union) = (0.0f64, 0.0f64);
+        for (&x, &y) in a.ink.iter().zip(&b.ink) {
+            overlap += f64::from(x.min(y));
+            union += f64::from(x.max(y));
+        }
+        if union == 0.0 {
+            return 0.0;
+        }
+        overlap / union * aspect_a.min(aspect_b) / aspect_a.max(aspect_b)
+    }
+}
+
+/// PNG を明るさの画素 (0 = 黒, 255 = 白) に変換します。透明な部分は白地に合成します。
+///
+/// インターレースの無い、すべての色の種類とビット深度の PNG に対応しています。エラーは理由の文字列です。
+pub fn decode_png(data: &[u8]) -> Result<(usize, usize, Vec<u8>), String> {
+    if !data.starts_with(PNG_SIGNATURE) {
+        return Err("not a PNG file".to_string());
+    }
+    let mut pos = PNG_SIGNATURE.len();
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+    while pos + 8 <= data.len() {
+        let length = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        let kind = &data[pos + 4..pos + 8];
+        let body = data
+            .get(pos + 8..pos + 8 + length as usize)
+            .ok_or("a chunk extends past the end of the file")?;
+        match kind {
+            b"IHDR" if body.len() >= 13 => header = Some(body),
+            b"PLTE" => palette = body,
+            b"tRNS" => transparency = body,
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + length as usize;
+    }
+    let header = header.ok_or("the IHDR chunk is missing")?;
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let (depth, color_type, interlace) = (header[8] as usize, header[9], header[12]);
+    if width == 0 || height == 0 {
+        return Err("the image is empty".to_string());
+    }
+    if interlace != 0 {
+        return Err(
+            "interlaced PNGs are not supported; save the image without interlacing".to_string(),
+        );
+    }
+    let channels = match color_type {
+        0 | 3 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return Err(format!("unknown color type {color_type}")),
+    };
+    if !matches!(depth, 1 | 2 | 4 | 8 | 16) {
+        return Err(format!("unsupported bit depth {depth}"));
+    }
+
+    let mut raw = Vec::new();
+    ZlibDecoder::new(compressed.as_slice())
+        .read_to_end(&mut raw)
+        .map_err(|e| format!("the image data cannot be decompressed: {e}"))?;
+    let stride = (width * channels * depth).div_ceil(8);
+    let bpp = (channels * depth).div_ceil(8);
+    if raw.len() < (stride + 1) * height {
+        return Err("the image data is truncated".to_string());
+    }
+
+    // --- フィルタの解除 ---
+    let mut pixels = vec![0u8; stride * height];
+    for y in 0..height {
+        let filter = raw[y * (stride + 1)];
+        let line = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (done, rest) = pixels.split_at_mut(y * stride);
+        let previous = if y == 0 {
+            None
+        } else {
+            Some(&done[(y - 1) * stride..])
+        };
+        let current = &mut rest[..stride];
+        for i in 0..stride {
+            let left = if i >= bpp { current[i - bpp] } else { 0 };
+            let up = previous.map_or(0, |p| p[i]);
+            let up_left = if i >= bpp {
+                previous.map_or(0, |p| p[i - bpp])
+            } else {
+                0
+            };
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(format!("unknown filter type {filter}")),
+            };
+            current[i] = line[i].wrapping_add(predictor);
+        }
+    }
+
+    // --- 明るさへの変換 ---
+    let max = ((1u32 << depth) - 1) as f32;
+    let sample = |row: &[u8], index: usize| -> u32 {
+        match depth {
+            16 => u32::from(row[index * 2]) << 8 | u32::from(row[index * 2 + 1]),
+            8 => u32::from(row[index]),
+            _ => {
+                let bit = index * depth;
+                u32::from(row[bit / 8] >> (8 - depth - bit % 8)) & ((1 << depth) - 1)
+            }
+        }
+    };
+    let mut luminance = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row = &pixels[y * stride..(y + 1) * stride];
+        for x in 0..width {
+            let value = |c: usize| sample(row, x * channels + c) as f32 / max;
+            let (gray, alpha) = match color_type {
+                0 => (value(0), 1.0),
+                2 => (rgb_luminance(value(0), value(1), value(2)), 1.0),
+                3 => {
+                    let index = sample(row, x) as usize;
+                    let rgb = palette
+                        .get(index * 3..index * 3 + 3)
+                        .ok_or("a pixel refers to a missing palette entry")?;
+                    let alpha = transparency
+                        .get(index)
+                        .map_or(1.0, |&a| f32::from(a) / 255.0);
+                    let [r, g, b] = [rgb[0], rgb[1], rgb[2]].map(|c| f32::from(c) / 255.0);
+                    (rgb_luminance(r, g, b), alpha)
+                }
+                4 => (value(0), value(1)),
+                _ => (rgb_luminance(value(0), value(1), value(2)), value(3)),
+            };
+            // 白地に合成する
+            let composited = gray * alpha + (1.0 - alpha);
+            luminance.push((composited * 255.0).round() as u8);
+        }
+    }
+    Ok((width, height, luminance))
+}
+
+fn rgb_luminance(r: f32, g: f32, b: f32) -> f32 {
+    0.299 * r + 0.587 * g + 0.114 * b
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = (
+        (p - i16::from(a)).abs(),
+        (p - i16::from(b)).abs(),
+        (p - i16::from(c)).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
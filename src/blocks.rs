@@ -0,0 +1,357 @@
+//! Unicode のブロック (Unicode Character Database の Blocks.txt、Unicode 15.1)
+//!
+//! `info --coverage` と文字見本 (`--emit charset-sample`) で、cmap のコードポイントをブロックごとにまとめるのに使います。
+//! OS/2 の ulUnicodeRange のビットは複数のブロックをまとめたものなので、ブロックの代わりにはなりません。
+
+/// Unicode のブロック 1 つ (`start..=end`)
+pub struct Block {
+    pub start: u32,
+    pub end: u32,
+    pub name: &'static str,
+}
+
+/// すべてのブロック (先頭のコードポイントの順)
+#[rustfmt::skip]
+pub const BLOCKS: &[Block] = &[
+    Block { start: 0x0000, end: 0x007F, name: "Basic Latin" },
+    Block { start: 0x0080, end: 0x00FF, name: "Latin-1 Supplement" },
+    Block { start: 0x0100, end: 0x017F, name: "Latin Extended-A" },
+    Block { start: 0x0180, end: 0x024F, name: "Latin Extended-B" },
+    Block { start: 0x0250, end: 0x02AF, name: "IPA Extensions" },
+    Block { start: 0x02B0, end: 0x02FF, name: "Spacing Modifier Letters" },
+    Block { start: 0x0300, end: 0x036F, name: "Combining Diacritical Marks" },
+    Block { start: 0x0370, end: 0x03FF, name: "Greek and Coptic" },
+    Block { start: 0x0400, end: 0x04FF, name: "Cyrillic" },
+    Block { start: 0x0500, end: 0x052F, name: "Cyrillic Supplement" },
+    Block { start: 0x0530, end: 0x058F, name: "Armenian" },
+    Block { start: 0x0590, end: 0x05FF, name: "Hebrew" },
+    Block { start: 0x0600, end: 0x06FF, name: "Arabic" },
+    Block { start: 0x0700, end: 0x074F, name: "Syriac" },
+    Block { start: 0x0750, end: 0x077F, name: "Arabic Supplement" },
+    Block { start: 0x0780, end: 0x07BF, name: "Thaana" },
+    Block { start: 0x07C0, end: 0x07FF, name: "NKo" },
+    Block { start: 0x0800, end: 0x083F, name: "Samaritan" },
+    Block { start: 0x0840, end: 0x085F, name: "Mandaic" },
+    Block { start: 0x0860, end: 0x086F, name: "Syriac Supplement" },
+    Block { start: 0x0870, end: 0x089F, name: "Arabic Extended-B" },
+    Block { start: 0x08A0, end: 0x08FF, name: "Arabic Extended-A" },
+    Block { start: 0x0900, end: 0x097F, name: "Devanagari" },
+    Block { start: 0x0980, end: 0x09FF, name: "Bengali" },
+    Block { start: 0x0A00, end: 0x0A7F, name: "Gurmukhi" },
+    Block { start: 0x0A80, end: 0x0AFF, name: "Gujarati" },
+    Block { start: 0x0B00, end: 0x0B7F, name: "Oriya" },
+    Block { start: 0x0B80, end: 0x0BFF, name: "Tamil" },
+    Block { start: 0x0C00, end: 0x0C7F, name: "Telugu" },
+    Block { start: 0x0C80, end: 0x0CFF, name: "Kannada" },
+    Block { start: 0x0D00, end: 0x0D7F, name: "Malayalam" },
+    Block { start: 0x0D80, end: 0x0DFF, name: "Sinhala" },
+    Block { start: 0x0E00, end: 0x0E7F, name: "Thai" },
+    Block { start: 0x0E80, end: 0x0EFF, name: "Lao" },
+    Block { start: 0x0F00, end: 0x0FFF, name: "Tibetan" },
+    Block { start: 0x1000, end: 0x109F, name: "Myanmar" },
+    Block { start: 0x10A0, end: 0x10FF, name: "Georgian" },
+    Block { start: 0x1100, end: 0x11FF, name: "Hangul Jamo" },
+    Block { start: 0x1200, end: 0x137F, name: "Ethiopic" },
+    Block { start: 0x1380, end: 0x139F, name: "Ethiopic Supplement" },
+    Block { start: 0x13A0, end: 0x13FF, name: "Cherokee" },
+    Block { start: 0x1400, end: 0x167F, name: "Unified Canadian Aboriginal Syllabics" },
+    Block { start: 0x1680, end: 0x169F, name: "Ogham" },
+    Block { start: 0x16A0, end: 0x16FF, name: "Runic" },
+    Block { start: 0x1700, end: 0x171F, name: "Tagalog" },
+    Block { start: 0x1720, end: 0x173F, name: "Hanunoo" },
+    Block { start: 0x1740, end: 0x175F, name: "Buhid" },
+    Block { start: 0x1760, end: 0x177F, name: "Tagbanwa" },
+    Block { start: 0x1780, end: 0x17FF, name: "Khmer" },
+    Block { start: 0x1800, end: 0x18AF, name: "Mongolian" },
+    Block { start: 0x18B0, end: 0x18FF, name: "Unified Canadian Aboriginal Syllabics Extended" },
+    Block { start: 0x1900, end: 0x194F, name: "Limbu" },
+    Block { start: 0x1950, end: 0x197F, name: "Tai Le" },
+    Block { start: 0x1980, end: 0x19DF, name: "New Tai Lue" },
+    Block { start: 0x19E0, end: 0x19FF, name: "Khmer Symbols" },
+    Block { start: 0x1A00, end: 0x1A1F, name: "Buginese" },
+    Block { start: 0x1A20, end: 0x1AAF, name: "Tai Tham" },
+    Block { start: 0x1AB0, end: 0x1AFF, name: "Combining Diacritical Marks Extended" },
+    Block { start: 0x1B00, end: 0x1B7F, name: "Balinese" },
+    Block { start: 0x1B80, end: 0x1BBF, name: "Sundanese" },
+    Block { start: 0x1BC0, end: 0x1BFF, name: "Batak" },
+    Block { start: 0x1C00, end: 0x1C4F, name: "Lepcha" },
+    Block { start: 0x1C50, end: 0x1C7F, name: "Ol Chiki" },
+    Block { start: 0x1C80, end: 0x1C8F, name: "Cyrillic Extended-C" },
+    Block { start: 0x1C90, end: 0x1CBF, name: "Georgian Extended" },
+    Block { start: 0x1CC0, end: 0x1CCF, name: "Sundanese Supplement" },
+    Block { start: 0x1CD0, end: 0x1CFF, name: "Vedic Extensions" },
+    Block { start: 0x1D00, end: 0x1D7F, name: "Phonetic Extensions" },
+    Block { start: 0x1D80, end: 0x1DBF, name: "Phonetic Extensions Supplement" },
+    Block { start: 0x1DC0, end: 0x1DFF, name: "Combining Diacritical Marks Supplement" },
+    Block { start: 0x1E00, end: 0x1EFF, name: "Latin Extended Additional" },
+    Block { start: 0x1F00, end: 0x1FFF, name: "Greek Extended" },
+    Block { start: 0x2000, end: 0x206F, name: "General Punctuation" },
+    Block { start: 0x2070, end: 0x209F, name: "Superscripts and Subscripts" },
+    Block { start: 0x20A0, end: 0x20CF, name: "Currency Symbols" },
+    Block { start: 0x20D0, end: 0x20FF, name: "Combining Diacritical Marks for Symbols" },
+    Block { start: 0x2100, end: 0x214F, name: "Letterlike Symbols" },
+    Block { start: 0x2150, end: 0x218F, name: "Number Forms" },
+    Block { start: 0x2190, end: 0x21FF, name: "Arrows" },
+    Block { start: 0x2200, end: 0x22FF, name: "Mathematical Operators" },
+    Block { start: 0x2300, end: 0x23FF, name: "Miscellaneous Technical" },
+    Block { start: 0x2400, end: 0x243F, name: "Control Pictures" },
+    Block { start: 0x2440, end: 0x245F, name: "Optical Character Recognition" },
+    Block { start: 0x2460, end: 0x24FF, name: "Enclosed Alphanumerics" },
+    Block { start: 0x2500, end: 0x257F, name: "Box Drawing" },
+    Block { start: 0x2580, end: 0x259F, name: "Block Elements" },
+    Block { start: 0x25A0, end: 0x25FF, name: "Geometric Shapes" },
+    Block { start: 0x2600, end: 0x26FF, name: "Miscellaneous Symbols" },
+    Block { start: 0x2700, end: 0x27BF, name: "Dingbats" },
+    Block { start: 0x27C0, end: 0x27EF, name: "Miscellaneous Mathematical Symbols-A" },
+    Block { start: 0x27F0, end: 0x27FF, name: "Supplemental Arrows-A" },
+    Block { start: 0x2800, end: 0x28FF, name: "Braille Patterns" },
+    Block { start: 0x2900, end: 0x297F, name: "Supplemental Arrows-B" },
+    Block { start: 0x2980, end: 0x29FF, name: "Miscellaneous Mathematical Symbols-B" },
+    Block { start: 0x2A00, end: 0x2AFF, name: "Supplemental Mathematical Operators" },
+    Block { start: 0x2B00, end: 0x2BFF, name: "Miscellaneous Symbols and Arrows" },
+    Block { start: 0x2C00, end: 0x2C5F, name: "Glagolitic" },
+    Block { start: 0x2C60, end: 0x2C7F, name: "Latin Extended-C" },
+    Block { start: 0x2C80, end: 0x2CFF, name: "Coptic" },
+    Block { start: 0x2D00, end: 0x2D2F, name: "Georgian Supplement" },
+    Block { start: 0x2D30, end: 0x2D7F, name: "Tifinagh" },
+    Block { start: 0x2D80, end: 0x2DDF, name: "Ethiopic Extended" },
+    Block { start: 0x2DE0, end: 0x2DFF, name: "Cyrillic Extended-A" },
+    Block { start: 0x2E00, end: 0x2E7F, name: "Supplemental Punctuation" },
+    Block { start: 0x2E80, end: 0x2EFF, name: "CJK Radicals Supplement" },
+    Block { start: 0x2F00, end: 0x2FDF, name: "Kangxi Radicals" },
+    Block { start: 0x2FF0, end: 0x2FFF, name: "Ideographic Description Characters" },
+    Block { start: 0x3000, end: 0x303F, name: "CJK Symbols and Punctuation" },
+    Block { start: 0x3040, end: 0x309F, name: "Hiragana" },
+    Block { start: 0x30A0, end: 0x30FF, name: "Katakana" },
+    Block { start: 0x3100, end: 0x312F, name: "Bopomofo" },
+    Block { start: 0x3130, end: 0x318F, name: "Hangul Compatibility Jamo" },
+    Block { start: 0x3190, end: 0x319F, name: "Kanbun" },
+    Block { start: 0x31A0, end: 0x31BF, name: "Bopomofo Extended" },
+    Block { start: 0x31C0, end: 0x31EF, name: "CJK Strokes" },
+    Block { start: 0x31F0, end: 0x31FF, name: "Katakana Phonetic Extensions" },
+    Block { start: 0x3200, end: 0x32FF, name: "Enclosed CJK Letters and Months" },
+    Block { start: 0x3300, end: 0x33FF, name: "CJK Compatibility" },
+    Block { start: 0x3400, end: 0x4DBF, name: "CJK Unified Ideographs Extension A" },
+    Block { start: 0x4DC0, end: 0x4DFF, name: "Yijing Hexagram Symbols" },
+    Block { start: 0x4E00, end: 0x9FFF, name: "CJK Unified Ideographs" },
+    Block { start: 0xA000, end: 0xA48F, name: "Yi Syllables" },
+    Block { start: 0xA490, end: 0xA4CF, name: "Yi Radicals" },
+    Block { start: 0xA4D0, end: 0xA4FF, name: "Lisu" },
+    Block { start: 0xA500, end: 0xA63F, name: "Vai" },
+    Block { start: 0xA640, end: 0xA69F, name: "Cyrillic Extended-B" },
+    Block { start: 0xA6A0, end: 0xA6FF, name: "Bamum" },
+    Block { start: 0xA700, end: 0xA71F, name: "Modifier Tone Letters" },
+    Block { start: 0xA720, end: 0xA7FF, name: "Latin Extended-D" },
+    Block { start: 0xA800, end: 0xA82F, name: "Syloti Nagri" },
+    Block { start: 0xA830, end: 0xA83F, name: "Common Indic Number Forms" },
+    Block { start: 0xA840, end: 0xA87F, name: "Phags-pa" },
+    Block { start: 0xA880, end: 0xA8DF, name: "Saurashtra" },
+    Block { start: 0xA8E0, end: 0xA8FF, name: "Devanagari Extended" },
+    Block { start: 0xA900, end: 0xA92F, name: "Kayah Li" },
+    Block { start: 0xA930, end: 0xA95F, name: "Rejang" },
+    Block { start: 0xA960, end: 0xA97F, name: "Hangul Jamo Extended-A" },
+    Block { start: 0xA980, end: 0xA9DF, name: "Javanese" },
+    Block { start: 0xA9E0, end: 0xA9FF, name: "Myanmar Extended-B" },
+    Block { start: 0xAA00, end: 0xAA5F, name: "Cham" },
+    Block { start: 0xAA60, end: 0xAA7F, name: "Myanmar Extended-A" },
+    Block { start: 0xAA80, end: 0xAADF, name: "Tai Viet" },
+    Block { start: 0xAAE0, end: 0xAAFF, name: "Meetei Mayek Extensions" },
+    Block { start: 0xAB00, end: 0xAB2F, name: "Ethiopic Extended-A" },
+    Block { start: 0xAB30, end: 0xAB6F, name: "Latin Extended-E" },
+    Block { start: 0xAB70, end: 0xABBF, name: "Cherokee Supplement" },
+    Block { start: 0xABC0, end: 0xABFF, name: "Meetei Mayek" },
+    Block { start: 0xAC00, end: 0xD7AF, name: "Hangul Syllables" },
+    Block { start: 0xD7B0, end: 0xD7FF, name: "Hangul Jamo Extended-B" },
+    Block { start: 0xD800, end: 0xDB7F, name: "High Surrogates" },
+    Block { start: 0xDB80, end: 0xDBFF, name: "High Private Use Surrogates" },
+    Block { start: 0xDC00, end: 0xDFFF, name: "Low Surrogates" },
+    Block { start: 0xE000, end: 0xF8FF, name: "Private Use Area" },
+    Block { start: 0xF900, end: 0xFAFF, name: "CJK Compatibility Ideographs" },
+    Block { start: 0xFB00, end: 0xFB4F, name: "Alphabetic Presentation Forms" },
+    Block { start: 0xFB50, end: 0xFDFF, name: "Arabic Presentation Forms-A" },
+    Block { start: 0xFE00, end: 0xFE0F, name: "Variation Selectors" },
+    Block { start: 0xFE10, end: 0xFE1F, name: "Vertical Forms" },
+    Block { start: 0xFE20, end: 0xFE2F, name: "Combining Half Marks" },
+    Block { start: 0xFE30, end: 0xFE4F, name: "CJK Compatibility Forms" },
+    Block { start: 0xFE50, end: 0xFE6F, name: "Small Form Variants" },
+    Block { start: 0xFE70, end: 0xFEFF, name: "Arabic Presentation Forms-B" },
+    Block { start: 0xFF00, end: 0xFFEF, name: "Halfwidth and Fullwidth Forms" },
+    Block { start: 0xFFF0, end: 0xFFFF, name: "Specials" },
+    Block { start: 0x10000, end: 0x1007F, name: "Linear B Syllabary" },
+    Block { start: 0x10080, end: 0x100FF, name: "Linear B Ideograms" },
+    Block { start: 0x10100, end: 0x1013F, name: "Aegean Numbers" },
+    Block { start: 0x10140, end: 0x1018F, name: "Ancient Greek Numbers" },
+    Block { start: 0x10190, end: 0x101CF, name: "Ancient Symbols" },
+    Block { start: 0x101D0, end: 0x101FF, name: "Phaistos Disc" },
+    Block { start: 0x10280, end: 0x1029F, name: "Lycian" },
+    Block { start: 0x102A0, end: 0x102DF, name: "Carian" },
+    Block { start: 0x102E0, end: 0x102FF, name: "Coptic Epact Numbers" },
+    Block { start: 0x10300, end: 0x1032F, name: "Old Italic" },
+    Block { start: 0x10330, end: 0x1034F, name: "Gothic" },
+    Block { start: 0x10350, end: 0x1037F, name: "Old Permic" },
+    Block { start: 0x10380, end: 0x1039F, name: "Ugaritic" },
+    Block { start: 0x103A0, end: 0x103DF, name: "Old Persian" },
+    Block { start: 0x10400, end: 0x1044F, name: "Deseret" },
+    Block { start: 0x10450, end: 0x1047F, name: "Shavian" },
+    Block { start: 0x10480, end: 0x104AF, name: "Osmanya" },
+    Block { start: 0x104B0, end: 0x104FF, name: "Osage" },
+    Block { start: 0x10500, end: 0x1052F, name: "Elbasan" },
+    Block { start: 0x10530, end: 0x1056F, name: "Caucasian Albanian" },
+    Block { start: 0x10570, end: 0x105BF, name: "Vithkuqi" },
+    Block { start: 0x10600, end: 0x1077F, name: "Linear A" },
+    Block { start: 0x10780, end: 0x107BF, name: "Latin Extended-F" },
+    Block { start: 0x10800, end: 0x1083F, name: "Cypriot Syllabary" },
+    Block { start: 0x10840, end: 0x1085F, name: "Imperial Aramaic" },
+    Block { start: 0x10860, end: 0x1087F, name: "Palmyrene" },
+    Block { start: 0x10880, end: 0x108AF, name: "Nabataean" },
+    Block { start: 0x108E0, end: 0x108FF, name: "Hatran" },
+    Block { start: 0x10900, end: 0x1091F, name: "Phoenician" },
+    Block { start: 0x10920, end: 0x1093F, name: "Lydian" },
+    Block { start: 0x10980, end: 0x1099F, name: "Meroitic Hieroglyphs" },
+    Block { start: 0x109A0, end: 0x109FF, name: "Meroitic Cursive" },
+    Block { start: 0x10A00, end: 0x10A5F, name: "Kharoshthi" },
+    Block { start: 0x10A60, end: 0x10A7F, name: "Old South Arabian" },
+    Block { start: 0x10A80, end: 0x10A9F, name: "Old North Arabian" },
+    Block { start: 0x10AC0, end: 0x10AFF, name: "Manichaean" },
+    Block { start: 0x10B00, end: 0x10B3F, name: "Avestan" },
+    Block { start: 0x10B40, end: 0x10B5F, name: "Inscriptional Parthian" },
+    Block { start: 0x10B60, end: 0x10B7F, name: "Inscriptional Pahlavi" },
+    Block { start: 0x10B80, end: 0x10BAF, name: "Psalter Pahlavi" },
+    Block { start: 0x10C00, end: 0x10C4F, name: "Old Turkic" },
+    Block { start: 0x10C80, end: 0x10CFF, name: "Old Hungarian" },
+    Block { start: 0x10D00, end: 0x10D3F, name: "Hanifi Rohingya" },
+    Block { start: 0x10E60, end: 0x10E7F, name: "Rumi Numeral Symbols" },
+    Block { start: 0x10E80, end: 0x10EBF, name: "Yezidi" },
+    Block { start: 0x10EC0, end: 0x10EFF, name: "Arabic Extended-C" },
+    Block { start: 0x10F00, end: 0x10F2F, name: "Old Sogdian" },
+    Block { start: 0x10F30, end: 0x10F6F, name: "Sogdian" },
+    Block { start: 0x10F70, end: 0x10FAF, name: "Old Uyghur" },
+    Block { start: 0x10FB0, end: 0x10FDF, name: "Chorasmian" },
+    Block { start: 0x10FE0, end: 0x10FFF, name: "Elymaic" },
+    Block { start: 0x11000, end: 0x1107F, name: "Brahmi" },
+    Block { start: 0x11080, end: 0x110CF, name: "Kaithi" },
+    Block { start: 0x110D0, end: 0x110FF, name: "Sora Sompeng" },
+    Block { start: 0x11100, end: 0x1114F, name: "Chakma" },
+    Block { start: 0x11150, end: 0x1117F, name: "Mahajani" },
+    Block { start: 0x11180, end: 0x111DF, name: "Sharada" },
+    Block { start: 0x111E0, end: 0x111FF, name: "Sinhala Archaic Numbers" },
+    Block { start: 0x11200, end: 0x1124F, name: "Khojki" },
+    Block { start: 0x11280, end: 0x112AF, name: "Multani" },
+    Block { start: 0x112B0, end: 0x112FF, name: "Khudawadi" },
+    Block { start: 0x11300, end: 0x1137F, name: "Grantha" },
+    Block { start: 0x11400, end: 0x1147F, name: "Newa" },
+    Block { start: 0x11480, end: 0x114DF, name: "Tirhuta" },
+    Block { start: 0x11580, end: 0x115FF, name: "Siddham" },
+    Block { start: 0x11600, end: 0x1165F, name: "Modi" },
+    Block { start: 0x11660, end: 0x1167F, name: "Mongolian Supplement" },
+    Block { start: 0x11680, end: 0x116CF, name: "Takri" },
+    Block { start: 0x11700, end: 0x1174F, name: "Ahom" },
+    Block { start: 0x11800, end: 0x1184F, name: "Dogra" },
+    Block { start: 0x118A0, end: 0x118FF, name: "Warang Citi" },
+    Block { start: 0x11900, end: 0x1195F, name: "Dives Akuru" },
+    Block { start: 0x119A0, end: 0x119FF, name: "Nandinagari" },
+    Block { start: 0x11A00, end: 0x11A4F, name: "Zanabazar Square" },
+    Block { start: 0x11A50, end: 0x11AAF, name: "Soyombo" },
+    Block { start: 0x11AB0, end: 0x11ABF, name: "Unified Canadian Aboriginal Syllabics Extended-A" },
+    Block { start: 0x11AC0, end: 0x11AFF, name: "Pau Cin Hau" },
+    Block { start: 0x11B00, end: 0x11B5F, name: "Devanagari Extended-A" },
+    Block { start: 0x11C00, end: 0x11C6F, name: "Bhaiksuki" },
+    Block { start: 0x11C70, end: 0x11CBF, name: "Marchen" },
+    Block { start: 0x11D00, end: 0x11D5F, name: "Masaram Gondi" },
+    Block { start: 0x11D60, end: 0x11DAF, name: "Gunjala Gondi" },
+    Block { start: 0x11EE0, end: 0x11EFF, name: "Makasar" },
+    Block { start: 0x11F00, end: 0x11F5F, name: "Kawi" },
+    Block { start: 0x11FB0, end: 0x11FBF, name: "Lisu Supplement" },
+    Block { start: 0x11FC0, end: 0x11FFF, name: "Tamil Supplement" },
+    Block { start: 0x12000, end: 0x123FF, name: "Cuneiform" },
+    Block { start: 0x12400, end: 0x1247F, name: "Cuneiform Numbers and Punctuation" },
+    Block { start: 0x12480, end: 0x1254F, name: "Early Dynastic Cuneiform" },
+    Block { start: 0x12F90, end: 0x12FFF, name: "Cypro-Minoan" },
+    Block { start: 0x13000, end: 0x1342F, name: "Egyptian Hieroglyphs" },
+    Block { start: 0x13430, end: 0x1343F, name: "Egyptian Hieroglyph Format Controls" },
+    Block { start: 0x14400, end: 0x1467F, name: "Anatolian Hieroglyphs" },
+    Block { start: 0x16800, end: 0x16A3F, name: "Bamum Supplement" },
+    Block { start: 0x16A40, end: 0x16A6F, name: "Mro" },
+    Block { start: 0x16A70, end: 0x16ACF, name: "Tangsa" },
+    Block { start: 0x16AD0, end: 0x16AFF, name: "Bassa Vah" },
+    Block { start: 0x16B00, end: 0x16B8F, name: "Pahawh Hmong" },
+    Block { start: 0x16E40, end: 0x16E9F, name: "Medefaidrin" },
+    Block { start: 0x16F00, end: 0x16F9F, name: "Miao" },
+    Block { start: 0x16FE0, end: 0x16FFF, name: "Ideographic Symbols and Punctuation" },
+    Block { start: 0x17000, end: 0x187FF, name: "Tangut" },
+    Block { start: 0x18800, end: 0x18AFF, name: "Tangut Components" },
+    Block { start: 0x18B00, end: 0x18CFF, name: "Khitan Small Script" },
+    Block { start: 0x18D00, end: 0x18D7F, name: "Tangut Supplement" },
+    Block { start: 0x1AFF0, end: 0x1AFFF, name: "Kana Extended-B" },
+    Block { start: 0x1B000, end: 0x1B0FF, name: "Kana Supplement" },
+    Block { start: 0x1B100, end: 0x1B12F, name: "Kana Extended-A" },
+    Block { start: 0x1B130, end: 0x1B16F, name: "Small Kana Extension" },
+    Block { start: 0x1B170, end: 0x1B2FF, name: "Nushu" },
+    Block { start: 0x1BC00, end: 0x1BC9F, name: "Duployan" },
+    Block { start: 0x1BCA0, end: 0x1BCAF, name: "Shorthand Format Controls" },
+    Block { start: 0x1CF00, end: 0x1CFCF, name: "Znamenny Musical Notation" },
+    Block { start: 0x1D000, end: 0x1D0FF, name: "Byzantine Musical Symbols" },
+    Block { start: 0x1D100, end: 0x1D1FF, name: "Musical Symbols" },
+    Block { start: 0x1D200, end: 0x1D24F, name: "Ancient Greek Musical Notation" },
+    Block { start: 0x1D2C0, end: 0x1D2DF, name: "Kaktovik Numerals" },
+    Block { start: 0x1D2E0, end: 0x1D2FF, name: "Mayan Numerals" },
+    Block { start: 0x1D300, end: 0x1D35F, name: "Tai Xuan Jing Symbols" },
+    Block { start: 0x1D360, end: 0x1D37F, name: "Counting Rod Numerals" },
+    Block { start: 0x1D400, end: 0x1D7FF, name: "Mathematical Alphanumeric Symbols" },
+    Block { start: 0x1D800, end: 0x1DAAF, name: "Sutton SignWriting" },
+    Block { start: 0x1DF00, end: 0x1DFFF, name: "Latin Extended-G" },
+    Block { start: 0x1E000, end: 0x1E02F, name: "Glagolitic Supplement" },
+    Block { start: 0x1E030, end: 0x1E08F, name: "Cyrillic Extended-D" },
+    Block { start: 0x1E100, end: 0x1E14F, name: "Nyiakeng Puachue Hmong" },
+    Block { start: 0x1E290, end: 0x1E2BF, name: "Toto" },
+    Block { start: 0x1E2C0, end: 0x1E2FF, name: "Wancho" },
+    Block { start: 0x1E4D0, end: 0x1E4FF, name: "Nag Mundari" },
+    Block { start: 0x1E7E0, end: 0x1E7FF, name: "Ethiopic Extended-B" },
+    Block { start: 0x1E800, end: 0x1E8DF, name: "Mende Kikakui" },
+    Block { start: 0x1E900, end: 0x1E95F, name: "Adlam" },
+    Block { start: 0x1EC70, end: 0x1ECBF, name: "Indic Siyaq Numbers" },
+    Block { start: 0x1ED00, end: 0x1ED4F, name: "Ottoman Siyaq Numbers" },
+    Block { start: 0x1EE00, end: 0x1EEFF, name: "Arabic Mathematical Alphabetic Symbols" },
+    Block { start: 0x1F000, end: 0x1F02F, name: "Mahjong Tiles" },
+    Block { start: 0x1F030, end: 0x1F09F, name: "Domino Tiles" },
+    Block { start: 0x1F0A0, end: 0x1F0FF, name: "Playing Cards" },
+    Block { start: 0x1F100, end: 0x1F1FF, name: "Enclosed Alphanumeric Supplement" },
+    Block { start: 0x1F200, end: 0x1F2FF, name: "Enclosed Ideographic Supplement" },
+    Block { start: 0x1F300, end: 0x1F5FF, name: "Miscellaneous Symbols and Pictographs" },
+    Block { start: 0x1F600, end: 0x1F64F, name: "Emoticons" },
+    Block { start: 0x1F650, end: 0x1F67F, name: "Ornamental Dingbats" },
+    Block { start: 0x1F680, end: 0x1F6FF, name: "Transport and Map Symbols" },
+    Block { start: 0x1F700, end: 0x1F77F, name: "Alchemical Symbols" },
+    Block { start: 0x1F780, end: 0x1F7FF, name: "Geometric Shapes Extended" },
+    Block { start: 0x1F800, end: 0x1F8FF, name: "Supplemental Arrows-C" },
+    Block { start: 0x1F900, end: 0x1F9FF, name: "Supplemental Symbols and Pictographs" },
+    Block { start: 0x1FA00, end: 0x1FA6F, name: "Chess Symbols" },
+    Block { start: 0x1FA70, end: 0x1FAFF, name: "Symbols and Pictographs Extended-A" },
+    Block { start: 0x1FB00, end: 0x1FBFF, name: "Symbols for Legacy Computing" },
+    Block { start: 0x20000, end: 0x2A6DF, name: "CJK Unified Ideographs Extension B" },
+    Block { start: 0x2A700, end: 0x2B73F, name: "CJK Unified Ideographs Extension C" },
+    Block { start: 0x2B740, end: 0x2B81F, name: "CJK Unified Ideographs Extension D" },
+    Block { start: 0x2B820, end: 0x2CEAF, name: "CJK Unified Ideographs Extension E" },
+    Block { start: 0x2CEB0, end: 0x2EBEF, name: "CJK Unified Ideographs Extension F" },
+    Block { start: 0x2EBF0, end: 0x2EE5F, name: "CJK Unified Ideographs Extension I" },
+    Block { start: 0x2F800, end: 0x2FA1F, name: "CJK Compatibility Ideographs Supplement" },
+    Block { start: 0x30000, end: 0x3134F, name: "CJK Unified Ideographs Extension G" },
+    Block { start: 0x31350, end: 0x323AF, name: "CJK Unified Ideographs Extension H" },
+    Block { start: 0xE0000, end: 0xE007F, name: "Tags" },
+    Block { start: 0xE0100, end: 0xE01EF, name: "Variation Selectors Supplement" },
+    Block { start: 0xF0000, end: 0xFFFFF, name: "Supplementary Private Use Area-A" },
+    Block { start: 0x100000, end: 0x10FFFF, name: "Supplementary Private Use Area-B" },
+];
+
+impl Block {
+    /// ブロックに含まれるコードポイントの数 (未割り当てのものを含む)
+    pub fn size(&self) -> u32 {
+        self.end - self.start + 1
+    }
+}
+
+/// `codepoint` を含むブロック (どのブロックにも含まれなければ `None`)
+pub fn block_of(codepoint: u32) -> Option<&'static Block> {
+    let index = BLOCKS.partition_point(|block| block.end < codepoint);
+    BLOCKS.get(index).filter(|block| block.start <= codepoint)
+}
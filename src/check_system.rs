@@ -0,0 +1,53 @@
+//! `check-system` サブコマンド: インストール済みの全フォントを読み取って検証し、壊れているフォントを報告します。
+
+use font_export::{FontExtractorError, FontRequest, validate};
+
+/// --- `check-system` サブコマンドの引数 ---
+#[derive(clap::Args, Debug)]
+pub struct CheckSystemArgs {
+    /// Also list fonts that passed the check
+    #[arg(long, short)]
+    verbose: bool,
+}
+
+pub fn run(args: &CheckSystemArgs) -> Result<(), FontExtractorError> {
+    let families = font_export::enumerate_font_families()?;
+    println!("Checking {} installed font families...", families.len());
+
+    let mut failed = 0;
+    let mut warned = 0;
+    for family in &families {
+        let report = match font_export::extract_font_data(&FontRequest::named(family)) {
+            Ok(data) => validate::check(&data, 0),
+            Err(e) => validate::Report {
+                errors: vec![format!("cannot read font data: {e}")],
+                ..Default::default()
+            },
+        };
+        if !report.errors.is_empty() {
+            failed += 1;
+            println!("FAIL  {family}");
+        } else if !report.warnings.is_empty() {
+            warned += 1;
+            println!("WARN  {family}");
+        } else if args.verbose {
+            println!("OK    {family}");
+        }
+        for problem in report.errors.iter().chain(&report.warnings) {
+            println!("        {problem}");
+        }
+    }
+
+    println!();
+    println!(
+        "{} font families checked: {failed} failed, {warned} with warnings.",
+        families.len()
+    );
+    if failed > 0 {
+        return Err(FontExtractorError::UnhealthyFonts {
+            failed,
+            total: families.len(),
+        });
+    }
+    Ok(())
+}
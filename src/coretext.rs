@@ -0,0 +1,559 @@
+//! Core Text によるインストール済みフォントの検索 (macOS)
+//!
+//! macOS では Core Text が GDI のフォントマッパーと同じ役割を持ちます。ファミリー名と太さ・斜体から
+//! フォントディスクリプタを作り、`CTFontDescriptorCreateMatchingFontDescriptor` で選ばれたフォントのファイル
+//! (`kCTFontURLAttribute`) を読み取ります。コレクションのフェイスは PostScript 名 (name ID 6) で探します。
+//!
+//! ファミリーとフェイスの列挙、レジストリの Fonts キーの代わりのフォントファイルの一覧 ([`fontreg::registered_fonts`])、
+//! PostScript 名の検索も Core Text で行います。TrueType / OpenType 以外の形式 (ビットマップ、Type 1 など) は対象外です。
+//!
+//! `coretext` フィーチャーで有効になり、[`CoreText`] として [`FontSource`] を実装します。
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::{c_char, c_void};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::fontreg::{self, RegisteredFont};
+use crate::sfnt::{self, Sfnt, name};
+use crate::source::FontSource;
+use crate::{
+    Backend, FontExtractorError, FontFace, FontRequest, FontTables, ReadTimes, TableDirectory,
+    fontfile,
+};
+
+type CFTypeRef = *const c_void;
+type CFStringRef = CFTypeRef;
+type CFArrayRef = CFTypeRef;
+type CFDictionaryRef = CFTypeRef;
+type CFIndex = isize;
+type Boolean = u8;
+
+/// kCFStringEncodingUTF8
+const UTF8: u32 = 0x0800_0100;
+/// kCFNumberSInt32Type
+const NUMBER_SINT32: CFIndex = 3;
+/// kCFNumberSInt64Type
+const NUMBER_SINT64: CFIndex = 4;
+/// kCFNumberDoubleType
+const NUMBER_DOUBLE: CFIndex = 13;
+/// kCTFontItalicTrait (kCTFontSymbolicTrait のビット)
+const ITALIC_TRAIT: i32 = 1 << 0;
+/// 対象にする kCTFontFormatAttribute (OpenType PostScript、OpenType TrueType、TrueType)
+const SFNT_FORMATS: [i32; 3] = [1, 2, 3];
+/// ファイルのパスの最大長 (PATH_MAX)
+const MAX_PATH_LEN: usize = 1024;
+
+/// LOGFONT の太さ (100 ～ 900) に対応する kCTFontWeightTrait の値 (NSFontWeight の定数と同じ)
+const WEIGHTS: [(i32, f64); 9] = [
+    (100, -0.8),
+    (200, -0.6),
+    (300, -0.4),
+    (400, 0.0),
+    (500, 0.23),
+    (600, 0.3),
+    (700, 0.4),
+    (800, 0.56),
+    (900, 0.62),
+];
+
+/// CFDictionaryKeyCallBacks / CFDictionaryValueCallBacks (アドレスだけを渡す)
+#[repr(C)]
+struct CallBacks {
+    _private: [u8; 0],
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+unsafe extern "C" {
+    static kCFTypeDictionaryKeyCallBacks: CallBacks;
+    static kCFTypeDictionaryValueCallBacks: CallBacks;
+
+    fn CFRelease(cf: CFTypeRef);
+    fn CFGetTypeID(cf: CFTypeRef) -> usize;
+    fn CFStringGetTypeID() -> usize;
+    fn CFStringCreateWithBytes(
+        alloc: CFTypeRef,
+        bytes: *const u8,
+        num_bytes: CFIndex,
+        encoding: u32,
+        is_external_representation: Boolean,
+    ) -> CFStringRef;
+    fn CFStringGetLength(string: CFStringRef) -> CFIndex;
+    fn CFStringGetMaximumSizeForEncoding(length: CFIndex, encoding: u32) -> CFIndex;
+    fn CFStringGetCString(
+        string: CFStringRef,
+        buffer: *mut c_char,
+        buffer_size: CFIndex,
+        encoding: u32,
+    ) -> Boolean;
+    fn CFArrayGetCount(array: CFArrayRef) -> CFIndex;
+    fn CFArrayGetValueAtIndex(array: CFArrayRef, index: CFIndex) -> CFTypeRef;
+    fn CFDictionaryCreate(
+        alloc: CFTypeRef,
+        keys: *const CFTypeRef,
+        values: *const CFTypeRef,
+        num_values: CFIndex,
+        key_call_backs: *const CallBacks,
+        value_call_backs: *const CallBacks,
+    ) -> CFDictionaryRef;
+    fn CFDictionaryGetValue(dictionary: CFDictionaryRef, key: CFTypeRef) -> CFTypeRef;
+    fn CFNumberCreate(alloc: CFTypeRef, number_type: CFIndex, value: *const c_void) -> CFTypeRef;
+    fn CFNumberGetValue(number: CFTypeRef, number_type: CFIndex, value: *mut c_void) -> Boolean;
+    fn CFURLGetFileSystemRepresentation(
+        url: CFTypeRef,
+        resolve_against_base: Boolean,
+        buffer: *mut u8,
+        max_buf_len: CFIndex,
+    ) -> Boolean;
+}
+
+#[link(name = "CoreText", kind = "framework")]
+unsafe extern "C" {
+    static kCTFontFamilyNameAttribute: CFStringRef;
+    static kCTFontNameAttribute: CFStringRef;
+    static kCTFontStyleNameAttribute: CFStringRef;
+    static kCTFontDisplayNameAttribute: CFStringRef;
+    static kCTFontTraitsAttribute: CFStringRef;
+    static kCTFontWeightTrait: CFStringRef;
+    static kCTFontSymbolicTrait: CFStringRef;
+    static kCTFontURLAttribute: CFStringRef;
+    static kCTFontFormatAttribute: CFStringRef;
+
+    fn CTFontDescriptorCreateWithAttributes(attributes: CFDictionaryRef) -> CFTypeRef;
+    fn CTFontDescriptorCreateMatchingFontDescriptor(
+        descriptor: CFTypeRef,
+        mandatory_attributes: CFTypeRef,
+    ) -> CFTypeRef;
+    fn CTFontDescriptorCreateMatchingFontDescriptors(
+        descriptor: CFTypeRef,
+        mandatory_attributes: CFTypeRef,
+    ) -> CFArrayRef;
+    fn CTFontDescriptorCopyAttribute(descriptor: CFTypeRef, attribute: CFStringRef) -> CFTypeRef;
+    fn CTFontManagerCopyAvailableFontFamilyNames() -> CFArrayRef;
+    fn CTFontManagerCopyAvailableFontURLs() -> CFArrayRef;
+    fn CTFontManagerCreateFontDescriptorsFromURL(url: CFTypeRef) -> CFArrayRef;
+}
+
+/// 所有している (Create / Copy で得た) Core Foundation のオブジェクト
+struct Owned(CFTypeRef);
+
+impl Owned {
+    /// Create / Copy の戻り値を所有します。NULL ならエラーです。
+    fn new(object: CFTypeRef, api_name: &str) -> Result<Self, FontExtractorError> {
+        if object.is_null() {
+            return Err(api_error(api_name, "the call returned NULL"));
+        }
+        Ok(Self(object))
+    }
+
+    /// Create / Copy の戻り値を所有します (NULL なら `None`)。
+    fn maybe(object: CFTypeRef) -> Option<Self> {
+        (!object.is_null()).then_some(Self(object))
+    }
+
+    fn string(text: &str) -> Result<Self, FontExtractorError> {
+        let string = unsafe {
+            CFStringCreateWithBytes(
+                std::ptr::null(),
+                text.as_ptr(),
+                text.len() as CFIndex,
+                UTF8,
+                0,
+            )
+        };
+        Self::new(string, "CFStringCreateWithBytes")
+    }
+
+    fn number<T>(number_type: CFIndex, value: &T) -> Result<Self, FontExtractorError> {
+        let number =
+            unsafe { CFNumberCreate(std::ptr::null(), number_type, (value as *const T).cast()) };
+        Self::new(number, "CFNumberCreate")
+    }
+
+    fn dictionary(entries: &[(CFStringRef, &Owned)]) -> Result<Self, FontExtractorError> {
+        let keys: Vec<CFTypeRef> = entries.iter().map(|(key, _)| *key).collect();
+        let values: Vec<CFTypeRef> = entries.iter().map(|(_, value)| value.0).collect();
+        let dictionary = unsafe {
+            CFDictionaryCreate(
+                std::ptr::null(),
+                keys.as_ptr(),
+                values.as_ptr(),
+                entries.len() as CFIndex,
+                &raw const kCFTypeDictionaryKeyCallBacks,
+                &raw const kCFTypeDictionaryValueCallBacks,
+            )
+        };
+        Self::new(dictionary, "CFDictionaryCreate")
+    }
+
+    /// 配列の要素 (所有しない)
+    fn items(&self) -> impl Iterator<Item = CFTypeRef> + '_ {
+        let count = unsafe { CFArrayGetCount(self.0) };
+        (0..count).map(|index| unsafe { CFArrayGetValueAtIndex(self.0, index) })
+    }
+}
+
+impl Drop for Owned {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.0) };
+    }
+}
+
+/// フォントディスクリプタ (所有しない) の属性
+struct Descriptor(CFTypeRef);
+
+impl Descriptor {
+    fn attribute(&self, attribute: CFStringRef) -> Option<Owned> {
+        Owned::maybe(unsafe { CTFontDescriptorCopyAttribute(self.0, attribute) })
+    }
+
+    fn string(&self, attribute: CFStringRef) -> Option<String> {
+        to_string(self.attribute(attribute)?.0)
+    }
+
+    /// TrueType / OpenType のフォントかどうか
+    fn is_sfnt(&self) -> bool {
+        self.attribute(unsafe { kCTFontFormatAttribute })
+            .and_then(|format| to_i32(format.0))
+            .is_some_and(|format| SFNT_FORMATS.contains(&format))
+    }
+
+    /// フォントファイルのパス
+    fn path(&self) -> Option<PathBuf> {
+        let url = self.attribute(unsafe { kCTFontURLAttribute })?;
+        url_path(url.0)
+    }
+
+    /// 太さ (100 ～ 900) と斜体かどうか
+    fn weight_and_italic(&self) -> (i32, bool) {
+        let Some(traits) = self.attribute(unsafe { kCTFontTraitsAttribute }) else {
+            return (400, false);
+        };
+        let weight = to_f64(unsafe { CFDictionaryGetValue(traits.0, kCTFontWeightTrait) })
+            .map_or(400, logfont_weight);
+        let symbolic = to_i32(unsafe { CFDictionaryGetValue(traits.0, kCTFontSymbolicTrait) });
+        (weight, symbolic.is_some_and(|s| s & ITALIC_TRAIT != 0))
+    }
+
+    fn face(&self) -> FontFace {
+        let (weight, italic) = self.weight_and_italic();
+        FontFace {
+            weight,
+            italic,
+            style: self
+                .string(unsafe { kCTFontStyleNameAttribute })
+                .unwrap_or_default(),
+            full_name: self
+                .string(unsafe { kCTFontDisplayNameAttribute })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Core Text が `request` の名前・太さ・斜体で選ぶフォントのファイルとフェイスの番号を返します。
+///
+/// GDI と同じく名前が無ければ代替フォントが選ばれることがあります (代替かどうかは呼び出し側が name テーブルで確かめます)。
+/// TrueType / OpenType 以外のフォントが選ばれた場合はエラーにします。
+pub fn match_font(request: &FontRequest<'_>) -> Result<(PathBuf, u32), FontExtractorError> {
+    let logfont = &request.logfont;
+    let weight = Owned::number(NUMBER_DOUBLE, &trait_weight(logfont.weight))?;
+    let symbolic = Owned::number(
+        NUMBER_SINT32,
+        &if logfont.italic { ITALIC_TRAIT } else { 0 },
+    )?;
+    let traits = Owned::dictionary(&[
+        (unsafe { kCTFontWeightTrait }, &weight),
+        (unsafe { kCTFontSymbolicTrait }, &symbolic),
+    ])?;
+    let family = Owned::string(request.name)?;
+    let attributes = Owned::dictionary(&[
+        (unsafe { kCTFontFamilyNameAttribute }, &family),
+        (unsafe { kCTFontTraitsAttribute }, &traits),
+    ])?;
+    let descriptor = Owned::new(
+        unsafe { CTFontDescriptorCreateWithAttributes(attributes.0) },
+        "CTFontDescriptorCreateWithAttributes",
+    )?;
+    let matched = Owned::maybe(unsafe {
+        CTFontDescriptorCreateMatchingFontDescriptor(descriptor.0, std::ptr::null())
+    })
+    .ok_or_else(|| FontExtractorError::NotInstalled {
+        name: request.name.to_string(),
+        backend: "Core Text",
+    })?;
+    let font = Descriptor(matched.0);
+    let api_name = format!(
+        "CTFontDescriptorCreateMatchingFontDescriptor (font: '{}')",
+        request.name
+    );
+    let path = font
+        .path()
+        .ok_or_else(|| api_error(&api_name, "the matched font has no file"))?;
+    if !font.is_sfnt() {
+        return Err(api_error(
+            &api_name,
+            &format!(
+                "matched {}, which is not a TrueType or OpenType font",
+                path.display()
+            ),
+        ));
+    }
+    let postscript_name = font.string(unsafe { kCTFontNameAttribute });
+    let index = face_index(&path, postscript_name.as_deref())?;
+    Ok((path, index))
+}
+
+/// コレクションのファイルなら、PostScript 名が `postscript_name` のフェイスの番号 (見つからなければ 0)
+fn face_index(path: &Path, postscript_name: Option<&str>) -> Result<u32, FontExtractorError> {
+    let Some(postscript_name) = postscript_name else {
+        return Ok(0);
+    };
+    let data = fontfile::read(path)?;
+    if data.get(..4) != Some(b"ttcf") {
+        return Ok(0);
+    }
+    let num_fonts = sfnt::read_u32(&data, 8)?;
+    Ok((0..num_fonts)
+        .find(|&index| {
+            Sfnt::parse_face(&data, index)
+                .ok()
+                .and_then(|font| name::parse(font.table(b"name")?).ok())
+                .and_then(|records| name::find(&records, 6))
+                .as_deref()
+                == Some(postscript_name)
+        })
+        .unwrap_or(0))
+}
+
+/// TrueType / OpenType のファミリー名を、重複を除いて名前順に返します。
+///
+/// システムが内部で使う '.' で始まるファミリーは除きます。
+pub fn families() -> Result<Vec<String>, FontExtractorError> {
+    let names = Owned::new(
+        unsafe { CTFontManagerCopyAvailableFontFamilyNames() },
+        "CTFontManagerCopyAvailableFontFamilyNames",
+    )?;
+    let families: BTreeSet<String> = names
+        .items()
+        .filter_map(to_string)
+        .filter(|family| !family.starts_with('.'))
+        .collect();
+    Ok(families.into_iter().collect())
+}
+
+/// `family` の TrueType / OpenType のフェイスを、太さ・斜体の順に重複を除いて返します。
+pub fn faces(family: &str) -> Result<Vec<FontFace>, FontExtractorError> {
+    let faces: BTreeSet<FontFace> = matching(unsafe { kCTFontFamilyNameAttribute }, family)?
+        .iter()
+        .flat_map(|fonts| fonts.items())
+        .map(Descriptor)
+        .filter(|font| {
+            font.is_sfnt()
+                && font
+                    .string(unsafe { kCTFontFamilyNameAttribute })
+                    .as_deref()
+                    == Some(family)
+        })
+        .map(|font| font.face())
+        .collect();
+    Ok(faces.into_iter().collect())
+}
+
+/// PostScript 名が `name` のフォントを探し、そのファミリー名とフェイスを返します。見つからなければ `None` です。
+pub fn find_postscript_name(name: &str) -> Result<Option<(String, FontFace)>, FontExtractorError> {
+    let fonts = matching(unsafe { kCTFontNameAttribute }, name)?;
+    Ok(fonts
+        .iter()
+        .flat_map(|fonts| fonts.items())
+        .map(Descriptor)
+        .filter(|font| {
+            font.is_sfnt() && font.string(unsafe { kCTFontNameAttribute }).as_deref() == Some(name)
+        })
+        .find_map(|font| {
+            Some((
+                font.string(unsafe { kCTFontFamilyNameAttribute })?,
+                font.face(),
+            ))
+        }))
+}
+
+/// 属性 `attribute` が `value` のフォントディスクリプタの配列 (無ければ `None`)
+fn matching(attribute: CFStringRef, value: &str) -> Result<Option<Owned>, FontExtractorError> {
+    let value = Owned::string(value)?;
+    let attributes = Owned::dictionary(&[(attribute, &value)])?;
+    let descriptor = Owned::new(
+        unsafe { CTFontDescriptorCreateWithAttributes(attributes.0) },
+        "CTFontDescriptorCreateWithAttributes",
+    )?;
+    Ok(Owned::maybe(unsafe {
+        CTFontDescriptorCreateMatchingFontDescriptors(descriptor.0, std::ptr::null())
+    }))
+}
+
+/// Core Text が名前から選んだフォントファイル (コレクションならそのフェイス) を読み取るバックエンド
+pub struct CoreText;
+
+impl FontSource for CoreText {
+    fn backend(&self) -> Backend {
+        Backend::CoreText
+    }
+
+    /// コレクションのフェイスは GDI と同じく単体のフォントに組み立て直します。
+    fn read(&self, request: &FontRequest<'_>) -> Result<(Vec<u8>, ReadTimes), FontExtractorError> {
+        let started = Instant::now();
+        let (path, index) = match_font(request)?;
+        let resolution = started.elapsed();
+        let buffer = fontfile::read_face(&path, index)?;
+        let times = ReadTimes {
+            resolution,
+            read: started.elapsed() - resolution,
+        };
+        Ok((buffer, times))
+    }
+
+    fn collection(&self, request: &FontRequest<'_>) -> Result<Option<Vec<u8>>, FontExtractorError> {
+        crate::read_collection_file(&match_font(request)?.0)
+    }
+
+    fn tables(
+        &self,
+        request: &FontRequest<'_>,
+        tags: &[sfnt::Tag],
+    ) -> Result<FontTables, FontExtractorError> {
+        let (path, index) = match_font(request)?;
+        fontfile::face_tables(&fontfile::read(&path)?, index, tags)
+    }
+
+    fn directory(&self, request: &FontRequest<'_>) -> Result<TableDirectory, FontExtractorError> {
+        let (path, index) = match_font(request)?;
+        fontfile::face_directory(&fontfile::read(&path)?, index)
+    }
+
+    fn families(&self) -> Result<Vec<String>, FontExtractorError> {
+        families()
+    }
+
+    fn faces(&self, family: &str) -> Result<Vec<FontFace>, FontExtractorError> {
+        faces(family)
+    }
+
+    fn find_postscript_name(
+        &self,
+        name: &str,
+    ) -> Option<Result<Option<(String, FontFace)>, FontExtractorError>> {
+        Some(find_postscript_name(name))
+    }
+}
+
+/// Core Text が知っている TrueType / OpenType のフォントファイルと、含まれるフェイスのフルネームを返します。
+///
+/// ホームディレクトリの下 (~/Library/Fonts) のファイルはこのユーザー向け (`User`)、
+/// それ以外 (/Library/Fonts、/System/Library/Fonts) はすべてのユーザー向け (`System`) とします。
+pub fn installed_fonts() -> Result<Vec<RegisteredFont>, FontExtractorError> {
+    let urls = Owned::new(
+        unsafe { CTFontManagerCopyAvailableFontURLs() },
+        "CTFontManagerCopyAvailableFontURLs",
+    )?;
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut files: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    for url in urls.items() {
+        let Some(path) = url_path(url) else {
+            continue;
+        };
+        let Some(fonts) = Owned::maybe(unsafe { CTFontManagerCreateFontDescriptorsFromURL(url) })
+        else {
+            continue;
+        };
+        let names: Vec<String> = fonts
+            .items()
+            .map(Descriptor)
+            .filter(Descriptor::is_sfnt)
+            .filter_map(|font| font.string(unsafe { kCTFontDisplayNameAttribute }))
+            .collect();
+        if !names.is_empty() {
+            let entry = files.entry(path).or_default();
+            for name in names {
+                if !entry.contains(&name) {
+                    entry.push(name);
+                }
+            }
+        }
+    }
+    Ok(files
+        .into_iter()
+        .map(|(path, names)| RegisteredFont {
+            scope: fontreg::scope_of(&path, home.as_deref()),
+            names,
+            path,
+        })
+        .collect())
+}
+
+/// LOGFONT の太さ (0 は標準) に最も近い kCTFontWeightTrait の値
+fn trait_weight(weight: i32) -> f64 {
+    let weight = if weight == 0 { 400 } else { weight };
+    WEIGHTS
+        .iter()
+        .min_by_key(|(logfont, _)| (logfont - weight).abs())
+        .map_or(0.0, |&(_, value)| value)
+}
+
+/// kCTFontWeightTrait の値に最も近い LOGFONT の太さ
+fn logfont_weight(value: f64) -> i32 {
+    WEIGHTS
+        .iter()
+        .min_by(|(_, a), (_, b)| (a - value).abs().total_cmp(&(b - value).abs()))
+        .map_or(400, |&(weight, _)| weight)
+}
+
+fn to_string(string: CFTypeRef) -> Option<String> {
+    if string.is_null() || unsafe { CFGetTypeID(string) != CFStringGetTypeID() } {
+        return None;
+    }
+    let len = unsafe { CFStringGetLength(string) };
+    let size = unsafe { CFStringGetMaximumSizeForEncoding(len, UTF8) } + 1;
+    let mut buffer = vec![0u8; size.max(1) as usize];
+    let ok = unsafe { CFStringGetCString(string, buffer.as_mut_ptr().cast(), size, UTF8) };
+    if ok == 0 {
+        return None;
+    }
+    let len = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    buffer.truncate(len);
+    String::from_utf8(buffer).ok()
+}
+
+fn to_f64(number: CFTypeRef) -> Option<f64> {
+    let mut value = 0.0f64;
+    (!number.is_null()
+        && unsafe { CFNumberGetValue(number, NUMBER_DOUBLE, (&raw mut value).cast()) } != 0)
+        .then_some(value)
+}
+
+/// 整数の値 (symbolic trait は uint32_t のため、i64 で読み取ってから切り詰める)
+fn to_i32(number: CFTypeRef) -> Option<i32> {
+    let mut value = 0i64;
+    (!number.is_null()
+        && unsafe { CFNumberGetValue(number, NUMBER_SINT64, (&raw mut value).cast()) } != 0)
+        .then_some(value as i32)
+}
+
+fn url_path(url: CFTypeRef) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut buffer = [0u8; MAX_PATH_LEN];
+    let ok = unsafe {
+        CFURLGetFileSystemRepresentation(url, 1, buffer.as_mut_ptr(), buffer.len() as CFIndex)
+    };
+    if ok == 0 {
+        return None;
+    }
+    let len = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(&buffer[..len])))
+}
+
+fn api_error(api_name: &str, reason: &str) -> FontExtractorError {
+    FontExtractorError::CoreText {
+        api_name: api_name.to_string(),
+        reason: reason.to_string(),
+    }
+}
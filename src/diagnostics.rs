@@ -0,0 +1,339 @@
+//! 不具合の報告に添付する診断情報 (`--diagnostics FILE`)
+//!
+//! 実行中のイベントとメッセージを記録しておき、実行が失敗したとき (パニックを含む) に次のファイルを zip にまとめます。
+//! フォントのデータ (グリフ) は含めません。
+//!
+//! - `summary.txt`: バージョン、有効なフィーチャー、エラー (パニックならバックトレース) と失敗したフォントの一覧
+//! - `events.ndjson`: `--events ndjson` と同じ形式のイベント (`--events` の指定にかかわらず記録)
+//! - `messages.txt`: 表示したメッセージ (警告を含む)
+//! - `system.txt`: OS とフォントキャッシュのバージョン、インストールされているフォントの数
+//! - `config.txt`: コマンドラインと解析したオプション
+//! - `tables/<フォント名>.txt`: 失敗したフォントのテーブルディレクトリ (`--table-list` と同じ表)
+//!
+//! `--sink-header` の値と URL のクエリ・認証情報は、すべてのファイルで `<redacted>` に置き換えます。
+//!
+//! パニックのフックからも書き出すため、記録はプロセス全体で 1 つの状態に保持します。
+
+use std::backtrace::Backtrace;
+use std::fmt::{Debug, Display, Write as _};
+use std::fs::{self, File};
+use std::io::Write as _;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use font_export::{FontExtractorError, FontRequest, output, sysinfo};
+
+use crate::Args;
+
+/// 記録中の診断情報 (`--diagnostics` が指定されていなければ `None`)
+static BUNDLE: Mutex<Option<Bundle>> = Mutex::new(None);
+
+struct Bundle {
+    path: PathBuf,
+    /// 書き出すすべてのファイルで伏せる値
+    secrets: Vec<String>,
+    command_line: String,
+    options: String,
+    events: Vec<String>,
+    messages: Vec<String>,
+    failures: Vec<Failure>,
+}
+
+/// 失敗したフォント
+struct Failure {
+    font: String,
+    error: String,
+    /// テーブルディレクトリの表 (読み取れなければその理由)
+    tables: String,
+}
+
+/// 診断情報の記録を始め、パニックしたときにも書き出すようにします。
+pub fn start(path: &Path, args: &Args) {
+    let command_line = std::env::args()
+        .map(|arg| {
+            if arg.is_empty() || arg.contains([' ', '"']) {
+                format!("\"{}\"", arg.replace('"', "\\\""))
+            } else {
+                arg
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let bundle = Bundle {
+        path: path.to_path_buf(),
+        secrets: secrets(args),
+        command_line,
+        options: format!("{args:#?}"),
+        events: Vec::new(),
+        messages: Vec::new(),
+        failures: Vec::new(),
+    };
+    *BUNDLE.lock().unwrap_or_else(|e| e.into_inner()) = Some(bundle);
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        // 記録中にパニックした場合 (ロックを持ったまま) は書き出さない
+        let Ok(mut bundle) = BUNDLE.try_lock() else {
+            return;
+        };
+        if let Some(bundle) = bundle.take() {
+            let report = format!("{info}\n\nBacktrace:\n{}", Backtrace::force_capture());
+            report_written(&bundle.path, bundle.write("Panic", &report));
+        }
+    }));
+}
+
+/// イベント (JSON の 1 行) を記録します。
+pub fn record_event(line: &str) {
+    with_bundle(|bundle| bundle.events.push(line.to_string()));
+}
+
+/// 表示したメッセージを記録します。
+pub fn record_message(message: &dyn Display) {
+    with_bundle(|bundle| bundle.messages.push(message.to_string()));
+}
+
+/// 失敗したフォントを、そのテーブルディレクトリとともに記録します。
+pub fn record_failure(
+    request: &FontRequest<'_>,
+    stdin_data: Option<&[u8]>,
+    error: &FontExtractorError,
+) {
+    if !is_recording() {
+        return;
+    }
+    let directory = match stdin_data {
+        Some(data) => font_export::table_directory_from_data(data),
+        None => font_export::extract_table_directory(request),
+    };
+    let tables = match directory {
+        Ok(directory) => crate::table_list(request.name, &directory),
+        Err(e) => format!(
+            "The table directory of '{}' could not be read: {e}\n",
+            request.name
+        ),
+    };
+    with_bundle(|bundle| {
+        bundle.failures.push(Failure {
+            font: request.name.to_string(),
+            error: describe_error(error),
+            tables,
+        })
+    });
+}
+
+/// 実行の結果に応じて診断情報を書き出します。
+///
+/// `--diagnostics` が無く、予期しないエラーで失敗した場合は、`--diagnostics` を案内します。
+/// 書き出しの失敗は警告にとどめ、抽出の結果を上書きしません。
+pub fn finish(result: &Result<(), FontExtractorError>, args: &Args) {
+    let bundle = BUNDLE.lock().unwrap_or_else(|e| e.into_inner()).take();
+    let Some(bundle) = bundle else {
+        if let Err(e) = result
+            && args.events.is_none()
+            && is_unexpected(e)
+        {
+            eprintln!(
+                "To report this as a bug, re-run with --diagnostics bundle.zip and attach the zip (it contains no glyph data)."
+            );
+        }
+        return;
+    };
+    match result {
+        Ok(()) if bundle.failures.is_empty() => eprintln!(
+            "No errors occurred; the diagnostics bundle '{}' was not written.",
+            bundle.path.display()
+        ),
+        Ok(()) => report_written(&bundle.path, bundle.write("Result", "Succeeded\n")),
+        Err(e) => report_written(&bundle.path, bundle.write("Error", &describe_error(e))),
+    }
+}
+
+/// GDI・fontconfig・Core Text の呼び出しやフォントデータの読み取りの失敗など、指定の誤りでは起きないエラーかどうか
+fn is_unexpected(error: &FontExtractorError) -> bool {
+    match error {
+        #[cfg(windows)]
+        FontExtractorError::WinApi { .. } => true,
+        #[cfg(backend = "fontconfig")]
+        FontExtractorError::Fontconfig { .. } => true,
+        #[cfg(backend = "coretext")]
+        FontExtractorError::CoreText { .. } => true,
+        FontExtractorError::ZeroSizeFont { .. }
+        | FontExtractorError::CorruptFontData { .. }
+        | FontExtractorError::ChecksumMismatch { .. }
+        | FontExtractorError::AllBackendsFailed { .. }
+        | FontExtractorError::ExtractionFailed { .. } => true,
+        _ => false,
+    }
+}
+
+fn is_recording() -> bool {
+    BUNDLE.lock().is_ok_and(|bundle| bundle.is_some())
+}
+
+fn with_bundle(record: impl FnOnce(&mut Bundle)) {
+    if let Ok(mut bundle) = BUNDLE.lock()
+        && let Some(bundle) = bundle.as_mut()
+    {
+        record(bundle);
+    }
+}
+
+fn report_written(path: &Path, result: Result<(), FontExtractorError>) {
+    match result {
+        Ok(()) => eprintln!(
+            "Diagnostics written to '{}'. Attach it to a bug report at https://github.com/calloc134/font-export/issues.",
+            path.display()
+        ),
+        Err(e) => eprintln!("Warning: could not write the diagnostics bundle: {e}"),
+    }
+}
+
+/// エラーメッセージと、原因を含む詳細 (Debug 表現)
+fn describe_error(error: &(impl Display + Debug)) -> String {
+    format!("{error}\n{error:?}\n")
+}
+
+impl Bundle {
+    /// zip を書き出します。`heading` と `outcome` は summary.txt の結果の欄です。
+    fn write(&self, heading: &str, outcome: &str) -> Result<(), FontExtractorError> {
+        let mut summary = format!(
+            "font-export {}\nFeatures: {}\n\n{heading}:\n{outcome}",
+            env!("CARGO_PKG_VERSION"),
+            features().join(", ")
+        );
+        if !self.failures.is_empty() {
+            let _ = writeln!(summary, "\nFailed font(s):");
+            for failure in &self.failures {
+                let _ = write!(summary, "\n{}:\n{}", failure.font, failure.error);
+            }
+        }
+        let mut system = String::new();
+        for (item, value) in sysinfo::describe() {
+            let _ = writeln!(system, "{item}: {value}");
+        }
+        let config = format!(
+            "Command line:\n{}\n\nOptions:\n{}\n",
+            self.command_line, self.options
+        );
+
+        let mut files = vec![
+            ("summary.txt".to_string(), summary),
+            ("events.ndjson".to_string(), lines(&self.events)),
+            ("messages.txt".to_string(), lines(&self.messages)),
+            ("system.txt".to_string(), system),
+            ("config.txt".to_string(), config),
+        ];
+        for (i, failure) in self.failures.iter().enumerate() {
+            files.push((
+                format!(
+                    "tables/{:02}-{}.txt",
+                    i + 1,
+                    output::file_name_safe(&failure.font)
+                ),
+                failure.tables.clone(),
+            ));
+        }
+        self.write_zip(&files)
+    }
+
+    fn write_zip(&self, files: &[(String, String)]) -> Result<(), FontExtractorError> {
+        let write_error = |source: std::io::Error| FontExtractorError::FileWrite {
+            path: self.path.display().to_string(),
+            source,
+        };
+        if let Some(parent_dir) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent_dir).map_err(|e| FontExtractorError::FileCreate {
+                path: parent_dir.display().to_string(),
+                source: e,
+            })?;
+        }
+        let file = File::create(&self.path).map_err(|e| FontExtractorError::FileCreate {
+            path: self.path.display().to_string(),
+            source: e,
+        })?;
+        let mut writer = ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, contents) in files {
+            writer
+                .start_file(name.as_str(), options)
+                .map_err(|e| write_error(std::io::Error::other(e)))?;
+            writer
+                .write_all(redact(contents, &self.secrets).as_bytes())
+                .map_err(write_error)?;
+        }
+        writer
+            .finish()
+            .map(|_| ())
+            .map_err(|e| write_error(std::io::Error::other(e)))
+    }
+}
+
+fn lines(lines: &[String]) -> String {
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
+
+/// 有効なフィーチャー
+fn features() -> Vec<&'static str> {
+    let mut features = vec!["archive"];
+    if cfg!(feature = "network") {
+        features.push("network");
+    }
+    if cfg!(feature = "plugins") {
+        features.push("plugins");
+    }
+    // バックエンドはこのターゲットで有効になったものだけ
+    for (enabled, backend) in [
+        (cfg!(backend = "gdi"), "gdi"),
+        (cfg!(backend = "dwrite"), "dwrite"),
+        (cfg!(backend = "fontconfig"), "fontconfig"),
+        (cfg!(backend = "coretext"), "coretext"),
+    ] {
+        if enabled {
+            features.push(backend);
+        }
+    }
+    features
+}
+
+/// 診断情報に載せない値 (`--sink-header` の値、URL のクエリと認証情報)
+fn secrets(args: &Args) -> Vec<String> {
+    let mut secrets = Vec::new();
+    let mut urls: Vec<&str> = args.sink.iter().map(String::as_str).collect();
+    #[cfg(feature = "network")]
+    {
+        secrets.extend(
+            args.sink_header
+                .iter()
+                .filter_map(|header| Some(header.split_once(':')?.1.trim().to_string())),
+        );
+        urls.extend(args.notify_webhook.as_deref());
+    }
+    urls.extend(args.zone_origin.as_deref());
+    for url in urls {
+        if let Some((_, query)) = url.split_once('?') {
+            secrets.push(query.to_string());
+        }
+        if let Some((_, rest)) = url.split_once("://") {
+            let authority = rest.split(['/', '?']).next().unwrap_or_default();
+            if let Some((user_info, _)) = authority.rsplit_once('@') {
+                secrets.push(user_info.to_string());
+            }
+        }
+    }
+    secrets.retain(|secret| !secret.is_empty());
+    // 長いものから伏せる (一方が他方を含む場合に、短いほうだけ伏せて残りが見えないように)
+    secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    secrets
+}
+
+fn redact(text: &str, secrets: &[String]) -> String {
+    secrets.iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret, "<redacted>")
+    })
+}
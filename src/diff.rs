@@ -0,0 +1,282 @@
+//! `diff` サブコマンド: 2 つのフォントファイルを比較します。
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use font_export::FontExtractorError;
+use font_export::limits::Limits;
+use font_export::sfnt::glyf::{Glyf, Outline};
+use font_export::sfnt::{self, Sfnt, Tag, cmap, name};
+
+/// 差分表示で列挙するコードポイント範囲の最大数
+const MAX_LISTED_RANGES: usize = 12;
+
+/// アウトラインが変わったグリフを列挙する最大数
+const MAX_LISTED_GLYPHS: usize = 50;
+
+/// 差分表示する name レコードの最大文字数 (ライセンス文などは省略)
+const MAX_NAME_CHARS: usize = 60;
+
+/// --- `diff` サブコマンドの引数 ---
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// Older version of the font file
+    old: PathBuf,
+
+    /// Newer version of the font file
+    new: PathBuf,
+
+    /// Report which sfnt tables changed instead of only whether the files differ
+    #[arg(long)]
+    tables: bool,
+
+    /// List characters whose glyph outlines changed (TrueType outlines only)
+    #[arg(long)]
+    glyphs: bool,
+
+    /// Face to compare when the files are TrueType Collections
+    #[arg(long, default_value_t = 0)]
+    face: u32,
+
+    #[command(flatten)]
+    limits: Limits,
+}
+
+pub fn run(args: &DiffArgs) -> Result<(), FontExtractorError> {
+    let old_data = args.limits.read(&args.old)?;
+    let new_data = args.limits.read(&args.new)?;
+
+    println!("Comparing {} -> {}", args.old.display(), args.new.display());
+    if old_data == new_data {
+        println!("Files are identical.");
+        return Ok(());
+    }
+    if !args.tables && !args.glyphs {
+        println!(
+            "Files differ: {} -> {} bytes ({:+})",
+            old_data.len(),
+            new_data.len(),
+            new_data.len() as i64 - old_data.len() as i64
+        );
+        return Ok(());
+    }
+
+    let old = Sfnt::parse_face(&old_data, args.face)?;
+    let new = Sfnt::parse_face(&new_data, args.face)?;
+    if args.tables {
+        print_table_changes(&old, &new);
+    }
+    if args.glyphs {
+        print_glyph_changes(&old, &new)?;
+    }
+    Ok(())
+}
+
+/// 変更・追加・削除されたテーブルを表示します。
+fn print_table_changes(old: &Sfnt<'_>, new: &Sfnt<'_>) {
+    // 両方のフォントに現れるタグをソートして列挙
+    let tags: BTreeSet<Tag> = old
+        .tables
+        .iter()
+        .chain(new.tables.iter())
+        .map(|t| t.tag)
+        .collect();
+
+    let mut unchanged = 0;
+    for tag in &tags {
+        let label = sfnt::tag_to_string(tag);
+        match (old.table(tag), new.table(tag)) {
+            (Some(a), Some(b)) if a == b => unchanged += 1,
+            (Some(a), Some(b)) => {
+                println!(
+                    "  {label}  changed  {} -> {} bytes ({:+})",
+                    a.len(),
+                    b.len(),
+                    b.len() as i64 - a.len() as i64
+                );
+                match tag {
+                    b"cmap" => print_cmap_changes(a, b),
+                    b"name" => print_name_changes(a, b),
+                    b"maxp" => print_glyph_count_change(a, b),
+                    _ => {}
+                }
+            }
+            (Some(a), None) => println!("  {label}  removed  ({} bytes)", a.len()),
+            (None, Some(b)) => println!("  {label}  added    ({} bytes)", b.len()),
+            (None, None) => {}
+        }
+    }
+    println!("{unchanged} table(s) unchanged.");
+}
+
+/// 両方のフォントの cmap にある文字について、グリフのアウトラインを比較して変わったものを表示します。
+/// グリフ ID はバージョン間で振り直されることがあるため、文字 (コードポイント) で対応付けます。
+fn print_glyph_changes(old: &Sfnt<'_>, new: &Sfnt<'_>) -> Result<(), FontExtractorError> {
+    let (Some(old_glyf), Some(new_glyf)) = (Glyf::from_font(old)?, Glyf::from_font(new)?) else {
+        println!(
+            "Glyph outlines can only be compared between fonts with TrueType outlines ('glyf')."
+        );
+        return Ok(());
+    };
+    let old_map = cmap::parse(old.table(b"cmap").unwrap_or_default())?;
+    let new_map = cmap::parse(new.table(b"cmap").unwrap_or_default())?;
+
+    let mut compared = BTreeSet::new();
+    let mut changed = Vec::new();
+    for (codepoint, old_glyph) in &old_map {
+        let Some(new_glyph) = new_map.get(codepoint) else {
+            continue;
+        };
+        // 同じグリフを指す文字 (異体の割り当てなど) は 1 度だけ比較する
+        if !compared.insert((*old_glyph, *new_glyph)) {
+            continue;
+        }
+        let before = old_glyf.outline(*old_glyph)?;
+        let after = new_glyf.outline(*new_glyph)?;
+        if let Some(change) = describe_change(&before, &after) {
+            changed.push((*codepoint, change));
+        }
+    }
+
+    println!(
+        "Compared the outlines of {} glyph(s) shared through cmap: {} changed.",
+        compared.len(),
+        changed.len()
+    );
+    for (codepoint, change) in changed.iter().take(MAX_LISTED_GLYPHS) {
+        let character = char::from_u32(*codepoint)
+            .filter(|c| !c.is_control())
+            .map_or(String::new(), |c| format!(" '{c}'"));
+        println!("  U+{codepoint:04X}{character}  {change}");
+    }
+    if changed.len() > MAX_LISTED_GLYPHS {
+        println!("  ... ({} more)", changed.len() - MAX_LISTED_GLYPHS);
+    }
+    Ok(())
+}
+
+/// アウトラインの変化を説明する文字列を返します。変化が無ければ `None` を返します。
+fn describe_change(before: &Outline, after: &Outline) -> Option<String> {
+    if before == after {
+        return None;
+    }
+    if before.contours.len() != after.contours.len() {
+        return Some(format!(
+            "{} -> {} contour(s)",
+            before.contours.len(),
+            after.contours.len()
+        ));
+    }
+    if before.num_points() != after.num_points() {
+        return Some(format!(
+            "{} -> {} point(s)",
+            before.num_points(),
+            after.num_points()
+        ));
+    }
+    let moved = before
+        .contours
+        .iter()
+        .flatten()
+        .zip(after.contours.iter().flatten())
+        .map(|(a, b)| (a.x - b.x).abs().max((a.y - b.y).abs()))
+        .fold(0.0, f64::max);
+    if moved > 0.0 {
+        Some(format!("points moved (up to {moved} units)"))
+    } else {
+        Some("on-curve/off-curve points changed".to_string())
+    }
+}
+
+/// cmap の差分 (追加・削除されたコードポイント) を表示します。
+fn print_cmap_changes(old: &[u8], new: &[u8]) {
+    let (old_map, new_map) = match (cmap::parse(old), cmap::parse(new)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => {
+            println!("        (could not compare cmap: {e})");
+            return;
+        }
+    };
+    let gained = new_map.keys().filter(|c| !old_map.contains_key(c)).copied();
+    let lost = old_map.keys().filter(|c| !new_map.contains_key(c)).copied();
+    print_codepoints("gained", gained);
+    print_codepoints("lost", lost);
+
+    let remapped = old_map
+        .iter()
+        .filter(|&(c, g)| new_map.get(c).is_some_and(|n| n != g))
+        .count();
+    if remapped > 0 {
+        println!("        {remapped} codepoint(s) now map to a different glyph");
+    }
+}
+
+fn print_codepoints(verb: &str, codepoints: impl Iterator<Item = u32>) {
+    let codepoints: Vec<u32> = codepoints.collect();
+    if codepoints.is_empty() {
+        return;
+    }
+    let ranges = cmap::to_ranges(codepoints.iter().copied());
+    let mut listed: Vec<String> = ranges
+        .iter()
+        .take(MAX_LISTED_RANGES)
+        .map(cmap::format_range)
+        .collect();
+    if ranges.len() > MAX_LISTED_RANGES {
+        listed.push(format!(
+            "... ({} more ranges)",
+            ranges.len() - MAX_LISTED_RANGES
+        ));
+    }
+    println!(
+        "        {verb} {} codepoint(s): {}",
+        codepoints.len(),
+        listed.join(", ")
+    );
+}
+
+/// name テーブルで値が変わった name ID を表示します。
+fn print_name_changes(old: &[u8], new: &[u8]) {
+    let (old_records, new_records) = match (name::parse(old), name::parse(new)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => {
+            println!("        (could not compare name: {e})");
+            return;
+        }
+    };
+    let name_ids: BTreeSet<u16> = old_records
+        .iter()
+        .chain(new_records.iter())
+        .map(|r| r.name_id)
+        .collect();
+    for name_id in name_ids {
+        let before = name::find(&old_records, name_id);
+        let after = name::find(&new_records, name_id);
+        if before != after {
+            println!(
+                "        {} (ID {name_id}): {:?} -> {:?}",
+                name::label(name_id),
+                shorten(&before.unwrap_or_default()),
+                shorten(&after.unwrap_or_default())
+            );
+        }
+    }
+}
+
+fn shorten(value: &str) -> String {
+    if value.chars().count() > MAX_NAME_CHARS {
+        let head: String = value.chars().take(MAX_NAME_CHARS).collect();
+        format!("{head}...")
+    } else {
+        value.to_string()
+    }
+}
+
+/// maxp の numGlyphs の変化を表示します。
+fn print_glyph_count_change(old: &[u8], new: &[u8]) {
+    if let (Ok(a), Ok(b)) = (sfnt::read_u16(old, 4), sfnt::read_u16(new, 4))
+        && a != b
+    {
+        println!("        glyph count {a} -> {b}");
+    }
+}
@@ -0,0 +1,13 @@
+//! フォントデータのハッシュ計算
+
+use sha2::{Digest, Sha256};
+
+/// データの SHA-256 を返します。
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// データの SHA-256 を小文字の 16 進文字列で返します。
+pub fn sha256_hex(data: &[u8]) -> String {
+    sha256(data).iter().map(|b| format!("{b:02x}")).collect()
+}
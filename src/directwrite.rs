@@ -0,0 +1,449 @@
+//! DirectWrite による名前解決との照合
+//!
+//! GDI と DirectWrite はフォント名の解決方法が異なります。DirectWrite は WWS ファミリー名と太さ・斜体で探し、
+//! GDI のレガシーなファミリー名や FontSubstitutes の代替設定は使いません。
+//! そのため同じ名前でも、DirectWrite を使うアプリケーション (Edge、新しい Office など) では別のフォントが表示されることがあります。
+//! GDI で抽出したフォントと、DirectWrite が同じ名前・太さ・斜体で選ぶフォントが同じかを確かめます。
+//!
+//! また、GDI では引けない PostScript 名からのフォントの検索 (`--postscript-name`) と、
+//! GDI の GetFontData が失敗したフォントの読み取り (抽出のフォールバック) にも DirectWrite を使います。
+//!
+//! `dwrite` フィーチャーで有効になり、[`DirectWrite`] として [`FontSource`] を実装します。
+//! `gdi` フィーチャーを外したビルドでは、名前からのフォントの選択と列挙もすべて DirectWrite で行います。
+
+use std::collections::BTreeSet;
+use std::ffi::{OsStr, c_void};
+use std::os::windows::ffi::OsStrExt;
+use std::time::Instant;
+
+use windows::Win32::Foundation::BOOL;
+use windows::Win32::Graphics::DirectWrite::{
+    DWRITE_FACTORY_TYPE_SHARED, DWRITE_FONT_SIMULATIONS_NONE, DWRITE_FONT_STRETCH_NORMAL,
+    DWRITE_FONT_STYLE_ITALIC, DWRITE_FONT_STYLE_NORMAL, DWRITE_FONT_WEIGHT,
+    DWRITE_INFORMATIONAL_STRING_FULL_NAME, DWRITE_INFORMATIONAL_STRING_ID,
+    DWRITE_INFORMATIONAL_STRING_POSTSCRIPT_NAME, DWriteCreateFactory, IDWriteFactory, IDWriteFont,
+    IDWriteFontCollection, IDWriteFontFace, IDWriteFontFamily, IDWriteFontFile,
+    IDWriteLocalizedStrings,
+};
+#[cfg(backend = "gdi")]
+use windows::Win32::Graphics::Gdi::LOGFONTW;
+use windows::core::{Error as WinError, PCWSTR, w};
+
+use crate::sfnt::{self, Sfnt, Tag};
+use crate::source::FontSource;
+use crate::{Backend, FontExtractorError, FontFace, FontRequest, ReadTimes};
+
+/// 同じフォントかどうかを比べるテーブル (head の checkSumAdjustment は比べない)
+const COMPARED_TABLES: &[&Tag] = &[b"head", b"name"];
+
+/// LOGFONT の太さが 0 (FW_DONTCARE) のときに DirectWrite に渡す太さ
+const DEFAULT_WEIGHT: i32 = 400;
+
+/// DirectWrite での解決結果
+#[derive(Debug)]
+pub enum Resolution {
+    /// GDI と同じフォントを選んだ
+    Same,
+    /// 別のフォントを選んだ (そのフルネーム)
+    Different { full_name: String },
+    /// システムのフォントコレクションにそのファミリー名が無い
+    FamilyNotFound,
+}
+
+/// `request` の名前・太さ・斜体で DirectWrite が選ぶフォントが、GDI で抽出した `data` と同じかを調べます。
+pub fn cross_check(
+    request: &FontRequest<'_>,
+    data: &[u8],
+) -> Result<Resolution, FontExtractorError> {
+    let Some(font) = matching_font(request)? else {
+        return Ok(Resolution::FamilyNotFound);
+    };
+    let face =
+        unsafe { font.CreateFontFace() }.map_err(api_error("IDWriteFont::CreateFontFace"))?;
+
+    let gdi = Sfnt::parse_face(data, 0)?;
+    for tag in COMPARED_TABLES {
+        let same = with_table(&face, tag, |table| match (gdi.table(tag), table) {
+            (Some(a), Some(b)) => without_adjustment(tag, a) == without_adjustment(tag, b),
+            (a, b) => a.is_none() && b.is_none(),
+        })?;
+        if !same {
+            return Ok(Resolution::Different {
+                full_name: full_name(&font).unwrap_or_else(|| "an unnamed font".to_string()),
+            });
+        }
+    }
+    Ok(Resolution::Same)
+}
+
+/// `request` の名前・太さ・斜体で DirectWrite が選ぶフォントのデータを、フォントファイルから読み取ります。
+/// システムのフォントコレクションにそのファミリー名が無ければ `None` です。
+///
+/// GDI を通さず、DirectWrite のフォントファイルローダーでファイルを読むため、GetFontData が失敗するフォントでも読めることがあります。
+/// コレクションのフェイスは単体のフォントに組み立て直します。
+pub fn read_font(request: &FontRequest<'_>) -> Result<Option<Vec<u8>>, FontExtractorError> {
+    matching_font(request)?
+        .map(|font| read_face(&font))
+        .transpose()
+}
+
+/// フォントのデータ (コレクションのフェイスなら単体のフォントに組み立て直したもの)
+fn read_face(font: &IDWriteFont) -> Result<Vec<u8>, FontExtractorError> {
+    let (data, index) = font_file(font)?;
+    if data.get(..4) != Some(b"ttcf") {
+        return Ok(data);
+    }
+    sfnt::split_collection(&data)?
+        .into_iter()
+        .nth(index as usize)
+        .ok_or_else(|| FontExtractorError::CorruptFontData {
+            reason: format!("the collection has no face #{index}"),
+        })
+}
+
+/// フォントを含むファイルの内容と、ファイル内のフェイスの番号
+fn font_file(font: &IDWriteFont) -> Result<(Vec<u8>, u32), FontExtractorError> {
+    let face =
+        unsafe { font.CreateFontFace() }.map_err(api_error("IDWriteFont::CreateFontFace"))?;
+    let mut count = 1;
+    let mut files: [Option<IDWriteFontFile>; 1] = [None];
+    unsafe { face.GetFiles(&mut count, Some(files.as_mut_ptr())) }
+        .map_err(api_error("IDWriteFontFace::GetFiles"))?;
+    let [Some(file)] = files else {
+        return Err(FontExtractorError::WinApi {
+            api_name: "IDWriteFontFace::GetFiles".to_string(),
+            source: WinError::empty(),
+        });
+    };
+    Ok((read_file(&file)?, unsafe { face.GetIndex() }))
+}
+
+/// フォントファイルの内容をファイルローダーのストリームから読み取ります。
+fn read_file(file: &IDWriteFontFile) -> Result<Vec<u8>, FontExtractorError> {
+    let mut key: *mut c_void = std::ptr::null_mut();
+    let mut key_size = 0;
+    unsafe { file.GetReferenceKey(&mut key, &mut key_size) }
+        .map_err(api_error("IDWriteFontFile::GetReferenceKey"))?;
+    let loader = unsafe { file.GetLoader() }.map_err(api_error("IDWriteFontFile::GetLoader"))?;
+    let stream = unsafe { loader.CreateStreamFromKey(key, key_size) }
+        .map_err(api_error("IDWriteFontFileLoader::CreateStreamFromKey"))?;
+    let size =
+        unsafe { stream.GetFileSize() }.map_err(api_error("IDWriteFontFileStream::GetFileSize"))?;
+    let mut fragment: *mut c_void = std::ptr::null_mut();
+    let mut context: *mut c_void = std::ptr::null_mut();
+    unsafe { stream.ReadFileFragment(&mut fragment, 0, size, &mut context) }
+        .map_err(api_error("IDWriteFontFileStream::ReadFileFragment"))?;
+    let data = unsafe { std::slice::from_raw_parts(fragment as *const u8, size as usize) }.to_vec();
+    unsafe { stream.ReleaseFileFragment(context) };
+    Ok(data)
+}
+
+/// PostScript 名 (name ID 6、例: "NotoSansJP-Regular") が `name` のインストール済みフォントを探し、
+/// 名前からそのフォントを選ぶためのファミリー名とフェイス (太さと斜体) を返します。見つからなければ `None` です。
+/// GDI が有効なビルドでは GDI で選ぶための名前 (LOGFONT のフェイス名) を、それ以外では DirectWrite のファミリー名を返します。
+///
+/// 太字や斜体をシミュレーションしただけのフォントは対象外です。
+pub fn find_postscript_name(name: &str) -> Result<Option<(String, FontFace)>, FontExtractorError> {
+    let (factory, collection) = system_collection()?;
+    for family_index in 0..unsafe { collection.GetFontFamilyCount() } {
+        let family = unsafe { collection.GetFontFamily(family_index) }
+            .map_err(api_error("IDWriteFontCollection::GetFontFamily"))?;
+        for font_index in 0..unsafe { family.GetFontCount() } {
+            let font = unsafe { family.GetFont(font_index) }
+                .map_err(api_error("IDWriteFontList::GetFont"))?;
+            if unsafe { font.GetSimulations() } != DWRITE_FONT_SIMULATIONS_NONE {
+                continue;
+            }
+            let Some(strings) =
+                informational_strings(&font, DWRITE_INFORMATIONAL_STRING_POSTSCRIPT_NAME)
+            else {
+                continue;
+            };
+            let matches = (0..unsafe { strings.GetCount() })
+                .any(|index| localized_string(&strings, index).as_deref() == Some(name));
+            if !matches {
+                continue;
+            }
+
+            return selection(&factory, &family, &font, name).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// GDI で `font` を選ぶための LOGFONT のフェイス名と、太さ・斜体 (PostScript 名 `name` のフォント)
+#[cfg(backend = "gdi")]
+fn selection(
+    factory: &IDWriteFactory,
+    _family: &IDWriteFontFamily,
+    font: &IDWriteFont,
+    name: &str,
+) -> Result<(String, FontFace), FontExtractorError> {
+    let interop =
+        unsafe { factory.GetGdiInterop() }.map_err(api_error("IDWriteFactory::GetGdiInterop"))?;
+    let mut logfont = LOGFONTW::default();
+    let mut is_system_font = BOOL(0);
+    unsafe { interop.ConvertFontToLOGFONT(font, &mut logfont, &mut is_system_font) }
+        .map_err(api_error("IDWriteGdiInterop::ConvertFontToLOGFONT"))?;
+    let face_name = &logfont.lfFaceName;
+    let len = face_name
+        .iter()
+        .position(|&c| c == 0)
+        .unwrap_or(face_name.len());
+    let face = FontFace {
+        weight: logfont.lfWeight,
+        italic: logfont.lfItalic != 0,
+        style: face_names(font).unwrap_or_default(),
+        full_name: full_name(font).unwrap_or_else(|| name.to_string()),
+    };
+    Ok((String::from_utf16_lossy(&face_name[..len]), face))
+}
+
+/// DirectWrite で `font` を選ぶためのファミリー名と、太さ・斜体 (PostScript 名 `name` のフォント)
+#[cfg(not(backend = "gdi"))]
+fn selection(
+    _factory: &IDWriteFactory,
+    family: &IDWriteFontFamily,
+    font: &IDWriteFont,
+    name: &str,
+) -> Result<(String, FontFace), FontExtractorError> {
+    let family_names = unsafe { family.GetFamilyNames() }
+        .map_err(api_error("IDWriteFontFamily::GetFamilyNames"))?;
+    let family_name = english_or_first(&family_names).unwrap_or_else(|| name.to_string());
+    Ok((family_name, face_of(font, name)))
+}
+
+/// DirectWrite のフォントの太さ・斜体・名前 (フルネームが無ければ `fallback_name`)
+fn face_of(font: &IDWriteFont, fallback_name: &str) -> FontFace {
+    FontFace {
+        weight: unsafe { font.GetWeight() }.0,
+        italic: unsafe { font.GetStyle() } != DWRITE_FONT_STYLE_NORMAL,
+        style: face_names(font).unwrap_or_default(),
+        full_name: full_name(font).unwrap_or_else(|| fallback_name.to_string()),
+    }
+}
+
+/// システムのフォントコレクションのファミリー名 (英語 → 最初の言語の順) を、重複を除いて名前順に返します。
+pub fn families() -> Result<Vec<String>, FontExtractorError> {
+    let (_, collection) = system_collection()?;
+    let mut families = BTreeSet::new();
+    for index in 0..unsafe { collection.GetFontFamilyCount() } {
+        let family = unsafe { collection.GetFontFamily(index) }
+            .map_err(api_error("IDWriteFontCollection::GetFontFamily"))?;
+        let names = unsafe { family.GetFamilyNames() }
+            .map_err(api_error("IDWriteFontFamily::GetFamilyNames"))?;
+        families.extend(english_or_first(&names));
+    }
+    Ok(families.into_iter().collect())
+}
+
+/// `family` のフェイスを、太さ・斜体の順に重複を除いて返します。ファミリーが無ければ空です。
+///
+/// 太字や斜体をシミュレーションしただけのフォントは含めません。
+pub fn faces(family: &str) -> Result<Vec<FontFace>, FontExtractorError> {
+    let (_, collection) = system_collection()?;
+    let Some(family) = find_family(&collection, family)? else {
+        return Ok(Vec::new());
+    };
+    let mut faces = BTreeSet::new();
+    for index in 0..unsafe { family.GetFontCount() } {
+        let font =
+            unsafe { family.GetFont(index) }.map_err(api_error("IDWriteFontList::GetFont"))?;
+        if unsafe { font.GetSimulations() } == DWRITE_FONT_SIMULATIONS_NONE {
+            faces.insert(face_of(&font, ""));
+        }
+    }
+    Ok(faces.into_iter().collect())
+}
+
+/// DirectWrite のフォントファイルローダーで名前からフォントを選んで読み取るバックエンド
+pub struct DirectWrite;
+
+impl FontSource for DirectWrite {
+    fn backend(&self) -> Backend {
+        Backend::DirectWrite
+    }
+
+    fn read(&self, request: &FontRequest<'_>) -> Result<(Vec<u8>, ReadTimes), FontExtractorError> {
+        let started = Instant::now();
+        let font = installed_font(request)?;
+        let resolution = started.elapsed();
+        let data = read_face(&font)?;
+        let times = ReadTimes {
+            resolution,
+            read: started.elapsed() - resolution,
+        };
+        Ok((data, times))
+    }
+
+    fn collection(&self, request: &FontRequest<'_>) -> Result<Option<Vec<u8>>, FontExtractorError> {
+        let (data, _) = font_file(&installed_font(request)?)?;
+        Ok((data.get(..4) == Some(b"ttcf")).then_some(data))
+    }
+
+    fn families(&self) -> Result<Vec<String>, FontExtractorError> {
+        families()
+    }
+
+    fn faces(&self, family: &str) -> Result<Vec<FontFace>, FontExtractorError> {
+        faces(family)
+    }
+
+    fn find_postscript_name(
+        &self,
+        name: &str,
+    ) -> Option<Result<Option<(String, FontFace)>, FontExtractorError>> {
+        Some(find_postscript_name(name))
+    }
+}
+
+/// [`matching_font`] で選んだフォント。ファミリー名が無ければエラーです。
+fn installed_font(request: &FontRequest<'_>) -> Result<IDWriteFont, FontExtractorError> {
+    matching_font(request)?.ok_or_else(|| FontExtractorError::NotInstalled {
+        name: request.name.to_string(),
+        backend: "DirectWrite",
+    })
+}
+
+/// システムのフォントコレクションから、`request` の名前・太さ・斜体で DirectWrite が選ぶフォント。
+/// そのファミリー名が無ければ `None` です。
+fn matching_font(request: &FontRequest<'_>) -> Result<Option<IDWriteFont>, FontExtractorError> {
+    let (_, collection) = system_collection()?;
+    let Some(family) = find_family(&collection, request.name)? else {
+        return Ok(None);
+    };
+    let weight = match request.logfont.weight {
+        0 => DEFAULT_WEIGHT,
+        weight => weight,
+    };
+    let style = if request.logfont.italic {
+        DWRITE_FONT_STYLE_ITALIC
+    } else {
+        DWRITE_FONT_STYLE_NORMAL
+    };
+    let font = unsafe {
+        family.GetFirstMatchingFont(
+            DWRITE_FONT_WEIGHT(weight),
+            DWRITE_FONT_STRETCH_NORMAL,
+            style,
+        )
+    }
+    .map_err(api_error("IDWriteFontFamily::GetFirstMatchingFont"))?;
+    Ok(Some(font))
+}
+
+/// `collection` のファミリー名が `name` のファミリー (無ければ `None`)
+fn find_family(
+    collection: &IDWriteFontCollection,
+    name: &str,
+) -> Result<Option<IDWriteFontFamily>, FontExtractorError> {
+    let family_name: Vec<u16> = OsStr::new(name)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut index = 0;
+    let mut exists = BOOL(0);
+    unsafe { collection.FindFamilyName(PCWSTR(family_name.as_ptr()), &mut index, &mut exists) }
+        .map_err(api_error("IDWriteFontCollection::FindFamilyName"))?;
+    if !exists.as_bool() {
+        return Ok(None);
+    }
+    let family = unsafe { collection.GetFontFamily(index) }
+        .map_err(api_error("IDWriteFontCollection::GetFontFamily"))?;
+    Ok(Some(family))
+}
+
+/// DirectWrite のファクトリーとシステムのフォントコレクション
+fn system_collection() -> Result<(IDWriteFactory, IDWriteFontCollection), FontExtractorError> {
+    let factory: IDWriteFactory = unsafe { DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED) }
+        .map_err(api_error("DWriteCreateFactory"))?;
+    let mut collection: Option<IDWriteFontCollection> = None;
+    unsafe { factory.GetSystemFontCollection(&mut collection, false) }
+        .map_err(api_error("IDWriteFactory::GetSystemFontCollection"))?;
+    let collection = collection.ok_or_else(|| FontExtractorError::WinApi {
+        api_name: "IDWriteFactory::GetSystemFontCollection".to_string(),
+        source: WinError::empty(),
+    })?;
+    Ok((factory, collection))
+}
+
+/// DirectWrite のフォントフェイスのテーブルを `f` に渡します (テーブルが無ければ `None`)。
+fn with_table<T>(
+    face: &IDWriteFontFace,
+    tag: &Tag,
+    f: impl FnOnce(Option<&[u8]>) -> T,
+) -> Result<T, FontExtractorError> {
+    // DirectWrite のタグは先頭の文字が最下位バイト (DWRITE_MAKE_OPENTYPE_TAG)
+    let dwrite_tag = u32::from_le_bytes(*tag);
+    let mut data: *mut c_void = std::ptr::null_mut();
+    let mut size = 0;
+    let mut context: *mut c_void = std::ptr::null_mut();
+    let mut exists = BOOL(0);
+    unsafe { face.TryGetFontTable(dwrite_tag, &mut data, &mut size, &mut context, &mut exists) }
+        .map_err(api_error("IDWriteFontFace::TryGetFontTable"))?;
+    if !exists.as_bool() {
+        return Ok(f(None));
+    }
+    let table = unsafe { std::slice::from_raw_parts(data as *const u8, size as usize) };
+    let result = f(Some(table));
+    unsafe { face.ReleaseFontTable(context) };
+    Ok(result)
+}
+
+/// head の checkSumAdjustment を 0 にしたテーブル (TTC のフェイスを単体のフォントに組み立て直すと値が変わるため)
+fn without_adjustment(tag: &Tag, table: &[u8]) -> Vec<u8> {
+    let mut table = table.to_vec();
+    if tag == b"head" && table.len() >= 12 {
+        table[8..12].fill(0);
+    }
+    table
+}
+
+/// フォントのフルネーム (英語 → 最初の言語の順)
+fn full_name(font: &IDWriteFont) -> Option<String> {
+    let strings = informational_strings(font, DWRITE_INFORMATIONAL_STRING_FULL_NAME)?;
+    english_or_first(&strings)
+}
+
+/// フォントのフェイス名 ("Bold Italic" など、英語 → 最初の言語の順)
+fn face_names(font: &IDWriteFont) -> Option<String> {
+    let strings = unsafe { font.GetFaceNames() }.ok()?;
+    english_or_first(&strings)
+}
+
+/// フォントの情報文字列 (フォントに無ければ `None`)
+fn informational_strings(
+    font: &IDWriteFont,
+    id: DWRITE_INFORMATIONAL_STRING_ID,
+) -> Option<IDWriteLocalizedStrings> {
+    let mut strings: Option<IDWriteLocalizedStrings> = None;
+    let mut exists = BOOL(0);
+    unsafe { font.GetInformationalStrings(id, &mut strings, &mut exists) }.ok()?;
+    strings.filter(|_| exists.as_bool())
+}
+
+/// 英語の文字列 (無ければ最初の言語の文字列)
+fn english_or_first(strings: &IDWriteLocalizedStrings) -> Option<String> {
+    let mut index = 0;
+    let mut exists = BOOL(0);
+    unsafe { strings.FindLocaleName(w!("en-us"), &mut index, &mut exists) }.ok()?;
+    if !exists.as_bool() {
+        index = 0;
+    }
+    localized_string(strings, index)
+}
+
+/// `index` 番目の言語の文字列
+fn localized_string(strings: &IDWriteLocalizedStrings, index: u32) -> Option<String> {
+    let len = unsafe { strings.GetStringLength(index) }.ok()?;
+    let mut buffer = vec![0u16; len as usize + 1];
+    unsafe { strings.GetString(index, &mut buffer) }.ok()?;
+    Some(String::from_utf16_lossy(&buffer[..len as usize]))
+}
+
+fn api_error(api_name: &'static str) -> impl Fn(WinError) -> FontExtractorError {
+    move |e| FontExtractorError::WinApi {
+        api_name: api_name.to_string(),
+        source: e,
+    }
+}
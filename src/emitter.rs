@@ -0,0 +1,330 @@
+//! `--emit` で書き出すフォント以外のファイル (エミッター)
+//!
+//! エミッターは書き出したフォントのデータと名前を受け取り、同じ書き込み先に置くファイル (成果物) を返します。
+//! 組み込みのエミッター (文字の見本、`@font-face` CSS、preload、ファミリー CSS) も同じ [`Emitter`] を実装し、
+//! [`Registry`] に名前で登録されています。
+//!
+//! ライブラリの利用者は独自の [`Emitter`] を [`Registry::register`] で登録できます。
+//! `plugins` フィーチャーが有効な場合は、外部のプログラムを実行するエミッター ([`External`]) を
+//! `--emitter NAME=PROGRAM` で実行時に登録でき、CLI をフォークせずに独自の形式を追加できます。
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::{FontExtractorError, output, sample, webfont};
+
+/// エミッターに渡す、書き出したフォント
+#[derive(Debug, Clone, Copy)]
+pub struct EmitInput<'a> {
+    /// フォントのデータ (変換後に書き込んだもの)
+    pub data: &'a [u8],
+    /// フォントを書き込んだファイル名 ("Arial.ttf" など)
+    pub file_name: &'a str,
+    /// name テーブルのファミリー名 (読み取れなければ指定されたフォント名)
+    pub family: &'a str,
+    /// 指定されたフォント名 (`--all-styles` などではフェイスのフルネーム)
+    pub font_name: &'a str,
+}
+
+/// エミッターが書き出すファイル
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub file_name: String,
+    /// 表示用の説明 ("@font-face CSS" など)
+    pub label: String,
+    pub contents: Vec<u8>,
+}
+
+impl Artifact {
+    /// フォントのファイル名の拡張子を `extension` に置き換えた名前の成果物を作ります ("Arial.ttf" → "Arial.css")。
+    pub fn beside(
+        font_file: &str,
+        extension: &str,
+        label: impl Into<String>,
+        contents: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            file_name: PathBuf::from(font_file)
+                .with_extension(extension)
+                .to_string_lossy()
+                .into_owned(),
+            label: label.into(),
+            contents: contents.into(),
+        }
+    }
+}
+
+/// 追加のファイルを書き出すエミッターの共通インターフェース
+pub trait Emitter {
+    /// 書き出したフォントごとに呼ばれ、そのフォントの成果物を返します。
+    fn emit(&mut self, font: &EmitInput<'_>) -> Result<Vec<Artifact>, FontExtractorError>;
+
+    /// すべてのフォントを書き出した後に一度だけ呼ばれ、複数のフォントにまたがる成果物を返します。
+    fn finish(&mut self) -> Result<Vec<Artifact>, FontExtractorError> {
+        Ok(Vec::new())
+    }
+}
+
+/// 組み込みのエミッターの設定
+#[derive(Debug, Clone)]
+pub struct EmitOptions {
+    /// `@font-face` 規則の `font-display`
+    pub font_display: webfont::FontDisplay,
+    /// ファミリー CSS のカスタムプロパティの代替フォント (`None` ならフォントに合わせて選ぶ)
+    pub css_fallback: Option<String>,
+}
+
+/// 名前で選べるエミッターの一覧
+pub struct Registry {
+    emitters: BTreeMap<String, (String, Box<dyn Emitter>)>,
+}
+
+impl Registry {
+    /// 組み込みのエミッターを登録した一覧を作ります。
+    pub fn with_builtins(options: &EmitOptions) -> Self {
+        let mut registry = Self {
+            emitters: BTreeMap::new(),
+        };
+        registry.register(
+            "charset-sample",
+            "HTML page showing every character in the font's cmap, grouped by Unicode block",
+            Box::new(CharsetSample),
+        );
+        registry.register(
+            "font-face-css",
+            "CSS file with an @font-face rule (family, weight, style, font-display) loading the font file",
+            Box::new(FontFaceCss {
+                display: options.font_display,
+            }),
+        );
+        registry.register(
+            "preload",
+            "HTML snippet with a <link rel=\"preload\"> element for the font file",
+            Box::new(Preload),
+        );
+        registry.register(
+            "family-css",
+            "One CSS file per family with the @font-face rules of all its faces written in the run, a --font-<family> custom property with a fallback stack, and per-weight utility classes",
+            Box::new(FamilyCss {
+                display: options.font_display,
+                fallback: options.css_fallback.clone(),
+                families: BTreeMap::new(),
+            }),
+        );
+        registry
+    }
+
+    /// `name` でエミッターを登録します。同じ名前のエミッター (組み込みのものを含む) は置き換えます。
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        emitter: Box<dyn Emitter>,
+    ) {
+        self.emitters
+            .insert(name.into(), (description.into(), emitter));
+    }
+
+    /// 登録されたエミッターの名前と説明 (名前順)
+    pub fn describe(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.emitters
+            .iter()
+            .map(|(name, (description, _))| (name.as_str(), description.as_str()))
+    }
+
+    /// `names` のエミッターを指定の順に取り出します。重複した名前は 1 つにまとめます。
+    ///
+    /// 登録されていない名前があれば、登録されている名前を示すエラーを返します。
+    pub fn take(mut self, names: &[String]) -> Result<Vec<Box<dyn Emitter>>, FontExtractorError> {
+        let known = self.emitters.keys().cloned().collect::<Vec<_>>().join(", ");
+        let mut selected = Vec::new();
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                continue;
+            }
+            let Some((_, emitter)) = self.emitters.remove(name) else {
+                return Err(FontExtractorError::UnknownEmitter {
+                    name: name.clone(),
+                    known,
+                });
+            };
+            selected.push(emitter);
+        }
+        Ok(selected)
+    }
+}
+
+/// `charset-sample`: フォントがカバーする文字の見本の HTML
+struct CharsetSample;
+
+impl Emitter for CharsetSample {
+    fn emit(&mut self, font: &EmitInput<'_>) -> Result<Vec<Artifact>, FontExtractorError> {
+        Ok(vec![Artifact::beside(
+            font.file_name,
+            "charset.html",
+            "Charset sample",
+            sample::charset_html(font.data, font.file_name, font.family)?,
+        )])
+    }
+}
+
+/// `font-face-css`: フォントを読み込む `@font-face` 規則
+struct FontFaceCss {
+    display: webfont::FontDisplay,
+}
+
+impl Emitter for FontFaceCss {
+    fn emit(&mut self, font: &EmitInput<'_>) -> Result<Vec<Artifact>, FontExtractorError> {
+        Ok(vec![Artifact::beside(
+            font.file_name,
+            "css",
+            "@font-face CSS",
+            webfont::font_face_css(font.data, font.file_name, font.family, self.display),
+        )])
+    }
+}
+
+/// `preload`: `<link rel="preload">` の HTML 断片
+struct Preload;
+
+impl Emitter for Preload {
+    fn emit(&mut self, font: &EmitInput<'_>) -> Result<Vec<Artifact>, FontExtractorError> {
+        Ok(vec![Artifact::beside(
+            font.file_name,
+            "preload.html",
+            "Preload snippet",
+            webfont::preload_html(font.data, font.file_name),
+        )])
+    }
+}
+
+/// `family-css`: ファミリー単位の CSS (`<ファミリー名>.family.css`)。フェイスを集め、最後にまとめて書き出す
+struct FamilyCss {
+    display: webfont::FontDisplay,
+    fallback: Option<String>,
+    /// ファミリーごとのフォントに合わせた代替フォントとフェイス
+    families: BTreeMap<String, (&'static str, Vec<webfont::FamilyFace>)>,
+}
+
+impl Emitter for FamilyCss {
+    fn emit(&mut self, font: &EmitInput<'_>) -> Result<Vec<Artifact>, FontExtractorError> {
+        let (weight, italic) = webfont::face_style(font.data);
+        let font_face =
+            webfont::font_face_css(font.data, font.file_name, font.family, self.display);
+        self.families
+            .entry(font.family.to_string())
+            .or_insert_with(|| (webfont::default_fallback(font.data), Vec::new()))
+            .1
+            .push(webfont::FamilyFace {
+                weight,
+                italic,
+                font_face,
+            });
+        Ok(Vec::new())
+    }
+
+    fn finish(&mut self) -> Result<Vec<Artifact>, FontExtractorError> {
+        Ok(self
+            .families
+            .iter()
+            .map(|(family, (fallback, faces))| Artifact {
+                file_name: format!("{}.family.css", output::file_name_safe(family)),
+                label: format!("Family CSS for '{family}' ({} face(s))", faces.len()),
+                contents: webfont::family_css(
+                    family,
+                    faces,
+                    self.fallback.as_deref().unwrap_or(fallback),
+                )
+                .into_bytes(),
+            })
+            .collect())
+    }
+}
+
+/// 外部のプログラムを実行するエミッター (`--emitter NAME=PROGRAM`)
+///
+/// フォントのデータを標準入力に渡し、標準出力に書かれた内容を `<フォントのファイル名の拡張子を NAME にした名前>` で書き出します。
+/// 名前は環境変数 `FONT_EXPORT_FILE_NAME`、`FONT_EXPORT_FAMILY`、`FONT_EXPORT_FONT_NAME` で渡します。
+/// 何も出力しなければ書き出さず、0 以外の終了コードはエラーにします。標準エラー出力はそのまま表示します。
+#[cfg(feature = "plugins")]
+#[derive(Debug, Clone)]
+pub struct External {
+    pub name: String,
+    pub program: PathBuf,
+}
+
+#[cfg(feature = "plugins")]
+impl External {
+    /// `--emitter` の値 (`NAME=PROGRAM`) を解析します。
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let Some((name, program)) = value.split_once('=') else {
+            return Err("expected NAME=PROGRAM, e.g. bundle=C:\\tools\\bundle.exe".to_string());
+        };
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid_name {
+            return Err(format!(
+                "emitter name '{name}' must consist of ASCII letters, digits, '-', '_' and '.' (it becomes the file extension)"
+            ));
+        }
+        if program.is_empty() {
+            return Err("the program is empty".to_string());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            program: PathBuf::from(program),
+        })
+    }
+}
+
+#[cfg(feature = "plugins")]
+impl Emitter for External {
+    fn emit(&mut self, font: &EmitInput<'_>) -> Result<Vec<Artifact>, FontExtractorError> {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        let failed = |reason: String| FontExtractorError::EmitterFailed {
+            name: self.name.clone(),
+            reason,
+        };
+        let mut child = Command::new(&self.program)
+            .env("FONT_EXPORT_FILE_NAME", font.file_name)
+            .env("FONT_EXPORT_FAMILY", font.family)
+            .env("FONT_EXPORT_FONT_NAME", font.font_name)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| failed(format!("could not start '{}': {e}", self.program.display())))?;
+        // 標準出力を読みながら書き込まないと、パイプが一杯になったときに互いに待ち続ける
+        let mut stdin = child.stdin.take();
+        let output = std::thread::scope(|scope| {
+            let writer = scope.spawn(move || match stdin.as_mut() {
+                Some(stdin) => stdin.write_all(font.data),
+                None => Ok(()),
+            });
+            let output = child.wait_with_output();
+            // 入力を読まずに終了するプログラムもあるため、書き込みの失敗 (パイプの切断) は無視する
+            let _ = writer.join();
+            output
+        })
+        .map_err(|e| failed(e.to_string()))?;
+        if !output.status.success() {
+            return Err(failed(format!(
+                "'{}' exited with {}",
+                self.program.display(),
+                output.status
+            )));
+        }
+        if output.stdout.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(vec![Artifact::beside(
+            font.file_name,
+            &self.name,
+            format!("'{}' output", self.name),
+            output.stdout,
+        )])
+    }
+}
@@ -0,0 +1,240 @@
+//! `ensure` サブコマンド: 望ましい状態 (インストールされているべきフォントと最低バージョン) を記したスペックと
+//! このマシンを比べ、足りないフォントを配布元 (アーカイブかディレクトリ) からインストールし、食い違いを報告します。
+//!
+//! スペックの形式 (TOML):
+//!
+//! ```toml
+//! source = "\\\\fileserver\\fonts\\approved.zip"
+//!
+//! [[font]]
+//! name = "Noto Sans JP"
+//! min_version = "2.004"
+//!
+//! [[font]]
+//! name = "Source Code Pro"
+//! files = ["SourceCodePro-Regular.otf", "SourceCodePro-Bold.otf"]
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use font_export::sfnt::{Sfnt, name};
+use font_export::{FontExtractorError, FontRequest, fontreg, verify};
+
+use crate::verify_archive::{self, Entry};
+
+/// --- `ensure` サブコマンドの引数 ---
+#[derive(clap::Args, Debug)]
+pub struct EnsureArgs {
+    /// TOML file listing the fonts that must be installed (name, optional min_version and files) and the source archive or directory to install missing ones from
+    #[arg(long, value_name = "FILE")]
+    spec: PathBuf,
+
+    /// Archive (.zip / .tar) or directory to install missing fonts from, instead of the spec's source
+    #[arg(long, value_name = "PATH")]
+    source: Option<PathBuf>,
+
+    /// Only report drift; do not install anything
+    #[arg(long)]
+    check: bool,
+}
+
+/// スペック全体
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Spec {
+    /// 足りないフォントのインストール元 (スペックのファイルからの相対パスも可)
+    source: Option<PathBuf>,
+    #[serde(default, rename = "font")]
+    fonts: Vec<SpecFont>,
+}
+
+/// スペックの 1 フォント
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct SpecFont {
+    /// ファミリー名 (`--font-name` と同じ名前)
+    name: String,
+    /// 最低バージョン (name ID 5 と比べる)
+    min_version: Option<String>,
+    /// インストール元から使うファイル名 (省略時はファミリー名が一致するすべてのファイル)
+    #[serde(default)]
+    files: Vec<String>,
+}
+
+/// インストールされているフォントの状態
+enum State {
+    /// 最低バージョンを満たしている (バージョン)
+    Current(String),
+    /// インストールされていない (GDI が代替フォントを返した)
+    Missing,
+    /// 最低バージョンより古い (インストールされているバージョン)
+    Outdated(String),
+}
+
+pub fn run(args: &EnsureArgs) -> Result<(), FontExtractorError> {
+    let spec = load_spec(&args.spec)?;
+    // インストール元は必要になったときに一度だけ読む
+    let source = args.source.clone().or_else(|| {
+        spec.source
+            .as_ref()
+            .map(|source| args.spec.parent().unwrap_or(Path::new("")).join(source))
+    });
+    let mut entries: Option<Vec<Entry>> = None;
+
+    let mut drifted = 0;
+    for font in &spec.fonts {
+        let required = font.min_version.as_deref();
+        match state(font)? {
+            State::Current(version) => println!("OK         {} ({version})", font.name),
+            State::Outdated(version) => {
+                // 既にあるフォントはユーザーごとのインストールで置き換えられないため、報告だけにする
+                drifted += 1;
+                println!(
+                    "OUTDATED   {}: {version} installed, {} required",
+                    font.name,
+                    required.unwrap_or_default()
+                );
+            }
+            State::Missing => {
+                let Some(source) = &source else {
+                    drifted += 1;
+                    println!("MISSING    {} (the spec has no source)", font.name);
+                    continue;
+                };
+                if entries.is_none() {
+                    entries = Some(verify_archive::read_entries(source)?);
+                }
+                let files = match candidates(font, entries.as_deref().unwrap_or_default()) {
+                    Ok(files) => files,
+                    Err(reason) => {
+                        drifted += 1;
+                        println!("MISSING    {}: {reason}", font.name);
+                        continue;
+                    }
+                };
+                let names: Vec<&str> = files.iter().map(|(name, _)| *name).collect();
+                if args.check {
+                    drifted += 1;
+                    println!(
+                        "MISSING    {} (would install {})",
+                        font.name,
+                        names.join(", ")
+                    );
+                    continue;
+                }
+                for (file_name, data) in &files {
+                    fontreg::install_for_user(file_name, data)?;
+                }
+                // 読み込んだフォントを GDI が実際に選ぶかを確かめる
+                match state(font)? {
+                    State::Current(version) => println!(
+                        "INSTALLED  {} ({version}) from {}",
+                        font.name,
+                        names.join(", ")
+                    ),
+                    _ => {
+                        drifted += 1;
+                        println!(
+                            "FAILED     {}: installed {}, but GDI still does not select it",
+                            font.name,
+                            names.join(", ")
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    println!();
+    let total = spec.fonts.len();
+    if drifted > 0 {
+        return Err(FontExtractorError::FontsDrifted { drifted, total });
+    }
+    println!("All {total} font(s) match the spec.");
+    Ok(())
+}
+
+fn load_spec(path: &Path) -> Result<Spec, FontExtractorError> {
+    let text = fs::read_to_string(path).map_err(|e| FontExtractorError::FileRead {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    toml::from_str(&text).map_err(|e| FontExtractorError::SpecParse {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// GDI が `font` の名前で選ぶフォントの name テーブルから、インストールされているかとバージョンを調べます。
+fn state(font: &SpecFont) -> Result<State, FontExtractorError> {
+    let tables = font_export::extract_font_tables(&FontRequest::named(&font.name), &[*b"name"])?;
+    let records = name::parse(tables.table(b"name").unwrap_or_default())?;
+    if !name::has_family(&records, &font.name) {
+        return Ok(State::Missing);
+    }
+    let version = name::find(&records, 5).unwrap_or_default();
+    match &font.min_version {
+        Some(minimum) if !verify::version_at_least(&version, minimum) => {
+            Ok(State::Outdated(version))
+        }
+        _ => Ok(State::Current(version)),
+    }
+}
+
+/// インストール元から `font` のファイルを選びます。
+///
+/// `files` があればそのファイル名 (ディレクトリを除いた部分) のもの、無ければファミリー名が一致するすべてのファイルで、
+/// どれも最低バージョンを満たしている必要があります。使えるファイルが無ければ理由を返します。
+fn candidates<'e>(
+    font: &SpecFont,
+    entries: &'e [Entry],
+) -> Result<Vec<(&'e str, &'e [u8])>, String> {
+    let mut files = Vec::new();
+    for (path, data) in entries {
+        let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+        let wanted = if font.files.is_empty() {
+            is_family(data.as_deref().unwrap_or_default(), &font.name)
+        } else {
+            font.files
+                .iter()
+                .any(|wanted| wanted.eq_ignore_ascii_case(file_name))
+        };
+        if !wanted {
+            continue;
+        }
+        let data = data
+            .as_deref()
+            .map_err(|reason| format!("'{path}' in the source is unreadable: {reason}"))?;
+        if let Some(minimum) = &font.min_version {
+            let (_, version) = verify::identify(data).map_err(|e| format!("'{path}': {e}"))?;
+            if !verify::version_at_least(&version, minimum) {
+                return Err(format!(
+                    "the source only has {version} ('{path}'), {minimum} required"
+                ));
+            }
+        }
+        files.push((file_name, data));
+    }
+    if let Some(absent) = font.files.iter().find(|wanted| {
+        !files
+            .iter()
+            .any(|(file_name, _)| wanted.eq_ignore_ascii_case(file_name))
+    }) {
+        return Err(format!("'{absent}' is not in the source"));
+    }
+    if files.is_empty() {
+        return Err("no font file of this family is in the source".to_string());
+    }
+    Ok(files)
+}
+
+/// `data` のフォント (コレクションなら最初のフェイス) のファミリー名かフルネームが `family` かどうか
+fn is_family(data: &[u8], family: &str) -> bool {
+    Sfnt::parse_face(data, 0)
+        .ok()
+        .and_then(|font| name::parse(font.table(b"name")?).ok())
+        .is_some_and(|records| name::has_family(&records, family))
+}
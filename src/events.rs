@@ -0,0 +1,122 @@
+//! 機械可読なイベントの出力 (`--events ndjson`)
+//!
+//! イベント出力中は人間向けのメッセージを抑制し、標準出力には 1 行に 1 つの JSON オブジェクトだけを書き出します。
+//! フォントデータを標準出力に書き出すとき (`-o -`) は、メッセージもイベントも標準エラー出力に書き出します。
+//! 各行は `"schema"` (スキーマのバージョン) と `"event"` (イベントの種類) を持ちます。
+//!
+//! ```json
+//! {"schema":1,"event":"read","font":"Meiryo","bytes":9752544,"sha256":"3f1c..."}
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// イベントのスキーマのバージョン (フィールドの追加では変えず、互換性のない変更のときだけ上げる)
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// `--events` の出力形式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventFormat {
+    /// One JSON object per line on stdout
+    Ndjson,
+}
+
+/// 抽出の各段階で発生するイベント
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// GDI がフォント名を解決し、実際に選ばれたフォントのファミリー名が分かった
+    Resolved {
+        font: &'a str,
+        family: Option<String>,
+        /// インストール先 ("user" か "system"、レジストリで見つからなければ `None`)
+        scope: Option<&'static str>,
+    },
+    /// フォントデータを読み取った
+    Read {
+        font: &'a str,
+        bytes: usize,
+        sha256: String,
+        /// インストール済みフォントを読み取った方法 ("gdi"、"directwrite" か "registry"、ファイルや標準入力なら `None`)
+        backend: Option<&'static str>,
+    },
+    /// フォントデータを加工した (`step` は加工の種類)
+    Converted {
+        font: &'a str,
+        step: &'a str,
+        bytes: usize,
+    },
+    /// 書き込んだ
+    Written {
+        font: &'a str,
+        location: &'a str,
+        bytes: usize,
+    },
+    /// 既存のファイルがあるため書き込まなかった
+    Skipped { font: &'a str, file: &'a str },
+    /// 失敗した
+    Failed { font: &'a str, error: String },
+    /// 実行全体の段階ごとの所要時間 (`--timings`、実行の最後に 1 回)
+    Timings {
+        total_ms: u128,
+        stages_ms: BTreeMap<&'a str, u128>,
+        fonts: usize,
+        bytes_read: u64,
+        bytes_written: u64,
+    },
+}
+
+/// 人間向けのメッセージとイベントの出力先
+pub struct Reporter {
+    format: Option<EventFormat>,
+    /// フォントデータを標準出力に書き出すため、メッセージとイベントを標準エラー出力に回す
+    to_stderr: bool,
+}
+
+impl Reporter {
+    pub fn new(format: Option<EventFormat>, to_stderr: bool) -> Self {
+        Self { format, to_stderr }
+    }
+
+    /// 人間向けのメッセージを表示します (イベント出力中は表示しません)。
+    pub fn say(&self, message: impl Display) {
+        #[cfg(feature = "archive")]
+        crate::diagnostics::record_message(&message);
+        if self.format.is_none() {
+            self.print(message);
+        }
+    }
+
+    fn print(&self, line: impl Display) {
+        if self.to_stderr {
+            eprintln!("{line}");
+        } else {
+            println!("{line}");
+        }
+    }
+
+    /// イベントを出力します (`--events` が指定されていなければ何もしません)。
+    pub fn emit(&self, event: Event<'_>) {
+        #[derive(Serialize)]
+        struct Line<'a> {
+            schema: u32,
+            #[serde(flatten)]
+            event: Event<'a>,
+        }
+
+        let line = Line {
+            schema: SCHEMA_VERSION,
+            event,
+        };
+        let json = serde_json::to_string(&line).expect("events are always serializable");
+        // --diagnostics の診断情報には --events の指定にかかわらずイベントを記録する
+        #[cfg(feature = "archive")]
+        crate::diagnostics::record_event(&json);
+        if self.format == Some(EventFormat::Ndjson) {
+            self.print(json);
+        }
+    }
+}
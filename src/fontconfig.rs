@@ -0,0 +1,473 @@
+//! fontconfig によるインストール済みフォントの検索 (Windows 以外)
+//!
+//! Windows では GDI のフォントマッパーが名前をフォントに解決してデータを返しますが、Linux などでは
+//! fontconfig が同じ役割を持ちます。`FcFontMatch` で名前 (と太さ・斜体・ピクセルサイズ) をフォントファイルと
+//! フェイスの番号に解決し、そのファイルを読み取ります。GDI と同じく、名前が無ければ代替フォントが返ります。
+//!
+//! ファミリーとフェイスの列挙、レジストリの Fonts キーの代わりのフォントファイルの一覧 ([`fontreg::registered_fonts`])、
+//! PostScript 名の検索は `FcFontList` で行います。TrueType / OpenType (CFF) 以外の形式 (PCF、Type 1 など) は対象外です。
+//!
+//! `fontconfig` フィーチャーで有効になり、[`Fontconfig`] として [`FontSource`] を実装します。
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::fontreg::{self, RegisteredFont};
+use crate::source::FontSource;
+use crate::{
+    Backend, FontExtractorError, FontFace, FontRequest, FontTables, ReadTimes, TableDirectory,
+    fontfile, sfnt,
+};
+
+/// `FcBool` / `FcResult` などの列挙値
+type FcBool = c_int;
+type FcResult = c_int;
+const FC_RESULT_MATCH: FcResult = 0;
+const FC_MATCH_PATTERN: c_int = 0;
+/// FC_SLANT_ITALIC (立体は 0、斜体は 100、斜めにしたものは 110)
+const FC_SLANT_ITALIC: c_int = 100;
+
+// パターンのプロパティ名
+const FC_FAMILY: &CStr = c"family";
+const FC_STYLE: &CStr = c"style";
+const FC_FULLNAME: &CStr = c"fullname";
+const FC_POSTSCRIPT_NAME: &CStr = c"postscriptname";
+const FC_WEIGHT: &CStr = c"weight";
+const FC_SLANT: &CStr = c"slant";
+const FC_PIXEL_SIZE: &CStr = c"pixelsize";
+const FC_FILE: &CStr = c"file";
+const FC_INDEX: &CStr = c"index";
+const FC_FONTFORMAT: &CStr = c"fontformat";
+
+/// 対象にする FC_FONTFORMAT (GDI の TRUETYPE_FONTTYPE と同じく、TrueType と OpenType のアウトライン)
+const SFNT_FORMATS: &[&str] = &["TrueType", "CFF"];
+
+#[repr(C)]
+struct FcFontSet {
+    nfont: c_int,
+    sfont: c_int,
+    fonts: *mut *mut c_void,
+}
+
+#[link(name = "fontconfig")]
+unsafe extern "C" {
+    fn FcInit() -> FcBool;
+    fn FcPatternCreate() -> *mut c_void;
+    fn FcPatternDestroy(pattern: *mut c_void);
+    fn FcPatternAddString(pattern: *mut c_void, object: *const c_char, s: *const u8) -> FcBool;
+    fn FcPatternAddInteger(pattern: *mut c_void, object: *const c_char, i: c_int) -> FcBool;
+    fn FcPatternAddDouble(pattern: *mut c_void, object: *const c_char, d: f64) -> FcBool;
+    fn FcPatternGetString(
+        pattern: *const c_void,
+        object: *const c_char,
+        n: c_int,
+        s: *mut *const u8,
+    ) -> FcResult;
+    fn FcPatternGetInteger(
+        pattern: *const c_void,
+        object: *const c_char,
+        n: c_int,
+        i: *mut c_int,
+    ) -> FcResult;
+    fn FcConfigSubstitute(config: *mut c_void, pattern: *mut c_void, kind: c_int) -> FcBool;
+    fn FcDefaultSubstitute(pattern: *mut c_void);
+    fn FcFontMatch(config: *mut c_void, pattern: *mut c_void, result: *mut FcResult)
+    -> *mut c_void;
+    fn FcObjectSetCreate() -> *mut c_void;
+    fn FcObjectSetAdd(set: *mut c_void, object: *const c_char) -> FcBool;
+    fn FcObjectSetDestroy(set: *mut c_void);
+    fn FcFontList(config: *mut c_void, pattern: *mut c_void, set: *mut c_void) -> *mut FcFontSet;
+    fn FcFontSetDestroy(set: *mut FcFontSet);
+    fn FcWeightFromOpenType(weight: c_int) -> c_int;
+    fn FcWeightToOpenType(weight: c_int) -> c_int;
+    fn FcGetVersion() -> c_int;
+}
+
+/// --- RAII ラッパー: FcPattern ---
+struct Pattern(*mut c_void);
+impl Pattern {
+    fn new() -> Result<Self, FontExtractorError> {
+        init()?;
+        let pattern = unsafe { FcPatternCreate() };
+        if pattern.is_null() {
+            return Err(api_error("FcPatternCreate", "out of memory"));
+        }
+        Ok(Self(pattern))
+    }
+
+    fn add_string(&mut self, object: &CStr, value: &str) -> Result<(), FontExtractorError> {
+        let value = CString::new(value).map_err(|_| FontExtractorError::InvalidFontName {
+            name: value.to_string(),
+            reason: "contains a NUL character".to_string(),
+        })?;
+        let added =
+            unsafe { FcPatternAddString(self.0, object.as_ptr(), value.as_ptr() as *const u8) };
+        check(added, "FcPatternAddString")
+    }
+
+    fn add_integer(&mut self, object: &CStr, value: c_int) -> Result<(), FontExtractorError> {
+        check(
+            unsafe { FcPatternAddInteger(self.0, object.as_ptr(), value) },
+            "FcPatternAddInteger",
+        )
+    }
+
+    fn add_double(&mut self, object: &CStr, value: f64) -> Result<(), FontExtractorError> {
+        check(
+            unsafe { FcPatternAddDouble(self.0, object.as_ptr(), value) },
+            "FcPatternAddDouble",
+        )
+    }
+}
+impl Drop for Pattern {
+    fn drop(&mut self) {
+        unsafe { FcPatternDestroy(self.0) };
+    }
+}
+
+/// --- RAII ラッパー: FcFontList の結果 ---
+struct FontSet(*mut FcFontSet);
+impl FontSet {
+    /// `pattern` に一致するフォントの `objects` のプロパティを列挙します。
+    fn list(pattern: &Pattern, objects: &[&CStr]) -> Result<Self, FontExtractorError> {
+        let object_set = unsafe { FcObjectSetCreate() };
+        if object_set.is_null() {
+            return Err(api_error("FcObjectSetCreate", "out of memory"));
+        }
+        for object in objects {
+            unsafe { FcObjectSetAdd(object_set, object.as_ptr()) };
+        }
+        let set = unsafe { FcFontList(std::ptr::null_mut(), pattern.0, object_set) };
+        unsafe { FcObjectSetDestroy(object_set) };
+        if set.is_null() {
+            return Err(api_error("FcFontList", "could not list the fonts"));
+        }
+        Ok(Self(set))
+    }
+
+    /// 列挙したフォントのパターン (解放はこの FontSet が行う)
+    fn fonts(&self) -> impl Iterator<Item = FontPattern<'_>> {
+        let set = unsafe { &*self.0 };
+        let fonts: &[*mut c_void] = if set.fonts.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(set.fonts, set.nfont.max(0) as usize) }
+        };
+        fonts.iter().map(|&pattern| FontPattern {
+            pattern,
+            _set: std::marker::PhantomData,
+        })
+    }
+}
+impl Drop for FontSet {
+    fn drop(&mut self) {
+        unsafe { FcFontSetDestroy(self.0) };
+    }
+}
+
+/// 読み取り専用のパターン (FcFontMatch の結果か FcFontList の要素)
+struct FontPattern<'a> {
+    pattern: *mut c_void,
+    _set: std::marker::PhantomData<&'a FontSet>,
+}
+
+impl FontPattern<'_> {
+    /// `object` の `n` 番目の文字列の値
+    fn string(&self, object: &CStr, n: usize) -> Option<String> {
+        let mut value: *const u8 = std::ptr::null();
+        let result =
+            unsafe { FcPatternGetString(self.pattern, object.as_ptr(), n as c_int, &mut value) };
+        (result == FC_RESULT_MATCH && !value.is_null()).then(|| {
+            unsafe { CStr::from_ptr(value as *const c_char) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    /// `object` のすべての文字列の値 (ファミリー名やフルネームは言語ごとに複数ある)
+    fn strings(&self, object: &CStr) -> Vec<String> {
+        (0..)
+            .map_while(|n| self.string(object, n))
+            .collect::<Vec<_>>()
+    }
+
+    fn integer(&self, object: &CStr) -> Option<c_int> {
+        let mut value = 0;
+        let result = unsafe { FcPatternGetInteger(self.pattern, object.as_ptr(), 0, &mut value) };
+        (result == FC_RESULT_MATCH).then_some(value)
+    }
+
+    /// TrueType / OpenType のフォントかどうか
+    fn is_sfnt(&self) -> bool {
+        self.string(FC_FONTFORMAT, 0)
+            .is_some_and(|format| SFNT_FORMATS.contains(&format.as_str()))
+    }
+
+    /// フォントファイルのパスと、コレクション内のフェイスの番号
+    /// (FC_INDEX の上位 16 ビットはバリアブルフォントの名前付きインスタンスの番号)
+    fn file(&self) -> Option<(PathBuf, u32)> {
+        let path = PathBuf::from(self.string(FC_FILE, 0)?);
+        let index = self.integer(FC_INDEX).unwrap_or(0) as u32 & 0xFFFF;
+        Some((path, index))
+    }
+
+    /// フェイス (太さは OpenType の usWeightClass と同じ 100〜900 に直す)
+    fn face(&self) -> FontFace {
+        let style = self.string(FC_STYLE, 0).unwrap_or_default();
+        let full_name = self.string(FC_FULLNAME, 0).unwrap_or_else(|| {
+            let family = self.string(FC_FAMILY, 0).unwrap_or_default();
+            format!("{family} {style}").trim().to_string()
+        });
+        FontFace {
+            weight: self
+                .integer(FC_WEIGHT)
+                .map_or(400, |weight| unsafe { FcWeightToOpenType(weight) }),
+            italic: self
+                .integer(FC_SLANT)
+                .is_some_and(|slant| slant >= FC_SLANT_ITALIC),
+            style,
+            full_name,
+        }
+    }
+}
+
+/// --- RAII ラッパー: FcFontMatch の結果 ---
+struct Matched(*mut c_void);
+impl Matched {
+    fn pattern(&self) -> FontPattern<'_> {
+        FontPattern {
+            pattern: self.0,
+            _set: std::marker::PhantomData,
+        }
+    }
+}
+impl Drop for Matched {
+    fn drop(&mut self) {
+        unsafe { FcPatternDestroy(self.0) };
+    }
+}
+
+/// fontconfig が `request` の名前・太さ・斜体・ピクセルサイズで選ぶフォントのファイルとフェイスの番号を返します。
+///
+/// GDI と同じく名前が無ければ代替フォントが選ばれます (代替かどうかは呼び出し側が name テーブルで確かめます)。
+/// TrueType / OpenType 以外のフォントが選ばれた場合はエラーにします。
+pub fn match_font(request: &FontRequest<'_>) -> Result<(PathBuf, u32), FontExtractorError> {
+    let mut pattern = Pattern::new()?;
+    pattern.add_string(FC_FAMILY, request.name)?;
+    let logfont = &request.logfont;
+    pattern.add_integer(FC_WEIGHT, unsafe { FcWeightFromOpenType(logfont.weight) })?;
+    if logfont.italic {
+        pattern.add_integer(FC_SLANT, FC_SLANT_ITALIC)?;
+    }
+    // 負の高さは文字の高さのピクセル数 (ビットマップのストライクの選択)
+    if logfont.height < 0 {
+        pattern.add_double(FC_PIXEL_SIZE, f64::from(-logfont.height))?;
+    }
+    unsafe {
+        FcConfigSubstitute(std::ptr::null_mut(), pattern.0, FC_MATCH_PATTERN);
+        FcDefaultSubstitute(pattern.0);
+    }
+    let mut result = FC_RESULT_MATCH;
+    let matched = unsafe { FcFontMatch(std::ptr::null_mut(), pattern.0, &mut result) };
+    if matched.is_null() {
+        return Err(api_error(
+            &format!("FcFontMatch (font: '{}')", request.name),
+            "no font matched",
+        ));
+    }
+    let matched = Matched(matched);
+    let font = matched.pattern();
+    let (path, index) = font.file().ok_or_else(|| {
+        api_error(
+            &format!("FcFontMatch (font: '{}')", request.name),
+            "the matched font has no file",
+        )
+    })?;
+    if !font.is_sfnt() {
+        return Err(api_error(
+            &format!("FcFontMatch (font: '{}')", request.name),
+            &format!(
+                "matched {}, which is not a TrueType or OpenType font",
+                path.display()
+            ),
+        ));
+    }
+    Ok((path, index))
+}
+
+/// TrueType / OpenType のファミリー名を、重複を除いて名前順に返します。
+///
+/// fontconfig はファミリー名を複数持つことがあり (タイポグラフィック ファミリー名と従来のファミリー名、
+/// ローカライズされた名前)、そのすべてを返します。
+pub fn families() -> Result<Vec<String>, FontExtractorError> {
+    let fonts = FontSet::list(&Pattern::new()?, &[FC_FAMILY, FC_FONTFORMAT])?;
+    let families: BTreeSet<String> = fonts
+        .fonts()
+        .filter(|font| font.is_sfnt())
+        .flat_map(|font| font.strings(FC_FAMILY))
+        .collect();
+    Ok(families.into_iter().collect())
+}
+
+/// `family` の TrueType / OpenType のフェイスを、太さ・斜体の順に重複を除いて返します。
+pub fn faces(family: &str) -> Result<Vec<FontFace>, FontExtractorError> {
+    let mut pattern = Pattern::new()?;
+    pattern.add_string(FC_FAMILY, family)?;
+    let fonts = FontSet::list(
+        &pattern,
+        &[
+            FC_FAMILY,
+            FC_STYLE,
+            FC_FULLNAME,
+            FC_WEIGHT,
+            FC_SLANT,
+            FC_FONTFORMAT,
+        ],
+    )?;
+    let faces: BTreeSet<FontFace> = fonts
+        .fonts()
+        .filter(|font| font.is_sfnt())
+        .map(|font| font.face())
+        .collect();
+    Ok(faces.into_iter().collect())
+}
+
+/// PostScript 名が `name` のフォントを探し、そのファミリー名とフェイスを返します。見つからなければ `None` です。
+pub fn find_postscript_name(name: &str) -> Result<Option<(String, FontFace)>, FontExtractorError> {
+    let mut pattern = Pattern::new()?;
+    pattern.add_string(FC_POSTSCRIPT_NAME, name)?;
+    let fonts = FontSet::list(
+        &pattern,
+        &[
+            FC_FAMILY,
+            FC_STYLE,
+            FC_FULLNAME,
+            FC_WEIGHT,
+            FC_SLANT,
+            FC_FONTFORMAT,
+        ],
+    )?;
+    Ok(fonts
+        .fonts()
+        .filter(|font| font.is_sfnt())
+        .find_map(|font| Some((font.string(FC_FAMILY, 0)?, font.face()))))
+}
+
+/// fontconfig が名前から選んだフォントファイル (コレクションならそのフェイス) を読み取るバックエンド
+pub struct Fontconfig;
+
+impl FontSource for Fontconfig {
+    fn backend(&self) -> Backend {
+        Backend::Fontconfig
+    }
+
+    /// コレクションのフェイスは GDI と同じく単体のフォントに組み立て直します。
+    fn read(&self, request: &FontRequest<'_>) -> Result<(Vec<u8>, ReadTimes), FontExtractorError> {
+        let started = Instant::now();
+        let (path, index) = match_font(request)?;
+        let resolution = started.elapsed();
+        let buffer = fontfile::read_face(&path, index)?;
+        let times = ReadTimes {
+            resolution,
+            read: started.elapsed() - resolution,
+        };
+        Ok((buffer, times))
+    }
+
+    fn collection(&self, request: &FontRequest<'_>) -> Result<Option<Vec<u8>>, FontExtractorError> {
+        crate::read_collection_file(&match_font(request)?.0)
+    }
+
+    fn tables(
+        &self,
+        request: &FontRequest<'_>,
+        tags: &[sfnt::Tag],
+    ) -> Result<FontTables, FontExtractorError> {
+        let (path, index) = match_font(request)?;
+        fontfile::face_tables(&fontfile::read(&path)?, index, tags)
+    }
+
+    fn directory(&self, request: &FontRequest<'_>) -> Result<TableDirectory, FontExtractorError> {
+        let (path, index) = match_font(request)?;
+        fontfile::face_directory(&fontfile::read(&path)?, index)
+    }
+
+    fn families(&self) -> Result<Vec<String>, FontExtractorError> {
+        families()
+    }
+
+    fn faces(&self, family: &str) -> Result<Vec<FontFace>, FontExtractorError> {
+        faces(family)
+    }
+
+    fn find_postscript_name(
+        &self,
+        name: &str,
+    ) -> Option<Result<Option<(String, FontFace)>, FontExtractorError>> {
+        Some(find_postscript_name(name))
+    }
+}
+
+/// fontconfig が知っている TrueType / OpenType のフォントファイルと、含まれるフェイスのフルネームを返します。
+///
+/// ホームディレクトリの下 (~/.local/share/fonts、~/.fonts など) のファイルはこのユーザー向け (`User`)、
+/// それ以外 (/usr/share/fonts など) はすべてのユーザー向け (`System`) とします。
+pub fn installed_fonts() -> Result<Vec<RegisteredFont>, FontExtractorError> {
+    let fonts = FontSet::list(
+        &Pattern::new()?,
+        &[FC_FILE, FC_INDEX, FC_FULLNAME, FC_FONTFORMAT],
+    )?;
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut files: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    for font in fonts.fonts().filter(|font| font.is_sfnt()) {
+        let Some((path, _)) = font.file() else {
+            continue;
+        };
+        let names = files.entry(path).or_default();
+        for name in font.strings(FC_FULLNAME) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    Ok(files
+        .into_iter()
+        .map(|(path, names)| RegisteredFont {
+            scope: fontreg::scope_of(&path, home.as_deref()),
+            names,
+            path,
+        })
+        .collect())
+}
+
+/// 読み込まれている fontconfig のバージョン ("2.14.1" など)
+pub fn version() -> String {
+    let version = unsafe { FcGetVersion() };
+    format!(
+        "{}.{}.{}",
+        version / 10000,
+        version / 100 % 100,
+        version % 100
+    )
+}
+
+/// fontconfig の設定とフォントの一覧を読み込みます (2 回目以降は何もしない)。
+fn init() -> Result<(), FontExtractorError> {
+    check(unsafe { FcInit() }, "FcInit")
+}
+
+fn check(result: FcBool, api_name: &str) -> Result<(), FontExtractorError> {
+    if result == 0 {
+        return Err(api_error(api_name, "the call failed"));
+    }
+    Ok(())
+}
+
+fn api_error(api_name: &str, reason: &str) -> FontExtractorError {
+    FontExtractorError::Fontconfig {
+        api_name: api_name.to_string(),
+        reason: reason.to_string(),
+    }
+}
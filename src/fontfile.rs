@@ -0,0 +1,147 @@
+//! インストールされていないフォントファイルの読み取り
+//!
+//! フォント名の代わりにフォントファイルのパスが指定された場合、GDI を経由せずにファイルから
+//! GDI 経由と同じ形 ([`FontTables`] / [`TableDirectory`] / フォントデータ全体) で読み取ります。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+
+use crate::sfnt::{self, Sfnt, Tag};
+use crate::{FontExtractorError, FontTables, TableDirectory};
+
+/// パスとして扱う拡張子 (区切り文字を含まない名前でも、この拡張子で終わる既存のファイルならパスとみなす)
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "otc"];
+
+/// `name` がフォントファイルのパスを指していればそのパスを返します。
+///
+/// フォントのファミリー名と区別するため、パス区切り文字を含むかフォントの拡張子で終わり、
+/// かつファイルが存在する場合だけパスとみなします (カレントディレクトリの "Arial" という名前のファイルは対象外)。
+pub fn as_path(name: &str) -> Option<&Path> {
+    let path = Path::new(name);
+    let looks_like_path = name.contains(['/', '\\'])
+        || path.extension().is_some_and(|e| {
+            FONT_EXTENSIONS.contains(&e.to_string_lossy().to_ascii_lowercase().as_str())
+        });
+    (looks_like_path && path.is_file()).then_some(path)
+}
+
+/// フォントファイル全体を読み取ります。
+pub fn read(path: &Path) -> Result<Vec<u8>, FontExtractorError> {
+    let data = fs::read(path).map_err(|e| FontExtractorError::FileRead {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    if data.is_empty() {
+        return Err(FontExtractorError::ZeroSizeFont {
+            font_name: path.display().to_string(),
+        });
+    }
+    Ok(data)
+}
+
+/// フォントファイルを読み取り、コレクションなら `index` 番目のフェイスを単体のフォントに組み立て直します。
+pub fn read_face(path: &Path, index: u32) -> Result<Vec<u8>, FontExtractorError> {
+    let data = read(path)?;
+    if data.get(..4) != Some(b"ttcf") {
+        return Ok(data);
+    }
+    sfnt::split_collection(&data)?
+        .into_iter()
+        .nth(index as usize)
+        .ok_or_else(|| sfnt::corrupt(format!("{} has no face #{index}", path.display())))
+}
+
+/// エラーメッセージで標準入力を指す名前
+const STDIN_NAME: &str = "<stdin>";
+
+/// 標準入力に渡されたフォントデータ全体を読み取ります。
+///
+/// 標準入力がコンソールのまま (パイプやリダイレクトが無い) なら、入力を待たずにエラーにします。
+pub fn read_stdin() -> Result<Vec<u8>, FontExtractorError> {
+    let mut stdin = std::io::stdin().lock();
+    if stdin.is_terminal() {
+        return Err(FontExtractorError::FileRead {
+            path: STDIN_NAME.to_string(),
+            source: std::io::Error::other(
+                "no data is piped in; use e.g. `type font.ttf | font-export ...`",
+            ),
+        });
+    }
+    let mut data = Vec::new();
+    stdin
+        .read_to_end(&mut data)
+        .map_err(|e| FontExtractorError::FileRead {
+            path: STDIN_NAME.to_string(),
+            source: e,
+        })?;
+    if data.is_empty() {
+        return Err(FontExtractorError::ZeroSizeFont {
+            font_name: STDIN_NAME.to_string(),
+        });
+    }
+    Ok(data)
+}
+
+/// フォントファイル (コレクションの場合は最初のフェイス) から `tags` のテーブルを取り出します。
+pub fn tables(path: &Path, tags: &[Tag]) -> Result<FontTables, FontExtractorError> {
+    tables_from_data(&read(path)?, tags)
+}
+
+/// フォントデータ (コレクションの場合は最初のフェイス) から `tags` のテーブルを取り出します。
+pub fn tables_from_data(data: &[u8], tags: &[Tag]) -> Result<FontTables, FontExtractorError> {
+    face_tables(data, 0, tags)
+}
+
+/// フォントデータ (コレクションの場合は `index` 番目のフェイス) から `tags` のテーブルを取り出します。
+pub fn face_tables(
+    data: &[u8],
+    index: u32,
+    tags: &[Tag],
+) -> Result<FontTables, FontExtractorError> {
+    let font = Sfnt::parse_face(data, index)?;
+    let tables: BTreeMap<Tag, Vec<u8>> = tags
+        .iter()
+        .filter_map(|tag| font.table(tag).map(|table| (*tag, table.to_vec())))
+        .collect();
+    Ok(FontTables {
+        total_size: data.len() as u32,
+        num_tables: (data.get(..4) != Some(b"ttcf")).then_some(font.tables.len() as u16),
+        tables,
+    })
+}
+
+/// フォントファイル (コレクションの場合は最初のフェイス) のテーブルディレクトリを読み取ります。
+pub fn table_directory(path: &Path) -> Result<TableDirectory, FontExtractorError> {
+    face_directory(&read(path)?, 0)
+}
+
+/// フォントデータ (コレクションの場合は `index` 番目のフェイス) のテーブルディレクトリを読み取ります。
+pub fn face_directory(data: &[u8], index: u32) -> Result<TableDirectory, FontExtractorError> {
+    let collection = if data.get(..4) == Some(b"ttcf") {
+        let num_fonts = sfnt::read_u32(data, 8)?;
+        Some(
+            (0..num_fonts as usize)
+                .map(|i| sfnt::read_u32(data, 12 + i * 4))
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    } else {
+        None
+    };
+    let offset = match &collection {
+        Some(offsets) => *offsets.get(index as usize).ok_or_else(|| {
+            sfnt::corrupt(format!(
+                "the collection has {} face(s), not #{index}",
+                offsets.len()
+            ))
+        })?,
+        None => 0,
+    } as usize;
+    Ok(TableDirectory {
+        sfnt_version: sfnt::read_u32(data, offset)?,
+        total_size: data.len() as u32,
+        tables: sfnt::read_directory(data, offset)?,
+        collection,
+    })
+}
@@ -0,0 +1,120 @@
+//! コマンドラインで受け取ったフォント名の検証、`--font-name-hex` のデコードと `--from-file` の読み取り
+//!
+//! レガシーなコードページのコンソール (cmd.exe など) やバッチファイルを経由すると、
+//! コードページで表せない文字 (日本語のフェイス名など) が `?` や U+FFFD に置き換えられたまま渡されることがあります。
+//! GDI はそのような名前でもエラーにせず代替フォントを選ぶため、抽出前に検出して止めます。
+
+use std::fs;
+use std::path::Path;
+
+use crate::FontExtractorError;
+
+/// `--font-name-hex` の値 (UTF-16 のコード単位を 4 桁の 16 進数で並べたもの) をデコードします。
+/// 空白、`,`、`-` による区切りと `U+` / `0x` の接頭辞は無視します (例: `30E1 30A4 30EA 30AA` → メイリオ)。
+pub fn decode_hex(hex: &str) -> Result<String, FontExtractorError> {
+    let invalid = |reason: String| FontExtractorError::InvalidFontName {
+        name: hex.to_string(),
+        reason,
+    };
+    let mut units = Vec::new();
+    for group in hex
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '-')
+        .filter(|group| !group.is_empty())
+    {
+        let digits = ["U+", "u+", "0x", "0X"]
+            .iter()
+            .find_map(|prefix| group.strip_prefix(prefix))
+            .unwrap_or(group);
+        if digits.len() % 4 != 0 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid(format!(
+                "'{group}' is not a sequence of 4-digit hex UTF-16 code units"
+            )));
+        }
+        for i in (0..digits.len()).step_by(4) {
+            units.push(u16::from_str_radix(&digits[i..i + 4], 16).expect("validated above"));
+        }
+    }
+    if units.is_empty() {
+        return Err(invalid("no code units given".to_string()));
+    }
+    let name = String::from_utf16(&units)
+        .map_err(|_| invalid("contains an unpaired surrogate code unit".to_string()))?;
+    check(&name)?;
+    Ok(name)
+}
+
+/// `--from-file` のファイルからフォント名を 1 行に 1 つずつ読み取ります。
+/// `#` から行末まではコメントで、空行は無視します。
+pub fn read_list(path: &Path) -> Result<Vec<String>, FontExtractorError> {
+    let text = fs::read_to_string(path).map_err(|e| FontExtractorError::FileRead {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    Ok(text
+        .lines()
+        .map(|line| {
+            let line = line.trim_start_matches('\u{FEFF}');
+            line.split_once('#').map_or(line, |(name, _)| name).trim()
+        })
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// フォント名がコンソールのコードページで化けていないか (置換文字を含まないか) を検証します。
+pub fn check(name: &str) -> Result<(), FontExtractorError> {
+    let replaced = name.chars().find(|c| matches!(c, '\u{FFFD}' | '?'));
+    if let Some(c) = replaced {
+        return Err(FontExtractorError::InvalidFontName {
+            name: name.to_string(),
+            reason: format!(
+                "it contains the replacement character '{c}' (received as {}), so it was probably mangled by the console code page; \
+                 run `chcp 65001` first or pass the name as UTF-16 code units with --font-name-hex",
+                to_hex(name)
+            ),
+        });
+    }
+    if name.chars().any(char::is_control) {
+        return Err(FontExtractorError::InvalidFontName {
+            name: name.escape_default().to_string(),
+            reason: "it contains control characters".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// ワイルドカード `*` を含む名前かどうかを返します。
+/// `?` はコンソールで化けた文字と区別できないため、ワイルドカードとしては扱いません。
+pub fn is_pattern(name: &str) -> bool {
+    name.contains('*')
+}
+
+/// ファミリー名が `*` (0 文字以上の任意の文字列) を含むパターンに一致するかを返します。
+/// GDI のフェイス名と同じく大文字と小文字は区別しません。
+pub fn matches_pattern(pattern: &str, family: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let family = family.to_lowercase();
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = family.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// 文字列を `--font-name-hex` の形式 (UTF-16 コード単位の 16 進数) で表します。
+pub fn to_hex(name: &str) -> String {
+    name.encode_utf16()
+        .map(|unit| format!("{unit:04X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
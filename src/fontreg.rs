@@ -0,0 +1,483 @@
+//! レジストリの Fonts キーからのフォントファイルの検索 (`--bypass-cache`)
+//!
+//! GDI の名前解決と GetFontData はフォントキャッシュサービス (FontCache) を通ります。
+//! キャッシュが壊れていると古いデータが返ったりフォントが見つからなかったりするため、
+//! インストール時に登録されたフォントファイルを直接読み取ります。
+//!
+//! すべてのユーザー向けのフォントは HKLM に、Windows 10 以降でユーザーごとにインストールしたフォントは
+//! HKCU に登録されます (ファイルは %LOCALAPPDATA%\Microsoft\Windows\Fonts)。`--scope` はこの区別に使います。
+//!
+//! 値の名前はファイルに含まれるフォントのフルネームを ` & ` でつないだもので、末尾に ` (TrueType)` などが付きます。
+//! 値のデータは %WINDIR%\Fonts からの相対パスか、ユーザーごとにインストールしたフォントでは絶対パスです。
+//!
+//! ```text
+//! HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion\Fonts
+//!     Arial Bold (TrueType) = arialbd.ttf
+//!     MS Gothic & MS UI Gothic & MS PGothic (TrueType) = msgothic.ttc
+//! ```
+//!
+//! Windows 以外にはレジストリが無いため、fontconfig (macOS では Core Text) が知っているフォントファイルと
+//! そのフェイスのフルネームを同じ形で返します。ホームディレクトリの下のファイルがこのユーザー向けです。
+
+#[cfg(backend = "gdi")]
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+#[cfg(windows)]
+use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, ERROR_NO_MORE_ITEMS, ERROR_SUCCESS};
+#[cfg(backend = "gdi")]
+use windows::Win32::Foundation::{LPARAM, WPARAM};
+#[cfg(backend = "gdi")]
+use windows::Win32::Graphics::Gdi::AddFontResourceW;
+#[cfg(windows)]
+use windows::Win32::System::Registry::{
+    HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_READ, REG_EXPAND_SZ, REG_SZ, RegCloseKey,
+    RegEnumValueW, RegOpenKeyExW, RegQueryInfoKeyW,
+};
+#[cfg(backend = "gdi")]
+use windows::Win32::System::Registry::{
+    KEY_SET_VALUE, REG_OPTION_NON_VOLATILE, RegCreateKeyExW, RegSetValueExW,
+};
+#[cfg(windows)]
+use windows::Win32::System::SystemInformation::GetSystemWindowsDirectoryW;
+#[cfg(backend = "gdi")]
+use windows::Win32::UI::WindowsAndMessaging::{
+    HWND_BROADCAST, SMTO_ABORTIFHUNG, SendMessageTimeoutW, WM_FONTCHANGE,
+};
+#[cfg(backend = "gdi")]
+use windows::core::HSTRING;
+#[cfg(windows)]
+use windows::core::{Error as WinError, PCWSTR, PWSTR, w};
+
+use crate::sfnt::{self, Sfnt, name};
+use crate::{FontExtractorError, FontRequest, fontfile};
+
+/// この太さ以上を "Bold" の名前で探す (FW_SEMIBOLD)
+const BOLD_WEIGHT: i32 = 600;
+
+/// Fonts キーのパス (HKLM と HKCU で共通)
+#[cfg(windows)]
+const FONTS_KEY: PCWSTR = w!(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Fonts");
+
+/// WM_FONTCHANGE を通知するときに 1 つのウィンドウの応答を待つ最大時間 (ミリ秒)
+#[cfg(backend = "gdi")]
+const FONTCHANGE_TIMEOUT_MS: u32 = 1000;
+
+/// フォントのインストール先 (`--scope`)
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scope {
+    /// Fonts installed for all users and for the current user
+    #[default]
+    All,
+    /// Fonts installed for the current user only (HKCU, %LOCALAPPDATA%\Microsoft\Windows\Fonts)
+    User,
+    /// Fonts installed for all users (HKLM, %WINDIR%\Fonts)
+    System,
+}
+
+impl Scope {
+    /// `font` のインストール先 (`User` か `System`) がこの範囲に含まれるかどうか
+    pub fn includes(self, font: Scope) -> bool {
+        self == Self::All || self == font
+    }
+
+    /// 表示用の説明 ("installed for the current user" など)
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::All => "installed",
+            Self::User => "installed for the current user",
+            Self::System => "installed for all users",
+        }
+    }
+
+    /// イベントなどに出力する名前
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::User => "user",
+            Self::System => "system",
+        }
+    }
+}
+
+/// --- RAII ラッパー: レジストリキー ---
+#[cfg(windows)]
+struct RegKey(HKEY);
+#[cfg(windows)]
+impl RegKey {
+    /// `root\SOFTWARE\Microsoft\Windows NT\CurrentVersion\Fonts` を開きます。キーが無ければ `None` です。
+    fn open_fonts(root: HKEY) -> Result<Option<Self>, FontExtractorError> {
+        let mut key = HKEY::default();
+        let status = unsafe { RegOpenKeyExW(root, FONTS_KEY, 0, KEY_READ, &mut key) };
+        match status {
+            ERROR_SUCCESS => Ok(Some(Self(key))),
+            ERROR_FILE_NOT_FOUND => Ok(None),
+            status => Err(api_error(
+                "RegOpenKeyExW (Fonts)",
+                status.to_hresult().into(),
+            )),
+        }
+    }
+
+    /// 文字列の値をすべて (名前, データ) の組で返します。
+    fn string_values(&self) -> Result<Vec<(String, String)>, FontExtractorError> {
+        let mut max_name_len = 0;
+        let mut max_data_len = 0;
+        let status = unsafe {
+            RegQueryInfoKeyW(
+                self.0,
+                PWSTR::null(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(&mut max_name_len),
+                Some(&mut max_data_len),
+                None,
+                None,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return Err(api_error("RegQueryInfoKeyW", status.to_hresult().into()));
+        }
+
+        let mut values = Vec::new();
+        let mut name = vec![0u16; max_name_len as usize + 1];
+        let mut data = vec![0u8; max_data_len as usize + 2];
+        for index in 0.. {
+            let mut name_len = name.len() as u32;
+            let mut data_len = data.len() as u32;
+            let mut value_type = 0;
+            let status = unsafe {
+                RegEnumValueW(
+                    self.0,
+                    index,
+                    PWSTR(name.as_mut_ptr()),
+                    &mut name_len,
+                    None,
+                    Some(&mut value_type),
+                    Some(data.as_mut_ptr()),
+                    Some(&mut data_len),
+                )
+            };
+            match status {
+                ERROR_SUCCESS => {}
+                ERROR_NO_MORE_ITEMS => break,
+                status => return Err(api_error("RegEnumValueW", status.to_hresult().into())),
+            }
+            if value_type != REG_SZ.0 && value_type != REG_EXPAND_SZ.0 {
+                continue;
+            }
+            let wide: Vec<u16> = data[..data_len as usize]
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .take_while(|&c| c != 0)
+                .collect();
+            values.push((
+                String::from_utf16_lossy(&name[..name_len as usize]),
+                String::from_utf16_lossy(&wide),
+            ));
+        }
+        Ok(values)
+    }
+}
+#[cfg(backend = "gdi")]
+impl RegKey {
+    /// このユーザーの Fonts キー (HKCU) を書き込み用に開きます。キーが無ければ作ります。
+    fn create_user_fonts() -> Result<Self, FontExtractorError> {
+        let mut key = HKEY::default();
+        let status = unsafe {
+            RegCreateKeyExW(
+                HKEY_CURRENT_USER,
+                FONTS_KEY,
+                0,
+                PCWSTR::null(),
+                REG_OPTION_NON_VOLATILE,
+                KEY_SET_VALUE,
+                None,
+                &mut key,
+                None,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return Err(api_error(
+                "RegCreateKeyExW (Fonts)",
+                status.to_hresult().into(),
+            ));
+        }
+        Ok(Self(key))
+    }
+
+    /// 文字列 (REG_SZ) の値を書き込みます。
+    fn set_string(&self, name: &str, value: &str) -> Result<(), FontExtractorError> {
+        let data: Vec<u8> = value
+            .encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let status =
+            unsafe { RegSetValueExW(self.0, &HSTRING::from(name), 0, REG_SZ, Some(&data)) };
+        if status != ERROR_SUCCESS {
+            return Err(api_error("RegSetValueExW", status.to_hresult().into()));
+        }
+        Ok(())
+    }
+}
+#[cfg(windows)]
+impl Drop for RegKey {
+    fn drop(&mut self) {
+        let _ = unsafe { RegCloseKey(self.0) };
+    }
+}
+
+/// Fonts キーに登録されたフォントファイル (Windows 以外では fontconfig が知っているフォントファイル)
+#[derive(Debug, Clone)]
+pub struct RegisteredFont {
+    /// ファイルに含まれるフォントのフルネーム
+    pub names: Vec<String>,
+    pub path: PathBuf,
+    /// 登録されていたキー (HKLM なら `System`、HKCU なら `User`。Windows 以外ではホームディレクトリの下なら `User`)
+    pub scope: Scope,
+}
+
+/// すべてのユーザー向け (HKLM) とこのユーザー向け (HKCU) に登録されたフォントファイルを返します。
+#[cfg(windows)]
+pub fn registered_fonts() -> Result<Vec<RegisteredFont>, FontExtractorError> {
+    let fonts_dir = fonts_dir();
+    let mut fonts = Vec::new();
+    for (root, scope) in [
+        (HKEY_LOCAL_MACHINE, Scope::System),
+        (HKEY_CURRENT_USER, Scope::User),
+    ] {
+        let Some(key) = RegKey::open_fonts(root)? else {
+            continue;
+        };
+        for (value_name, file) in key.string_values()? {
+            // "Arial Bold (TrueType)" → ["Arial Bold"]
+            let names = match value_name.rsplit_once(" (") {
+                Some((names, _)) if value_name.ends_with(')') => names,
+                _ => value_name.as_str(),
+            };
+            fonts.push(RegisteredFont {
+                names: names
+                    .split(" & ")
+                    .map(|name| name.trim().to_string())
+                    .collect(),
+                // 相対パスは Fonts フォルダから (絶対パスなら join はそのパスを返す)
+                path: fonts_dir.join(file),
+                scope,
+            });
+        }
+    }
+    Ok(fonts)
+}
+
+/// fontconfig (macOS では Core Text) が知っている TrueType / OpenType のフォントファイルを返します。
+/// どちらのバックエンドも無効なら空です。
+#[cfg(not(windows))]
+pub fn registered_fonts() -> Result<Vec<RegisteredFont>, FontExtractorError> {
+    #[cfg(backend = "fontconfig")]
+    return crate::fontconfig::installed_fonts();
+    #[cfg(backend = "coretext")]
+    return crate::coretext::installed_fonts();
+    #[cfg(not(any(backend = "fontconfig", backend = "coretext")))]
+    Ok(Vec::new())
+}
+
+/// フォントファイルのインストール先 (ホームディレクトリの下ならこのユーザー向け)
+#[cfg(any(backend = "fontconfig", backend = "coretext"))]
+pub(crate) fn scope_of(path: &Path, home: Option<&Path>) -> Scope {
+    match home {
+        Some(home) if !home.as_os_str().is_empty() && path.starts_with(home) => Scope::User,
+        _ => Scope::System,
+    }
+}
+
+/// `scope` にインストールされた `request` のフォントのファイルを探し、そのパスと一致したフルネームを返します。
+/// 見つからなければ `None` です。
+pub fn find(
+    request: &FontRequest<'_>,
+    scope: Scope,
+) -> Result<Option<(PathBuf, String)>, FontExtractorError> {
+    let fonts = registered_fonts()?;
+    Ok(locate(&fonts, request, scope).map(|(font, name)| (font.path.clone(), name.to_string())))
+}
+
+/// `fonts` から `scope` にインストールされた `request` のフォントを探し、そのファイルと一致したフルネームを返します。
+///
+/// ファミリー名に太さと斜体に応じた "Bold"、"Italic" などを付けた名前で探し、無ければファミリー名そのもので探します
+/// (GDI と同じく、その場合は別の太さのフェイスになることがあります)。
+pub fn locate<'f>(
+    fonts: &'f [RegisteredFont],
+    request: &FontRequest<'_>,
+    scope: Scope,
+) -> Option<(&'f RegisteredFont, &'f str)> {
+    // GDI と同じく大文字と小文字は区別しない
+    wanted_names(request).into_iter().find_map(|wanted| {
+        let wanted = wanted.to_lowercase();
+        fonts
+            .iter()
+            .filter(|font| scope.includes(font.scope))
+            .find_map(|font| {
+                font.names
+                    .iter()
+                    .find(|name| name.to_lowercase() == wanted)
+                    .map(|name| (font, name.as_str()))
+            })
+    })
+}
+
+/// `family` のフェイスが `scope` にインストールされているかどうか
+/// (登録されたフルネームがファミリー名そのものか、ファミリー名と空白で始まるもの)。
+pub fn family_in_scope(fonts: &[RegisteredFont], family: &str, scope: Scope) -> bool {
+    let family = family.to_lowercase();
+    fonts
+        .iter()
+        .filter(|font| scope.includes(font.scope))
+        .flat_map(|font| &font.names)
+        .any(|name| {
+            let name = name.to_lowercase();
+            name == family
+                || name
+                    .strip_prefix(&family)
+                    .is_some_and(|rest| rest.starts_with(' '))
+        })
+}
+
+/// `path` のフォントファイルを読み取ります。コレクションなら `name` のフェイスを単体のフォントに組み立て直します。
+pub fn read_face(path: &Path, name: &str) -> Result<Vec<u8>, FontExtractorError> {
+    let data = fontfile::read(path)?;
+    if data.get(..4) != Some(b"ttcf") {
+        return Ok(data);
+    }
+    let faces = sfnt::split_collection(&data)?;
+    let index = faces
+        .iter()
+        .position(|face| {
+            Sfnt::parse_face(face, 0)
+                .ok()
+                .and_then(|font| name::parse(font.table(b"name")?).ok())
+                .is_some_and(|records| name::has_family(&records, name))
+        })
+        .unwrap_or(0);
+    Ok(faces.into_iter().nth(index).unwrap_or_default())
+}
+
+/// 探す名前の候補 (優先順)
+fn wanted_names(request: &FontRequest<'_>) -> Vec<String> {
+    let family = request.name;
+    let bold = request.logfont.weight >= BOLD_WEIGHT;
+    let mut names = match (bold, request.logfont.italic) {
+        (false, false) => vec![format!("{family} Regular")],
+        (true, false) => vec![format!("{family} Bold")],
+        (false, true) => vec![format!("{family} Italic"), format!("{family} Oblique")],
+        (true, true) => vec![
+            format!("{family} Bold Italic"),
+            format!("{family} Bold Oblique"),
+        ],
+    };
+    if !bold && !request.logfont.italic {
+        names.insert(0, family.to_string());
+    } else {
+        names.push(family.to_string());
+    }
+    names
+}
+
+/// フォントファイルをこのユーザーだけにインストールします (管理者権限は不要)。インストールしたファイルのパスを返します。
+///
+/// Windows 10 以降の「このユーザーのみにインストール」と同じく、ファイルを %LOCALAPPDATA%\Microsoft\Windows\Fonts に
+/// コピーして HKCU の Fonts キーに登録し、このセッションでもすぐに使えるように読み込んで WM_FONTCHANGE を通知します。
+/// 値の名前は `data` のフェイスのフルネームを ` & ` でつないで ` (TrueType)` を付けたものです。
+#[cfg(backend = "gdi")]
+pub fn install_for_user(file_name: &str, data: &[u8]) -> Result<PathBuf, FontExtractorError> {
+    let dir = user_fonts_dir();
+    fs::create_dir_all(&dir).map_err(|e| FontExtractorError::FileCreate {
+        path: dir.display().to_string(),
+        source: e,
+    })?;
+    let path = dir.join(file_name);
+    fs::write(&path, data).map_err(|e| FontExtractorError::FileWrite {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+
+    let value_name = format!("{} (TrueType)", full_names(data, file_name).join(" & "));
+    RegKey::create_user_fonts()?.set_string(&value_name, &path.to_string_lossy())?;
+
+    if unsafe { AddFontResourceW(&HSTRING::from(path.as_os_str())) } == 0 {
+        return Err(api_error("AddFontResourceW", WinError::from_win32()));
+    }
+    // 応答しないウィンドウで止まらないように、タイムアウト付きで通知する
+    let _ = unsafe {
+        SendMessageTimeoutW(
+            HWND_BROADCAST,
+            WM_FONTCHANGE,
+            WPARAM(0),
+            LPARAM(0),
+            SMTO_ABORTIFHUNG,
+            FONTCHANGE_TIMEOUT_MS,
+            None,
+        )
+    };
+    Ok(path)
+}
+
+/// フォントファイル (コレクションならすべてのフェイス) のフルネーム。読み取れなければファイル名の拡張子を除いた部分
+#[cfg(backend = "gdi")]
+fn full_names(data: &[u8], file_name: &str) -> Vec<String> {
+    let num_faces = if data.get(..4) == Some(b"ttcf") {
+        sfnt::read_u32(data, 8).unwrap_or(0)
+    } else {
+        1
+    };
+    let names: Vec<String> = (0..num_faces)
+        .filter_map(|index| {
+            let font = Sfnt::parse_face(data, index).ok()?;
+            name::find(&name::parse(font.table(b"name")?).ok()?, 4)
+        })
+        .collect();
+    if names.is_empty() {
+        let stem = Path::new(file_name).file_stem().unwrap_or_default();
+        return vec![stem.to_string_lossy().into_owned()];
+    }
+    names
+}
+
+/// %LOCALAPPDATA%\Microsoft\Windows\Fonts (このユーザーだけにインストールしたフォントの置き場所)
+#[cfg(backend = "gdi")]
+fn user_fonts_dir() -> PathBuf {
+    let local_app_data = std::env::var_os("LOCALAPPDATA").map_or_else(
+        || {
+            std::env::var_os("USERPROFILE")
+                .map_or_else(PathBuf::new, PathBuf::from)
+                .join(r"AppData\Local")
+        },
+        PathBuf::from,
+    );
+    local_app_data.join(r"Microsoft\Windows\Fonts")
+}
+
+/// %WINDIR%\Fonts (ターミナルサーバーでもシステムの Windows フォルダ)
+#[cfg(windows)]
+pub(crate) fn fonts_dir() -> PathBuf {
+    let mut buffer = [0u16; 260];
+    let len = unsafe { GetSystemWindowsDirectoryW(Some(&mut buffer)) } as usize;
+    let windows = if len == 0 || len > buffer.len() {
+        std::env::var_os("WINDIR").map_or_else(|| PathBuf::from(r"C:\Windows"), PathBuf::from)
+    } else {
+        PathBuf::from(String::from_utf16_lossy(&buffer[..len]))
+    };
+    windows.join("Fonts")
+}
+
+#[cfg(windows)]
+fn api_error(api_name: &str, source: WinError) -> FontExtractorError {
+    FontExtractorError::WinApi {
+        api_name: api_name.to_string(),
+        source,
+    }
+}
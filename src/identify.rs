@@ -0,0 +1,114 @@
+//! `identify` サブコマンド: 画像に写った文字列に似ているインストール済みフォントを探します。
+//!
+//! 各ファミリーで同じ文字列を GDI で描画し、画像の文字との重なりが大きい順に並べます。
+
+use std::fs;
+use std::path::PathBuf;
+
+use clap::builder::NonEmptyStringValueParser;
+use font_export::bitmap::{self, InkImage};
+use font_export::logfont::LogFont;
+use font_export::{FontExtractorError, FontRequest, fontname, render};
+
+/// 太字で描画するときの LOGFONT の太さ (FW_BOLD)
+const BOLD_WEIGHT: i32 = 700;
+
+/// --- `identify` サブコマンドの引数 ---
+#[derive(clap::Args, Debug)]
+pub struct IdentifyArgs {
+    /// PNG image of the text (e.g., a screenshot), ideally cropped to one line of text on a plain background
+    #[arg(long)]
+    image: PathBuf,
+
+    /// The text shown in the image, rendered with each candidate font for comparison
+    #[arg(long, value_parser = NonEmptyStringValueParser::new())]
+    text: String,
+
+    /// Only try these families; `*` matches any text (e.g., "Yu*,MS*"); repeatable or comma-separated
+    #[arg(long, value_delimiter = ',')]
+    candidates: Vec<String>,
+
+    /// Render the candidates in bold
+    #[arg(long)]
+    bold: bool,
+
+    /// Render the candidates in italic
+    #[arg(long)]
+    italic: bool,
+
+    /// Number of best matches to show
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+}
+
+pub fn run(args: &IdentifyArgs) -> Result<(), FontExtractorError> {
+    let image_error = |reason: String| FontExtractorError::ImageDecode {
+        path: args.image.display().to_string(),
+        reason,
+    };
+    let data = fs::read(&args.image).map_err(|e| FontExtractorError::FileRead {
+        path: args.image.display().to_string(),
+        source: e,
+    })?;
+    let (width, height, luminance) = bitmap::decode_png(&data).map_err(image_error)?;
+    let image = InkImage::from_luminance(width, height, &luminance);
+    if image.crop_to_ink().is_none() {
+        return Err(image_error("no text found in the image".to_string()));
+    }
+
+    let families: Vec<String> = font_export::enumerate_font_families()?
+        .into_iter()
+        .filter(|family| {
+            args.candidates.is_empty()
+                || args
+                    .candidates
+                    .iter()
+                    .any(|pattern| fontname::matches_pattern(pattern, family))
+        })
+        .collect();
+    if families.is_empty() {
+        return Err(FontExtractorError::InvalidFontName {
+            name: args.candidates.join(","),
+            reason: "no installed font family matches it; run `list` to see the installed families"
+                .to_string(),
+        });
+    }
+
+    let logfont = LogFont {
+        weight: if args.bold {
+            BOLD_WEIGHT
+        } else {
+            LogFont::default().weight
+        },
+        italic: args.italic,
+        ..LogFont::default()
+    };
+    let mut scores: Vec<(f64, &str)> = Vec::new();
+    let mut lacking = 0;
+    for family in &families {
+        let request = FontRequest {
+            name: family,
+            logfont,
+        };
+        match render::render_text(&request, &args.text) {
+            Ok(Some(rendered)) => scores.push((image.similarity(&rendered), family)),
+            Ok(None) => lacking += 1,
+            Err(e) => eprintln!("Warning: skipping '{family}': {e}"),
+        }
+    }
+    scores.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    println!(
+        "Compared {} font family(ies) with the image; {lacking} lack characters of \"{}\" and were skipped.",
+        scores.len(),
+        args.text
+    );
+    for (rank, (score, family)) in scores.iter().take(args.top).enumerate() {
+        println!("  {:>3}. {score:.3}  {family}", rank + 1);
+    }
+    if let Some((_, best)) = scores.first() {
+        println!();
+        println!("Extract the best match with: font-export -f \"{best}\"");
+    }
+    Ok(())
+}
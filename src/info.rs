@@ -0,0 +1,340 @@
+//! `info` サブコマンド: インストール済みフォントのメタデータを表示します (ファイルは書き込みません)。
+
+use std::collections::BTreeMap;
+
+use font_export::limits::Limits;
+use font_export::sfnt::os2::{self, Os2};
+use font_export::sfnt::vertical::{Base, BaseAxis, Vhea, Vorg};
+use font_export::sfnt::{self, cmap, name};
+use font_export::{FontExtractorError, blocks};
+
+/// 概要に表示する name ID (著作権、ファミリー、サブファミリー、フルネーム、バージョン、デザイナー、ライセンスの URL)
+const SUMMARY_NAME_IDS: [u16; 7] = [1, 2, 4, 5, 0, 9, 14];
+
+/// --- `info` サブコマンドの引数 ---
+#[derive(clap::Args, Debug)]
+pub struct InfoArgs {
+    /// Name of the font to inspect (e.g., "Arial", "Meiryo"), or the path of a font file
+    #[arg(long, short, required_unless_present = "stdin_data")]
+    font_name: Option<String>,
+
+    /// Inspect the font data piped to standard input, e.g. `type font.ttf | font-export info --stdin-data`
+    #[arg(long, conflicts_with = "font_name")]
+    stdin_data: bool,
+
+    /// Dump every name record (all platforms, encodings and languages)
+    #[arg(long)]
+    names_all: bool,
+
+    /// Also print the Unicode blocks the cmap covers, each with the number of codepoints the font has in it, and the total codepoint count
+    #[arg(long)]
+    coverage: bool,
+
+    /// Also print the sfnt table directory (tag, offset, length and checksum of each table)
+    #[arg(long)]
+    list_tables: bool,
+
+    #[command(flatten)]
+    limits: Limits,
+}
+
+pub fn run(args: &InfoArgs) -> Result<(), FontExtractorError> {
+    // メタデータの表示に必要なテーブルだけを読み取る
+    const TAGS: [sfnt::Tag; 8] = [
+        *b"cmap", *b"OS/2", *b"name", *b"maxp", *b"vhea", *b"vmtx", *b"VORG", *b"BASE",
+    ];
+    // clap の required_unless_present により、stdin_data でなければ font_name がある
+    let (font_name, font, directory) = match &args.font_name {
+        Some(font_name) if !args.stdin_data => {
+            font_export::fontname::check(font_name)?;
+            let request = font_export::FontRequest::named(font_name);
+            if let Some(path) = request.file_path() {
+                args.limits.read(path)?;
+            }
+            let directory = args
+                .list_tables
+                .then(|| font_export::extract_table_directory(&request))
+                .transpose()?;
+            (
+                font_name.as_str(),
+                font_export::extract_font_tables(&request, &TAGS)?,
+                directory,
+            )
+        }
+        _ => {
+            let data = font_export::read_font_stdin()?;
+            args.limits
+                .check_size("standard input", data.len() as u64)?;
+            args.limits.check(&data)?;
+            let directory = args
+                .list_tables
+                .then(|| font_export::table_directory_from_data(&data))
+                .transpose()?;
+            (
+                "<stdin>",
+                font_export::font_tables_from_data(&data, &TAGS)?,
+                directory,
+            )
+        }
+    };
+
+    println!("Font: {font_name}");
+    match font.num_tables {
+        Some(count) => println!("Data size: {} bytes, {count} table(s)", font.total_size),
+        None => println!("Data size: {} bytes (TrueType Collection)", font.total_size),
+    }
+
+    let names = font.table(b"name").map(name::parse).transpose()?;
+    match &names {
+        Some(records) => print_name_summary(records),
+        None => println!("No name table."),
+    }
+
+    let cmap = match font.table(b"cmap") {
+        Some(table) => cmap::parse(table)?,
+        None => BTreeMap::new(),
+    };
+    match font.table(b"OS/2") {
+        Some(table) => print_range_report(&Os2::parse(table)?, &cmap),
+        None => println!("No OS/2 table; Unicode and code page ranges are not declared."),
+    }
+
+    if args.coverage {
+        print_coverage(&cmap);
+    }
+
+    print_vertical_report(&font)?;
+
+    if args.names_all
+        && let Some(records) = &names
+    {
+        print_all_names(records);
+    }
+
+    if let Some(directory) = &directory {
+        println!();
+        print!("{}", crate::table_list(font_name, directory));
+    }
+    Ok(())
+}
+
+/// cmap がカバーする Unicode のブロックと、そのうちフォントにあるコードポイントの数を表示します。
+fn print_coverage(cmap: &BTreeMap<u32, u16>) {
+    // ブロックの先頭 → (ブロック, フォントにあるコードポイントの数)
+    let mut blocks: BTreeMap<u32, (&blocks::Block, usize)> = BTreeMap::new();
+    let mut other = 0;
+    for &codepoint in cmap.keys() {
+        match blocks::block_of(codepoint) {
+            Some(block) => blocks.entry(block.start).or_insert((block, 0)).1 += 1,
+            None => other += 1,
+        }
+    }
+
+    println!();
+    println!(
+        "Coverage (cmap): {} codepoint(s) in {} block(s)",
+        cmap.len(),
+        blocks.len()
+    );
+    for (block, covered) in blocks.values() {
+        println!(
+            "  {:<17} {:<48} {covered:>6} / {:<6} {:>5.1}%",
+            format!("U+{:04X}..U+{:04X}", block.start, block.end),
+            block.name,
+            block.size(),
+            *covered as f64 * 100.0 / block.size() as f64
+        );
+    }
+    if other > 0 {
+        println!("  {:<17} {:<48} {other:>6}", "", "No block (unassigned)");
+    }
+}
+
+/// 縦書き用のテーブル (vhea / vmtx / VORG / BASE) の有無と内容を表示します。
+fn print_vertical_report(font: &font_export::FontTables) -> Result<(), FontExtractorError> {
+    println!();
+    println!("Vertical metrics:");
+    let num_glyphs = font
+        .table(b"maxp")
+        .map(|maxp| sfnt::read_u16(maxp, 4))
+        .transpose()?;
+    match (font.table(b"vhea"), font.table(b"vmtx")) {
+        (Some(vhea), vmtx) => {
+            let vhea = Vhea::parse(vhea)?;
+            println!(
+                "  vhea  version 0x{:08X}, ascender {}, descender {}, line gap {}, max advance {}, {} long metric(s)",
+                vhea.version,
+                vhea.ascender,
+                vhea.descender,
+                vhea.line_gap,
+                vhea.advance_height_max,
+                vhea.num_long_metrics
+            );
+            match (vmtx, num_glyphs) {
+                (None, _) => println!("  ! vmtx is missing"),
+                (Some(vmtx), Some(num_glyphs)) if vmtx.len() < vhea.vmtx_len(num_glyphs) => {
+                    println!(
+                        "  ! vmtx has {} bytes but {num_glyphs} glyph(s) need {}",
+                        vmtx.len(),
+                        vhea.vmtx_len(num_glyphs)
+                    )
+                }
+                (Some(vmtx), _) => println!("  vmtx  {} bytes", vmtx.len()),
+            }
+        }
+        (None, Some(_)) => println!("  ! vmtx is present without vhea"),
+        (None, None) => println!(
+            "  No vhea/vmtx; renderers fall back to their own defaults for vertical text (see --synthesize-vertical)."
+        ),
+    }
+    if let Some(vorg) = font.table(b"VORG") {
+        let vorg = Vorg::parse(vorg)?;
+        println!(
+            "  VORG  default origin Y {}, {} per-glyph override(s)",
+            vorg.default_origin_y, vorg.num_overrides
+        );
+    }
+    match font.table(b"BASE") {
+        Some(base) => {
+            let base = Base::parse(base)?;
+            print_base_axis("horizontal", base.horizontal.as_ref());
+            print_base_axis("vertical", base.vertical.as_ref());
+        }
+        None => println!("  No BASE table."),
+    }
+    Ok(())
+}
+
+fn print_base_axis(label: &str, axis: Option<&BaseAxis>) {
+    let tags = |tags: &[sfnt::Tag]| -> String {
+        tags.iter()
+            .map(sfnt::tag_to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match axis {
+        Some(axis) => println!(
+            "  BASE  {label} baselines: {} (scripts: {})",
+            tags(&axis.baselines),
+            tags(&axis.scripts)
+        ),
+        None => println!("  BASE  no {label} axis"),
+    }
+}
+
+/// ファミリー名などの主な名前を表示します (Windows 英語のレコードを優先)。
+fn print_name_summary(records: &[name::NameRecord<'_>]) {
+    println!();
+    for name_id in SUMMARY_NAME_IDS {
+        let value = name::find(records, name_id).unwrap_or_else(|| "(not set)".to_string());
+        // 複数行の値 (著作権表示など) は 2 行目以降も値の列にそろえる
+        let value = value.trim_end().replace('\n', &format!("\n{:13}", ""));
+        println!("{:<12} {value}", format!("{}:", name::label(name_id)));
+    }
+}
+
+/// name テーブルの全レコードを表示します。
+fn print_all_names(records: &[name::NameRecord<'_>]) {
+    println!();
+    println!("Name records ({}):", records.len());
+    for record in records {
+        let language = match record.language_label() {
+            Some(label) => format!("{label} (0x{:04X})", record.language_id),
+            None => format!("0x{:04X}", record.language_id),
+        };
+        let value = match record.decode() {
+            Some(text) => format!("{text:?}"),
+            None => {
+                let hex: Vec<String> = record.bytes.iter().map(|b| format!("{b:02X}")).collect();
+                format!("<undecoded: {}>", hex.join(" "))
+            }
+        };
+        println!(
+            "  {:>3} {:<22} {:<9} {:<22} {:<16} {value}",
+            record.name_id,
+            name::label(record.name_id),
+            record.platform_label(),
+            record.encoding_label(),
+            language
+        );
+    }
+}
+
+/// OS/2 の ulUnicodeRange / ulCodePageRange を表示し、cmap の実際のカバー範囲との食い違いを報告します。
+fn print_range_report(os2: &Os2, cmap: &BTreeMap<u32, u16>) {
+    let count_in = |ranges: &[(u32, u32)]| -> usize {
+        ranges
+            .iter()
+            .map(|&(start, end)| cmap.range(start..=end).count())
+            .sum()
+    };
+
+    println!();
+    println!("Unicode ranges (OS/2 ulUnicodeRange):");
+    let mut mismatches = Vec::new();
+    for range in os2::UNICODE_RANGES {
+        let declared = os2.has_unicode_bit(range.bit);
+        let covered = count_in(range.ranges);
+        if declared {
+            println!(
+                "  bit {:>3}  {:<40} {covered:>6} codepoint(s) in cmap",
+                range.bit, range.name
+            );
+        }
+        match (declared, covered) {
+            (true, 0) => mismatches.push(format!(
+                "bit {} ({}) is declared but cmap has no codepoints in that range",
+                range.bit, range.name
+            )),
+            (false, n) if n > 0 => mismatches.push(format!(
+                "bit {} ({}) is not declared but cmap covers {n} codepoint(s)",
+                range.bit, range.name
+            )),
+            _ => {}
+        }
+    }
+
+    println!();
+    println!("Code pages (OS/2 ulCodePageRange):");
+    if os2.code_page_range.is_none() {
+        println!("  (not present in this OS/2 table version)");
+    }
+    for code_page in os2::CODE_PAGES {
+        let declared = os2.has_code_page_bit(code_page.bit);
+        if declared {
+            println!("  bit {:>3}  {}", code_page.bit, code_page.name);
+        }
+        if code_page.probes.is_empty() || os2.code_page_range.is_none() {
+            continue;
+        }
+        let missing: Vec<String> = code_page
+            .probes
+            .iter()
+            .filter(|c| !cmap.contains_key(c))
+            .map(|c| format!("U+{c:04X}"))
+            .collect();
+        if declared && !missing.is_empty() {
+            mismatches.push(format!(
+                "code page {} is declared but cmap lacks {}",
+                code_page.name,
+                missing.join(", ")
+            ));
+        } else if !declared && missing.is_empty() && code_page.bit <= os2::LAST_ANSI_CODE_PAGE_BIT {
+            // OEM コードページのビットは立てないのが普通なので、ANSI コードページのみ報告する
+            mismatches.push(format!(
+                "code page {} is not declared but cmap covers its key characters",
+                code_page.name
+            ));
+        }
+    }
+
+    println!();
+    if mismatches.is_empty() {
+        println!("Declared ranges match cmap coverage.");
+    } else {
+        println!("Mismatches between declared ranges and cmap coverage:");
+        for mismatch in &mismatches {
+            println!("  ! {mismatch}");
+        }
+    }
+}
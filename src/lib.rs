@@ -0,0 +1,1172 @@
+//! Windows にインストールされたフォントのデータを GDI 経由で取り出すライブラリ
+//!
+//! Windows 以外では、fontconfig (macOS では Core Text) が名前から選んだフォントファイルを読み取ります。
+//! 名前を解決する方法 (バックエンド) は Cargo のフィーチャーで選べます ([`source`])。
+//! Windows に固有の機能 (DirectWrite との照合、Windows カタログ、GDI での描画など) は Windows でだけ使えます。
+//!
+//! フォント名を指定してフォントファイルのデータ全体を読み取るには [`extract_font`] を使います。
+//! サイズや文字セットなども指定する場合は [`FontRequest`] を組み立てて [`extract_font_data`] に渡します。
+//!
+//! ```no_run
+//! let data = font_export::extract_font("Arial")?;
+//! std::fs::write("Arial.ttf", data)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::BTreeMap;
+#[cfg(backend = "gdi")]
+use std::collections::BTreeSet;
+#[cfg(backend = "gdi")]
+use std::ffi::{OsStr, c_void};
+#[cfg(backend = "gdi")]
+use std::marker::PhantomData;
+#[cfg(backend = "gdi")]
+use std::os::windows::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+pub mod bitmap;
+pub mod blocks;
+#[cfg(backend = "coretext")]
+pub mod coretext;
+pub mod digest;
+#[cfg(backend = "dwrite")]
+pub mod directwrite;
+pub mod emitter;
+#[cfg(backend = "fontconfig")]
+pub mod fontconfig;
+mod fontfile;
+pub mod fontname;
+pub mod fontreg;
+pub mod limits;
+pub mod logfont;
+pub mod output;
+pub mod remap;
+#[cfg(backend = "gdi")]
+pub mod render;
+pub mod sample;
+pub mod sfnt;
+pub mod sink;
+pub mod source;
+pub mod sysinfo;
+pub mod template;
+pub mod validate;
+pub mod verify;
+pub mod webfont;
+#[cfg(windows)]
+pub mod wincatalog;
+pub mod zone;
+
+// Windows API 関連
+#[cfg(windows)]
+use windows::core::Error as WinError;
+#[cfg(backend = "gdi")]
+use windows::{
+    Win32::Foundation::LPARAM,
+    Win32::Graphics::Gdi::{
+        CreateCompatibleDC, CreateFontW, DEFAULT_CHARSET, DeleteDC, DeleteObject, ENUMLOGFONTEXW,
+        EnumFontFamiliesExW, GDI_ERROR, GetFontData, HDC, HFONT, HGDIOBJ, LOGFONTW, SelectObject,
+        TEXTMETRICW, TRUETYPE_FONTTYPE,
+    },
+    core::PCWSTR,
+};
+
+/// --- カスタムエラー型定義 ---
+#[derive(Error, Debug)]
+pub enum FontExtractorError {
+    #[cfg(windows)]
+    #[error("Windows API call '{api_name}' failed: {source}")]
+    WinApi { api_name: String, source: WinError },
+    #[cfg(backend = "fontconfig")]
+    #[error("fontconfig call '{api_name}' failed: {reason}")]
+    Fontconfig { api_name: String, reason: String },
+    #[cfg(backend = "coretext")]
+    #[error("Core Text call '{api_name}' failed: {reason}")]
+    CoreText { api_name: String, reason: String },
+    #[error("Font '{font_name}' reported size 0 or could not be read.")]
+    ZeroSizeFont { font_name: String },
+    #[error("Failed to create/ensure output directory or file '{path}': {source}")]
+    FileCreate {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Failed to write to output file '{path}': {source}")]
+    FileWrite {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Failed to read file '{path}': {source}")]
+    FileRead {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Font data is corrupt or unsupported: {reason}")]
+    CorruptFontData { reason: String },
+    #[error("Output file '{path}' already exists with different content")]
+    OutputConflict { path: String },
+    #[error("Failed to parse catalog '{path}': {reason}")]
+    CatalogParse { path: String, reason: String },
+    #[error(
+        "Font data for '{family}' ({version}) does not match the catalog: expected {expected}, got {actual}"
+    )]
+    UntrustedFontData {
+        family: String,
+        version: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Failed to parse LOGFONT file '{path}': {reason}")]
+    LogFontParse { path: String, reason: String },
+    #[error("Failed to parse remap file '{path}' at line {line}: {reason}")]
+    RemapParse {
+        path: String,
+        line: usize,
+        reason: String,
+    },
+    #[error("Failed to decode image '{path}': {reason}")]
+    ImageDecode { path: String, reason: String },
+    #[error(
+        "Font rejected: {what} is {value}, over the limit of {limit}; raise {option} if the font is trusted"
+    )]
+    LimitExceeded {
+        what: String,
+        value: u64,
+        limit: u64,
+        option: &'static str,
+    },
+    #[error(
+        "'{requested}' is not installed and GDI substituted '{got}'; pass --allow-substitution to extract it anyway"
+    )]
+    FontSubstituted { requested: String, got: String },
+    #[error("No installed font has the exact family or full name '{name}'; {candidates}")]
+    NoExactMatch { name: String, candidates: String },
+    #[error(
+        "No installed font has the PostScript name '{name}' (PostScript names are case-sensitive)"
+    )]
+    PostScriptNameNotFound { name: String },
+    #[error(
+        "'{name}' is not registered under the Fonts registry key; --bypass-cache can only read fonts installed there"
+    )]
+    NotRegistered { name: String },
+    #[error("'{name}' is not {scope}; pass --scope all to use fonts from both scopes")]
+    NotInScope { name: String, scope: &'static str },
+    #[error("Could not read '{name}' through any backend ({reasons})")]
+    AllBackendsFailed { name: String, reasons: String },
+    #[error("{backend} has no font named '{name}'")]
+    NotInstalled { name: String, backend: &'static str },
+    #[error("{what} needs the {features} feature, which this build does not include")]
+    BackendUnavailable {
+        what: &'static str,
+        features: &'static str,
+    },
+    #[error("Font '{font}' has no '{tag}' table")]
+    TableNotFound { font: String, tag: String },
+    #[error(
+        "Font data of '{font}' failed checksum verification ({problems}); it may be truncated or corrupt, pass --no-verify to write it anyway"
+    )]
+    ChecksumMismatch { font: String, problems: String },
+    #[error(
+        "'{font}' is marked {embedding} embedding (OS/2 fsType 0x{fs_type:04X}), so its license does not allow copying it; pass --ignore-embedding-restrictions if you are licensed to"
+    )]
+    EmbeddingRestricted {
+        font: String,
+        embedding: &'static str,
+        fs_type: u16,
+    },
+    #[error("Invalid font name '{name}': {reason}")]
+    InvalidFontName { name: String, reason: String },
+    #[error("Invalid template '{template}': {reason}")]
+    InvalidTemplate { template: String, reason: String },
+    #[error("Invalid output sink '{spec}': {reason}")]
+    InvalidSink { spec: String, reason: String },
+    #[error("Unknown emitter '{name}' (known: {known})")]
+    UnknownEmitter { name: String, known: String },
+    #[cfg(feature = "plugins")]
+    #[error("Emitter '{name}' failed: {reason}")]
+    EmitterFailed { name: String, reason: String },
+    #[cfg(feature = "network")]
+    #[error("Failed to upload to '{url}': {reason}")]
+    Upload { url: String, reason: String },
+    #[cfg(feature = "network")]
+    #[error("Failed to notify webhook '{url}': {reason}")]
+    Webhook { url: String, reason: String },
+    #[error("Extraction failed for {failed} of {total} font(s)")]
+    ExtractionFailed { failed: usize, total: usize },
+    #[error("Self-test failed for {failed} of {total} font(s)")]
+    SelftestFailed { failed: usize, total: usize },
+    #[error("{failed} of {total} installed font(s) failed the health check")]
+    UnhealthyFonts { failed: usize, total: usize },
+    #[error("Failed to parse font spec '{path}': {reason}")]
+    SpecParse { path: String, reason: String },
+    #[error("{drifted} of {total} font(s) do not match the spec")]
+    FontsDrifted { drifted: usize, total: usize },
+    #[error("{problems} problem(s) found in '{path}'")]
+    ArchiveVerificationFailed { path: String, problems: usize },
+}
+
+/// --- RAII ラッパー: SafeDC ---
+#[cfg(backend = "gdi")]
+pub struct SafeDC(HDC);
+#[cfg(backend = "gdi")]
+impl SafeDC {
+    pub fn new() -> Result<Self, FontExtractorError> {
+        let hdc = unsafe { CreateCompatibleDC(None) };
+        if hdc.is_invalid() {
+            Err(FontExtractorError::WinApi {
+                api_name: "CreateCompatibleDC".to_string(),
+                source: WinError::from_win32(),
+            })
+        } else {
+            Ok(Self(hdc))
+        }
+    }
+    pub fn get(&self) -> HDC {
+        self.0
+    }
+}
+#[cfg(backend = "gdi")]
+impl Drop for SafeDC {
+    fn drop(&mut self) {
+        if !self.0.is_invalid() {
+            let _ = unsafe { DeleteDC(self.0) };
+        }
+    }
+}
+
+/// --- GDI を使わない場合: DC の代わり ---
+/// GDI 以外のバックエンドは DC を使わないため何も持ちません。
+/// 複数のフォントで DC を使い回す API ([`extract_font_data_on`] など) を GDI と共通にするためのものです。
+#[cfg(not(backend = "gdi"))]
+pub struct SafeDC;
+#[cfg(not(backend = "gdi"))]
+impl SafeDC {
+    pub fn new() -> Result<Self, FontExtractorError> {
+        Ok(Self)
+    }
+}
+
+/// --- CreateFontW に渡すフォントの指定 ---
+#[derive(Debug, Clone, Copy)]
+pub struct FontRequest<'a> {
+    pub name: &'a str,
+    pub logfont: logfont::LogFont,
+}
+
+impl<'a> FontRequest<'a> {
+    /// 名前だけを指定し、サイズなどは GDI の既定値に任せます。
+    pub fn named(name: &'a str) -> Self {
+        Self {
+            name,
+            logfont: logfont::LogFont::default(),
+        }
+    }
+
+    /// 名前がインストールされていないフォントファイルのパスを指していれば、そのパスを返します。
+    pub fn file_path(&self) -> Option<&'a Path> {
+        fontfile::as_path(self.name)
+    }
+}
+
+/// --- RAII ラッパー: SafeFont ---
+#[cfg(backend = "gdi")]
+pub struct SafeFont(HFONT);
+#[cfg(backend = "gdi")]
+impl SafeFont {
+    pub fn create(request: &FontRequest<'_>) -> Result<Self, FontExtractorError> {
+        let font_name_wide: Vec<u16> = OsStr::new(request.name)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+        let pcwstr_font_name = PCWSTR(font_name_wide.as_ptr());
+        let lf = &request.logfont;
+        let font = unsafe {
+            CreateFontW(
+                lf.height,
+                lf.width,
+                lf.escapement,
+                lf.orientation,
+                lf.weight,
+                lf.italic.into(),
+                lf.underline.into(),
+                lf.strike_out.into(),
+                lf.charset.into(),
+                lf.out_precision.into(),
+                lf.clip_precision.into(),
+                lf.quality.into(),
+                lf.pitch_and_family.into(),
+                pcwstr_font_name,
+            )
+        };
+        if font.is_invalid() {
+            Err(FontExtractorError::WinApi {
+                api_name: format!("CreateFontW (font: '{}')", request.name),
+                source: WinError::from_win32(),
+            })
+        } else {
+            Ok(Self(font))
+        }
+    }
+    pub fn get(&self) -> HFONT {
+        self.0
+    }
+}
+#[cfg(backend = "gdi")]
+impl Drop for SafeFont {
+    fn drop(&mut self) {
+        if !self.0.is_invalid() {
+            let _ = unsafe { DeleteObject(self.0) };
+        }
+    }
+}
+
+/// --- RAII ラッパー: FontSelector ---
+#[cfg(backend = "gdi")]
+pub struct FontSelector<'dc> {
+    dc: &'dc SafeDC,
+    old_font: Option<HGDIOBJ>,
+    _marker: PhantomData<&'dc ()>,
+}
+#[cfg(backend = "gdi")]
+impl<'dc> FontSelector<'dc> {
+    pub fn select(dc: &'dc SafeDC, font: &SafeFont) -> Result<Self, FontExtractorError> {
+        let old_font = unsafe { SelectObject(dc.get(), font.get()) };
+        if old_font.is_invalid() {
+            Err(FontExtractorError::WinApi {
+                api_name: "SelectObject (select new font)".to_string(),
+                source: WinError::from_win32(),
+            })
+        } else {
+            Ok(Self {
+                dc,
+                old_font: Some(old_font),
+                _marker: PhantomData,
+            })
+        }
+    }
+}
+#[cfg(backend = "gdi")]
+impl<'dc> Drop for FontSelector<'dc> {
+    fn drop(&mut self) {
+        if let Some(old_font_handle) = self.old_font {
+            let _ = unsafe { SelectObject(self.dc.get(), old_font_handle) };
+        }
+    }
+}
+
+/// --- インストール済みフォントファミリーの列挙 ---
+/// 名前からフォントを選ぶバックエンド ([`source::native`]) が読み取れる TrueType / OpenType のファミリー名を、
+/// 重複を除いて名前順に返します。
+pub fn enumerate_font_families() -> Result<Vec<String>, FontExtractorError> {
+    source::native(&SafeDC::new()?)?.families()
+}
+
+/// GetFontData で読み取れる TrueType / OpenType のファミリー名を、重複を除いて名前順に返します。
+#[cfg(backend = "gdi")]
+fn gdi_families(dc: &SafeDC) -> Vec<String> {
+    // 文字セットを DEFAULT_CHARSET、フェイス名を空にすると全ファミリーが列挙される
+    let logfont = LOGFONTW {
+        lfCharSet: DEFAULT_CHARSET,
+        ..Default::default()
+    };
+    let mut families = BTreeSet::new();
+    unsafe {
+        EnumFontFamiliesExW(
+            dc.get(),
+            &logfont,
+            Some(collect_family),
+            LPARAM(&mut families as *mut BTreeSet<String> as isize),
+            0,
+        )
+    };
+    families.into_iter().collect()
+}
+
+/// `scope` にインストールされたファミリーだけを [`enumerate_font_families`] と同じ順に返します。
+/// ファミリー名はレジストリの Fonts キーに登録されたフルネームと照合します ([`fontreg::family_in_scope`])。
+pub fn enumerate_font_families_in(
+    scope: fontreg::Scope,
+) -> Result<Vec<String>, FontExtractorError> {
+    let mut families = enumerate_font_families()?;
+    if scope != fontreg::Scope::All {
+        let fonts = fontreg::registered_fonts()?;
+        families.retain(|family| fontreg::family_in_scope(&fonts, family, scope));
+    }
+    Ok(families)
+}
+
+/// EnumFontFamiliesExW のコールバック: `lparam` の BTreeSet にファミリー名を追加します。
+#[cfg(backend = "gdi")]
+unsafe extern "system" fn collect_family(
+    logfont: *const LOGFONTW,
+    _metric: *const TEXTMETRICW,
+    font_type: u32,
+    lparam: LPARAM,
+) -> i32 {
+    let families = unsafe { &mut *(lparam.0 as *mut BTreeSet<String>) };
+    let family = from_wide_nul(unsafe { &(*logfont).lfFaceName });
+    // ラスターフォントと縦書き用の '@' 付きの名前は除外
+    if font_type & TRUETYPE_FONTTYPE != 0 && !family.starts_with('@') {
+        families.insert(family);
+    }
+    1 // 列挙を続ける
+}
+
+/// ファミリー内のフェイス (スタイル) の 1 つ
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FontFace {
+    /// LOGFONT の lfWeight (400 = 標準, 700 = 太字)
+    pub weight: i32,
+    pub italic: bool,
+    /// スタイル名 ("Bold Italic" など)
+    pub style: String,
+    /// フルネーム ("Arial Bold Italic" など)
+    pub full_name: String,
+}
+
+/// --- ファミリー内のフェイスの列挙 ---
+/// `family` の TrueType / OpenType のフェイスを、太さ・斜体の順に重複を除いて返します。
+pub fn enumerate_font_faces(family: &str) -> Result<Vec<FontFace>, FontExtractorError> {
+    source::native(&SafeDC::new()?)?.faces(family)
+}
+
+/// GDI で `family` のフェイスを列挙します。
+/// 文字セットごとに同じフェイスが列挙されるため、重複はここで取り除きます。
+#[cfg(backend = "gdi")]
+fn gdi_faces(dc: &SafeDC, family: &str) -> Vec<FontFace> {
+    let mut logfont = LOGFONTW {
+        lfCharSet: DEFAULT_CHARSET,
+        ..Default::default()
+    };
+    // lfFaceName は終端の NUL を含めて 32 文字まで
+    for (dst, src) in logfont.lfFaceName[..31]
+        .iter_mut()
+        .zip(OsStr::new(family).encode_wide())
+    {
+        *dst = src;
+    }
+    let mut faces = BTreeSet::new();
+    unsafe {
+        EnumFontFamiliesExW(
+            dc.get(),
+            &logfont,
+            Some(collect_face),
+            LPARAM(&mut faces as *mut BTreeSet<FontFace> as isize),
+            0,
+        )
+    };
+    faces.into_iter().collect()
+}
+
+/// EnumFontFamiliesExW のコールバック: `lparam` の BTreeSet にフェイスを追加します。
+#[cfg(backend = "gdi")]
+unsafe extern "system" fn collect_face(
+    logfont: *const LOGFONTW,
+    _metric: *const TEXTMETRICW,
+    font_type: u32,
+    lparam: LPARAM,
+) -> i32 {
+    let faces = unsafe { &mut *(lparam.0 as *mut BTreeSet<FontFace>) };
+    // フェイス名を指定した列挙では、LOGFONTW は実際には ENUMLOGFONTEXW を指している
+    let enum_logfont = unsafe { &*(logfont as *const ENUMLOGFONTEXW) };
+    let face_name = from_wide_nul(&enum_logfont.elfLogFont.lfFaceName);
+    if font_type & TRUETYPE_FONTTYPE != 0 && !face_name.starts_with('@') {
+        faces.insert(FontFace {
+            weight: enum_logfont.elfLogFont.lfWeight,
+            italic: enum_logfont.elfLogFont.lfItalic != 0,
+            style: from_wide_nul(&enum_logfont.elfStyle),
+            full_name: from_wide_nul(&enum_logfont.elfFullName),
+        });
+    }
+    1 // 列挙を続ける
+}
+
+/// `--exact` で名前を完全一致で解決した結果
+#[derive(Debug, Clone, Copy)]
+pub enum ExactMatch<'a> {
+    /// ファミリー名に一致した (GDI が標準のフェイスを選ぶ)
+    Family(&'a str),
+    /// フェイスのフルネームに一致した (太さと斜体でそのフェイスを選ぶ)
+    Face { family: &'a str, face: &'a FontFace },
+}
+
+/// インストール済みのファミリーとそのフェイスの一覧 (名前の完全一致による解決に使う)
+pub struct FaceIndex {
+    families: Vec<(String, Vec<FontFace>)>,
+}
+
+/// 一致しなかったときに候補として示す名前の最大数
+const MAX_CANDIDATES: usize = 10;
+
+impl FaceIndex {
+    /// すべてのファミリーのフェイスを列挙します。
+    pub fn load() -> Result<Self, FontExtractorError> {
+        let families = enumerate_font_families()?
+            .into_iter()
+            .map(|family| {
+                let faces = enumerate_font_faces(&family)?;
+                Ok((family, faces))
+            })
+            .collect::<Result<_, FontExtractorError>>()?;
+        Ok(Self { families })
+    }
+
+    /// `name` をファミリー名かフェイスのフルネームとの完全一致 (GDI と同じく大文字と小文字は区別しない) で解決します。
+    ///
+    /// GDI のあいまいな照合とは違い、一致しなければ似た名前を候補に挙げてエラーにします。
+    pub fn resolve(&self, name: &str) -> Result<ExactMatch<'_>, FontExtractorError> {
+        let wanted = name.to_lowercase();
+        if let Some((family, _)) = self
+            .families
+            .iter()
+            .find(|(family, _)| family.to_lowercase() == wanted)
+        {
+            return Ok(ExactMatch::Family(family));
+        }
+        for (family, faces) in &self.families {
+            if let Some(face) = faces.iter().find(|f| f.full_name.to_lowercase() == wanted) {
+                return Ok(ExactMatch::Face { family, face });
+            }
+        }
+
+        // 候補: 要求した名前を含むか、要求した名前に含まれるか、最初の語が同じ名前
+        let first_word = wanted.split_whitespace().next().unwrap_or_default();
+        let mut candidates: Vec<&str> = self
+            .families
+            .iter()
+            .flat_map(|(family, faces)| {
+                std::iter::once(family.as_str()).chain(faces.iter().map(|f| f.full_name.as_str()))
+            })
+            .filter(|candidate| {
+                let candidate = candidate.to_lowercase();
+                candidate.contains(&wanted)
+                    || wanted.contains(&candidate)
+                    || candidate.split_whitespace().next() == Some(first_word)
+            })
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        let candidates = if candidates.is_empty() {
+            "no similar names are installed (see `list --faces`)".to_string()
+        } else {
+            let more = candidates.len().saturating_sub(MAX_CANDIDATES);
+            let mut list = format!(
+                "similar names: {}",
+                candidates[..candidates.len().min(MAX_CANDIDATES)].join(", ")
+            );
+            if more > 0 {
+                list.push_str(&format!(" and {more} more"));
+            }
+            list
+        };
+        Err(FontExtractorError::NoExactMatch {
+            name: name.to_string(),
+            candidates,
+        })
+    }
+}
+
+/// NUL 終端の UTF-16 の固定長配列を文字列に変換します。
+#[cfg(backend = "gdi")]
+fn from_wide_nul(wide: &[u16]) -> String {
+    let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+    String::from_utf16_lossy(&wide[..len])
+}
+
+/// --- フォント名を指定してフォントデータを取得 ---
+/// サイズなどは GDI の既定値で `name` のフォントを選択し、フォントファイルのデータ全体を返します。
+/// TrueType / OpenType Collection のフェイスは単体のフォントに組み立て直されます。
+/// `name` がコレクションファイルのパスなら、最初のフェイスを単体のフォントにして返します。
+pub fn extract_font(name: &str) -> Result<Vec<u8>, FontExtractorError> {
+    let request = FontRequest::named(name);
+    if let Some(path) = request.file_path() {
+        return fontfile::read_face(path, 0);
+    }
+    extract_font_data(&request)
+}
+
+/// --- GDI 経由でフォントデータを取得 ---
+pub fn extract_font_data(request: &FontRequest<'_>) -> Result<Vec<u8>, FontExtractorError> {
+    if let Some(path) = request.file_path() {
+        return fontfile::read(path);
+    }
+    extract_font_data_on(&SafeDC::new()?, request)
+}
+
+/// --- 既存の DC を使ってフォントデータを取得 ---
+/// 複数のフォントを続けて読み取るときに DC を使い回すためのものです。
+/// フォントは読み取りの間だけ `dc` に選択され、終わると元のフォントに戻ります。
+pub fn extract_font_data_on(
+    dc: &SafeDC,
+    request: &FontRequest<'_>,
+) -> Result<Vec<u8>, FontExtractorError> {
+    extract_font_data_timed(dc, request).map(|(buffer, _)| buffer)
+}
+
+/// [`extract_font_data_timed`] で計測した所要時間
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadTimes {
+    /// CreateFontW と SelectObject (GDI のフォントマッパーが名前をフォントに解決する) にかかった時間
+    pub resolution: Duration,
+    /// GetFontData による読み取り (コレクションのフェイスの組み立てを含む) やファイルの読み取りにかかった時間
+    pub read: Duration,
+}
+
+/// インストール済みフォントのデータを読み取った方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// GDI の GetFontData
+    #[cfg(backend = "gdi")]
+    Gdi,
+    /// DirectWrite のフォントファイルローダー
+    #[cfg(backend = "dwrite")]
+    DirectWrite,
+    /// fontconfig が名前から選んだフォントファイル
+    #[cfg(backend = "fontconfig")]
+    Fontconfig,
+    /// Core Text が名前から選んだフォントファイル
+    #[cfg(backend = "coretext")]
+    CoreText,
+    /// レジストリの Fonts キー (Windows 以外ではバックエンドのフォントの一覧) に登録されたファイル
+    Registry,
+}
+
+impl Backend {
+    /// 名前からフォントを選ぶ通常の方法 ([`source::native`])。有効なバックエンドが無ければ `None` です。
+    pub fn native() -> Option<Self> {
+        SafeDC::new()
+            .ok()
+            .and_then(|dc| source::native(&dc).ok().map(|source| source.backend()))
+    }
+
+    /// 表示用の名前
+    pub fn label(self) -> &'static str {
+        match self {
+            #[cfg(backend = "gdi")]
+            Self::Gdi => "GDI",
+            #[cfg(backend = "dwrite")]
+            Self::DirectWrite => "DirectWrite",
+            #[cfg(backend = "fontconfig")]
+            Self::Fontconfig => "fontconfig",
+            #[cfg(backend = "coretext")]
+            Self::CoreText => "Core Text",
+            Self::Registry => "the registered font file",
+        }
+    }
+
+    /// イベントなどに出力する名前
+    pub fn keyword(self) -> &'static str {
+        match self {
+            #[cfg(backend = "gdi")]
+            Self::Gdi => "gdi",
+            #[cfg(backend = "dwrite")]
+            Self::DirectWrite => "directwrite",
+            #[cfg(backend = "fontconfig")]
+            Self::Fontconfig => "fontconfig",
+            #[cfg(backend = "coretext")]
+            Self::CoreText => "coretext",
+            Self::Registry => "registry",
+        }
+    }
+}
+
+/// [`extract_font_data_with_fallback`] で読み取ったデータ
+#[derive(Debug)]
+pub struct FallbackRead {
+    pub data: Vec<u8>,
+    pub times: ReadTimes,
+    /// 読み取りに成功した方法
+    pub backend: Backend,
+    /// 先に試して失敗した方法とその理由
+    pub failures: Vec<(Backend, String)>,
+    /// [`Backend::Registry`] で読み取ったファイル
+    pub path: Option<PathBuf>,
+}
+
+/// バックエンドがフォントのデータを返さなかったことを示すエラーかどうか
+/// (別の方法なら読み取れる可能性がある)
+pub fn is_unreadable(error: &FontExtractorError) -> bool {
+    match error {
+        #[cfg(windows)]
+        FontExtractorError::WinApi { .. } => true,
+        #[cfg(backend = "fontconfig")]
+        FontExtractorError::Fontconfig { .. } => true,
+        #[cfg(backend = "coretext")]
+        FontExtractorError::CoreText { .. } => true,
+        FontExtractorError::ZeroSizeFont { .. } | FontExtractorError::AllBackendsFailed { .. } => {
+            true
+        }
+        _ => false,
+    }
+}
+
+/// --- 有効なバックエンド、レジストリに登録されたファイルの順に試してフォントデータを取得 ---
+/// GetFontData は一部の OpenType (CFF) フォントやリモートのフォントで GDI_ERROR を返すことがあります。
+/// 最初のバックエンド (GDI) が読み取れなかったとき ([`is_unreadable`]) だけ次のバックエンド (DirectWrite) を試し、
+/// それ以外のエラーはそのまま返します。レジストリのファイルは `scope` に登録されたものから探します。
+/// Windows 以外ではバックエンドが選んだファイルが読み取れなければ、フォントの一覧からフルネームで探したファイルを試します。
+/// バックエンドが 1 つも有効でないビルドでは、レジストリに登録されたファイルだけを試します。
+pub fn extract_font_data_with_fallback(
+    dc: &SafeDC,
+    request: &FontRequest<'_>,
+    scope: fontreg::Scope,
+) -> Result<FallbackRead, FontExtractorError> {
+    if request.file_path().is_some() {
+        let (data, times) = extract_font_data_timed(dc, request)?;
+        return Ok(FallbackRead {
+            data,
+            times,
+            backend: Backend::Registry,
+            failures: Vec::new(),
+            path: None,
+        });
+    }
+    let mut failures = Vec::new();
+    for source in source::sources(dc) {
+        match source.read(request) {
+            Ok((data, times)) => {
+                return Ok(FallbackRead {
+                    data,
+                    times,
+                    backend: source.backend(),
+                    failures,
+                    path: None,
+                });
+            }
+            Err(e) if failures.is_empty() && !is_unreadable(&e) => return Err(e),
+            Err(e) => failures.push((source.backend(), e.to_string())),
+        }
+    }
+
+    let started = Instant::now();
+    match extract_font_data_uncached(request, scope) {
+        Ok((data, path)) => Ok(FallbackRead {
+            data,
+            times: ReadTimes {
+                read: started.elapsed(),
+                ..ReadTimes::default()
+            },
+            backend: Backend::Registry,
+            failures,
+            path: Some(path),
+        }),
+        // バックエンドが 1 つも無いビルドでは、名前から選べないことを伝える
+        Err(e) if failures.is_empty() => Err(source::native(dc).err().unwrap_or(e)),
+        Err(e) => {
+            failures.push((Backend::Registry, e.to_string()));
+            Err(FontExtractorError::AllBackendsFailed {
+                name: request.name.to_string(),
+                reasons: failures
+                    .iter()
+                    .map(|(backend, reason)| format!("{}: {reason}", backend.label()))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            })
+        }
+    }
+}
+
+/// --- 既存の DC を使ってフォントデータを取得し、名前の解決と読み取りの所要時間を返す ---
+pub fn extract_font_data_timed(
+    dc: &SafeDC,
+    request: &FontRequest<'_>,
+) -> Result<(Vec<u8>, ReadTimes), FontExtractorError> {
+    let started = Instant::now();
+    if let Some(path) = request.file_path() {
+        let buffer = fontfile::read(path)?;
+        let times = ReadTimes {
+            resolution: Duration::ZERO,
+            read: started.elapsed(),
+        };
+        return Ok((buffer, times));
+    }
+    source::native(dc)?.read(request)
+}
+
+/// GDI のフォントマッパーが選んだフォントを GetFontData で読み取ります。
+#[cfg(backend = "gdi")]
+fn gdi_read(
+    dc: &SafeDC,
+    request: &FontRequest<'_>,
+) -> Result<(Vec<u8>, ReadTimes), FontExtractorError> {
+    let started = Instant::now();
+    // --- リソースの確保 (RAII) ---
+    // フォントマッパーによる名前の解決は DC にフォントを選択したときに行われる
+    let font = SafeFont::create(request)?;
+    let _font_selector = FontSelector::select(dc, &font)?;
+    let resolution = started.elapsed();
+
+    // --- フォントデータの取得 ---
+    // テーブルを指定しない読み取りが None を返すことはない
+    let buffer = read_font_data(dc, 0)?.unwrap_or_default();
+    if buffer.is_empty() {
+        return Err(FontExtractorError::ZeroSizeFont {
+            font_name: request.name.to_string(),
+        });
+    }
+
+    // --- TrueType / OpenType Collection のフェイス ---
+    // コレクション内のフォントでは GetFontData(0) がフェイスのオフセットテーブルから始まるデータを返すが、
+    // テーブルのオフセットはコレクションファイルの先頭からの値のままなので、単体のフォントとしては壊れている。
+    let buffer = if &buffer[..buffer.len().min(4)] != b"ttcf" && is_collection_face(dc) {
+        rebuild_collection_face(dc, &buffer)?
+    } else {
+        buffer
+    };
+
+    // --- リソース解放 (変更なし、RAIIにより自動) ---
+    let times = ReadTimes {
+        resolution,
+        read: started.elapsed() - resolution,
+    };
+    Ok((buffer, times))
+}
+
+/// --- フォントを含むコレクション全体を取得 ---
+/// フォントが TrueType / OpenType Collection のフェイスなら、'ttcf' を指定した GetFontData でコレクションファイル全体
+/// (他のフェイスを含む) を返します。コレクションに含まれないフォントでは `None` を返します。
+pub fn extract_collection_on(
+    dc: &SafeDC,
+    request: &FontRequest<'_>,
+) -> Result<Option<Vec<u8>>, FontExtractorError> {
+    if let Some(path) = request.file_path() {
+        return read_collection_file(path);
+    }
+    source::native(dc)?.collection(request)
+}
+
+/// GDI のフォントマッパーが選んだフォントのコレクション全体を GetFontData で読み取ります。
+#[cfg(backend = "gdi")]
+fn gdi_collection(
+    dc: &SafeDC,
+    request: &FontRequest<'_>,
+) -> Result<Option<Vec<u8>>, FontExtractorError> {
+    let font = SafeFont::create(request)?;
+    let _font_selector = FontSelector::select(dc, &font)?;
+    read_font_data(dc, TTCF_TABLE)
+}
+
+/// --- フォントファイルがコレクションならその全体を取得 ---
+/// TrueType / OpenType Collection でなければ `None` を返します。
+pub fn read_collection_file(path: &Path) -> Result<Option<Vec<u8>>, FontExtractorError> {
+    let data = fontfile::read(path)?;
+    Ok((data.get(..4) == Some(b"ttcf")).then_some(data))
+}
+
+/// --- フォントキャッシュを通さずにフォントデータを取得 (`--bypass-cache`) ---
+/// レジストリの Fonts キーに登録されたファイルを直接読み取り、そのデータと読み取ったファイルのパスを返します。
+/// GDI のフォントマッパーを使わないため、名前は `scope` に登録されたフルネームと照合します。
+pub fn extract_font_data_uncached(
+    request: &FontRequest<'_>,
+    scope: fontreg::Scope,
+) -> Result<(Vec<u8>, PathBuf), FontExtractorError> {
+    let (path, name) =
+        fontreg::find(request, scope)?.ok_or_else(|| FontExtractorError::NotRegistered {
+            name: request.name.to_string(),
+        })?;
+    Ok((fontreg::read_face(&path, &name)?, path))
+}
+
+/// コレクション全体を読み取るための GetFontData のテーブル指定 ('ttcf')
+#[cfg(backend = "gdi")]
+const TTCF_TABLE: u32 = u32::from_le_bytes(*b"ttcf");
+
+/// 選択中のフォントが TrueType / OpenType Collection のフェイスかどうか
+/// (単体のフォントでは 'ttcf' の読み取りが GDI_ERROR になる)
+#[cfg(backend = "gdi")]
+fn is_collection_face(dc: &SafeDC) -> bool {
+    unsafe { GetFontData(dc.get(), TTCF_TABLE, 0, None, 0) != GDI_ERROR as u32 }
+}
+
+/// コレクションのフェイスのテーブルを 1 つずつ読み取り、単体のフォントに組み立て直します。
+///
+/// `face_data` は GetFontData(0) が返したデータで、先頭のテーブルディレクトリだけを使います。
+/// sfnt バージョンはそのまま引き継ぐため、CFF のフェイス (OTC) は 'OTTO' のフォントになります。
+#[cfg(backend = "gdi")]
+fn rebuild_collection_face(dc: &SafeDC, face_data: &[u8]) -> Result<Vec<u8>, FontExtractorError> {
+    let sfnt_version = sfnt::read_u32(face_data, 0)?;
+    let mut tables = Vec::new();
+    for record in sfnt::read_directory(face_data, 0)? {
+        let data = read_font_data(dc, u32::from_le_bytes(record.tag))?.ok_or_else(|| {
+            sfnt::corrupt(format!(
+                "table '{}' is listed in the face's directory but cannot be read",
+                sfnt::tag_to_string(&record.tag)
+            ))
+        })?;
+        tables.push((record.tag, data));
+    }
+    let tables: Vec<(sfnt::Tag, &[u8])> = tables
+        .iter()
+        .map(|(tag, data)| (*tag, data.as_slice()))
+        .collect();
+    sfnt::rebuild::build(sfnt_version, &tables)
+}
+
+/// GDI (またはフォントファイル) から個別に読み取ったテーブル
+pub struct FontTables {
+    /// フォントデータ全体のサイズ (データ自体は読み取りません)
+    pub total_size: u32,
+    /// テーブル数 (TrueType Collection の場合は `None`)
+    pub num_tables: Option<u16>,
+    tables: BTreeMap<sfnt::Tag, Vec<u8>>,
+}
+
+impl FontTables {
+    /// 読み取ったテーブルを返します。フォントに存在しないテーブルは `None` です。
+    pub fn table(&self, tag: &sfnt::Tag) -> Option<&[u8]> {
+        self.tables.get(tag).map(Vec::as_slice)
+    }
+}
+
+/// --- GDI 経由で指定したテーブルだけを取得 ---
+/// メタデータの表示にはフォント全体が不要なので、GetFontData のテーブル指定で必要な分だけ読み取ります。
+pub fn extract_font_tables(
+    request: &FontRequest<'_>,
+    tags: &[sfnt::Tag],
+) -> Result<FontTables, FontExtractorError> {
+    if let Some(path) = request.file_path() {
+        return fontfile::tables(path, tags);
+    }
+    source::native(&SafeDC::new()?)?.tables(request, tags)
+}
+
+/// GDI のフォントマッパーが選んだフォントから `tags` のテーブルを GetFontData で読み取ります。
+#[cfg(backend = "gdi")]
+fn gdi_tables(
+    dc: &SafeDC,
+    request: &FontRequest<'_>,
+    tags: &[sfnt::Tag],
+) -> Result<FontTables, FontExtractorError> {
+    let font = SafeFont::create(request)?;
+    let _font_selector = FontSelector::select(dc, &font)?;
+
+    let total_size = unsafe { GetFontData(dc.get(), 0, 0, None, 0) };
+    if total_size == GDI_ERROR as u32 {
+        return Err(FontExtractorError::WinApi {
+            api_name: "GetFontData (get size)".to_string(),
+            source: WinError::from_win32(),
+        });
+    }
+    if total_size == 0 {
+        return Err(FontExtractorError::ZeroSizeFont {
+            font_name: request.name.to_string(),
+        });
+    }
+
+    // オフセットテーブル (先頭 12 バイト) からテーブル数を読み取る
+    let header = read_font_range(dc, 0, 0, 12)?;
+    let num_tables = (header.len() == 12 && &header[..4] != b"ttcf")
+        .then(|| u16::from_be_bytes([header[4], header[5]]));
+
+    let mut tables = BTreeMap::new();
+    for tag in tags {
+        // GetFontData のテーブル指定はタグのバイト列をそのままリトルエンディアンの DWORD として渡す
+        if let Some(data) = read_font_data(dc, u32::from_le_bytes(*tag))? {
+            tables.insert(*tag, data);
+        }
+    }
+    Ok(FontTables {
+        total_size,
+        num_tables,
+        tables,
+    })
+}
+
+/// --- 標準入力に渡されたフォントデータを取得 ---
+/// パイプで渡されたフォントを、一時ファイルを作らずに処理するためのものです。
+pub fn read_font_stdin() -> Result<Vec<u8>, FontExtractorError> {
+    fontfile::read_stdin()
+}
+
+/// --- フォントデータ (標準入力から受け取ったものなど) から指定したテーブルを取り出す ---
+pub fn font_tables_from_data(
+    data: &[u8],
+    tags: &[sfnt::Tag],
+) -> Result<FontTables, FontExtractorError> {
+    fontfile::tables_from_data(data, tags)
+}
+
+/// GDI (またはフォントファイル) から読み取ったテーブルディレクトリ
+pub struct TableDirectory {
+    pub sfnt_version: u32,
+    pub total_size: u32,
+    pub tables: Vec<sfnt::TableRecord>,
+    /// TrueType Collection に含まれる場合、コレクション内の各フェイスのオフセット
+    pub collection: Option<Vec<u32>>,
+}
+
+/// --- GDI 経由でテーブルディレクトリだけを取得 ---
+/// フォントデータの先頭 (オフセットテーブルとテーブルディレクトリ) だけを読み取ります。
+pub fn extract_table_directory(
+    request: &FontRequest<'_>,
+) -> Result<TableDirectory, FontExtractorError> {
+    if let Some(path) = request.file_path() {
+        return fontfile::table_directory(path);
+    }
+    source::native(&SafeDC::new()?)?.directory(request)
+}
+
+/// --- メモリ上のフォントデータ (コレクションなら最初のフェイス) のテーブルディレクトリ ---
+pub fn table_directory_from_data(data: &[u8]) -> Result<TableDirectory, FontExtractorError> {
+    fontfile::face_directory(data, 0)
+}
+
+/// GDI のフォントマッパーが選んだフォントのテーブルディレクトリを GetFontData で読み取ります。
+#[cfg(backend = "gdi")]
+fn gdi_directory(
+    dc: &SafeDC,
+    request: &FontRequest<'_>,
+) -> Result<TableDirectory, FontExtractorError> {
+    let font = SafeFont::create(request)?;
+    let _font_selector = FontSelector::select(dc, &font)?;
+
+    let total_size = unsafe { GetFontData(dc.get(), 0, 0, None, 0) };
+    if total_size == GDI_ERROR as u32 {
+        return Err(FontExtractorError::WinApi {
+            api_name: "GetFontData (get size)".to_string(),
+            source: WinError::from_win32(),
+        });
+    }
+
+    let header = read_font_range(dc, 0, 0, 12)?;
+    let sfnt_version = sfnt::read_u32(&header, 0)?;
+    let num_tables = sfnt::read_u16(&header, 4)?;
+    let directory = read_font_range(dc, 0, 0, 12 + 16 * num_tables as u32)?;
+    let tables = sfnt::read_directory(&directory, 0)?;
+
+    // コレクション全体は 'ttcf' タグで読み取れる
+    let collection = if !is_collection_face(dc) {
+        None
+    } else {
+        let ttc_header = read_font_range(dc, TTCF_TABLE, 0, 12)?;
+        let num_fonts = sfnt::read_u32(&ttc_header, 8)?;
+        // フェイス数は信頼できないヘッダーの値なので、オフセットの表がコレクションに収まるか確かめてから読み取る
+        let collection_size = unsafe { GetFontData(dc.get(), TTCF_TABLE, 0, None, 0) };
+        if collection_size == GDI_ERROR as u32 {
+            return Err(FontExtractorError::WinApi {
+                api_name: "GetFontData (get collection size)".to_string(),
+                source: WinError::from_win32(),
+            });
+        }
+        let offsets_len = num_fonts
+            .checked_mul(4)
+            .filter(|&len| 12 + u64::from(len) <= u64::from(collection_size))
+            .ok_or_else(|| {
+                sfnt::corrupt(format!(
+                    "the collection header lists {num_fonts} faces, more than the {collection_size}-byte collection can hold"
+                ))
+            })?;
+        let offsets = read_font_range(dc, TTCF_TABLE, 12, offsets_len)?;
+        Some(
+            (0..num_fonts as usize)
+                .map(|i| sfnt::read_u32(&offsets, i * 4))
+                .collect::<Result<_, _>>()?,
+        )
+    };
+
+    Ok(TableDirectory {
+        sfnt_version,
+        total_size,
+        tables,
+        collection,
+    })
+}
+
+/// 選択中のフォントの `table` (0 ならフォント全体) の `offset` から最大 `len` バイトを読み取ります。
+#[cfg(backend = "gdi")]
+fn read_font_range(
+    dc: &SafeDC,
+    table: u32,
+    offset: u32,
+    len: u32,
+) -> Result<Vec<u8>, FontExtractorError> {
+    let mut buffer: Vec<u8> = vec![0; len as usize];
+    let bytes_read = unsafe {
+        GetFontData(
+            dc.get(),
+            table,
+            offset,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            len,
+        )
+    };
+    if bytes_read == GDI_ERROR as u32 {
+        return Err(FontExtractorError::WinApi {
+            api_name: "GetFontData (get data)".to_string(),
+            source: WinError::from_win32(),
+        });
+    }
+    buffer.truncate(bytes_read as usize);
+    Ok(buffer)
+}
+
+/// 読み取りの最初のチャンクサイズ (以降は読み取るたびに倍にする)
+#[cfg(backend = "gdi")]
+const INITIAL_READ_CHUNK: u32 = 64 * 1024;
+/// チャンクサイズの上限
+#[cfg(backend = "gdi")]
+const MAX_READ_CHUNK: u32 = 4 * 1024 * 1024;
+/// 読み取り中にデータのサイズが変わった場合に最初から読み直す回数
+#[cfg(backend = "gdi")]
+const MAX_READ_ATTEMPTS: usize = 3;
+
+/// 選択中のフォントから `table` (0 ならフォント全体) を読み取ります。
+///
+/// 事前にサイズを問い合わせて一度に読むのではなく、チャンクを大きくしながら末尾まで読み進めます。
+/// 読み終えた後のサイズが読み取った量と異なる場合 (フォントの更新中など) は最初から読み直し、
+/// それでも揃わなければ最後に読み取ったデータを返します (短い最後のチャンクが末尾を表すため、データは途切れていません)。
+/// テーブルを指定した場合、GetFontData が GDI_ERROR を返すのはそのテーブルが存在しないときなので `None` を返します。
+#[cfg(backend = "gdi")]
+pub fn read_font_data(dc: &SafeDC, table: u32) -> Result<Option<Vec<u8>>, FontExtractorError> {
+    let mut attempts = 1;
+    loop {
+        let Some(buffer) = read_font_chunks(dc, table)? else {
+            return Ok(None);
+        };
+        let data_size = unsafe { GetFontData(dc.get(), table, 0, None, 0) };
+        if data_size == buffer.len() as u32 || attempts == MAX_READ_ATTEMPTS {
+            return Ok(Some(buffer));
+        }
+        attempts += 1;
+    }
+}
+
+/// `table` を先頭からチャンク単位で末尾まで読み取ります。
+#[cfg(backend = "gdi")]
+fn read_font_chunks(dc: &SafeDC, table: u32) -> Result<Option<Vec<u8>>, FontExtractorError> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut chunk = INITIAL_READ_CHUNK;
+    loop {
+        let start = buffer.len();
+        buffer.resize(start + chunk as usize, 0);
+        let bytes_read = unsafe {
+            GetFontData(
+                dc.get(),
+                table,
+                start as u32,
+                Some(buffer[start..].as_mut_ptr() as *mut c_void),
+                chunk,
+            )
+        };
+        if bytes_read == GDI_ERROR as u32 {
+            buffer.truncate(start);
+            if start > 0 {
+                // 末尾を越えた位置からの読み取りは失敗する (直前のチャンクがちょうど末尾まで読んでいた)
+                return Ok(Some(buffer));
+            }
+            if table != 0 {
+                return Ok(None);
+            }
+            return Err(FontExtractorError::WinApi {
+                api_name: "GetFontData (get data)".to_string(),
+                source: WinError::from_win32(),
+            });
+        }
+        buffer.truncate(start + bytes_read as usize);
+        if bytes_read < chunk {
+            return Ok(Some(buffer));
+        }
+        chunk = (chunk * 2).min(MAX_READ_CHUNK);
+    }
+}
@@ -0,0 +1,177 @@
+//! 信頼できない入力 (インストールされていないフォントファイルや標準入力のデータ) の大きさの上限
+//!
+//! 受け取ったフォントファイルや他の人が作ったフォントファイルは、細工によって巨大なテーブルや
+//! 大量のグリフ、深く入れ子になった複合グリフを持つことがあります。
+//! パースや変換の前に上限と比べ、超えていれば何も割り当てずにエラーにします。
+
+use std::fs;
+use std::path::Path;
+
+use crate::FontExtractorError;
+use crate::sfnt::glyf::{self, Glyf};
+use crate::sfnt::{self, Sfnt};
+
+const MIB: u64 = 1024 * 1024;
+
+/// フォントファイルに適用する上限
+#[derive(clap::Args, Debug, Clone, Copy)]
+#[command(next_help_heading = "Limits for font files given by path or piped in")]
+pub struct Limits {
+    /// Reject font files larger than this many MiB
+    #[arg(long, value_name = "MIB", default_value_t = 512)]
+    pub max_file_size: u64,
+
+    /// Reject fonts with a table larger than this many MiB
+    #[arg(long, value_name = "MIB", default_value_t = 256)]
+    pub max_table_size: u64,
+
+    /// Reject fonts with more glyphs than this (maxp numGlyphs)
+    #[arg(long, value_name = "N", default_value_t = 65535)]
+    pub max_glyphs: u32,
+
+    /// Reject fonts whose composite glyphs nest deeper than this (1 = components are simple glyphs)
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    pub max_composite_depth: u32,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_file_size: 512,
+            max_table_size: 256,
+            max_glyphs: 65535,
+            max_composite_depth: 8,
+        }
+    }
+}
+
+impl Limits {
+    /// ファイルを読み込む前に、ファイルのサイズを上限と比べます。
+    pub fn check_file(&self, path: &Path) -> Result<(), FontExtractorError> {
+        let size = fs::metadata(path)
+            .map_err(|e| FontExtractorError::FileRead {
+                path: path.display().to_string(),
+                source: e,
+            })?
+            .len();
+        self.check_size(&format!("'{}'", path.display()), size)
+    }
+
+    /// ファイル以外から受け取ったデータ (標準入力など) の大きさを上限と比べます。`source` は表示用の名前です。
+    pub fn check_size(&self, source: &str, size: u64) -> Result<(), FontExtractorError> {
+        exceeds(
+            format!("the size of {source} (MiB)"),
+            size.div_ceil(MIB),
+            self.max_file_size,
+            "--max-file-size",
+        )
+    }
+
+    /// 上限と比べながらフォントファイルを読み取ります。
+    pub fn read(&self, path: &Path) -> Result<Vec<u8>, FontExtractorError> {
+        self.check_file(path)?;
+        let data = fs::read(path).map_err(|e| FontExtractorError::FileRead {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        self.check(&data)?;
+        Ok(data)
+    }
+
+    /// フォントデータ (コレクションの場合はすべてのフェイス) のテーブルの大きさ、グリフ数、複合グリフの深さを上限と比べます。
+    pub fn check(&self, data: &[u8]) -> Result<(), FontExtractorError> {
+        let num_faces = if data.get(..4) == Some(b"ttcf") {
+            sfnt::read_u32(data, 8)?
+        } else {
+            1
+        };
+        for index in 0..num_faces {
+            let face = Sfnt::parse_face(data, index)?;
+            for record in &face.tables {
+                exceeds(
+                    format!(
+                        "the size of table '{}' (MiB)",
+                        sfnt::tag_to_string(&record.tag)
+                    ),
+                    u64::from(record.length).div_ceil(MIB),
+                    self.max_table_size,
+                    "--max-table-size",
+                )?;
+            }
+            if let Some(maxp) = face.table(b"maxp") {
+                exceeds(
+                    "the number of glyphs".to_string(),
+                    u64::from(sfnt::read_u16(maxp, 4)?),
+                    u64::from(self.max_glyphs),
+                    "--max-glyphs",
+                )?;
+            }
+            // 壊れた glyf / loca の検出は validate に任せ、ここでは大きさだけを見る
+            if let Ok(Some(glyf)) = Glyf::from_font(&face) {
+                let mut depths = vec![None; usize::from(glyf.num_glyphs())];
+                for glyph in 0..glyf.num_glyphs() {
+                    let depth = self.composite_depth(&glyf, glyph, 0, &mut depths)?;
+                    exceeds(
+                        format!("the composite glyph nesting depth (glyph {glyph})"),
+                        u64::from(depth),
+                        u64::from(self.max_composite_depth),
+                        "--max-composite-depth",
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `glyph` の複合グリフの入れ子の深さ (単純グリフや読み取れないグリフは 0) を求めます。
+    ///
+    /// `depths` は求めた深さの記録で、同じ構成要素を何度もたどらないようにします。
+    /// `nesting` はたどってきた複合グリフの数で、上限を超えた時点で打ち切るため循環参照でも止まります。
+    fn composite_depth(
+        &self,
+        glyf: &Glyf<'_>,
+        glyph: u16,
+        nesting: u32,
+        depths: &mut [Option<u32>],
+    ) -> Result<u32, FontExtractorError> {
+        if let Some(depth) = depths.get(usize::from(glyph)).copied().flatten() {
+            return Ok(depth);
+        }
+        exceeds(
+            format!("the composite glyph nesting depth (glyph {glyph})"),
+            u64::from(nesting),
+            u64::from(self.max_composite_depth),
+            "--max-composite-depth",
+        )?;
+        let components = glyf
+            .glyph_data(glyph)
+            .and_then(glyf::components)
+            .unwrap_or_default();
+        let mut depth = 0;
+        for component in components {
+            depth = depth.max(1 + self.composite_depth(glyf, component, nesting + 1, depths)?);
+        }
+        if let Some(slot) = depths.get_mut(usize::from(glyph)) {
+            *slot = Some(depth);
+        }
+        Ok(depth)
+    }
+}
+
+/// `value` が `limit` を超えていれば、どのオプションで上限を上げられるかを示すエラーを返します。
+fn exceeds(
+    what: String,
+    value: u64,
+    limit: u64,
+    option: &'static str,
+) -> Result<(), FontExtractorError> {
+    if value > limit {
+        return Err(FontExtractorError::LimitExceeded {
+            what,
+            value,
+            limit,
+            option,
+        });
+    }
+    Ok(())
+}
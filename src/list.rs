@@ -0,0 +1,120 @@
+//! `list` サブコマンド: インストール済みのフォントファミリー (と、そのフェイス) を列挙します。
+
+use font_export::sfnt::name;
+use font_export::{FontExtractorError, FontRequest, fontreg};
+
+/// ライセンスの説明を表示する最大文字数
+const MAX_LICENSE_CHARS: usize = 70;
+
+/// --- `list` サブコマンドの引数 ---
+#[derive(clap::Args, Debug)]
+pub struct ListArgs {
+    /// Only list fonts whose license description or URL (name IDs 13/14) contains this text (case-insensitive)
+    #[arg(long, value_name = "TEXT")]
+    license_contains: Option<String>,
+
+    /// Also list the faces (styles) of each family with their weight and full name
+    #[arg(long)]
+    faces: bool,
+
+    /// Only list families installed for this scope: user (per-user fonts), system (for all users) or all
+    #[arg(long, value_name = "SCOPE", default_value_t = fontreg::Scope::All, value_enum)]
+    scope: fontreg::Scope,
+}
+
+pub fn run(args: &ListArgs) -> Result<(), FontExtractorError> {
+    let families = font_export::enumerate_font_families_in(args.scope)?;
+    let Some(keyword) = &args.license_contains else {
+        for family in &families {
+            println!("{family}");
+            if args.faces {
+                print_faces(family);
+            }
+        }
+        println!();
+        println!(
+            "{} font families {}.",
+            families.len(),
+            args.scope.describe()
+        );
+        return Ok(());
+    };
+
+    let keyword = keyword.to_lowercase();
+    let mut matched = 0;
+    for family in &families {
+        // name テーブルだけを読み取る
+        let tables =
+            match font_export::extract_font_tables(&FontRequest::named(family), &[*b"name"]) {
+                Ok(tables) => tables,
+                Err(e) => {
+                    eprintln!("Warning: skipping '{family}': {e}");
+                    continue;
+                }
+            };
+        let records = match tables.table(b"name").map(name::parse).transpose() {
+            Ok(records) => records.unwrap_or_default(),
+            Err(e) => {
+                eprintln!("Warning: skipping '{family}': {e}");
+                continue;
+            }
+        };
+        let license = name::find(&records, 13).unwrap_or_default();
+        let license_url = name::find(&records, 14).unwrap_or_default();
+        if !license.to_lowercase().contains(&keyword)
+            && !license_url.to_lowercase().contains(&keyword)
+        {
+            continue;
+        }
+
+        matched += 1;
+        println!("{family}");
+        if args.faces {
+            print_faces(family);
+        }
+        if !license.is_empty() {
+            println!("    License: {}", summarize(&license));
+        }
+        if !license_url.is_empty() {
+            println!("    URL:     {license_url}");
+        }
+    }
+    println!();
+    println!(
+        "{matched} of {} font families match license text {:?}.",
+        families.len(),
+        args.license_contains.as_deref().unwrap_or_default()
+    );
+    Ok(())
+}
+
+/// ファミリー内のフェイスを 1 行ずつ表示します。
+fn print_faces(family: &str) {
+    let faces = match font_export::enumerate_font_faces(family) {
+        Ok(faces) => faces,
+        Err(e) => {
+            eprintln!("Warning: cannot list the faces of '{family}': {e}");
+            return;
+        }
+    };
+    for face in faces {
+        println!(
+            "    {:<20}  weight {}{}  {}",
+            face.style,
+            face.weight,
+            if face.italic { ", italic" } else { "" },
+            face.full_name
+        );
+    }
+}
+
+/// ライセンス文の 1 行目を最大 `MAX_LICENSE_CHARS` 文字に切り詰めます。
+fn summarize(license: &str) -> String {
+    let first_line = license.lines().next().unwrap_or_default().trim();
+    if first_line.chars().count() > MAX_LICENSE_CHARS || license.lines().count() > 1 {
+        let head: String = first_line.chars().take(MAX_LICENSE_CHARS).collect();
+        format!("{head}...")
+    } else {
+        first_line.to_string()
+    }
+}
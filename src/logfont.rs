@@ -0,0 +1,141 @@
+//! CreateFontW に渡す LOGFONT のパラメータ (`--logfont` の JSON)
+//!
+//! 各フィールドは CreateFontW の引数に対応し、省略したものは既定値になります。
+//! Windows 以外 (fontconfig) で使われるのは太さ、斜体と高さ (ピクセルサイズ) だけです。
+//!
+//! ```json
+//! { "height": -16, "weight": 700, "charset": 128, "quality": 5 }
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::FontExtractorError;
+
+/// フェイス名以外の LOGFONT のフィールド
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogFont {
+    /// lfHeight (0 なら GDI の既定サイズ、負の値は文字の高さのピクセル数)
+    pub height: i32,
+    pub width: i32,
+    pub escapement: i32,
+    pub orientation: i32,
+    pub weight: i32,
+    pub italic: bool,
+    pub underline: bool,
+    pub strike_out: bool,
+    pub charset: u8,
+    pub out_precision: u8,
+    pub clip_precision: u8,
+    pub quality: u8,
+    pub pitch_and_family: u8,
+}
+
+impl Default for LogFont {
+    /// これまで CreateFontW に渡していた値 (標準の太さ、DEFAULT_CHARSET、それ以外は既定値)
+    fn default() -> Self {
+        Self {
+            height: 0,
+            width: 0,
+            escapement: 0,
+            orientation: 0,
+            weight: 400, // FW_NORMAL
+            italic: false,
+            underline: false,
+            strike_out: false,
+            charset: 1,          // DEFAULT_CHARSET
+            out_precision: 0,    // OUT_DEFAULT_PRECIS
+            clip_precision: 0,   // CLIP_DEFAULT_PRECIS
+            quality: 0,          // DEFAULT_QUALITY
+            pitch_and_family: 0, // DEFAULT_PITCH | FF_DONTCARE
+        }
+    }
+}
+
+/// `--weight` で受け付けるキーワードと太さ (CSS / OpenType の usWeightClass と同じ値)
+const WEIGHT_KEYWORDS: &[(&str, i32)] = &[
+    ("thin", 100),
+    ("extralight", 200),
+    ("ultralight", 200),
+    ("light", 300),
+    ("normal", 400),
+    ("regular", 400),
+    ("medium", 500),
+    ("semibold", 600),
+    ("demibold", 600),
+    ("bold", 700),
+    ("extrabold", 800),
+    ("ultrabold", 800),
+    ("black", 900),
+    ("heavy", 900),
+];
+
+/// `--weight` の値 (キーワードか 100〜900 の数値) を LOGFONT の太さに変換します。
+pub fn parse_weight(value: &str) -> Result<i32, String> {
+    let keyword = value.to_ascii_lowercase().replace(['-', ' '], "");
+    if let Some((_, weight)) = WEIGHT_KEYWORDS.iter().find(|(name, _)| *name == keyword) {
+        return Ok(*weight);
+    }
+    match value.parse::<i32>() {
+        Ok(weight) if (100..=900).contains(&weight) => Ok(weight),
+        _ => Err(
+            "expected thin, extralight, light, normal, medium, semibold, bold, extrabold, black or a number from 100 to 900"
+                .to_string(),
+        ),
+    }
+}
+
+/// `--charset` で受け付けるキーワードと文字セット (Win32 の *_CHARSET の値)
+const CHARSET_KEYWORDS: &[(&str, u8)] = &[
+    ("ansi", 0),
+    ("default", 1),
+    ("symbol", 2),
+    ("mac", 77),
+    ("shiftjis", 128),
+    ("hangul", 129),
+    ("johab", 130),
+    ("gb2312", 134),
+    ("chinesebig5", 136),
+    ("big5", 136),
+    ("greek", 161),
+    ("turkish", 162),
+    ("vietnamese", 163),
+    ("hebrew", 177),
+    ("arabic", 178),
+    ("baltic", 186),
+    ("russian", 204),
+    ("thai", 222),
+    ("easteurope", 238),
+    ("oem", 255),
+];
+
+/// `--charset` の値 (キーワードか 0〜255 の数値) を LOGFONT の文字セットに変換します。
+pub fn parse_charset(value: &str) -> Result<u8, String> {
+    let keyword = value
+        .to_ascii_lowercase()
+        .trim_end_matches("_charset")
+        .replace(['-', '_', ' '], "");
+    if let Some((_, charset)) = CHARSET_KEYWORDS.iter().find(|(name, _)| *name == keyword) {
+        return Ok(*charset);
+    }
+    value.parse::<u8>().map_err(|_| {
+        "expected ansi, default, symbol, mac, shiftjis, hangul, johab, gb2312, chinesebig5, greek, turkish, vietnamese, hebrew, arabic, baltic, russian, thai, easteurope, oem or a number from 0 to 255"
+            .to_string()
+    })
+}
+
+impl LogFont {
+    pub fn load(path: &Path) -> Result<Self, FontExtractorError> {
+        let text = fs::read_to_string(path).map_err(|e| FontExtractorError::FileRead {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        serde_json::from_str(&text).map_err(|e| FontExtractorError::LogFontParse {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })
+    }
+}
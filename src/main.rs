@@ -1,257 +1,1655 @@
-use clap::Parser; // clap を使うために追加
-use std::ffi::{OsStr, c_void};
-use std::fs;
-use std::io::Write;
-use std::marker::PhantomData;
-use std::os::windows::ffi::OsStrExt;
+use clap::{Parser, Subcommand}; // clap を使うために追加
 use std::path::PathBuf; // PathBuf を使うために追加
+use std::time::Instant;
 
-use thiserror::Error;
-
-// Windows API 関連
-use windows::{
-    Win32::Graphics::Gdi::{
-        CLIP_DEFAULT_PRECIS, CreateCompatibleDC, CreateFontW, DEFAULT_CHARSET, DEFAULT_PITCH,
-        DEFAULT_QUALITY, DeleteDC, DeleteObject, FF_DONTCARE, FW_NORMAL, GDI_ERROR, GetFontData,
-        HDC, HFONT, HGDIOBJ, OUT_DEFAULT_PRECIS, SelectObject,
-    },
-    core::{Error as WinError, PCWSTR},
+#[cfg(backend = "dwrite")]
+use font_export::directwrite;
+use font_export::sfnt::os2::{Embedding, Os2};
+use font_export::sfnt::style::{self, StyleLinking};
+#[cfg(windows)]
+use font_export::wincatalog;
+use font_export::{
+    Backend, ExactMatch, FontExtractorError, FontFace, FontRequest, ReadTimes, SafeDC,
+    TableDirectory, digest, emitter, extract_collection_on, extract_font_data_timed,
+    extract_font_data_uncached, extract_font_data_with_fallback, extract_table_directory, fontname,
+    fontreg, limits, logfont, output, read_collection_file, remap, sfnt, sink,
+    source::find_postscript_name, template, verify, webfont, zone,
 };
 
+mod check_system;
+#[cfg(feature = "archive")]
+mod diagnostics;
+mod diff;
+#[cfg(backend = "gdi")]
+mod ensure;
+mod events;
+#[cfg(backend = "gdi")]
+mod identify;
+mod info;
+mod list;
+mod merge;
+#[cfg(feature = "network")]
+mod notify;
+mod selftest;
+mod timings;
+mod verify_archive;
+
+use timings::Stage;
+
 /// --- コマンドライン引数定義 (clap を使用) ---
 #[derive(Parser, Debug)]
-#[command(version, about = "Extracts font data from an installed font.", long_about = None)]
+#[command(
+    version,
+    about = "Extracts font data from an installed font.",
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
-    /// Name of the font to extract (e.g., "Arial", "Times New Roman")
-    #[arg(long, short)]
-    font_name: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Name of the font to extract (e.g., "Arial", "Times New Roman"), or the path of a font file to process instead; repeatable or comma-separated to extract several fonts in one run; `*` matches any text, e.g. "Noto Sans*" extracts every installed family whose name starts with "Noto Sans"
+    #[arg(
+        long,
+        short,
+        value_delimiter = ',',
+        required_unless_present_any = ["font_name_hex", "from_file", "all", "stdin_data", "postscript_name"]
+    )]
+    font_name: Vec<String>,
+
+    /// Name of the font as hex UTF-16 code units (e.g., "30E1 30A4 30EA 30AA" for メイリオ), for consoles that cannot pass the name intact; repeatable
+    #[arg(long, value_name = "HEX")]
+    font_name_hex: Vec<String>,
+
+    /// Text file with one font name per line ("#" starts a comment) to extract in addition to --font-name, e.g. a designer-provided font list
+    #[arg(long, value_name = "FILE")]
+    from_file: Option<PathBuf>,
+
+    /// PostScript name of an installed face to extract (e.g., "NotoSansJP-Regular"), as asset pipelines reference fonts; case-sensitive; repeatable or comma-separated; the output is named after it
+    #[arg(long, value_name = "NAME", value_delimiter = ',')]
+    postscript_name: Vec<String>,
+
+    /// Extract every installed font family (a full system font backup); fonts whose data GDI cannot return are reported and skipped
+    #[arg(long, conflicts_with_all = ["font_name", "font_name_hex", "from_file", "postscript_name"])]
+    all: bool,
+
+    /// Read installed fonts from the font files registered under the Fonts registry key instead of through GDI, for machines whose font cache is corrupted and makes GDI return stale or missing data
+    #[arg(long)]
+    bypass_cache: bool,
 
-    /// Directory where the font file should be saved
+    /// Fail when GDI cannot read an installed font, instead of falling back to DirectWrite and then to the font file registered under the Fonts registry key
+    #[arg(long)]
+    no_fallback: bool,
+
+    /// Copy the font file registered under the Fonts registry key byte-for-byte (a whole .ttc included, digital signature intact) instead of reconstructing the data through GetFontData; implies --bypass-cache and --whole-collection
+    #[arg(long, conflicts_with_all = ["split_ttc", "synthesize_vertical", "remap", "style_linking", "strip_tables", "stdin_data"])]
+    copy_original: bool,
+
+    /// Only use fonts installed for this scope: user (per-user fonts, HKCU and %LOCALAPPDATA%\Microsoft\Windows\Fonts), system (for all users, %WINDIR%\Fonts) or all; applies to --all, name patterns and extraction, and each extracted font reports the scope it came from
+    #[arg(long, value_name = "SCOPE", default_value_t = fontreg::Scope::All, value_enum)]
+    scope: fontreg::Scope,
+
+    /// Extract the font GDI substitutes when the requested name is not installed (written under the requested name and reported as SUBSTITUTED) instead of failing
+    #[arg(long)]
+    allow_substitution: bool,
+
+    /// Only extract a font whose family name or face full name (e.g. "Arial Bold") matches the name exactly, instead of letting GDI pick the closest font; fails listing similar names otherwise
+    #[arg(long)]
+    exact: bool,
+
+    /// Extract every face (style) of each family, e.g. Regular, Bold, Italic and Bold Italic, to a separate file named after the face's full name
+    #[arg(long, conflicts_with_all = ["weight", "italic", "stdin_data"])]
+    all_styles: bool,
+
+    /// Process the font data piped to standard input instead of an installed font, e.g. `type font.ttf | font-export --stdin-data -o -`; the output is named "stdin" plus the detected extension
+    #[arg(long, conflicts_with_all = ["font_name", "font_name_hex", "from_file", "all", "postscript_name"])]
+    stdin_data: bool,
+
+    /// Directory where the font file should be saved; may contain {hostname}, {username}, {date}, {time} and {env:NAME}; "-" writes the font data to standard output (messages go to standard error)
     #[arg(long, short, default_value = ".")]
     output_dir: PathBuf, // 保存先ディレクトリを PathBuf で受け取る
+
+    /// What to do when the output file already exists with different content
+    #[arg(long, value_enum, default_value_t = output::ConflictStrategy::Overwrite)]
+    on_conflict: output::ConflictStrategy,
+
+    /// JSON catalog of known-good SHA-256 hashes per family and version to verify the data against
+    #[arg(long, value_name = "CATALOG")]
+    verify_against: Option<PathBuf>,
+
+    /// Write fonts (and --table tables) whose OS/2 fsType is "Restricted License embedding" (by default they are refused and their license notice is printed); only for fonts you are licensed to copy
+    #[arg(long)]
+    ignore_embedding_restrictions: bool,
+
+    /// Only warn, instead of failing, when the extracted data does not match its table checksums or head checkSumAdjustment (e.g., data truncated by GetFontData)
+    #[arg(long)]
+    no_verify: bool,
+
+    /// Check that the extracted data matches a hash signed in the Windows system catalogs (flags modified system fonts)
+    #[cfg(windows)]
+    #[arg(long)]
+    check_windows_catalog: bool,
+
+    /// Warn if DirectWrite resolves the name (with the LOGFONT weight and italic flag) to a different font than GDI, i.e. apps using DirectWrite would show another font
+    #[cfg(backend = "dwrite")]
+    #[arg(long)]
+    check_directwrite: bool,
+
+    /// If the font is a face of a TrueType/OpenType Collection (.ttc), write the whole collection with all its faces instead of the face alone
+    #[arg(long)]
+    whole_collection: bool,
+
+    /// If the font is (a face of) a TrueType/OpenType Collection, write every face of the collection as a standalone .ttf/.otf file named after the face, for tools that cannot read .ttc
+    #[arg(long, conflicts_with = "whole_collection")]
+    split_ttc: bool,
+
+    /// Name the output file exactly as the font name (or font file name) instead of setting the extension detected from the data (.ttf, .otf or .ttc)
+    #[arg(long)]
+    no_auto_extension: bool,
+
+    /// Write a Zone.Identifier stream (Mark of the Web) with this security zone to each font file written to a local directory, e.g. "internet" for fonts that leave the machine
+    #[arg(long, value_name = "ZONE")]
+    zone_identifier: Option<zone::Zone>,
+
+    /// Origin to record as HostUrl in the Zone.Identifier written by --zone-identifier (same template variables as --output-dir)
+    #[arg(long, value_name = "URL", requires = "zone_identifier")]
+    zone_origin: Option<String>,
+
+    /// Remove any Zone.Identifier stream from the written font files, including one carried over from an input font file
+    #[arg(long, conflicts_with = "zone_identifier")]
+    strip_zone_identifier: bool,
+
+    /// Where to write instead of --output-dir: dir:PATH, zip:PATH, tar:PATH, an http(s):// URL (PUT) or s3://bucket/prefix (same template variables as --output-dir)
+    #[arg(long, value_name = "SPEC")]
+    sink: Option<String>,
+
+    /// Extra header for http(s) sinks in "Name: value" form (e.g. "Authorization: Bearer ..."); repeatable
+    #[cfg(feature = "network")]
+    #[arg(long, value_name = "HEADER")]
+    sink_header: Vec<String>,
+
+    /// Endpoint URL for s3:// sinks (defaults to https://s3.<AWS_REGION>.amazonaws.com)
+    #[cfg(feature = "network")]
+    #[arg(long, value_name = "URL")]
+    s3_endpoint: Option<String>,
+
+    /// Retry a failed http(s)/s3 upload this many times, with exponential backoff
+    #[cfg(feature = "network")]
+    #[arg(long, value_name = "N", default_value_t = 3)]
+    upload_retries: u32,
+
+    /// Limit the upload speed of http(s)/s3 sinks in bytes per second (e.g., "500K", "2M")
+    #[cfg(feature = "network")]
+    #[arg(long, value_name = "RATE", value_parser = sink::parse_rate)]
+    upload_limit: Option<u64>,
+
+    /// Request this pixel size (character height) from GDI, to pick a specific strike of a bitmap font
+    #[arg(long, value_name = "PX")]
+    pixel_size: Option<u16>,
+
+    /// Weight of the face to extract: thin, extralight, light, normal, medium, semibold, bold, extrabold, black, or 100-900 (e.g., "bold" for "Segoe UI Bold"); overrides the weight in --logfont
+    #[arg(long, value_name = "WEIGHT", value_parser = logfont::parse_weight)]
+    weight: Option<i32>,
+
+    /// Extract the italic (or oblique) face instead of the upright one; overrides the italic flag in --logfont
+    #[arg(long)]
+    italic: bool,
+
+    /// Character set to request from GDI: ansi, default, symbol, shiftjis, hangul, gb2312, chinesebig5, greek, russian, easteurope, oem, ... or 0-255 (e.g., "symbol" for Wingdings); overrides the charset in --logfont
+    #[arg(long, value_name = "CHARSET", value_parser = logfont::parse_charset)]
+    charset: Option<u8>,
+
+    /// JSON file with CreateFontW (LOGFONT) parameters: height, width, escapement, orientation, weight, italic, underline, strike_out, charset, out_precision, clip_precision, quality, pitch_and_family
+    #[arg(long, value_name = "FILE")]
+    logfont: Option<PathBuf>,
+
+    /// Languages to prefer, in order, when a name from the name table is used for output file names (--split-ttc), messages, events and the charset sample's font-family, e.g. "ja,en"; English is preferred by default
+    #[arg(long, value_name = "LANGS", value_delimiter = ',', value_parser = sfnt::name::parse_language)]
+    name_languages: Vec<String>,
+
+    /// Add synthesized vertical metrics (vhea/vmtx) if the font has none (TrueType outlines only)
+    #[arg(long)]
+    synthesize_vertical: bool,
+
+    /// CSV of "source,target[,keep]" codepoint pairs; the exported copy's cmap maps each target to the source's glyph (moving it, or copying it with "keep")
+    #[arg(long, value_name = "CSV")]
+    remap: Option<PathBuf>,
+
+    /// Comma-separated sfnt tables to drop from the exported copy (e.g., "DSIG,EBDT,EBLC,EBSC"); the table directory, offsets and checksums are rebuilt. A collection must be split with --split-ttc
+    #[arg(long, value_name = "TAGS", value_delimiter = ',', value_parser = sfnt::parse_tag)]
+    strip_tables: Vec<sfnt::Tag>,
+
+    /// Check that OS/2 fsSelection and head macStyle match the subfamily name (name ID 2) so regular/bold/italic files style-link; "fix" also rewrites the bits
+    #[arg(long, value_name = "MODE")]
+    style_linking: Option<StyleLinkingMode>,
+
+    /// Print the sfnt table directory (tags, offsets, lengths, checksums) without extracting the font
+    #[arg(long, visible_alias = "list-tables", conflicts_with = "stdin_data")]
+    table_list: bool,
+
+    /// Write only the bytes of this sfnt table (e.g., "cmap", "name", "OS/2") to "<font>.<tag>.bin" instead of the whole font; for installed fonts only that table is read
+    #[arg(long, value_name = "TAG", value_parser = sfnt::parse_tag, conflicts_with = "table_list")]
+    table: Option<sfnt::Tag>,
+
+    /// Also write an extra file next to the font: charset-sample (HTML page of every character the font covers, grouped by block), font-face-css (@font-face rule), preload (<link rel="preload"> snippet), family-css (per-family CSS with a custom property and weight classes), or an emitter registered with --emitter; repeatable
+    #[arg(long, value_name = "KIND")]
+    emit: Vec<String>,
+
+    /// Register an emitter that runs PROGRAM for each font, with the font data on standard input; its standard output is written next to the font with NAME as the extension (select it with --emit NAME); repeatable
+    #[cfg(feature = "plugins")]
+    #[arg(long, value_name = "NAME=PROGRAM", value_parser = emitter::External::parse)]
+    emitter: Vec<emitter::External>,
+
+    /// Fallback fonts after the family in the custom property written by --emit family-css (default: "system-ui, sans-serif", or "ui-monospace, monospace" for monospaced fonts)
+    #[arg(long, value_name = "STACK")]
+    css_fallback: Option<String>,
+
+    /// font-display value of the @font-face rule written by --emit font-face-css
+    #[arg(long, value_name = "VALUE", default_value_t = webfont::FontDisplay::Swap, value_enum)]
+    font_display: webfont::FontDisplay,
+
+    /// Print machine-readable events (resolved, read, converted, written, skipped, failed) instead of messages
+    #[arg(long, value_name = "FORMAT")]
+    events: Option<events::EventFormat>,
+
+    /// At the end of the run, print how long each stage took (enumeration, name resolution, GDI reads, checks, conversion, IO) and the throughput
+    #[arg(long)]
+    timings: bool,
+
+    /// If the run fails, write a zip for a bug report with the event log, the table directories (no glyph data) of the fonts that failed, OS and font cache versions and the options used
+    #[cfg(feature = "archive")]
+    #[arg(long, value_name = "FILE")]
+    diagnostics: Option<PathBuf>,
+
+    /// POST a JSON summary (counts, failures, duration) to this URL when the run finishes
+    #[cfg(feature = "network")]
+    #[arg(long, value_name = "URL")]
+    notify_webhook: Option<String>,
+
+    #[command(flatten)]
+    limits: limits::Limits,
 }
 
-/// --- カスタムエラー型定義 ---
-#[derive(Error, Debug)]
-pub enum FontExtractorError {
-    #[error("Windows API call '{api_name}' failed: {source}")]
-    WinApi { api_name: String, source: WinError },
-    #[error("Font '{font_name}' reported size 0 or could not be read.")]
-    ZeroSizeFont { font_name: String },
-    #[error("GetFontData reported unexpected size: expected {expected}, got {got}")]
-    FontDataSizeMismatch { expected: u32, got: u32 },
-    #[error("Failed to create/ensure output directory or file '{path}': {source}")]
-    FileCreate {
-        path: String,
-        source: std::io::Error,
-    },
-    #[error("Failed to write to output file '{path}': {source}")]
-    FileWrite {
-        path: String,
-        source: std::io::Error,
-    },
-}
-
-/// --- RAII ラッパー: SafeDC ---
-struct SafeDC(HDC);
-impl SafeDC {
-    fn new() -> Result<Self, FontExtractorError> {
-        let hdc = unsafe { CreateCompatibleDC(None) };
-        if hdc.is_invalid() {
-            Err(FontExtractorError::WinApi {
-                api_name: "CreateCompatibleDC".to_string(),
-                source: WinError::from_win32(),
-            })
+/// `--style-linking` の動作
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum StyleLinkingMode {
+    /// Report mismatches only
+    Check,
+    /// Report mismatches and set the fsSelection/macStyle bits from the subfamily name
+    Fix,
+}
+
+/// --- サブコマンド定義 ---
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two font files, optionally table by table
+    Diff(diff::DiffArgs),
+    /// Show metadata of an installed font without writing any file
+    Info(info::InfoArgs),
+    /// List installed font families, optionally filtered by license text
+    List(list::ListArgs),
+    /// Read and validate every installed font and report broken ones
+    CheckSystem(check_system::CheckSystemArgs),
+    /// Extract a set of standard fonts, validate them and report pass/fail
+    Selftest(selftest::SelftestArgs),
+    /// Rank installed fonts by how closely they match the text in an image (e.g., a screenshot)
+    #[cfg(backend = "gdi")]
+    Identify(identify::IdentifyArgs),
+    /// Combine the glyphs of several font files into one font
+    Merge(merge::MergeArgs),
+    /// Re-read every font in a backup archive or output directory and report corrupt or missing files
+    VerifyArchive(verify_archive::VerifyArchiveArgs),
+    /// Check the installed fonts against a desired-state spec, install missing ones for the current user from an archive, and report drift
+    #[cfg(backend = "gdi")]
+    Ensure(ensure::EnsureArgs),
+}
+
+/// --- main 関数 ---
+fn main() -> Result<(), FontExtractorError> {
+    // --- コマンドライン引数の解析 ---
+    let args = Args::parse();
+    match &args.command {
+        Some(Command::Diff(diff_args)) => diff::run(diff_args),
+        Some(Command::Info(info_args)) => info::run(info_args),
+        Some(Command::List(list_args)) => list::run(list_args),
+        Some(Command::CheckSystem(check_args)) => check_system::run(check_args),
+        Some(Command::Selftest(selftest_args)) => selftest::run(selftest_args),
+        #[cfg(backend = "gdi")]
+        Some(Command::Identify(identify_args)) => identify::run(identify_args),
+        Some(Command::Merge(merge_args)) => merge::run(merge_args),
+        Some(Command::VerifyArchive(verify_args)) => verify_archive::run(verify_args),
+        #[cfg(backend = "gdi")]
+        Some(Command::Ensure(ensure_args)) => ensure::run(ensure_args),
+        None => {
+            #[cfg(feature = "archive")]
+            if let Some(path) = &args.diagnostics {
+                diagnostics::start(path, &args);
+            }
+            let result = extract_fonts(&args);
+            #[cfg(feature = "archive")]
+            diagnostics::finish(&result, &args);
+            result
+        }
+    }
+}
+
+/// --- フォントの抽出 (サブコマンドが無い場合) ---
+fn extract_fonts(args: &Args) -> Result<(), FontExtractorError> {
+    // subcommand_negates_reqs により、サブコマンドが無い場合は font_name か font_name_hex か from_file か all か stdin_data が必須
+    let mut timings = timings::Timings::default();
+    let started = Instant::now();
+    let font_names = if args.stdin_data {
+        vec![STDIN_FONT_NAME.to_string()]
+    } else if args.all {
+        font_export::enumerate_font_families_in(args.scope)?
+    } else {
+        let mut font_names = args.font_name.clone();
+        for hex in &args.font_name_hex {
+            font_names.push(fontname::decode_hex(hex)?);
+        }
+        if let Some(path) = &args.from_file {
+            font_names.extend(fontname::read_list(path)?);
+        }
+        for font_name in &font_names {
+            fontname::check(font_name)?;
+        }
+        expand_patterns(font_names, args.scope)?
+    };
+    timings.since(Stage::Enumeration, started);
+    let mut base_logfont = match &args.logfont {
+        Some(path) => logfont::LogFont::load(path)?,
+        None => logfont::LogFont::default(),
+    };
+    if let Some(px) = args.pixel_size {
+        // 負の高さは (セル高さではなく) 文字の高さでの指定になり、ビットマップのストライクのサイズと一致する
+        base_logfont.height = -i32::from(px);
+    }
+    if let Some(weight) = args.weight {
+        base_logfont.weight = weight;
+    }
+    if args.italic {
+        base_logfont.italic = true;
+    }
+    if let Some(charset) = args.charset {
+        base_logfont.charset = charset;
+    }
+    let reporter = events::Reporter::new(
+        args.events,
+        sink::is_stdout(args.sink.as_deref(), &args.output_dir),
+    );
+    let started = Instant::now();
+    // --all と --stdin-data の名前は照合するまでもない
+    let index = (args.exact && !args.all && !args.stdin_data)
+        .then(font_export::FaceIndex::load)
+        .transpose()?;
+    // PostScript 名は DirectWrite (Windows 以外では fontconfig) で引き、
+    // GDI で選ぶためのファミリー名と太さ・斜体にする
+    let postscript_faces = args
+        .postscript_name
+        .iter()
+        .map(|name| {
+            let (family, face) = find_postscript_name(name)?
+                .ok_or_else(|| FontExtractorError::PostScriptNameNotFound { name: name.clone() })?;
+            Ok((name, family, face))
+        })
+        .collect::<Result<Vec<_>, FontExtractorError>>()?;
+    let mut jobs: Vec<Job<'_>> = postscript_faces
+        .iter()
+        .map(|(name, family, face)| Job {
+            face: Some(name.to_string()),
+            ..face_job(family, face, base_logfont)
+        })
+        .collect();
+    for name in &font_names {
+        let plain = Job {
+            request: FontRequest {
+                name,
+                logfont: base_logfont,
+            },
+            face: None,
+        };
+        let exact = match &index {
+            Some(index) if plain.request.file_path().is_none() => Some(index.resolve(name)?),
+            _ => None,
+        };
+        match exact {
+            Some(ExactMatch::Face { family, face }) => {
+                jobs.push(face_job(family, face, base_logfont))
+            }
+            Some(ExactMatch::Family(family)) if args.all_styles => {
+                jobs.extend(expand_styles(family, base_logfont, &reporter)?)
+            }
+            Some(ExactMatch::Family(family)) => jobs.push(Job {
+                request: FontRequest {
+                    name: family,
+                    logfont: base_logfont,
+                },
+                face: None,
+            }),
+            None if args.all_styles => jobs.extend(expand_styles(name, base_logfont, &reporter)?),
+            None => jobs.push(plain),
+        }
+    }
+    timings.since(Stage::Enumeration, started);
+    if args.table_list {
+        for (i, job) in jobs.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            print_table_list(&job.request)?;
+        }
+        return Ok(());
+    }
+
+    // 標準入力のデータは一度しか読めないため、ここで読み取っておく
+    let started = Instant::now();
+    let stdin_data = args
+        .stdin_data
+        .then(font_export::read_font_stdin)
+        .transpose()?;
+    timings.since(Stage::Read, started);
+
+    #[cfg(feature = "network")]
+    let mut summary = notify::RunSummary::start();
+    // 書き込み先と DC はすべてのフォントで共有する (zip / tar は 1 つのアーカイブにまとめる)
+    let mut sink = open_sink(args)?;
+    if let Some(tag) = &args.table {
+        for job in &jobs {
+            write_table(
+                job,
+                tag,
+                args,
+                stdin_data.as_deref(),
+                sink.as_mut(),
+                &reporter,
+            )?;
+        }
+        return sink.finish();
+    }
+    let dc = SafeDC::new()?;
+    let mut results = Vec::with_capacity(jobs.len());
+    let mut notes = RunNotes {
+        emitters: select_emitters(args)?,
+        registered: if args.stdin_data {
+            Vec::new()
         } else {
-            Ok(Self(hdc))
+            fontreg::registered_fonts()?
+        },
+        timings,
+        ..RunNotes::default()
+    };
+    for job in &jobs {
+        let result = extract_to_file(
+            &dc,
+            job,
+            stdin_data.as_deref(),
+            args,
+            &reporter,
+            sink.as_mut(),
+            &mut notes,
+        );
+        #[cfg(feature = "network")]
+        summary.record(job.name(), &result);
+        if let Err(e) = &result {
+            reporter.emit(events::Event::Failed {
+                font: job.name(),
+                error: e.to_string(),
+            });
+            #[cfg(feature = "archive")]
+            diagnostics::record_failure(&job.request, stdin_data.as_deref(), e);
         }
+        results.push((job.name(), result));
+    }
+    finish_emitters(&mut notes, sink.as_mut(), &reporter)?;
+    let started = Instant::now();
+    sink.finish()?;
+    notes.timings.since(Stage::Io, started);
+    // 同じファミリーの中で同じスタイルを名乗るフォントは一方が他方を隠してしまう
+    let linked: Vec<(&str, &StyleLinking)> = notes
+        .linked
+        .iter()
+        .map(|(font_name, linking)| (*font_name, linking))
+        .collect();
+    for conflict in style::family_conflicts(&linked) {
+        reporter.say(format!("Warning: style linking: {conflict}"));
+    }
+    if results.len() > 1 || args.all || args.from_file.is_some() {
+        print_run_summary(&results, &notes, args.all, &reporter);
     }
-    fn get(&self) -> HDC {
-        self.0
+    if args.timings {
+        notes.timings.report(&reporter);
+    }
+
+    // --- 完了通知 (通知の失敗は抽出結果を上書きしない) ---
+    #[cfg(feature = "network")]
+    if let Some(url) = &args.notify_webhook
+        && let Err(e) = summary.post(url)
+    {
+        eprintln!("Warning: {e}");
+    }
+
+    let total = results.len();
+    let failed = results
+        .iter()
+        .filter(|(_, r)| outcome(r, args.all) == "FAILED")
+        .count();
+    match results.pop() {
+        // フォントが 1 つならそのエラーをそのまま返す
+        Some((_, Err(e))) if total == 1 && !args.all => Err(e),
+        _ if failed > 0 => Err(FontExtractorError::ExtractionFailed { failed, total }),
+        _ => Ok(()),
     }
 }
-impl Drop for SafeDC {
-    fn drop(&mut self) {
-        if !self.0.is_invalid() {
-            let _ = unsafe { DeleteDC(self.0) };
+
+/// 抽出する 1 つのフォント
+struct Job<'a> {
+    request: FontRequest<'a>,
+    /// `--all-styles` / `--exact` で選んだフェイスのフルネーム ("Arial Bold Italic" など)、
+    /// または `--postscript-name` で指定した PostScript 名
+    face: Option<String>,
+}
+
+impl Job<'_> {
+    /// 表示と出力ファイル名に使う名前 (フェイスの名前、無ければ要求したフォント名)
+    fn name(&self) -> &str {
+        self.face.as_deref().unwrap_or(self.request.name)
+    }
+}
+
+/// --- ファミリーをフェイス (スタイル) ごとの抽出に展開 (`--all-styles`) ---
+/// フェイスは LOGFONT の太さと斜体で選び分けます。フォントファイルのパスと、
+/// フェイスを列挙できない (インストールされていない) 名前はそのまま 1 つの抽出にします。
+fn expand_styles<'a>(
+    name: &'a str,
+    logfont: logfont::LogFont,
+    reporter: &events::Reporter,
+) -> Result<Vec<Job<'a>>, FontExtractorError> {
+    let faces = if FontRequest::named(name).file_path().is_some() {
+        Vec::new()
+    } else {
+        font_export::enumerate_font_faces(name)?
+    };
+    if faces.is_empty() {
+        return Ok(vec![Job {
+            request: FontRequest { name, logfont },
+            face: None,
+        }]);
+    }
+    let mut jobs: Vec<Job<'a>> = Vec::new();
+    for face in &faces {
+        // GDI は太さと斜体でしかフェイスを選べないため、それが同じフェイスは最初のものしか取り出せない
+        if let Some(first) = jobs.iter().find(|job| {
+            job.request.logfont.weight == face.weight && job.request.logfont.italic == face.italic
+        }) {
+            reporter.say(format!(
+                "Warning: '{}' has the same weight and italic flag as '{}'; GDI cannot select it separately, skipped",
+                face.full_name,
+                first.name()
+            ));
+            continue;
         }
+        jobs.push(face_job(name, face, logfont));
+    }
+    Ok(jobs)
+}
+
+/// `family` の `face` を太さと斜体で選ぶ抽出
+fn face_job<'a>(family: &'a str, face: &FontFace, logfont: logfont::LogFont) -> Job<'a> {
+    Job {
+        request: FontRequest {
+            name: family,
+            logfont: logfont::LogFont {
+                weight: face.weight,
+                italic: face.italic,
+                ..logfont
+            },
+        },
+        face: Some(face.full_name.clone()),
     }
 }
 
-/// --- RAII ラッパー: SafeFont ---
-struct SafeFont(HFONT);
-impl SafeFont {
-    fn create(font_name: &str) -> Result<Self, FontExtractorError> {
-        let font_name_wide: Vec<u16> = OsStr::new(font_name)
-            .encode_wide()
-            .chain(std::iter::once(0))
+/// --- `*` を含むフォント名を、一致するインストール済みファミリー名に展開 ---
+/// パターンに一致したファミリーが既に一覧にあれば、重ねては抽出しません。
+fn expand_patterns(
+    font_names: Vec<String>,
+    scope: fontreg::Scope,
+) -> Result<Vec<String>, FontExtractorError> {
+    if !font_names.iter().any(|name| fontname::is_pattern(name)) {
+        return Ok(font_names);
+    }
+    let families = font_export::enumerate_font_families_in(scope)?;
+    let mut expanded: Vec<String> = Vec::new();
+    for name in font_names {
+        if !fontname::is_pattern(&name) {
+            expanded.push(name);
+            continue;
+        }
+        let matched: Vec<&String> = families
+            .iter()
+            .filter(|family| fontname::matches_pattern(&name, family))
             .collect();
-        let pcwstr_font_name = PCWSTR(font_name_wide.as_ptr());
-        let font = unsafe {
-            CreateFontW(
-                0,
-                0,
-                0,
-                0,
-                FW_NORMAL.0.try_into().unwrap(),
-                0,
-                0,
-                0,
-                DEFAULT_CHARSET.0.into(),
-                OUT_DEFAULT_PRECIS.0.into(),
-                CLIP_DEFAULT_PRECIS.0.into(),
-                DEFAULT_QUALITY.0.into(),
-                (DEFAULT_PITCH.0 | FF_DONTCARE.0).into(),
-                pcwstr_font_name,
-            )
-        };
-        if font.is_invalid() {
-            Err(FontExtractorError::WinApi {
-                api_name: format!("CreateFontW (font: '{}')", font_name),
-                source: WinError::from_win32(),
-            })
-        } else {
-            Ok(Self(font))
+        if matched.is_empty() {
+            return Err(FontExtractorError::InvalidFontName {
+                name,
+                reason:
+                    "no installed font family matches it; run `list` to see the installed families"
+                        .to_string(),
+            });
+        }
+        for family in matched {
+            if !expanded.contains(family) {
+                expanded.push(family.clone());
+            }
         }
     }
-    fn get(&self) -> HFONT {
-        self.0
+    Ok(expanded)
+}
+
+/// --- 書き込み先 (シンク) を開く ---
+fn open_sink(args: &Args) -> Result<Box<dyn sink::Sink>, FontExtractorError> {
+    // 出力先の {hostname} や {date} などのテンプレート変数を展開する
+    let output_dir = PathBuf::from(template::expand(&args.output_dir.to_string_lossy())?);
+    let sink_spec = args.sink.as_deref().map(template::expand).transpose()?;
+    sink::open(
+        sink_spec.as_deref(),
+        &sink::SinkOptions {
+            output_dir: &output_dir,
+            on_conflict: args.on_conflict,
+            #[cfg(feature = "network")]
+            headers: &args.sink_header,
+            #[cfg(feature = "network")]
+            s3_endpoint: args.s3_endpoint.as_deref(),
+            #[cfg(feature = "network")]
+            transfer: sink::TransferOptions {
+                retries: args.upload_retries,
+                limit: args.upload_limit,
+            },
+        },
+    )
+}
+
+/// --- 複数のフォントを抽出したときの結果の一覧 ---
+fn print_run_summary(
+    results: &[(&str, Result<Option<String>, FontExtractorError>)],
+    notes: &RunNotes<'_>,
+    all: bool,
+    reporter: &events::Reporter,
+) {
+    let substituted = &notes.substituted;
+    // 代替フォントが書き出されたものは OK ではなく SUBSTITUTED として数える
+    let label = |font_name: &str, result| match outcome(result, all) {
+        "OK" if substituted.iter().any(|(name, _)| *name == font_name) => "SUBSTITUTED",
+        label => label,
+    };
+    let count = |wanted: &str| {
+        results
+            .iter()
+            .filter(|(font_name, r)| label(font_name, r) == wanted)
+            .count()
+    };
+    reporter.say("");
+    let unreadable = if all {
+        format!(", {} unreadable", count("UNREADABLE"))
+    } else {
+        String::new()
+    };
+    let substitutions = match count("SUBSTITUTED") {
+        0 => String::new(),
+        n => format!(", {n} substituted"),
+    };
+    reporter.say(format!(
+        "Summary: {} extracted{substitutions}, {} skipped{unreadable}, {} failed",
+        count("OK"),
+        count("SKIPPED"),
+        count("FAILED")
+    ));
+    for (font_name, result) in results {
+        let label = label(font_name, result);
+        let substitute = substituted
+            .iter()
+            .find(|(name, _)| name == font_name)
+            .map(|(_, family)| format!(" (GDI picked '{family}')"))
+            .unwrap_or_default();
+        let fallback = notes
+            .fallbacks
+            .iter()
+            .find(|(name, _)| name == font_name)
+            .map(|(_, backend)| format!(" (read through {})", backend.label()))
+            .unwrap_or_default();
+        reporter.say(match result {
+            Ok(Some(location)) => {
+                format!("  {label:<11} {font_name} -> {location}{substitute}{fallback}")
+            }
+            Ok(None) => format!("  {label:<11} {font_name}"),
+            Err(e) => format!("  {label:<11} {font_name}: {e}"),
+        });
     }
 }
-impl Drop for SafeFont {
-    fn drop(&mut self) {
-        if !self.0.is_invalid() {
-            let _ = unsafe { DeleteObject(self.0) };
-        }
+
+/// 1 フォントの結果の分類 ("OK", "SKIPPED", "UNREADABLE" または "FAILED")
+///
+/// `--all` では、GDI がデータを返さないフォントは実行全体の失敗にせず "UNREADABLE" として報告だけします。
+fn outcome(result: &Result<Option<String>, FontExtractorError>, all: bool) -> &'static str {
+    match result {
+        Ok(Some(_)) => "OK",
+        Ok(None) => "SKIPPED",
+        Err(e) if all && font_export::is_unreadable(e) => "UNREADABLE",
+        Err(_) => "FAILED",
     }
 }
 
-/// --- RAII ラッパー: FontSelector ---
-struct FontSelector<'dc> {
-    dc: &'dc SafeDC,
-    old_font: Option<HGDIOBJ>,
-    _marker: PhantomData<&'dc ()>,
+/// --- 1 回の実行の中でフォントをまたいで記録する情報 ---
+#[derive(Default)]
+struct RunNotes<'a> {
+    /// `--style-linking` で読み取った値 (同じファミリーで同じスタイルを名乗るフォントの検出用)
+    linked: Vec<(&'a str, StyleLinking)>,
+    /// GDI が代替フォントを返したフォント名と、代わりに選ばれたファミリー名
+    substituted: Vec<(&'a str, String)>,
+    /// GDI で読み取れず、別の方法で読み取ったフォント名とその方法
+    fallbacks: Vec<(&'a str, Backend)>,
+    /// `--whole-collection` / `--split-ttc` で書き出したコレクションの SHA-256 と書き込み先
+    collections: Vec<(String, String)>,
+    /// `--emit` で選んだエミッター
+    emitters: Vec<Box<dyn emitter::Emitter>>,
+    /// レジストリの Fonts キーに登録されたフォント (インストール先の判定用)
+    registered: Vec<fontreg::RegisteredFont>,
+    /// 段階ごとの所要時間 (`--timings`)
+    timings: timings::Timings,
 }
-impl<'dc> FontSelector<'dc> {
-    fn select(dc: &'dc SafeDC, font: &SafeFont) -> Result<Self, FontExtractorError> {
-        let old_font = unsafe { SelectObject(dc.get(), font.get()) };
-        if old_font.is_invalid() {
-            Err(FontExtractorError::WinApi {
-                api_name: "SelectObject (select new font)".to_string(),
-                source: WinError::from_win32(),
-            })
-        } else {
-            Ok(Self {
-                dc,
-                old_font: Some(old_font),
-                _marker: PhantomData,
+
+/// `--stdin-data` で処理するフォントの名前 (出力ファイル名にも使う)
+const STDIN_FONT_NAME: &str = "stdin";
+
+/// --- フォントを抽出してファイルに保存 ---
+/// 書き込み先を返します。書き込みをスキップした場合は `None` を返します。
+/// `stdin_data` は `--stdin-data` で受け取ったデータで、指定されていれば GDI やファイルからは読み取りません。
+fn extract_to_file<'a>(
+    dc: &SafeDC,
+    job: &'a Job<'a>,
+    stdin_data: Option<&[u8]>,
+    args: &Args,
+    reporter: &events::Reporter,
+    sink: &mut dyn sink::Sink,
+    notes: &mut RunNotes<'a>,
+) -> Result<Option<String>, FontExtractorError> {
+    let request = &job.request;
+    let font_name = job.name();
+    reporter.say(format!("Extracting font data for: {}", font_name));
+    notes.timings.font();
+    // インストールされていないフォント (ファイルや標準入力) は信頼できない入力として上限と比べる
+    let installed = request.file_path().is_none() && stdin_data.is_none();
+    if let Some(path) = request.file_path() {
+        args.limits.check_file(path)?;
+    }
+    // --- インストール先 (ユーザーごとか、すべてのユーザー向けか) ---
+    // 登録された名前で見つからないフォント (代替フォントなど) は、範囲を指定したときだけエラーにする
+    let scope = if installed {
+        let located = fontreg::locate(&notes.registered, request, args.scope)
+            .map(|(font, _)| (font.scope, font.path.clone()));
+        match located {
+            Some((scope, path)) => {
+                reporter.say(format!(
+                    "Installed for: {} ({})",
+                    match scope {
+                        fontreg::Scope::User => "the current user",
+                        _ => "all users",
+                    },
+                    path.display()
+                ));
+                Some(scope)
+            }
+            None if args.scope != fontreg::Scope::All => {
+                return Err(FontExtractorError::NotInScope {
+                    name: font_name.to_string(),
+                    scope: args.scope.describe(),
+                });
+            }
+            None => None,
+        }
+    } else {
+        None
+    };
+    // `--bypass-cache` やフォールバックで読み取ったフォントファイル (コレクション全体の読み取りにも使う)
+    let mut registered_file = None;
+    // インストール済みフォントを読み取った方法
+    let mut backend = None;
+    let (buffer, times) = match stdin_data {
+        Some(data) => {
+            args.limits
+                .check_size("standard input", data.len() as u64)?;
+            (data.to_vec(), ReadTimes::default())
+        }
+        None if installed && (args.bypass_cache || args.copy_original) => {
+            let started = Instant::now();
+            let (data, path) = extract_font_data_uncached(request, args.scope)?;
+            if args.copy_original {
+                reporter.say(format!("Copying the original file {}", path.display()));
+            } else {
+                reporter.say(format!(
+                    "Read {} directly, bypassing the font cache",
+                    path.display()
+                ));
+            }
+            registered_file = Some(path);
+            backend = Some(Backend::Registry);
+            let times = ReadTimes {
+                read: started.elapsed(),
+                ..ReadTimes::default()
+            };
+            (data, times)
+        }
+        None if installed && !args.no_fallback => {
+            let read = extract_font_data_with_fallback(dc, request, args.scope)?;
+            if let Some((failed, reason)) = read.failures.first() {
+                reporter.say(format!(
+                    "Warning: {} could not read '{font_name}' ({reason}); read through {} instead",
+                    failed.label(),
+                    read.backend.label()
+                ));
+                notes.fallbacks.push((font_name, read.backend));
+            }
+            registered_file = read.path;
+            backend = Some(read.backend);
+            (read.data, read.times)
+        }
+        None => {
+            backend = installed.then(Backend::native).flatten();
+            extract_font_data_timed(dc, request)?
+        }
+    };
+    if !installed {
+        let started = Instant::now();
+        args.limits.check(&buffer)?;
+        notes.timings.since(Stage::Checks, started);
+    }
+    notes.timings.add(Stage::Resolution, times.resolution);
+    notes.timings.add(Stage::Read, times.read);
+    notes.timings.read(buffer.len());
+    reporter.emit(events::Event::Resolved {
+        font: font_name,
+        family: family_name(&buffer, &args.name_languages),
+        scope: scope.map(fontreg::Scope::keyword),
+    });
+    reporter.emit(events::Event::Read {
+        font: font_name,
+        bytes: buffer.len(),
+        sha256: digest::sha256_hex(&buffer),
+        backend: backend.map(Backend::keyword),
+    });
+    reporter.say(format!("Font data size: {} bytes", buffer.len()));
+
+    // --- GDI が要求どおりのフォントを選んだか (代替フォントの検出) ---
+    // 別のフォントを要求した名前で書き出すのは危険なため、`--allow-substitution` が無ければエラーにする
+    let started = Instant::now();
+    if installed
+        && let Some(family) = substitute_family(request.name, &buffer, &args.name_languages)
+    {
+        if !args.allow_substitution {
+            return Err(FontExtractorError::FontSubstituted {
+                requested: font_name.to_string(),
+                got: family,
+            });
+        }
+        reporter.say(format!(
+            "Warning: '{font_name}' is not installed; GDI substituted '{family}'"
+        ));
+        notes.substituted.push((font_name, family));
+    }
+
+    // --- 要求した太さのフェイスが選ばれたか (無ければ GDI は近い太さのフェイスを返す) ---
+    if let Some(weight) = args.weight
+        && installed
+        && let Some(selected) = weight_class(&buffer)
+        && i32::from(selected) != weight
+    {
+        reporter.say(format!(
+            "Warning: weight {weight} was requested but GDI selected '{}' with weight {selected}; the family has no face of that weight",
+            full_name(&buffer, &args.name_languages).unwrap_or_else(|| font_name.to_string())
+        ));
+    }
+
+    // --- 斜体のフェイスが選ばれたか (無ければ GDI は立体のフェイスを返し、描画時に傾けるだけ) ---
+    if args.italic
+        && installed
+        && StyleLinking::inspect(&buffer).is_ok_and(|linking| !linking.is_italic())
+    {
+        reporter.say(format!(
+            "Warning: italic was requested but GDI selected '{}', which is not italic; the family has no italic face (GDI only slants the upright one on screen)",
+            full_name(&buffer, &args.name_languages).unwrap_or_else(|| font_name.to_string())
+        ));
+    }
+    notes.timings.since(Stage::Checks, started);
+
+    // --- コレクション全体 (照合や変換はコレクションファイル全体に対して行う) ---
+    let mut collection_hash = None;
+    let started = Instant::now();
+    let collection = match stdin_data {
+        // 標準入力のデータはそのままコレクション全体
+        Some(data) => ((args.whole_collection || args.split_ttc) && data.get(..4) == Some(b"ttcf"))
+            .then(|| data.to_vec()),
+        None => (args.whole_collection || args.split_ttc || args.copy_original)
+            .then(|| match &registered_file {
+                Some(path) => read_collection_file(path),
+                None => extract_collection_on(dc, request),
             })
+            .transpose()?
+            .flatten(),
+    };
+    notes.timings.since(Stage::Read, started);
+    let buffer = match collection {
+        Some(collection) => {
+            notes.timings.read(collection.len());
+            let sha256 = digest::sha256_hex(&collection);
+            if let Some((_, location)) = notes.collections.iter().find(|(hash, _)| *hash == sha256)
+            {
+                reporter.say(format!(
+                    "Skipping: the collection containing '{font_name}' was already written to {location}"
+                ));
+                reporter.emit(events::Event::Skipped {
+                    font: font_name,
+                    file: location,
+                });
+                return Ok(None);
+            }
+            reporter.say(format!(
+                "'{font_name}' is part of a collection with {} face(s); {} ({} bytes)",
+                sfnt::read_u32(&collection, 8)?,
+                if args.split_ttc {
+                    "writing each face as a standalone font"
+                } else {
+                    "writing the whole collection"
+                },
+                collection.len()
+            ));
+            collection_hash = Some(sha256);
+            collection
         }
+        None => buffer,
+    };
+
+    // --- チェックサムの検証 (切り詰められたデータなどを書き出す前に見つける) ---
+    let started = Instant::now();
+    let problems = sfnt::checksum_problems(&buffer).unwrap_or_else(|e| vec![e.to_string()]);
+    if !problems.is_empty() {
+        let problems = problems.join("; ");
+        if !args.no_verify {
+            return Err(FontExtractorError::ChecksumMismatch {
+                font: font_name.to_string(),
+                problems,
+            });
+        }
+        reporter.say(format!("Warning: checksum verification failed: {problems}"));
+    }
+
+    // --- 埋め込みの制限 (OS/2 fsType が Restricted License のフォントは書き出さない) ---
+    check_embedding(
+        restricted_license(&buffer, &args.name_languages),
+        font_name,
+        args.ignore_embedding_restrictions,
+        |message| reporter.say(message),
+    )?;
+
+    // --- カタログのハッシュとの照合 ---
+    if let Some(catalog_path) = &args.verify_against {
+        match verify::Catalog::load(catalog_path)?.verify(&buffer)? {
+            verify::Verification::Trusted => {
+                reporter.say("Verified: data matches the known-good hash in the catalog")
+            }
+            verify::Verification::Unknown { family, version } => reporter.say(format!(
+                "Warning: '{family}' ({version}) is not listed in the catalog; cannot verify"
+            )),
+        }
+    }
+
+    // --- Windows のカタログとの照合 ---
+    #[cfg(windows)]
+    if args.check_windows_catalog {
+        match wincatalog::find_catalog(&buffer)? {
+            Some(catalog) => reporter.say(format!(
+                "Windows catalog: data matches a hash signed in {catalog}"
+            )),
+            None => reporter.say(
+                "Warning: data is not in any Windows catalog; this is not a Windows-shipped font or it has been modified",
+            ),
+        }
+    }
+
+    // --- DirectWrite の名前解決との照合 (フォントファイルと標準入力は対象外) ---
+    #[cfg(backend = "dwrite")]
+    if args.check_directwrite && installed {
+        match directwrite::cross_check(request, &buffer)? {
+            directwrite::Resolution::Same => {
+                reporter.say("DirectWrite: resolves the name to the same font")
+            }
+            directwrite::Resolution::Different { full_name } => reporter.say(format!(
+                "Warning: DirectWrite resolves '{}' to '{full_name}', not the font GDI returned; apps using DirectWrite will show a different font",
+                request.name
+            )),
+            directwrite::Resolution::FamilyNotFound => reporter.say(format!(
+                "Warning: DirectWrite has no family named '{}'; apps using DirectWrite will fall back to another font",
+                request.name
+            )),
+        }
+    }
+
+    notes.timings.since(Stage::Checks, started);
+
+    // --- 縦書きメトリクスの合成 (カタログとの照合は元のデータで行う) ---
+    let started = Instant::now();
+    let buffer = if args.synthesize_vertical {
+        match sfnt::vertical::add_vertical_metrics(&buffer)? {
+            Some(synthesized) => {
+                reporter.say("Synthesized vertical metrics (vhea/vmtx)");
+                reporter.emit(events::Event::Converted {
+                    font: font_name,
+                    step: "synthesize-vertical",
+                    bytes: synthesized.len(),
+                });
+                synthesized
+            }
+            None => {
+                reporter.say("Font already has vertical metrics; leaving them unchanged");
+                buffer
+            }
+        }
+    } else {
+        buffer
+    };
+
+    // --- スタイルリンクの検査と修正 ---
+    let buffer = match args.style_linking {
+        Some(mode) => {
+            let linking = StyleLinking::inspect(&buffer)?;
+            for problem in linking.problems() {
+                reporter.say(format!("Warning: style linking: {problem}"));
+            }
+            notes.linked.push((font_name, linking));
+            match mode {
+                StyleLinkingMode::Check => buffer,
+                StyleLinkingMode::Fix => match style::fix(&buffer)? {
+                    Some(fixed) => {
+                        reporter.say("Fixed fsSelection/macStyle to match the subfamily name");
+                        reporter.emit(events::Event::Converted {
+                            font: font_name,
+                            step: "fix-style-linking",
+                            bytes: fixed.len(),
+                        });
+                        fixed
+                    }
+                    None => buffer,
+                },
+            }
+        }
+        None => buffer,
+    };
+
+    // --- cmap の書き換え ---
+    let buffer = match &args.remap {
+        Some(remap_path) => {
+            let remapped = remap::Remap::load(remap_path)?.apply(&buffer)?;
+            if !remapped.missing.is_empty() {
+                reporter.say(format!(
+                    "Warning: remap: {} not in the font's cmap; skipped",
+                    codepoint_list(&remapped.missing)
+                ));
+            }
+            if !remapped.replaced.is_empty() {
+                reporter.say(format!(
+                    "Remap replaced the existing glyph of {}",
+                    codepoint_list(&remapped.replaced)
+                ));
+            }
+            reporter.say(format!(
+                "Remapped {} codepoint(s) in cmap",
+                remapped.remapped
+            ));
+            reporter.emit(events::Event::Converted {
+                font: font_name,
+                step: "remap",
+                bytes: remapped.data.len(),
+            });
+            remapped.data
+        }
+        None => buffer,
+    };
+
+    // --- 出力ファイル名の構築 ---
+    // ユーザー指定のフォント名に既に拡張子がある場合は上書きします (`--no-auto-extension` では名前をそのまま使います)。
+    // フォントファイルのパスが指定された場合はファイル名の部分だけを使います。
+    // `--all-styles` などで選んだフェイスはフルネーム (`--postscript-name` では PostScript 名) を使います。
+    // 太さや斜体で選んだフェイスも、読み取ったデータのフルネームを使います (コレクション全体は除く)。
+    let mut file_name = match (request.file_path(), &job.face) {
+        (Some(path), _) => PathBuf::from(path.file_name().unwrap_or_default()),
+        (None, Some(face)) => PathBuf::from(output::file_name_safe(face)),
+        (None, None)
+            if installed && selects_face(&request.logfont) && buffer.get(..4) != Some(b"ttcf") =>
+        {
+            let name = full_name(&buffer, &args.name_languages);
+            PathBuf::from(name.map_or_else(
+                || font_name.to_string(),
+                |name| output::file_name_safe(&name),
+            ))
+        }
+        (None, None) => PathBuf::from(font_name),
+    };
+    if !args.no_auto_extension {
+        file_name.set_extension(extension(&buffer)); // 拡張子を上書き
+    }
+
+    // --- コレクションの分割 (フェイスごとに単体のフォントとして書き出す) ---
+    let outputs: Vec<(String, Vec<u8>)> = if args.split_ttc && buffer.get(..4) == Some(b"ttcf") {
+        // フェイスのファイル名は生成したものなので、`--no-auto-extension` でも拡張子を付ける
+        let stem = file_name.with_extension("");
+        let stem = stem.to_string_lossy();
+        let mut names: Vec<String> = Vec::new();
+        let mut outputs: Vec<(String, Vec<u8>)> = Vec::new();
+        for (index, face) in sfnt::split_collection(&buffer)?.into_iter().enumerate() {
+            // フェイスのフルネームを使い、読み取れないか重複するときは番号を付ける
+            let mut name = full_name(&face, &args.name_languages)
+                .map(|name| output::file_name_safe(&name))
+                .unwrap_or_else(|| format!("{stem}-{index}"));
+            if names.contains(&name) {
+                name = format!("{name}-{index}");
+            }
+            outputs.push((format!("{name}.{}", extension(&face)), face));
+            names.push(name);
+        }
+        reporter.say(format!(
+            "Split the collection into {} standalone font(s)",
+            outputs.len()
+        ));
+        outputs
+    } else {
+        vec![(file_name.to_string_lossy().into_owned(), buffer)]
+    };
+
+    // --- テーブルの削除 (コレクションは分割したフェイスごとに) ---
+    let outputs = if args.strip_tables.is_empty() {
+        outputs
+    } else {
+        outputs
+            .into_iter()
+            .map(|(file_name, buffer)| strip_tables(args, reporter, font_name, file_name, buffer))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    notes.timings.since(Stage::Conversion, started);
+
+    // --- Zone.Identifier (指定が無ければ入力のフォントファイルのものを引き継ぐ) ---
+    let marking = if args.strip_zone_identifier {
+        Some(zone::Marking::Strip)
+    } else if let Some(zone) = args.zone_identifier {
+        let origin = args
+            .zone_origin
+            .as_deref()
+            .map(template::expand)
+            .transpose()?;
+        Some(zone::Marking::Write(zone::contents(
+            zone,
+            origin.as_deref(),
+        )))
+    } else {
+        request
+            .file_path()
+            .map(zone::read)
+            .transpose()?
+            .flatten()
+            .map(zone::Marking::Write)
+    };
+
+    // --- 書き込み先 (シンク) への書き込み ---
+    let mut locations = Vec::new();
+    for (file_name, buffer) in &outputs {
+        // GDI が返したデータが壊れていてもそのまま書き出さないよう、構造だけは必ず確かめる
+        sfnt::check_structure(buffer)?;
+        let started = Instant::now();
+        let location = sink.write(file_name, buffer)?;
+        let marked = match (&location, &marking) {
+            (Some(location), Some(marking)) => Some(sink.mark_zone(location, marking)),
+            _ => None,
+        };
+        notes.timings.since(Stage::Io, started);
+        match &location {
+            Some(location) => {
+                notes.timings.written(buffer.len());
+                reporter.say(format!("Font data written to: {location}"));
+                if let (Some(marking), Some(marked)) = (&marking, marked) {
+                    report_zone(args, reporter, marking, marked)?;
+                }
+                reporter.emit(events::Event::Written {
+                    font: font_name,
+                    location,
+                    bytes: buffer.len(),
+                });
+                locations.push(location.clone());
+            }
+            None => {
+                reporter.say(format!(
+                    "Skipping: {file_name} already exists with different content"
+                ));
+                reporter.emit(events::Event::Skipped {
+                    font: font_name,
+                    file: file_name,
+                });
+            }
+        }
+
+        // --- 追加のファイル (フォントと同じ書き込み先に置く) ---
+        if notes.emitters.is_empty() {
+            continue;
+        }
+        let family =
+            family_name(buffer, &args.name_languages).unwrap_or_else(|| font_name.to_string());
+        let input = emitter::EmitInput {
+            data: buffer,
+            file_name,
+            family: &family,
+            font_name,
+        };
+        for emitter in &mut notes.emitters {
+            let started = Instant::now();
+            let artifacts = emitter.emit(&input)?;
+            notes.timings.since(Stage::Conversion, started);
+            for artifact in artifacts {
+                write_artifact(&artifact, sink, &mut notes.timings, reporter)?;
+            }
+        }
+    }
+    // 分割した場合は書き込んだすべての場所をまとめて返す
+    let location = (!locations.is_empty()).then(|| locations.join(", "));
+
+    if let (Some(hash), Some(location)) = (collection_hash, &location) {
+        notes.collections.push((hash, location.clone()));
     }
+
+    reporter.say("Font data extracted successfully!");
+    Ok(location)
 }
-impl<'dc> Drop for FontSelector<'dc> {
-    fn drop(&mut self) {
-        if let Some(old_font_handle) = self.old_font {
-            let _ = unsafe { SelectObject(self.dc.get(), old_font_handle) };
+
+/// `--emit` と (`plugins` フィーチャーでは) `--emitter` から、使うエミッターを選びます。
+fn select_emitters(args: &Args) -> Result<Vec<Box<dyn emitter::Emitter>>, FontExtractorError> {
+    #[cfg_attr(not(feature = "plugins"), allow(unused_mut))]
+    let mut registry = emitter::Registry::with_builtins(&emitter::EmitOptions {
+        font_display: args.font_display,
+        css_fallback: args.css_fallback.clone(),
+    });
+    #[cfg(feature = "plugins")]
+    for external in &args.emitter {
+        registry.register(
+            external.name.clone(),
+            format!("Runs '{}'", external.program.display()),
+            Box::new(external.clone()),
+        );
+    }
+    registry.take(&args.emit)
+}
+
+/// すべてのフォントを書き出した後に、エミッターの残りの成果物 (`--emit family-css` など) を書き出します。
+fn finish_emitters(
+    notes: &mut RunNotes<'_>,
+    sink: &mut dyn sink::Sink,
+    reporter: &events::Reporter,
+) -> Result<(), FontExtractorError> {
+    for emitter in &mut notes.emitters {
+        let started = Instant::now();
+        let artifacts = emitter.finish()?;
+        notes.timings.since(Stage::Conversion, started);
+        for artifact in artifacts {
+            write_artifact(&artifact, sink, &mut notes.timings, reporter)?;
         }
     }
+    Ok(())
 }
 
-/// --- main 関数 ---
-fn main() -> Result<(), FontExtractorError> {
-    // --- コマンドライン引数の解析 ---
-    let args = Args::parse();
-    let font_name = &args.font_name;
-    println!("Extracting font data for: {}", font_name);
+/// エミッターの成果物をフォントと同じ書き込み先に書き込みます。
+fn write_artifact(
+    artifact: &emitter::Artifact,
+    sink: &mut dyn sink::Sink,
+    timings: &mut timings::Timings,
+    reporter: &events::Reporter,
+) -> Result<(), FontExtractorError> {
+    let started = Instant::now();
+    let written = sink.write(&artifact.file_name, &artifact.contents)?;
+    timings.since(Stage::Io, started);
+    match written {
+        Some(location) => {
+            timings.written(artifact.contents.len());
+            reporter.say(format!("{} written to: {location}", artifact.label));
+        }
+        None => reporter.say(format!(
+            "Skipping: {} already exists with different content",
+            artifact.file_name
+        )),
+    }
+    Ok(())
+}
 
-    // --- リソースの確保 (RAII) ---
-    let dc = SafeDC::new()?;
-    let font = SafeFont::create(font_name)?;
-    let _font_selector = FontSelector::select(&dc, &font)?;
+/// `Zone.Identifier` の設定結果を表示します。
+///
+/// `--zone-identifier` / `--strip-zone-identifier` を指定したのに設定できなければエラーにします。
+/// 入力ファイルからの引き継ぎだけなら警告にとどめます (NTFS 以外のドライブへの書き込みなど)。
+fn report_zone(
+    args: &Args,
+    reporter: &events::Reporter,
+    marking: &zone::Marking,
+    marked: Result<bool, FontExtractorError>,
+) -> Result<(), FontExtractorError> {
+    let explicit = args.zone_identifier.is_some() || args.strip_zone_identifier;
+    match (marking, marked) {
+        (_, Err(e)) if explicit => return Err(e),
+        (_, Err(e)) => reporter.say(format!(
+            "Warning: cannot carry over the input file's Zone.Identifier: {e}"
+        )),
+        (_, Ok(false)) if explicit => {
+            return Err(FontExtractorError::InvalidSink {
+                spec: args.sink.clone().unwrap_or_default(),
+                reason: "a Zone.Identifier can only be set on files in a local directory (--output-dir or dir:PATH)"
+                    .to_string(),
+            });
+        }
+        (_, Ok(false)) => reporter
+            .say("Warning: the input file's Zone.Identifier cannot be carried over to this sink"),
+        (zone::Marking::Strip, Ok(true)) => reporter.say("Removed the Zone.Identifier stream"),
+        (zone::Marking::Write(_), Ok(true)) if explicit => {
+            reporter.say("Wrote the Zone.Identifier stream")
+        }
+        (zone::Marking::Write(_), Ok(true)) => {
+            reporter.say("Carried over the Zone.Identifier stream of the input file")
+        }
+    }
+    Ok(())
+}
 
-    // --- フォントデータの取得 ---
-    let data_size = unsafe { GetFontData(dc.get(), 0, 0, None, 0) };
+/// フォントデータの先頭 (sfnt バージョン) から拡張子を判定します。
+fn extension(data: &[u8]) -> &'static str {
+    match data.get(..4) {
+        Some(b"OTTO") => "otf",
+        Some(b"\x00\x01\x00\x00") => "ttf",
+        Some(b"ttcf") => "ttc",
+        _ => "bin", // 不明な場合はデフォルトで bin 拡張子
+    }
+}
 
-    if data_size == GDI_ERROR as u32 {
-        return Err(FontExtractorError::WinApi {
-            api_name: "GetFontData (get size)".to_string(),
-            source: WinError::from_win32(),
-        });
+/// `--strip-tables` のテーブルを取り除きます。フォントに無いテーブルは警告にとどめます。
+fn strip_tables(
+    args: &Args,
+    reporter: &events::Reporter,
+    font_name: &str,
+    file_name: String,
+    buffer: Vec<u8>,
+) -> Result<(String, Vec<u8>), FontExtractorError> {
+    let stripped = sfnt::strip_tables(&buffer, &args.strip_tables)?;
+    if !stripped.missing.is_empty() {
+        reporter.say(format!(
+            "Warning: strip-tables: {file_name} has no {} table(s); skipped",
+            tag_list(&stripped.missing)
+        ));
     }
-    if data_size == 0 {
-        return Err(FontExtractorError::ZeroSizeFont {
-            font_name: font_name.to_string(),
-        });
+    if stripped.removed.is_empty() {
+        return Ok((file_name, buffer));
     }
-    println!("Font data size: {} bytes", data_size);
-
-    let mut buffer: Vec<u8> = vec![0; data_size as usize];
-    let bytes_written = unsafe {
-        GetFontData(
-            dc.get(),
-            0,
-            0,
-            Some(buffer.as_mut_ptr() as *mut c_void),
-            data_size,
-        )
+    reporter.say(format!(
+        "Stripped {} from {file_name} ({} -> {} bytes)",
+        tag_list(&stripped.removed),
+        buffer.len(),
+        stripped.data.len()
+    ));
+    reporter.emit(events::Event::Converted {
+        font: font_name,
+        step: "strip-tables",
+        bytes: stripped.data.len(),
+    });
+    Ok((file_name, stripped.data))
+}
+
+/// テーブルタグの一覧を "'DSIG', 'EBDT'" の形式で表します。
+fn tag_list(tags: &[sfnt::Tag]) -> String {
+    tags.iter()
+        .map(|tag| format!("'{}'", sfnt::tag_to_string(tag)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// コードポイントの一覧を "U+E001, U+E002" の形式で表します。
+fn codepoint_list(codepoints: &[u32]) -> String {
+    codepoints
+        .iter()
+        .map(|c| format!("U+{c:04X}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// フォントデータの name テーブルからファミリー名を `languages` の言語を優先して読み取ります (読み取れなければ `None`)。
+fn family_name(data: &[u8], languages: &[String]) -> Option<String> {
+    let font = sfnt::Sfnt::parse_face(data, 0).ok()?;
+    let records = sfnt::name::parse(font.table(b"name")?).ok()?;
+    sfnt::name::find_preferred(&records, 16, languages)
+        .or_else(|| sfnt::name::find_preferred(&records, 1, languages))
+}
+
+/// フォントデータの name テーブルからフルネーム (name ID 4) を `languages` の言語を優先して読み取ります (読み取れなければ `None`)。
+fn full_name(data: &[u8], languages: &[String]) -> Option<String> {
+    let font = sfnt::Sfnt::parse_face(data, 0).ok()?;
+    let records = sfnt::name::parse(font.table(b"name")?).ok()?;
+    sfnt::name::find_preferred(&records, 4, languages)
+}
+
+/// 太さや斜体で、ファミリーの標準のフェイス以外を選んだかどうか (`--weight`、`--italic`、`--logfont`)
+///
+/// そのフェイスの出力ファイルはファミリー名ではなくフルネームで名前を付け、標準のフェイスを上書きしないようにします。
+fn selects_face(logfont: &logfont::LogFont) -> bool {
+    // 0 (FW_DONTCARE) と 400 (FW_NORMAL) は標準の太さ
+    logfont.italic || !matches!(logfont.weight, 0 | 400)
+}
+
+/// Restricted License embedding のフォントを書き出さないようにします。
+///
+/// `restricted` は [`restricted_license`] の結果です。書き出さない場合はライセンスの記載を `say` で表示してエラーを返し、
+/// `ignore` (`--ignore-embedding-restrictions`) では警告にとどめます。
+fn check_embedding(
+    restricted: Option<(Os2, Vec<String>)>,
+    font_name: &str,
+    ignore: bool,
+    say: impl Fn(String),
+) -> Result<(), FontExtractorError> {
+    let Some((os2, notice)) = restricted else {
+        return Ok(());
     };
-    if bytes_written == GDI_ERROR as u32 {
-        return Err(FontExtractorError::WinApi {
-            api_name: "GetFontData (get data)".to_string(),
-            source: WinError::from_win32(),
-        });
+    if ignore {
+        say(format!(
+            "Warning: '{font_name}' is marked {} embedding; writing it because of --ignore-embedding-restrictions",
+            os2.embedding().label()
+        ));
+        return Ok(());
     }
-    if bytes_written != data_size {
-        return Err(FontExtractorError::FontDataSizeMismatch {
-            expected: data_size,
-            got: bytes_written,
-        });
+    say(format!("License notice of '{font_name}':"));
+    for line in &notice {
+        say(format!("  {}", line.replace('\n', "\n  ")));
     }
+    Err(FontExtractorError::EmbeddingRestricted {
+        font: font_name.to_string(),
+        embedding: os2.embedding().label(),
+        fs_type: os2.fs_type,
+    })
+}
 
-    // --- フォントデータの先頭でフォント種別を判定 ---
-    let ext = if buffer.len() >= 4 {
-        if &buffer[..4] == b"OTTO" {
-            "otf"
-        } else if &buffer[..4] == b"\x00\x01\x00\x00" {
-            "ttf"
-        } else if &buffer[..4] == b"ttcf" {
-            "ttc"
-        } else {
-            "bin" // 不明な場合はデフォルトで bin 拡張子
-        }
+/// OS/2 fsType が Restricted License embedding のフェイス (TTC はいずれかのフェイス) の OS/2 と、
+/// name テーブルのライセンスの記載 (License と License URL、どちらも無ければ Copyright) を返します。
+fn restricted_license(data: &[u8], languages: &[String]) -> Option<(Os2, Vec<String>)> {
+    let num_faces = if data.get(..4) == Some(b"ttcf") {
+        sfnt::read_u32(data, 8).ok()?
     } else {
-        "bin"
+        1
+    };
+    (0..num_faces).find_map(|index| {
+        let font = sfnt::Sfnt::parse_face(data, index).ok()?;
+        restricted_license_of(font.table(b"OS/2"), font.table(b"name"), languages)
+    })
+}
+
+/// [`restricted_license`] の 1 フェイス分 (OS/2 と name テーブルから)
+fn restricted_license_of(
+    os2: Option<&[u8]>,
+    name: Option<&[u8]>,
+    languages: &[String],
+) -> Option<(Os2, Vec<String>)> {
+    let os2 = Os2::parse(os2?).ok()?;
+    if os2.embedding() != Embedding::Restricted {
+        return None;
+    }
+    let records = name
+        .and_then(|name| sfnt::name::parse(name).ok())
+        .unwrap_or_default();
+    let notice = |name_id: u16| {
+        let value = sfnt::name::find_preferred(&records, name_id, languages)?;
+        Some(format!("{}: {value}", sfnt::name::label(name_id)))
     };
+    let mut lines: Vec<String> = [13, 14].into_iter().filter_map(notice).collect();
+    if lines.is_empty() {
+        lines.extend(notice(0));
+    }
+    if lines.is_empty() {
+        lines.push("(the font has no license or copyright notice)".to_string());
+    }
+    Some((os2, lines))
+}
 
-    // --- 出力パスの構築 ---
-    // ユーザー指定のフォント名に既に拡張子がある場合は上書きします。
-    let mut output_path = args.output_dir.join(font_name);
-    output_path.set_extension(ext); // 拡張子を上書き
+/// フォントデータの OS/2 テーブルから太さ (usWeightClass) を読み取ります (読み取れなければ `None`)。
+fn weight_class(data: &[u8]) -> Option<u16> {
+    let font = sfnt::Sfnt::parse_face(data, 0).ok()?;
+    sfnt::read_u16(font.table(b"OS/2")?, 4).ok()
+}
 
-    let output_path_str = output_path.display().to_string();
-    println!("Writing font data to: {}", output_path.display());
+/// GDI が `font_name` と別のファミリーを選んでいれば、そのファミリー名を返します。
+/// 名前はローカライズされたファミリー名やフルネームとも比較します (例: "メイリオ" と "Meiryo")。
+fn substitute_family(font_name: &str, data: &[u8], languages: &[String]) -> Option<String> {
+    let font = sfnt::Sfnt::parse_face(data, 0).ok()?;
+    let records = sfnt::name::parse(font.table(b"name")?).ok()?;
+    if sfnt::name::has_family(&records, font_name) {
+        return None;
+    }
+    Some(family_name(data, languages).unwrap_or_else(|| "an unnamed font".to_string()))
+}
 
-    // --- 保存先ディレクトリの作成 ---
-    if let Some(parent_dir) = output_path.parent() {
-        fs::create_dir_all(parent_dir).map_err(|e| FontExtractorError::FileCreate {
-            path: parent_dir.display().to_string(),
-            source: e,
+/// --- 1 つのテーブルだけを書き込む (`--table`) ---
+/// インストール済みフォントは GetFontData のテーブル指定でそのテーブルだけを読み取ります。
+fn write_table(
+    job: &Job<'_>,
+    tag: &sfnt::Tag,
+    args: &Args,
+    stdin_data: Option<&[u8]>,
+    sink: &mut dyn sink::Sink,
+    reporter: &events::Reporter,
+) -> Result<(), FontExtractorError> {
+    // 太さや斜体で選んだフェイスは、name テーブルも読み取ってフルネームで名前を付ける
+    let face_name = job.face.is_none()
+        && stdin_data.is_none()
+        && job.request.file_path().is_none()
+        && selects_face(&job.request.logfont);
+    // 埋め込みの制限を確かめるため、OS/2 と name テーブルも読み取る
+    let mut tags = vec![*tag];
+    for extra in [*b"OS/2", *b"name"] {
+        if !tags.contains(&extra) {
+            tags.push(extra);
+        }
+    }
+    // インストールされていないフォント (ファイルや標準入力) は、取り出す前に上限と比べる
+    let tables = match (stdin_data, job.request.file_path()) {
+        (Some(data), _) => {
+            args.limits
+                .check_size("standard input", data.len() as u64)?;
+            args.limits.check(data)?;
+            font_export::font_tables_from_data(data, &tags)?
+        }
+        (None, Some(path)) => font_export::font_tables_from_data(&args.limits.read(path)?, &tags)?,
+        (None, None) => font_export::extract_font_tables(&job.request, &tags)?,
+    };
+    // フォントファイルのパスが指定された場合はファイル名の部分だけを使う
+    let name = match job.request.file_path() {
+        Some(path) => path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned(),
+        None => face_name
+            .then(|| tables.table(b"name"))
+            .flatten()
+            .and_then(|table| sfnt::name::parse(table).ok())
+            .and_then(|records| sfnt::name::find_preferred(&records, 4, &args.name_languages))
+            .unwrap_or_else(|| job.name().to_string()),
+    };
+    check_embedding(
+        restricted_license_of(
+            tables.table(b"OS/2"),
+            tables.table(b"name"),
+            &args.name_languages,
+        ),
+        job.name(),
+        args.ignore_embedding_restrictions,
+        |message| reporter.say(message),
+    )?;
+    let tag_name = sfnt::tag_to_string(tag);
+    let data = tables
+        .table(tag)
+        .ok_or_else(|| FontExtractorError::TableNotFound {
+            font: job.name().to_string(),
+            tag: tag_name.clone(),
         })?;
+    let file_name = output::file_name_safe(&format!("{name}.{}.bin", tag_name.trim_end()));
+    match sink.write(&file_name, data)? {
+        Some(location) => reporter.say(format!(
+            "Table '{tag_name}' ({} bytes) written to: {location}",
+            data.len()
+        )),
+        None => reporter.say(format!(
+            "Skipping: {file_name} already exists with different content"
+        )),
     }
+    Ok(())
+}
 
-    // --- ファイルへの書き込み ---
-    let mut file = fs::File::create(&output_path).map_err(|e| FontExtractorError::FileCreate {
-        path: output_path_str.clone(),
-        source: e,
-    })?;
-    file.write_all(&buffer)
-        .map_err(|e| FontExtractorError::FileWrite {
-            path: output_path_str,
-            source: e,
-        })?;
+/// --- テーブルディレクトリの表示 ---
+fn print_table_list(request: &FontRequest<'_>) -> Result<(), FontExtractorError> {
+    let directory = extract_table_directory(request)?;
+    print!("{}", table_list(request.name, &directory));
+    Ok(())
+}
 
-    println!("Font data extracted successfully!");
+/// テーブルディレクトリの表 (`--table-list`、`info --list-tables` と `--diagnostics` の出力)
+fn table_list(font_name: &str, directory: &TableDirectory) -> String {
+    use std::fmt::Write;
 
-    // --- リソース解放 (変更なし、RAIIにより自動) ---
-    Ok(())
+    let mut text = format!(
+        "Table directory of '{font_name}' (sfnt version 0x{:08X}, {} table(s), {} bytes of font data):\n",
+        directory.sfnt_version,
+        directory.tables.len(),
+        directory.total_size
+    );
+    if let Some(face_offsets) = &directory.collection {
+        let offsets: Vec<String> = face_offsets.iter().map(|o| format!("0x{o:08X}")).collect();
+        let _ = writeln!(
+            text,
+            "Part of a TrueType Collection with {} face(s) at {}; table offsets are relative to the collection file.",
+            face_offsets.len(),
+            offsets.join(", ")
+        );
+    }
+    text.push_str("  Tag   Offset      Length      Checksum\n");
+    for table in &directory.tables {
+        let beyond = table.offset as u64 + table.length as u64 > directory.total_size as u64;
+        let _ = writeln!(
+            text,
+            "  {:<4}  0x{:08X}  {:>10}  0x{:08X}{}",
+            sfnt::tag_to_string(&table.tag),
+            table.offset,
+            table.length,
+            table.checksum,
+            if beyond {
+                "  ! extends past the font data returned by GDI"
+            } else {
+                ""
+            }
+        );
+    }
+    text
 }
@@ -0,0 +1,87 @@
+//! `merge` サブコマンド: 複数のフォントファイルのグリフを 1 つのフォントにまとめます。
+
+use std::fs;
+use std::path::PathBuf;
+
+use font_export::FontExtractorError;
+use font_export::limits::Limits;
+use font_export::sfnt::merge::{self, Overlap};
+use font_export::sfnt::{self, cmap};
+
+/// --- `merge` サブコマンドの引数 ---
+#[derive(clap::Args, Debug)]
+pub struct MergeArgs {
+    /// Font files to merge (TrueType outlines only); the first is the base whose names, metrics and layout tables are kept
+    #[arg(required = true, num_args = 2..)]
+    fonts: Vec<PathBuf>,
+
+    /// Path of the merged font file to write
+    #[arg(long, short)]
+    output: PathBuf,
+
+    /// Which font's glyph to use for a character that several fonts have
+    #[arg(long, value_enum, default_value_t = Overlap::First)]
+    prefer: Overlap,
+
+    /// Merge fonts whose OS/2 fsType is "Restricted License embedding" (by default they are refused and their license notice is printed); only for fonts you are licensed to copy
+    #[arg(long)]
+    ignore_embedding_restrictions: bool,
+
+    #[command(flatten)]
+    limits: Limits,
+}
+
+pub fn run(args: &MergeArgs) -> Result<(), FontExtractorError> {
+    let data = args
+        .fonts
+        .iter()
+        .map(|path| args.limits.read(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    // 制限されたフォントのグリフを写し取らないよう、まとめる前にすべての入力を確かめる
+    for (path, data) in args.fonts.iter().zip(&data) {
+        crate::check_embedding(
+            crate::restricted_license(data, &[]),
+            &path.display().to_string(),
+            args.ignore_embedding_restrictions,
+            |message| println!("{message}"),
+        )?;
+    }
+    let fonts: Vec<&[u8]> = data.iter().map(Vec::as_slice).collect();
+    let merged = merge::merge(&fonts, args.prefer)?;
+
+    fs::write(&args.output, &merged.data).map_err(|e| FontExtractorError::FileWrite {
+        path: args.output.display().to_string(),
+        source: e,
+    })?;
+
+    let font = sfnt::Sfnt::parse_face(&merged.data, 0)?;
+    let characters = cmap::parse(font.table(b"cmap").unwrap_or_default())?.len();
+    let glyphs = sfnt::read_u16(font.table(b"maxp").unwrap_or_default(), 4)?;
+    println!(
+        "Merged {} font(s) into {} ({characters} character(s), {glyphs} glyph(s)):",
+        args.fonts.len(),
+        args.output.display()
+    );
+    for (i, (path, (taken, added))) in args.fonts.iter().zip(&merged.contributions).enumerate() {
+        if i == 0 {
+            println!("  {}  {taken} character(s) (base)", path.display());
+        } else {
+            println!(
+                "  {}  {taken} character(s), {added} glyph(s) added",
+                path.display()
+            );
+        }
+    }
+    if !merged.dropped_tables.is_empty() {
+        let dropped: Vec<String> = merged
+            .dropped_tables
+            .iter()
+            .map(sfnt::tag_to_string)
+            .collect();
+        println!(
+            "Dropped table(s) that no longer match the glyphs: {}",
+            dropped.join(", ")
+        );
+    }
+    Ok(())
+}
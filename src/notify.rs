@@ -0,0 +1,88 @@
+//! 実行完了時の Webhook 通知 (`--notify-webhook`)
+//!
+//! 実行結果の要約を JSON で POST します。送信する JSON の形式:
+//!
+//! ```json
+//! {
+//!   "status": "failed",
+//!   "total": 1, "succeeded": 0, "skipped": 0, "failed": 1,
+//!   "failures": [{ "font": "Meiryo", "error": "..." }],
+//!   "duration_ms": 1234
+//! }
+//! ```
+
+use std::time::Instant;
+
+use serde::Serialize;
+
+use font_export::FontExtractorError;
+use font_export::sink::http_agent;
+
+/// 1 回の実行の要約
+#[derive(Serialize, Debug)]
+pub struct RunSummary {
+    status: &'static str,
+    total: usize,
+    succeeded: usize,
+    skipped: usize,
+    failed: usize,
+    failures: Vec<Failure>,
+    duration_ms: u128,
+    #[serde(skip)]
+    started: Instant,
+}
+
+/// 失敗したフォントとエラーメッセージ
+#[derive(Serialize, Debug)]
+struct Failure {
+    font: String,
+    error: String,
+}
+
+impl RunSummary {
+    /// 計測を開始します。
+    pub fn start() -> Self {
+        Self {
+            status: "succeeded",
+            total: 0,
+            succeeded: 0,
+            skipped: 0,
+            failed: 0,
+            failures: Vec::new(),
+            duration_ms: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// フォント 1 件の結果を記録します。`Ok(None)` は書き込みをスキップしたことを表します。
+    pub fn record(&mut self, font: &str, result: &Result<Option<String>, FontExtractorError>) {
+        self.total += 1;
+        match result {
+            Ok(Some(_)) => self.succeeded += 1,
+            Ok(None) => self.skipped += 1,
+            Err(e) => {
+                self.failed += 1;
+                self.status = "failed";
+                self.failures.push(Failure {
+                    font: font.to_string(),
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    /// 経過時間を確定し、`url` に POST します。
+    pub fn post(mut self, url: &str) -> Result<(), FontExtractorError> {
+        self.duration_ms = self.started.elapsed().as_millis();
+        let body = serde_json::to_string(&self).expect("RunSummary is always serializable");
+        http_agent()?
+            .post(url)
+            .set("Content-Type", "application/json")
+            .send_string(&body)
+            .map_err(|e| FontExtractorError::Webhook {
+                url: url.to_string(),
+                reason: e.to_string(),
+            })?;
+        Ok(())
+    }
+}
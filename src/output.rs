@@ -0,0 +1,104 @@
+//! 出力ファイル名の衝突処理
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::FontExtractorError;
+use crate::digest::sha256_hex;
+
+/// ハッシュ戦略でファイル名に付加する SHA-256 の桁数
+const HASH_SUFFIX_LEN: usize = 8;
+
+/// 出力先に別の内容のファイルが既に存在する場合の扱い
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Replace the existing file
+    #[default]
+    Overwrite,
+    /// Append a numeric suffix ("Arial-1.ttf", "Arial-2.ttf", ...)
+    Suffix,
+    /// Append the first hex digits of the data's SHA-256 ("Arial-1a2b3c4d.ttf")
+    Hash,
+    /// Fail with an error
+    Error,
+    /// Leave the existing file alone and do not write
+    Skip,
+}
+
+/// 衝突を解決した書き込み先を返します。`None` の場合は書き込みをスキップします。
+///
+/// 既存ファイルの内容が書き込むデータと同一であれば衝突とはみなしません。
+pub fn resolve_conflict(
+    path: &Path,
+    data: &[u8],
+    strategy: ConflictStrategy,
+) -> Result<Option<PathBuf>, FontExtractorError> {
+    if !is_conflict(path, data) {
+        return Ok(Some(path.to_path_buf()));
+    }
+    match strategy {
+        ConflictStrategy::Overwrite => Ok(Some(path.to_path_buf())),
+        ConflictStrategy::Skip => Ok(None),
+        ConflictStrategy::Error => Err(FontExtractorError::OutputConflict {
+            path: path.display().to_string(),
+        }),
+        ConflictStrategy::Hash => {
+            let hex = sha256_hex(data);
+            let candidate = with_suffix(path, &hex[..HASH_SUFFIX_LEN]);
+            if is_conflict(&candidate, data) {
+                Err(FontExtractorError::OutputConflict {
+                    path: candidate.display().to_string(),
+                })
+            } else {
+                Ok(Some(candidate))
+            }
+        }
+        ConflictStrategy::Suffix => {
+            let candidate = (1..)
+                .map(|n| with_suffix(path, &n.to_string()))
+                .find(|candidate| !is_conflict(candidate, data))
+                .expect("an unused suffix always exists");
+            Ok(Some(candidate))
+        }
+    }
+}
+
+/// `path` に `data` と異なる内容のファイルが存在するかどうか
+fn is_conflict(path: &Path, data: &[u8]) -> bool {
+    match fs::metadata(path) {
+        Ok(meta) if meta.len() == data.len() as u64 => {
+            fs::read(path).map_or(true, |existing| existing != data)
+        }
+        Ok(_) => true,
+        Err(_) => false,
+    }
+}
+
+/// `dir/Arial.ttf` → `dir/Arial-<suffix>.ttf`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut file_name = format!("{stem}-{suffix}");
+    if let Some(ext) = path.extension() {
+        file_name.push('.');
+        file_name.push_str(&ext.to_string_lossy());
+    }
+    path.with_file_name(file_name)
+}
+
+/// ファイル名に使えない文字 (`\ / : * ? " < > |`) を `_` に置き換えます。
+pub fn file_name_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,224 @@
+//! `--remap` の CSV による cmap の書き換え
+//!
+//! 1 行に `元のコードポイント,新しいコードポイント[,keep]` を書きます。
+//! 新しいコードポイントには元のコードポイントのグリフが割り当てられ、元のコードポイントの割り当ては消えます (移動)。
+//! 3 列目に `keep` を書くと元の割り当ても残します (複製)。`#` で始まる行と空行は無視します。
+//!
+//! ```csv
+//! # PUA のアイコンを固定のコードポイントへ移す
+//! U+E001,U+2605
+//! # 同じグリフを複数のコードポイントにも割り当てる
+//! U+0041,U+FF21,keep
+//! U+0041,0x1D400,keep
+//! ```
+//!
+//! コードポイントは `U+XXXX`、`0xXXXX` または文字そのもので書きます。
+//! 割り当ての参照は書き換え前の cmap に対して行うため、2 つのコードポイントの入れ替えも書けます。
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use crate::FontExtractorError;
+use crate::sfnt::rebuild::Rebuild;
+use crate::sfnt::{Sfnt, cmap, corrupt};
+
+/// OS/2 の usFirstCharIndex / usLastCharIndex のオフセット
+const FIRST_CHAR_INDEX_OFFSET: usize = 64;
+const LAST_CHAR_INDEX_OFFSET: usize = 66;
+
+/// CSV の 1 行
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    source: u32,
+    target: u32,
+    keep: bool,
+}
+
+/// `--remap` の CSV 全体
+#[derive(Debug)]
+pub struct Remap {
+    entries: Vec<Entry>,
+}
+
+/// 書き換えの結果
+#[derive(Debug)]
+pub struct Remapped {
+    pub data: Vec<u8>,
+    /// 新しく割り当てたコードポイントの数
+    pub remapped: usize,
+    /// フォントの cmap に無かったため無視した元のコードポイント
+    pub missing: Vec<u32>,
+    /// 既に別のグリフが割り当てられていて上書きしたコードポイント
+    pub replaced: Vec<u32>,
+}
+
+impl Remap {
+    pub fn load(path: &Path) -> Result<Self, FontExtractorError> {
+        let text = fs::read_to_string(path).map_err(|e| FontExtractorError::FileRead {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        Self::parse(&text).map_err(|(line, reason)| FontExtractorError::RemapParse {
+            path: path.display().to_string(),
+            line,
+            reason,
+        })
+    }
+
+    /// CSV をパースします。エラーは (行番号, 理由) です。
+    fn parse(text: &str) -> Result<Self, (usize, String)> {
+        let mut entries: Vec<Entry> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim_start_matches('\u{FEFF}').trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let keep = match fields.as_slice() {
+                [_, _] => false,
+                [_, _, mode] if mode.eq_ignore_ascii_case("keep") => true,
+                [_, _, ""] => false,
+                [_, _, mode] => {
+                    return Err((
+                        line_number,
+                        format!("unknown mode '{mode}'; only 'keep' is allowed"),
+                    ));
+                }
+                _ => {
+                    return Err((
+                        line_number,
+                        "expected 'source,target' or 'source,target,keep'".to_string(),
+                    ));
+                }
+            };
+            let source = parse_codepoint(fields[0]).map_err(|reason| (line_number, reason))?;
+            let target = parse_codepoint(fields[1]).map_err(|reason| (line_number, reason))?;
+            if let Some(previous) = entries.iter().find(|e| e.target == target)
+                && previous.source != source
+            {
+                return Err((
+                    line_number,
+                    format!(
+                        "U+{target:04X} is already mapped from U+{:04X} on an earlier line",
+                        previous.source
+                    ),
+                ));
+            }
+            entries.push(Entry {
+                source,
+                target,
+                keep,
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    /// フォントデータの cmap を書き換えたデータを返します。TTC には使えません。
+    ///
+    /// cmap は format 4 / 12 (と元のフォントにあれば format 14) で作り直し、Mac 用などの Unicode 以外のサブテーブルはそのまま残します。
+    /// シンボルフォントの cmap ((3,0) のサブテーブル) はエラーです。
+    /// OS/2 の usFirstCharIndex / usLastCharIndex も新しい cmap に合わせます。
+    pub fn apply(&self, data: &[u8]) -> Result<Remapped, FontExtractorError> {
+        if data.get(..4) == Some(b"ttcf") {
+            return Err(corrupt("cmap cannot be remapped in a TrueType Collection"));
+        }
+        let font = Sfnt::parse_face(data, 0)?;
+        let cmap_table = font
+            .table(b"cmap")
+            .ok_or_else(|| corrupt("the font has no 'cmap' table to remap"))?;
+        // シンボルフォントの (3,0) を (3,1) として書き直すと Windows での扱いが変わるため、書き換えない
+        let others = cmap::non_unicode_subtables(cmap_table)?;
+        if others.iter().any(|s| (s.platform, s.encoding) == (3, 0)) {
+            return Err(corrupt(
+                "the cmap of a symbol font (platform 3, encoding 0) cannot be remapped",
+            ));
+        }
+        let original = cmap::parse(cmap_table)?;
+
+        // keep の行が 1 つも無い元のコードポイントは割り当てを消す
+        let kept: BTreeSet<u32> = self
+            .entries
+            .iter()
+            .filter(|e| e.keep)
+            .map(|e| e.source)
+            .collect();
+        let mut map = original.clone();
+        for entry in &self.entries {
+            if !kept.contains(&entry.source) {
+                map.remove(&entry.source);
+            }
+        }
+
+        let mut remapped = 0;
+        let mut missing = BTreeSet::new();
+        let mut replaced = Vec::new();
+        for entry in &self.entries {
+            let Some(&glyph) = original.get(&entry.source) else {
+                missing.insert(entry.source);
+                continue;
+            };
+            if map
+                .insert(entry.target, glyph)
+                .is_some_and(|old| old != glyph)
+            {
+                replaced.push(entry.target);
+            }
+            remapped += 1;
+        }
+
+        let new_cmap = cmap::build(&map, cmap::variation_subtable(cmap_table)?, &others)?;
+        let os2 = font.table(b"OS/2").map(|os2| {
+            let mut os2 = os2.to_vec();
+            if os2.len() >= LAST_CHAR_INDEX_OFFSET + 2 {
+                let clamp = |c: Option<&u32>| c.map_or(0, |&c| c.min(0xFFFF) as u16);
+                let first = clamp(map.keys().next());
+                let last = clamp(map.keys().next_back());
+                os2[FIRST_CHAR_INDEX_OFFSET..FIRST_CHAR_INDEX_OFFSET + 2]
+                    .copy_from_slice(&first.to_be_bytes());
+                os2[LAST_CHAR_INDEX_OFFSET..LAST_CHAR_INDEX_OFFSET + 2]
+                    .copy_from_slice(&last.to_be_bytes());
+            }
+            os2
+        });
+
+        let mut rebuild = Rebuild::from_font(&font);
+        rebuild.replace(*b"cmap", &new_cmap);
+        if let Some(os2) = &os2 {
+            rebuild.replace(*b"OS/2", os2);
+        }
+        Ok(Remapped {
+            data: rebuild.finish()?,
+            remapped,
+            missing: missing.into_iter().collect(),
+            replaced,
+        })
+    }
+}
+
+/// `U+XXXX`、`0xXXXX` または 1 文字をコードポイントとして読み取ります。
+fn parse_codepoint(field: &str) -> Result<u32, String> {
+    let hex = ["U+", "u+", "0x", "0X"]
+        .iter()
+        .find_map(|prefix| field.strip_prefix(prefix));
+    let codepoint = match hex {
+        Some(digits) => u32::from_str_radix(digits, 16)
+            .map_err(|_| format!("'{field}' is not a hex codepoint"))?,
+        None => {
+            let mut chars = field.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => c as u32,
+                _ => {
+                    return Err(format!(
+                        "'{field}' is not a codepoint; write U+XXXX, 0xXXXX or a single character"
+                    ));
+                }
+            }
+        }
+    };
+    if char::from_u32(codepoint).is_none() {
+        return Err(format!("U+{codepoint:04X} is not a Unicode scalar value"));
+    }
+    Ok(codepoint)
+}
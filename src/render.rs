@@ -0,0 +1,144 @@
+//! GDI による文字列の描画 (`identify` の候補フォントの見本)
+
+use std::ffi::c_void;
+
+use windows::Win32::Foundation::{COLORREF, HANDLE, SIZE};
+use windows::Win32::Graphics::Gdi::{
+    ANTIALIASED_QUALITY, BI_RGB, BITMAPINFO, BITMAPINFOHEADER, CreateDIBSection, DIB_RGB_COLORS,
+    DeleteObject, GGI_MARK_NONEXISTING_GLYPHS, GdiFlush, GetGlyphIndicesW, GetTextExtentPoint32W,
+    HBITMAP, HGDIOBJ, SelectObject, SetBkMode, SetTextColor, TRANSPARENT, TextOutW,
+};
+use windows::core::{Error as WinError, PCWSTR};
+
+use crate::bitmap::InkImage;
+use crate::{FontExtractorError, FontRequest, FontSelector, SafeDC, SafeFont};
+
+/// 描画する文字の高さ (ピクセル)
+const RENDER_HEIGHT: i32 = 64;
+
+/// GetGlyphIndicesW がグリフの無い文字に返す値
+const MISSING_GLYPH: u16 = 0xFFFF;
+
+/// --- RAII ラッパー: DC に選択した DIB セクション ---
+struct SelectedBitmap<'dc> {
+    dc: &'dc SafeDC,
+    bitmap: HBITMAP,
+    old: HGDIOBJ,
+}
+impl Drop for SelectedBitmap<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            SelectObject(self.dc.get(), self.old);
+            let _ = DeleteObject(self.bitmap);
+        }
+    }
+}
+
+/// `request` のフォントで `text` を白地に黒で描画します。
+///
+/// LOGFONT の高さと品質は描画用の値で上書きします。
+/// フォントに `text` のグリフが 1 つでも無い場合は、代わりの四角が描かれるため `None` を返します。
+pub fn render_text(
+    request: &FontRequest<'_>,
+    text: &str,
+) -> Result<Option<InkImage>, FontExtractorError> {
+    let mut request = *request;
+    request.logfont.height = -RENDER_HEIGHT;
+    request.logfont.quality = ANTIALIASED_QUALITY.0;
+    let dc = SafeDC::new()?;
+    let font = SafeFont::create(&request)?;
+    let _font_selector = FontSelector::select(&dc, &font)?;
+
+    let wide: Vec<u16> = text.encode_utf16().collect();
+    let wide_nul: Vec<u16> = wide.iter().copied().chain(std::iter::once(0)).collect();
+    let mut glyphs = vec![0u16; wide.len()];
+    let converted = unsafe {
+        GetGlyphIndicesW(
+            dc.get(),
+            PCWSTR(wide_nul.as_ptr()),
+            wide.len() as i32,
+            glyphs.as_mut_ptr(),
+            GGI_MARK_NONEXISTING_GLYPHS,
+        )
+    };
+    if converted == u32::MAX {
+        return Err(api_error("GetGlyphIndicesW"));
+    }
+    if glyphs.contains(&MISSING_GLYPH) {
+        return Ok(None);
+    }
+
+    let mut extent = SIZE::default();
+    if !unsafe { GetTextExtentPoint32W(dc.get(), &wide, &mut extent) }.as_bool() {
+        return Err(api_error("GetTextExtentPoint32W"));
+    }
+    // 斜体や張り出しのあるグリフが切れないように余白を取る
+    let margin = RENDER_HEIGHT / 2;
+    let width = extent.cx + margin * 2;
+    let height = extent.cy + margin * 2;
+
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // 負の高さで上から下への並びにする
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut bits: *mut c_void = std::ptr::null_mut();
+    let bitmap = unsafe {
+        CreateDIBSection(
+            dc.get(),
+            &info,
+            DIB_RGB_COLORS,
+            &mut bits,
+            HANDLE::default(),
+            0,
+        )
+    }
+    .map_err(|e| FontExtractorError::WinApi {
+        api_name: "CreateDIBSection".to_string(),
+        source: e,
+    })?;
+    let old = unsafe { SelectObject(dc.get(), bitmap) };
+    let _bitmap = SelectedBitmap {
+        dc: &dc,
+        bitmap,
+        old,
+    };
+
+    let pixels =
+        unsafe { std::slice::from_raw_parts_mut(bits as *mut u8, (width * height * 4) as usize) };
+    pixels.fill(0xFF);
+    unsafe {
+        SetBkMode(dc.get(), TRANSPARENT);
+        SetTextColor(dc.get(), COLORREF(0));
+        if !TextOutW(dc.get(), margin, margin, &wide).as_bool() {
+            return Err(api_error("TextOutW"));
+        }
+        let _ = GdiFlush();
+    }
+
+    // BGRA の画素を明るさに変換する
+    let luminance: Vec<u8> = pixels
+        .chunks_exact(4)
+        .map(|p| ((u16::from(p[0]) + u16::from(p[1]) + u16::from(p[2])) / 3) as u8)
+        .collect();
+    Ok(Some(InkImage::from_luminance(
+        width as usize,
+        height as usize,
+        &luminance,
+    )))
+}
+
+fn api_error(api_name: &str) -> FontExtractorError {
+    FontExtractorError::WinApi {
+        api_name: api_name.to_string(),
+        source: WinError::from_win32(),
+    }
+}
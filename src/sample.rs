@@ -0,0 +1,78 @@
+//! 書き出したフォントの目視確認用の文字見本 (HTML)
+//!
+//! cmap に含まれる文字をすべて、Unicode のブロック ([`crate::blocks`]) ごとにまとめて並べます。
+//! 見本は書き出したフォントファイルを `@font-face` で読み込むため、フォントと同じ場所に置いて開きます。
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::sfnt::{Sfnt, cmap};
+use crate::{FontExtractorError, blocks};
+
+/// どのブロックにも含まれない文字の見出し
+const NO_BLOCK: &str = "No block";
+
+/// `data` のフォントがカバーする文字の見本を HTML で返します。
+///
+/// `font_file` は見本から参照するフォントファイル名、`family` は見出しと代替表示に使うファミリー名です。
+pub fn charset_html(
+    data: &[u8],
+    font_file: &str,
+    family: &str,
+) -> Result<String, FontExtractorError> {
+    let font = Sfnt::parse_face(data, 0)?;
+    let map = cmap::parse(font.table(b"cmap").unwrap_or_default())?;
+
+    // ブロックの見出し → 文字 (見出しはブロック内の最初の文字の順に並べる)
+    let mut blocks: BTreeMap<&str, Vec<char>> = BTreeMap::new();
+    for &codepoint in map.keys() {
+        let Some(c) = char::from_u32(codepoint).filter(|c| !c.is_control()) else {
+            continue;
+        };
+        let name = blocks::block_of(codepoint).map_or(NO_BLOCK, |block| block.name);
+        blocks.entry(name).or_default().push(c);
+    }
+    let mut blocks: Vec<(&str, Vec<char>)> = blocks.into_iter().collect();
+    blocks.sort_by_key(|(_, chars)| chars[0]);
+
+    let family = escape(family);
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{family}: character set</title>\n<style>\n\
+         @font-face {{ font-family: \"Exported\"; src: url(\"{}\"); }}\n\
+         body {{ font-family: sans-serif; }}\n\
+         .chars {{ font-family: \"Exported\", \"{family}\"; font-size: 28px; line-height: 1.6; }}\n\
+         .chars span {{ display: inline-block; min-width: 1.2em; text-align: center; }}\n\
+         </style>\n</head>\n<body>\n<h1>{family}</h1>\n<p>{} character(s) in {} block(s)</p>\n",
+        escape(font_file),
+        blocks.iter().map(|(_, chars)| chars.len()).sum::<usize>(),
+        blocks.len()
+    );
+    for (name, chars) in &blocks {
+        let _ = write!(
+            html,
+            "<h2>{} ({})</h2>\n<div class=\"chars\">",
+            escape(name),
+            chars.len()
+        );
+        for c in chars {
+            let _ = write!(
+                html,
+                "<span title=\"U+{:04X}\">{}</span>",
+                *c as u32,
+                escape(&c.to_string())
+            );
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    Ok(html)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,138 @@
+//! `selftest` サブコマンド: どの Windows にも入っているフォント (macOS では標準のフォント、それ以外では DejaVu) で抽出と検証を一通り実行し、結果を報告します。
+
+use font_export::sfnt::{self, Sfnt, Tag, name};
+use font_export::{FontExtractorError, FontRequest, validate};
+
+/// フォント名を指定しない場合に試すフォント (Windows の標準フォント)
+#[cfg(windows)]
+const DEFAULT_FONTS: &[&str] = &[
+    "Arial",
+    "Times New Roman",
+    "Courier New",
+    "Segoe UI",
+    "Tahoma",
+];
+/// フォント名を指定しない場合に試すフォント (macOS の標準フォント)
+#[cfg(target_os = "macos")]
+const DEFAULT_FONTS: &[&str] = &["Helvetica", "Times", "Courier", "Geneva"];
+/// フォント名を指定しない場合に試すフォント (ほとんどの Linux ディストリビューションにある DejaVu)
+#[cfg(not(any(windows, target_os = "macos")))]
+const DEFAULT_FONTS: &[&str] = &["DejaVu Sans", "DejaVu Serif", "DejaVu Sans Mono"];
+
+/// --- `selftest` サブコマンドの引数 ---
+#[derive(clap::Args, Debug)]
+pub struct SelftestArgs {
+    /// Fonts to test instead of the built-in set (Arial, Times New Roman, Courier New, Segoe UI and Tahoma on Windows; Helvetica, Times, Courier and Geneva on macOS; DejaVu Sans, Serif and Sans Mono elsewhere)
+    fonts: Vec<String>,
+}
+
+pub fn run(args: &SelftestArgs) -> Result<(), FontExtractorError> {
+    let fonts: Vec<&str> = if args.fonts.is_empty() {
+        DEFAULT_FONTS.to_vec()
+    } else {
+        args.fonts.iter().map(String::as_str).collect()
+    };
+
+    let mut failed = 0;
+    for font_name in &fonts {
+        match check_font(font_name) {
+            Ok(notes) => {
+                println!("PASS  {font_name}");
+                for note in notes {
+                    println!("        {note}");
+                }
+            }
+            Err(reason) => {
+                failed += 1;
+                println!("FAIL  {font_name}: {reason}");
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} of {} font(s) passed.",
+        fonts.len() - failed,
+        fonts.len()
+    );
+    if failed > 0 {
+        return Err(FontExtractorError::SelftestFailed {
+            failed,
+            total: fonts.len(),
+        });
+    }
+    Ok(())
+}
+
+/// 1 つのフォントを抽出して検証します。成功時は補足事項を、失敗時は理由を返します。
+fn check_font(font_name: &str) -> Result<Vec<String>, String> {
+    let request = FontRequest::named(font_name);
+    let mut notes = Vec::new();
+
+    // --- 抽出 ---
+    let data = font_export::extract_font_data(&request).map_err(|e| e.to_string())?;
+    let font = Sfnt::parse_face(&data, 0).map_err(|e| e.to_string())?;
+    notes.push(format!(
+        "extracted {} bytes, {} table(s)",
+        data.len(),
+        font.tables.len()
+    ));
+
+    // --- 構造の検証 (標準フォントなのでチェックサムの不一致も失敗とする) ---
+    let report = validate::check(&data, 0);
+    if !report.is_clean() {
+        let problems: Vec<String> = report.errors.into_iter().chain(report.warnings).collect();
+        return Err(problems.join("; "));
+    }
+    let codepoints = report.codepoints;
+
+    // --- GDI が要求どおりのフォントを選んだか (代替フォントの検出) ---
+    let records =
+        name::parse(font.table(b"name").unwrap_or_default()).map_err(|e| e.to_string())?;
+    let families: Vec<String> = [1, 16]
+        .into_iter()
+        .filter_map(|id| name::find(&records, id))
+        .collect();
+    if !families
+        .iter()
+        .any(|family| family.eq_ignore_ascii_case(font_name))
+    {
+        return Err(format!(
+            "GDI substituted a different font ({})",
+            families.first().map_or("unnamed", String::as_str)
+        ));
+    }
+
+    // --- テーブル単位の読み取りが全体の読み取りと一致するか ---
+    let tags: Vec<Tag> = font.tables.iter().map(|record| record.tag).collect();
+    let tables = font_export::extract_font_tables(&request, &tags).map_err(|e| e.to_string())?;
+    if let Some(tag) = tags.iter().find(|tag| tables.table(tag) != font.table(tag)) {
+        return Err(format!(
+            "per-table read of '{}' differs from the whole-font data",
+            sfnt::tag_to_string(tag)
+        ));
+    }
+
+    // --- 再構築の往復 (全テーブルが変わらずに残るか) ---
+    let table_data: Vec<(Tag, &[u8])> = tags
+        .iter()
+        .filter_map(|tag| font.table(tag).map(|data| (*tag, data)))
+        .collect();
+    let rebuilt = sfnt::rebuild::build(font.sfnt_version, &table_data)
+        .map_err(|e| format!("rebuilt font: {e}"))?;
+    let reparsed = Sfnt::parse_face(&rebuilt, 0).map_err(|e| format!("rebuilt font: {e}"))?;
+    let changed = tags.iter().find(|tag| {
+        // head は checkSumAdjustment が再計算されるので比較しない
+        tag != &b"head" && reparsed.table(tag) != font.table(tag)
+    });
+    if let Some(tag) = changed {
+        return Err(format!(
+            "table '{}' changed when the font was rebuilt",
+            sfnt::tag_to_string(tag)
+        ));
+    }
+    notes.push(format!(
+        "{codepoints} codepoint(s) in cmap; per-table reads and rebuild round-trip match"
+    ));
+    Ok(notes)
+}
@@ -0,0 +1,365 @@
+//! sfnt (TrueType / OpenType) コンテナの最小限のパーサ
+//!
+//! GetFontData が返すバイト列やディスク上のフォントファイルから
+//! テーブルディレクトリを読み取り、各テーブルのバイト列へのアクセスを提供します。
+
+pub mod cmap;
+pub mod glyf;
+pub mod merge;
+pub mod name;
+pub mod os2;
+pub mod rebuild;
+pub mod style;
+pub mod vertical;
+
+use crate::FontExtractorError;
+use rebuild::Rebuild;
+
+/// sfnt のテーブルタグ (例: `b"glyf"`)
+pub type Tag = [u8; 4];
+
+/// テーブルタグを表示用の文字列に変換します。
+pub fn tag_to_string(tag: &Tag) -> String {
+    tag.iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '?'
+            }
+        })
+        .collect()
+}
+
+/// コマンドラインのテーブルタグ ("cmap"、"OS/2" など) を検査します。4 文字に満たないタグ ("cvt") は空白で埋めます。
+pub fn parse_tag(value: &str) -> Result<Tag, String> {
+    if value.is_empty()
+        || value.len() > 4
+        || !value.bytes().all(|b| b.is_ascii_graphic() || b == b' ')
+    {
+        return Err(format!(
+            "'{value}' is not a table tag; use 1 to 4 ASCII characters such as \"cmap\" or \"OS/2\""
+        ));
+    }
+    let mut tag = *b"    ";
+    tag[..value.len()].copy_from_slice(value.as_bytes());
+    Ok(tag)
+}
+
+/// --- バイト列読み取りヘルパー ---
+pub fn read_u16(data: &[u8], offset: usize) -> Result<u16, FontExtractorError> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(|| corrupt(format!("unexpected end of data at offset {offset}")))
+}
+
+pub fn read_i16(data: &[u8], offset: usize) -> Result<i16, FontExtractorError> {
+    read_u16(data, offset).map(|v| v as i16)
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Result<u32, FontExtractorError> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| corrupt(format!("unexpected end of data at offset {offset}")))
+}
+
+pub(crate) fn corrupt(reason: impl Into<String>) -> FontExtractorError {
+    FontExtractorError::CorruptFontData {
+        reason: reason.into(),
+    }
+}
+
+/// テーブルディレクトリの 1 エントリ
+#[derive(Debug, Clone, Copy)]
+pub struct TableRecord {
+    pub tag: Tag,
+    pub checksum: u32,
+    pub offset: u32,
+    pub length: u32,
+}
+
+/// `offset` にあるオフセットテーブルとテーブルディレクトリを読み取ります。
+///
+/// テーブル本体が `data` に含まれているかどうかは確認しないため、先頭部分だけを読み取ったデータにも使えます。
+pub fn read_directory(data: &[u8], offset: usize) -> Result<Vec<TableRecord>, FontExtractorError> {
+    let sfnt_version = read_u32(data, offset)?;
+    if !matches!(sfnt_version, 0x0001_0000 | 0x4F54_544F | 0x7472_7565) {
+        return Err(corrupt(format!(
+            "unknown sfnt version 0x{sfnt_version:08X}"
+        )));
+    }
+    let num_tables = read_u16(data, offset + 4)?;
+    let mut tables = Vec::with_capacity(num_tables as usize);
+    for i in 0..num_tables as usize {
+        let record = offset + 12 + i * 16;
+        let tag_bytes = data
+            .get(record..record + 4)
+            .ok_or_else(|| corrupt("table directory is truncated"))?;
+        tables.push(TableRecord {
+            tag: [tag_bytes[0], tag_bytes[1], tag_bytes[2], tag_bytes[3]],
+            checksum: read_u32(data, record + 4)?,
+            offset: read_u32(data, record + 8)?,
+            length: read_u32(data, record + 12)?,
+        });
+    }
+    Ok(tables)
+}
+
+/// パース済みの sfnt フォント (TTC の場合はその中の 1 フェイス)
+#[derive(Debug)]
+pub struct Sfnt<'a> {
+    data: &'a [u8],
+    /// 0x00010000 (TrueType), 'OTTO' (CFF) など
+    pub sfnt_version: u32,
+    pub tables: Vec<TableRecord>,
+}
+
+impl<'a> Sfnt<'a> {
+    /// フォントデータをパースします。TTC の場合は `index` 番目のフェイスを返します。
+    pub fn parse_face(data: &'a [u8], index: u32) -> Result<Self, FontExtractorError> {
+        if data.get(..4) == Some(b"ttcf") {
+            let num_fonts = read_u32(data, 8)?;
+            if index >= num_fonts {
+                return Err(corrupt(format!(
+                    "collection has {num_fonts} faces, face #{index} requested"
+                )));
+            }
+            let offset = read_u32(data, 12 + index as usize * 4)?;
+            Self::parse_at(data, offset as usize)
+        } else if index == 0 {
+            Self::parse_at(data, 0)
+        } else {
+            Err(corrupt(format!(
+                "face #{index} requested but the data is not a collection"
+            )))
+        }
+    }
+
+    /// `offset` にあるテーブルディレクトリをパースします。
+    fn parse_at(data: &'a [u8], offset: usize) -> Result<Self, FontExtractorError> {
+        let tables = read_directory(data, offset)?;
+        if let Some(table) = tables
+            .iter()
+            .find(|t| t.offset as u64 + t.length as u64 > data.len() as u64)
+        {
+            return Err(corrupt(format!(
+                "table '{}' extends past the end of the data",
+                tag_to_string(&table.tag)
+            )));
+        }
+        Ok(Self {
+            data,
+            sfnt_version: read_u32(data, offset)?,
+            tables,
+        })
+    }
+
+    /// テーブルディレクトリに記録されたチェックサムと一致しないテーブルのタグを返します。
+    pub fn checksum_mismatches(&self) -> Vec<Tag> {
+        self.tables
+            .iter()
+            .filter(|record| {
+                let Some(data) = self.table(&record.tag) else {
+                    return false;
+                };
+                let actual = if &record.tag == b"head" && data.len() >= 12 {
+                    // head のチェックサムは checkSumAdjustment を 0 として計算する
+                    let mut head = data.to_vec();
+                    head[8..12].fill(0);
+                    checksum(&head)
+                } else {
+                    checksum(data)
+                };
+                actual != record.checksum
+            })
+            .map(|record| record.tag)
+            .collect()
+    }
+
+    /// 指定したタグのテーブルのバイト列を返します。
+    pub fn table(&self, tag: &Tag) -> Option<&'a [u8]> {
+        self.tables.iter().find(|t| &t.tag == tag).map(|t| {
+            let start = t.offset as usize;
+            &self.data[start..start + t.length as usize]
+        })
+    }
+}
+
+/// 書き出す前に、データが sfnt フォントまたはコレクションとして成り立っているかを確かめます。
+///
+/// 先頭のシグネチャ (sfnt バージョンまたは 'ttcf')、テーブル数 (0 でなく、テーブルディレクトリがデータに収まること)、
+/// 各テーブルがデータに収まり、単体のフォントではテーブルディレクトリと重ならないことを確かめ、満たさなければ [`FontExtractorError::CorruptFontData`] を返します。
+pub fn check_structure(data: &[u8]) -> Result<(), FontExtractorError> {
+    let collection = data.get(..4) == Some(b"ttcf");
+    let offsets = if collection {
+        let num_fonts =
+            read_u32(data, 8).map_err(|_| corrupt("the collection header is truncated"))?;
+        if num_fonts == 0 {
+            return Err(corrupt("the collection has no faces"));
+        }
+        if 12 + num_fonts as u64 * 4 > data.len() as u64 {
+            return Err(corrupt(format!(
+                "the collection header lists {num_fonts} faces but the data ends after {} bytes",
+                data.len()
+            )));
+        }
+        (0..num_fonts as usize)
+            .map(|index| read_u32(data, 12 + index * 4).map(|offset| offset as usize))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        vec![0]
+    };
+    for offset in offsets {
+        let sfnt_version = read_u32(data, offset).map_err(|_| {
+            corrupt(format!(
+                "no sfnt signature at offset {offset}; the data is not a TrueType/OpenType font"
+            ))
+        })?;
+        if !matches!(sfnt_version, 0x0001_0000 | 0x4F54_544F | 0x7472_7565) {
+            return Err(corrupt(format!(
+                "invalid sfnt signature 0x{sfnt_version:08X} at offset {offset}; the data is not a TrueType/OpenType font"
+            )));
+        }
+        let num_tables = read_u16(data, offset + 4)?;
+        let directory_end = offset as u64 + 12 + num_tables as u64 * 16;
+        if num_tables == 0 || directory_end > data.len() as u64 {
+            return Err(corrupt(format!(
+                "numTables is {num_tables}, which does not fit a table directory in {} bytes of data",
+                data.len()
+            )));
+        }
+        for table in read_directory(data, offset)? {
+            // コレクションではテーブルが別のフェイスのディレクトリより前にあってもよい
+            if !collection && (table.offset as u64) < directory_end {
+                return Err(corrupt(format!(
+                    "table '{}' at offset {} overlaps the table directory",
+                    tag_to_string(&table.tag),
+                    table.offset
+                )));
+            }
+            if table.offset as u64 + table.length as u64 > data.len() as u64 {
+                return Err(corrupt(format!(
+                    "table '{}' (offset {}, length {}) extends past the end of the data ({} bytes)",
+                    tag_to_string(&table.tag),
+                    table.offset,
+                    table.length,
+                    data.len()
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// テーブルのチェックサムと head の checkSumAdjustment を計算し直し、記録された値と一致しない点を返します (一致すれば空)。
+///
+/// コレクションは各フェイスのテーブルを確かめます。コレクションの checkSumAdjustment はファイル全体に対して定まらないため確かめません。
+pub fn checksum_problems(data: &[u8]) -> Result<Vec<String>, FontExtractorError> {
+    let collection = data.get(..4) == Some(b"ttcf");
+    let num_faces = if collection { read_u32(data, 8)? } else { 1 };
+    let mut problems = Vec::new();
+    for index in 0..num_faces {
+        let face = Sfnt::parse_face(data, index)?;
+        let mismatches: Vec<String> = face
+            .checksum_mismatches()
+            .iter()
+            .map(|tag| format!("'{}'", tag_to_string(tag)))
+            .collect();
+        if !mismatches.is_empty() {
+            let face = if collection {
+                format!("face #{index}: ")
+            } else {
+                String::new()
+            };
+            problems.push(format!(
+                "{face}table checksum mismatch in {}",
+                mismatches.join(", ")
+            ));
+        }
+        // テーブルが壊れていれば checkSumAdjustment も必ず一致しないので、テーブルが正しいときだけ確かめる
+        if !collection
+            && mismatches.is_empty()
+            && let Some(head) = face.table(b"head")
+        {
+            let recorded = read_u32(head, 8)?;
+            // ファイル全体 (checkSumAdjustment を 0 としたもの) のチェックサムとの和が 0xB1B0AFBA になる
+            let expected =
+                rebuild::CHECKSUM_MAGIC.wrapping_sub(checksum(data).wrapping_sub(recorded));
+            if recorded != expected {
+                problems.push(format!(
+                    "head checkSumAdjustment is 0x{recorded:08X}, expected 0x{expected:08X}"
+                ));
+            }
+        }
+    }
+    Ok(problems)
+}
+
+/// TrueType / OpenType Collection の各フェイスを単体のフォントに組み立て直します。
+///
+/// フェイス間で共有されているテーブルは各フォントに複製されます。コレクションでないデータはエラーです。
+pub fn split_collection(data: &[u8]) -> Result<Vec<Vec<u8>>, FontExtractorError> {
+    if data.get(..4) != Some(b"ttcf") {
+        return Err(corrupt("the data is not a TrueType/OpenType Collection"));
+    }
+    (0..read_u32(data, 8)?)
+        .map(|index| {
+            let face = Sfnt::parse_face(data, index)?;
+            Rebuild::from_font(&face).finish()
+        })
+        .collect()
+}
+
+/// テーブルの削除 ([`strip_tables`]) の結果
+#[derive(Debug)]
+pub struct Stripped {
+    pub data: Vec<u8>,
+    /// 取り除いたテーブル
+    pub removed: Vec<Tag>,
+    /// フォントに無かったため無視したテーブル
+    pub missing: Vec<Tag>,
+}
+
+/// `tags` のテーブルを取り除き、テーブルディレクトリ・オフセット・チェックサムを計算し直したフォントを返します。
+///
+/// 該当するテーブルが 1 つも無ければデータはそのままです。コレクションはエラーです。
+pub fn strip_tables(data: &[u8], tags: &[Tag]) -> Result<Stripped, FontExtractorError> {
+    if data.get(..4) == Some(b"ttcf") {
+        return Err(corrupt(
+            "tables cannot be stripped from a TrueType Collection (use --split-ttc)",
+        ));
+    }
+    let font = Sfnt::parse_face(data, 0)?;
+    let removed: Vec<Tag> = font
+        .tables
+        .iter()
+        .map(|record| record.tag)
+        .filter(|tag| tags.contains(tag))
+        .collect();
+    let missing = tags
+        .iter()
+        .filter(|tag| !removed.contains(tag))
+        .copied()
+        .collect();
+    let data = if removed.is_empty() {
+        data.to_vec()
+    } else {
+        Rebuild::from_font(&font)
+            .retain(|tag| !removed.contains(tag))
+            .finish()?
+    };
+    Ok(Stripped {
+        data,
+        removed,
+        missing,
+    })
+}
+
+/// テーブルのチェックサム (4 バイト境界まで 0 で埋めた big-endian u32 の総和)
+pub fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
@@ -0,0 +1,416 @@
+//! cmap テーブル (文字コード → グリフ ID) のパーサ
+
+use std::collections::BTreeMap;
+
+use super::{corrupt, read_u16, read_u32};
+use crate::FontExtractorError;
+
+/// Unicode 用サブテーブルの優先順位 (platformID, encodingID)
+const PREFERRED_ENCODINGS: [(u16, u16); 9] = [
+    (3, 10),
+    (0, 6),
+    (0, 4),
+    (3, 1),
+    (0, 3),
+    (0, 2),
+    (0, 1),
+    (0, 0),
+    (3, 0),
+];
+
+/// cmap テーブルから最も適切な Unicode サブテーブルを選び、
+/// コードポイント → グリフ ID の対応表を返します。
+pub fn parse(cmap: &[u8]) -> Result<BTreeMap<u32, u16>, FontExtractorError> {
+    let num_subtables = read_u16(cmap, 2)?;
+    let mut encodings = Vec::with_capacity(num_subtables as usize);
+    for i in 0..num_subtables as usize {
+        let record = 4 + i * 8;
+        encodings.push((
+            read_u16(cmap, record)?,
+            read_u16(cmap, record + 2)?,
+            read_u32(cmap, record + 4)? as usize,
+        ));
+    }
+
+    let offset = PREFERRED_ENCODINGS
+        .iter()
+        .find_map(|&(platform, encoding)| {
+            encodings
+                .iter()
+                .find(|&&(p, e, _)| p == platform && e == encoding)
+                .map(|&(_, _, offset)| offset)
+        })
+        .ok_or_else(|| corrupt("cmap has no Unicode subtable"))?;
+
+    parse_subtable(cmap, offset)
+}
+
+/// Unicode のコードポイントの最大値
+const MAX_CODEPOINT: u32 = 0x10FFFF;
+
+/// format 12/13 のグループ全体で展開するコードポイント数の上限 (Unicode のコードポイントの総数)
+const MAX_MAPPED_CODEPOINTS: u32 = MAX_CODEPOINT + 1;
+
+fn parse_subtable(cmap: &[u8], offset: usize) -> Result<BTreeMap<u32, u16>, FontExtractorError> {
+    let mut map = BTreeMap::new();
+    match read_u16(cmap, offset)? {
+        0 => {
+            for code in 0..256usize {
+                let glyph = *cmap
+                    .get(offset + 6 + code)
+                    .ok_or_else(|| corrupt("cmap format 0 subtable is truncated"))?;
+                if glyph != 0 {
+                    map.insert(code as u32, u16::from(glyph));
+                }
+            }
+        }
+        4 => {
+            let seg_count = read_u16(cmap, offset + 6)? as usize / 2;
+            let end_codes = offset + 14;
+            let start_codes = end_codes + seg_count * 2 + 2;
+            let id_deltas = start_codes + seg_count * 2;
+            let id_range_offsets = id_deltas + seg_count * 2;
+            for seg in 0..seg_count {
+                let end = read_u16(cmap, end_codes + seg * 2)?;
+                let start = read_u16(cmap, start_codes + seg * 2)?;
+                let delta = read_u16(cmap, id_deltas + seg * 2)?;
+                let range_offset_pos = id_range_offsets + seg * 2;
+                let range_offset = read_u16(cmap, range_offset_pos)? as usize;
+                if start > end {
+                    continue;
+                }
+                for code in start..=end {
+                    if code == 0xFFFF {
+                        break;
+                    }
+                    let glyph = if range_offset == 0 {
+                        code.wrapping_add(delta)
+                    } else {
+                        let pos = range_offset_pos + range_offset + (code - start) as usize * 2;
+                        match read_u16(cmap, pos)? {
+                            0 => 0,
+                            g => g.wrapping_add(delta),
+                        }
+                    };
+                    if glyph != 0 {
+                        map.insert(code as u32, glyph);
+                    }
+                }
+            }
+        }
+        6 => {
+            let first = read_u16(cmap, offset + 6)? as u32;
+            let count = read_u16(cmap, offset + 8)? as usize;
+            for i in 0..count {
+                let glyph = read_u16(cmap, offset + 10 + i * 2)?;
+                if glyph != 0 {
+                    map.insert(first + i as u32, glyph);
+                }
+            }
+        }
+        12 | 13 => {
+            let is_format_13 = read_u16(cmap, offset)? == 13;
+            let num_groups = read_u32(cmap, offset + 12)? as usize;
+            // グループは重なってもよいので、展開するコードポイントの合計で打ち切る
+            let mut mapped = 0u32;
+            for group in 0..num_groups {
+                let record = offset + 16 + group * 12;
+                let start = read_u32(cmap, record)?;
+                let end = read_u32(cmap, record + 4)?.min(MAX_CODEPOINT);
+                let start_glyph = read_u32(cmap, record + 8)?;
+                if start > end {
+                    return Err(corrupt(format!(
+                        "cmap group U+{start:04X}..U+{end:04X} is invalid"
+                    )));
+                }
+                mapped = mapped.saturating_add(end - start + 1);
+                if mapped > MAX_MAPPED_CODEPOINTS {
+                    return Err(corrupt(format!(
+                        "cmap groups map more than {MAX_MAPPED_CODEPOINTS} code points"
+                    )));
+                }
+                for code in start..=end {
+                    let glyph = if is_format_13 {
+                        Some(start_glyph)
+                    } else {
+                        start_glyph.checked_add(code - start)
+                    };
+                    let glyph = glyph
+                        .and_then(|glyph| u16::try_from(glyph).ok())
+                        .ok_or_else(|| {
+                            corrupt(format!(
+                                "cmap group U+{start:04X}..U+{end:04X} maps to glyph IDs above 65535"
+                            ))
+                        })?;
+                    if glyph != 0 {
+                        map.insert(code, glyph);
+                    }
+                }
+            }
+        }
+        format => {
+            return Err(corrupt(format!(
+                "unsupported cmap subtable format {format}"
+            )));
+        }
+    }
+    Ok(map)
+}
+
+/// コードポイントの集合を連続する範囲にまとめます。
+pub fn to_ranges(codepoints: impl IntoIterator<Item = u32>) -> Vec<(u32, u32)> {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for code in codepoints {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == code => *end = code,
+            _ => ranges.push((code, code)),
+        }
+    }
+    ranges
+}
+
+/// 範囲を `U+3041-U+3096` 形式で表示します。
+pub fn format_range(&(start, end): &(u32, u32)) -> String {
+    if start == end {
+        format!("U+{start:04X}")
+    } else {
+        format!("U+{start:04X}-U+{end:04X}")
+    }
+}
+
+/// format 4 のセグメントで、グリフ ID を配列に置かずに idDelta で表す最小の長さ
+/// (これより短い部分は glyphIdArray にまとめた方が小さくなる)
+const MIN_DELTA_SEGMENT: usize = 4;
+
+/// コードポイント → グリフ ID の対応表から cmap テーブルを組み立てます。
+///
+/// BMP の文字は format 4 (platform 0/3, encoding 3/1)、BMP 外の文字があれば全体を format 12
+/// (platform 0/3, encoding 4/10) で書き出します。`variations` には元のフォントの format 14 サブテーブル
+/// (異体字セレクタ) を、`others` には Unicode 以外のサブテーブル ([`non_unicode_subtables`]) をそのまま渡せます。
+pub fn build(
+    map: &BTreeMap<u32, u16>,
+    variations: Option<&[u8]>,
+    others: &[Subtable<'_>],
+) -> Result<Vec<u8>, FontExtractorError> {
+    let format4 = build_format4(map)?;
+    let format12 = map
+        .keys()
+        .any(|&code| code > 0xFFFF)
+        .then(|| build_format12(map));
+
+    // サブテーブルと、それを参照する (platformID, encodingID, サブテーブルの番号)
+    let mut subtables: Vec<&[u8]> = vec![&format4];
+    let mut records = vec![(0u16, 3u16, 0usize)];
+    if let Some(format12) = &format12 {
+        subtables.push(format12);
+        records.push((0, 4, subtables.len() - 1));
+    }
+    if let Some(variations) = variations {
+        subtables.push(variations);
+        records.push((0, 5, subtables.len() - 1));
+    }
+    records.push((3, 1, 0));
+    if format12.is_some() {
+        records.push((3, 10, 1));
+    }
+    for other in others {
+        // 複数のレコードが同じサブテーブルを指していれば、書き出すのも 1 つにする
+        let index = match subtables.iter().position(|s| std::ptr::eq(*s, other.data)) {
+            Some(index) => index,
+            None => {
+                subtables.push(other.data);
+                subtables.len() - 1
+            }
+        };
+        records.push((other.platform, other.encoding, index));
+    }
+    // レコードは platformID, encodingID の順に並べる必要がある
+    records.sort_by_key(|&(platform, encoding, _)| (platform, encoding));
+
+    let mut offsets = Vec::with_capacity(subtables.len());
+    let mut next = 4 + 8 * records.len();
+    for subtable in &subtables {
+        offsets.push(next as u32);
+        next += subtable.len();
+    }
+    let mut cmap = Vec::with_capacity(next);
+    cmap.extend_from_slice(&0u16.to_be_bytes()); // version
+    cmap.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    for (platform, encoding, index) in records {
+        cmap.extend_from_slice(&platform.to_be_bytes());
+        cmap.extend_from_slice(&encoding.to_be_bytes());
+        cmap.extend_from_slice(&offsets[index].to_be_bytes());
+    }
+    for subtable in subtables {
+        cmap.extend_from_slice(subtable);
+    }
+    Ok(cmap)
+}
+
+/// cmap テーブルの format 14 サブテーブル (異体字セレクタ) を返します。
+pub fn variation_subtable(cmap: &[u8]) -> Result<Option<&[u8]>, FontExtractorError> {
+    let num_subtables = read_u16(cmap, 2)?;
+    for i in 0..num_subtables as usize {
+        let offset = read_u32(cmap, 4 + i * 8 + 4)? as usize;
+        if read_u16(cmap, offset)? == 14 {
+            let length = read_u32(cmap, offset + 2)? as usize;
+            return cmap
+                .get(offset..offset + length)
+                .map(Some)
+                .ok_or_else(|| corrupt("cmap format 14 subtable is truncated"));
+        }
+    }
+    Ok(None)
+}
+
+/// cmap のエンコーディングレコードと、それが指すサブテーブル
+#[derive(Debug, Clone, Copy)]
+pub struct Subtable<'a> {
+    pub platform: u16,
+    pub encoding: u16,
+    pub data: &'a [u8],
+}
+
+/// cmap テーブルの Unicode 以外のサブテーブル (Mac の (1,0)、シンボルフォントの (3,0) など) を返します。
+///
+/// Unicode のサブテーブルは platform 0 と (3,1)、(3,10) です。
+pub fn non_unicode_subtables(cmap: &[u8]) -> Result<Vec<Subtable<'_>>, FontExtractorError> {
+    let num_subtables = read_u16(cmap, 2)?;
+    let mut subtables = Vec::new();
+    for i in 0..num_subtables as usize {
+        let record = 4 + i * 8;
+        let platform = read_u16(cmap, record)?;
+        let encoding = read_u16(cmap, record + 2)?;
+        if platform == 0 || (platform == 3 && matches!(encoding, 1 | 10)) {
+            continue;
+        }
+        let offset = read_u32(cmap, record + 4)? as usize;
+        // format 8 以降は length が 32 ビット (format 14 は Unicode 専用で platform 0 にしか無い)
+        let length = match read_u16(cmap, offset)? {
+            0 | 2 | 4 | 6 => read_u16(cmap, offset + 2)? as usize,
+            _ => read_u32(cmap, offset + 4)? as usize,
+        };
+        let subtable = cmap
+            .get(offset..offset.saturating_add(length))
+            .ok_or_else(|| {
+                corrupt(format!(
+                    "cmap subtable ({platform},{encoding}) is truncated"
+                ))
+            })?;
+        subtables.push(Subtable {
+            platform,
+            encoding,
+            data: subtable,
+        });
+    }
+    Ok(subtables)
+}
+
+/// BMP の文字の format 4 サブテーブル
+fn build_format4(map: &BTreeMap<u32, u16>) -> Result<Vec<u8>, FontExtractorError> {
+    // (開始コード, 終了コード, idDelta, glyphIdArray に置くグリフ ID)
+    let mut segments: Vec<(u16, u16, u16, Vec<u16>)> = Vec::new();
+    let bmp: Vec<(u16, u16)> = map
+        .range(..0xFFFF)
+        .map(|(&code, &glyph)| (code as u16, glyph))
+        .collect();
+    for run in bmp.chunk_by(|a, b| a.0 + 1 == b.0) {
+        // 連続するコードの中で、グリフ ID も連続する部分ごとに分ける
+        let mut pending: Vec<(u16, u16)> = Vec::new();
+        for piece in run.chunk_by(|a, b| a.1.wrapping_add(1) == b.1) {
+            if piece.len() >= MIN_DELTA_SEGMENT {
+                push_array_segment(&mut segments, &pending);
+                pending.clear();
+                let (start, glyph) = piece[0];
+                segments.push((
+                    start,
+                    piece[piece.len() - 1].0,
+                    glyph.wrapping_sub(start),
+                    Vec::new(),
+                ));
+            } else {
+                pending.extend_from_slice(piece);
+            }
+        }
+        push_array_segment(&mut segments, &pending);
+    }
+    // 最後のセグメントは 0xFFFF → .notdef でなければならない
+    segments.push((0xFFFF, 0xFFFF, 1, Vec::new()));
+
+    let seg_count = segments.len();
+    let entry_selector = 15 - (seg_count as u16).leading_zeros() as u16;
+    let search_range = 2 * (1u16 << entry_selector);
+    let mut end_codes = Vec::new();
+    let mut start_codes = Vec::new();
+    let mut deltas = Vec::new();
+    let mut range_offsets = Vec::new();
+    let mut glyph_array = Vec::new();
+    for (i, (start, end, delta, glyphs)) in segments.iter().enumerate() {
+        end_codes.extend_from_slice(&end.to_be_bytes());
+        start_codes.extend_from_slice(&start.to_be_bytes());
+        deltas.extend_from_slice(&delta.to_be_bytes());
+        let range_offset = if glyphs.is_empty() {
+            0
+        } else {
+            // idRangeOffset は、その値自身の位置から glyphIdArray の該当位置までのバイト数
+            2 * (seg_count - i) + glyph_array.len()
+        };
+        range_offsets.extend_from_slice(&(range_offset as u16).to_be_bytes());
+        for glyph in glyphs {
+            glyph_array.extend_from_slice(&glyph.to_be_bytes());
+        }
+    }
+
+    let length = 16 + 8 * seg_count + glyph_array.len();
+    if length > u16::MAX as usize {
+        return Err(corrupt(format!(
+            "too many BMP characters for a format 4 cmap subtable ({length} bytes)"
+        )));
+    }
+    let mut subtable = Vec::with_capacity(length);
+    for value in [
+        4,
+        length as u16,
+        0, // language
+        2 * seg_count as u16,
+        search_range,
+        entry_selector,
+        2 * seg_count as u16 - search_range,
+    ] {
+        subtable.extend_from_slice(&value.to_be_bytes());
+    }
+    subtable.extend_from_slice(&end_codes);
+    subtable.extend_from_slice(&0u16.to_be_bytes()); // reservedPad
+    subtable.extend_from_slice(&start_codes);
+    subtable.extend_from_slice(&deltas);
+    subtable.extend_from_slice(&range_offsets);
+    subtable.extend_from_slice(&glyph_array);
+    Ok(subtable)
+}
+
+/// 連続するコードの (コード, グリフ ID) を glyphIdArray を使うセグメントとして追加します。
+fn push_array_segment(segments: &mut Vec<(u16, u16, u16, Vec<u16>)>, codes: &[(u16, u16)]) {
+    if let (Some(&(start, _)), Some(&(end, _))) = (codes.first(), codes.last()) {
+        segments.push((start, end, 0, codes.iter().map(|&(_, g)| g).collect()));
+    }
+}
+
+/// すべての文字の format 12 サブテーブル
+fn build_format12(map: &BTreeMap<u32, u16>) -> Vec<u8> {
+    let entries: Vec<(u32, u16)> = map.iter().map(|(&code, &glyph)| (code, glyph)).collect();
+    let groups: Vec<&[(u32, u16)]> = entries
+        .chunk_by(|a, b| a.0 + 1 == b.0 && a.1.wrapping_add(1) == b.1)
+        .collect();
+    let mut subtable = Vec::with_capacity(16 + 12 * groups.len());
+    subtable.extend_from_slice(&12u16.to_be_bytes());
+    subtable.extend_from_slice(&0u16.to_be_bytes()); // reserved
+    subtable.extend_from_slice(&((16 + 12 * groups.len()) as u32).to_be_bytes());
+    subtable.extend_from_slice(&0u32.to_be_bytes()); // language
+    subtable.extend_from_slice(&(groups.len() as u32).to_be_bytes());
+    for group in groups {
+        subtable.extend_from_slice(&group[0].0.to_be_bytes());
+        subtable.extend_from_slice(&group[group.len() - 1].0.to_be_bytes());
+        subtable.extend_from_slice(&(group[0].1 as u32).to_be_bytes());
+    }
+    subtable
+}
@@ -0,0 +1,365 @@
+//! glyf / loca テーブル (TrueType アウトライン) のパーサ
+//!
+//! 複合グリフは構成要素のアウトラインを変換行列とオフセットを適用して展開し、
+//! 単純グリフと同じく輪郭 (点の列) として返します。ヒンティング命令は読み飛ばします。
+
+use super::{Sfnt, corrupt, read_i16, read_u16, read_u32};
+use crate::FontExtractorError;
+
+/// 複合グリフの入れ子の深さの上限 (循環参照への対策)
+const MAX_COMPONENT_DEPTH: usize = 16;
+
+// 単純グリフの点のフラグ
+const ON_CURVE_POINT: u8 = 0x01;
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const REPEAT_FLAG: u8 = 0x08;
+const X_IS_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE: u8 = 0x20;
+
+// 複合グリフの構成要素のフラグ
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+/// アウトラインの 1 点
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+    pub on_curve: bool,
+}
+
+/// グリフのアウトライン (輪郭ごとの点の列)。空のグリフ (スペースなど) は輪郭を持ちません。
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Outline {
+    pub contours: Vec<Vec<Point>>,
+}
+
+impl Outline {
+    pub fn num_points(&self) -> usize {
+        self.contours.iter().map(Vec::len).sum()
+    }
+
+    fn points(&self) -> impl Iterator<Item = &Point> {
+        self.contours.iter().flatten()
+    }
+}
+
+/// フォントの glyf / loca テーブル
+pub struct Glyf<'a> {
+    glyf: &'a [u8],
+    loca: &'a [u8],
+    long_loca: bool,
+    num_glyphs: u16,
+}
+
+impl<'a> Glyf<'a> {
+    /// フォントに glyf テーブルが無い (CFF アウトラインなど) 場合は `None` を返します。
+    pub fn from_font(font: &Sfnt<'a>) -> Result<Option<Self>, FontExtractorError> {
+        let Some(glyf) = font.table(b"glyf") else {
+            return Ok(None);
+        };
+        let require = |tag: &[u8; 4]| {
+            font.table(tag).ok_or_else(|| {
+                corrupt(format!(
+                    "font has a 'glyf' table but no '{}' table",
+                    super::tag_to_string(tag)
+                ))
+            })
+        };
+        Ok(Some(Self {
+            glyf,
+            loca: require(b"loca")?,
+            long_loca: read_i16(require(b"head")?, 50)? != 0,
+            num_glyphs: read_u16(require(b"maxp")?, 4)?,
+        }))
+    }
+
+    /// `glyph` のアウトラインを返します。
+    pub fn outline(&self, glyph: u16) -> Result<Outline, FontExtractorError> {
+        self.outline_at_depth(glyph, 0)
+    }
+
+    /// フォントのグリフ数 (maxp の numGlyphs)
+    pub fn num_glyphs(&self) -> u16 {
+        self.num_glyphs
+    }
+
+    /// glyf テーブル内のグリフデータの範囲 (輪郭を持たないグリフは空)
+    pub fn glyph_data(&self, glyph: u16) -> Result<&'a [u8], FontExtractorError> {
+        if glyph >= self.num_glyphs {
+            return Err(corrupt(format!(
+                "glyph {glyph} is out of range ({} glyphs)",
+                self.num_glyphs
+            )));
+        }
+        let i = glyph as usize;
+        let (start, end) = if self.long_loca {
+            (
+                read_u32(self.loca, i * 4)? as usize,
+                read_u32(self.loca, i * 4 + 4)? as usize,
+            )
+        } else {
+            (
+                read_u16(self.loca, i * 2)? as usize * 2,
+                read_u16(self.loca, i * 2 + 2)? as usize * 2,
+            )
+        };
+        if start >= end {
+            return Ok(&[]);
+        }
+        self.glyf
+            .get(start..end)
+            .ok_or_else(|| corrupt(format!("glyph {glyph} extends past the 'glyf' table")))
+    }
+
+    fn outline_at_depth(&self, glyph: u16, depth: usize) -> Result<Outline, FontExtractorError> {
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(corrupt(format!(
+                "composite glyph {glyph} is nested too deeply"
+            )));
+        }
+        let data = self.glyph_data(glyph)?;
+        if data.is_empty() {
+            return Ok(Outline::default());
+        }
+        let num_contours = read_i16(data, 0)?;
+        if num_contours >= 0 {
+            parse_simple(data, num_contours as usize)
+        } else {
+            self.parse_composite(data, depth)
+        }
+    }
+
+    fn parse_composite(&self, data: &[u8], depth: usize) -> Result<Outline, FontExtractorError> {
+        let mut outline = Outline::default();
+        let mut offset = 10;
+        loop {
+            let flags = read_u16(data, offset)?;
+            let component = read_u16(data, offset + 2)?;
+            offset += 4;
+            let (arg1, arg2) = if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                offset += 4;
+                if flags & ARGS_ARE_XY_VALUES != 0 {
+                    (
+                        read_i16(data, offset - 4)? as i32,
+                        read_i16(data, offset - 2)? as i32,
+                    )
+                } else {
+                    (
+                        read_u16(data, offset - 4)? as i32,
+                        read_u16(data, offset - 2)? as i32,
+                    )
+                }
+            } else {
+                offset += 2;
+                let bytes = data
+                    .get(offset - 2..offset)
+                    .ok_or_else(|| corrupt("composite glyph is truncated"))?;
+                if flags & ARGS_ARE_XY_VALUES != 0 {
+                    (bytes[0] as i8 as i32, bytes[1] as i8 as i32)
+                } else {
+                    (bytes[0] as i32, bytes[1] as i32)
+                }
+            };
+
+            // 変換行列 [a c; b d] (F2Dot14)
+            let f2dot14 = |at: usize| read_i16(data, at).map(|v| v as f64 / 16384.0);
+            let (a, b, c, d) = if flags & WE_HAVE_A_SCALE != 0 {
+                offset += 2;
+                let scale = f2dot14(offset - 2)?;
+                (scale, 0.0, 0.0, scale)
+            } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                offset += 4;
+                (f2dot14(offset - 4)?, 0.0, 0.0, f2dot14(offset - 2)?)
+            } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                offset += 8;
+                (
+                    f2dot14(offset - 8)?,
+                    f2dot14(offset - 6)?,
+                    f2dot14(offset - 4)?,
+                    f2dot14(offset - 2)?,
+                )
+            } else {
+                (1.0, 0.0, 0.0, 1.0)
+            };
+
+            let mut child = self.outline_at_depth(component, depth + 1)?;
+            for point in child.contours.iter_mut().flatten() {
+                (point.x, point.y) = (a * point.x + c * point.y, b * point.x + d * point.y);
+            }
+            // オフセットは座標値、または (親の点番号, 子の点番号) による点の位置合わせで指定される
+            let (dx, dy) = if flags & ARGS_ARE_XY_VALUES != 0 {
+                (arg1 as f64, arg2 as f64)
+            } else {
+                let parent = outline.points().nth(arg1 as usize);
+                let child_point = child.points().nth(arg2 as usize);
+                match (parent, child_point) {
+                    (Some(p), Some(q)) => (p.x - q.x, p.y - q.y),
+                    _ => {
+                        return Err(corrupt(
+                            "composite glyph anchors a point that does not exist",
+                        ));
+                    }
+                }
+            };
+            for point in child.contours.iter_mut().flatten() {
+                point.x += dx;
+                point.y += dy;
+            }
+            outline.contours.extend(child.contours);
+
+            if flags & MORE_COMPONENTS == 0 {
+                return Ok(outline);
+            }
+        }
+    }
+}
+
+fn parse_simple(data: &[u8], num_contours: usize) -> Result<Outline, FontExtractorError> {
+    let mut end_points = Vec::with_capacity(num_contours);
+    for i in 0..num_contours {
+        end_points.push(read_u16(data, 10 + i * 2)? as usize);
+    }
+    let num_points = end_points.last().map_or(0, |&last| last + 1);
+    let instructions_len = read_u16(data, 10 + num_contours * 2)? as usize;
+    let mut offset = 12 + num_contours * 2 + instructions_len;
+    let truncated = || corrupt("simple glyph is truncated");
+
+    let mut flags = Vec::with_capacity(num_points);
+    while flags.len() < num_points {
+        let flag = *data.get(offset).ok_or_else(truncated)?;
+        offset += 1;
+        let repeat = if flag & REPEAT_FLAG != 0 {
+            offset += 1;
+            *data.get(offset - 1).ok_or_else(truncated)? as usize
+        } else {
+            0
+        };
+        flags.extend(std::iter::repeat_n(flag, repeat + 1));
+    }
+    flags.truncate(num_points);
+
+    // 座標は前の点からの差分で、X 座標がすべて並んだ後に Y 座標が並ぶ
+    let mut read_coordinates = |short: u8, same_or_positive: u8| {
+        let mut value = 0i32;
+        let mut coordinates = Vec::with_capacity(num_points);
+        for &flag in &flags {
+            if flag & short != 0 {
+                let delta = *data.get(offset).ok_or_else(truncated)? as i32;
+                offset += 1;
+                value += if flag & same_or_positive != 0 {
+                    delta
+                } else {
+                    -delta
+                };
+            } else if flag & same_or_positive == 0 {
+                value += read_i16(data, offset)? as i32;
+                offset += 2;
+            }
+            coordinates.push(value);
+        }
+        Ok::<_, FontExtractorError>(coordinates)
+    };
+    let xs = read_coordinates(X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE)?;
+    let ys = read_coordinates(Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE)?;
+
+    let mut contours = Vec::with_capacity(num_contours);
+    let mut start = 0;
+    for end in end_points {
+        if end < start || end >= num_points {
+            return Err(corrupt("simple glyph has decreasing contour end points"));
+        }
+        contours.push(
+            (start..=end)
+                .map(|i| Point {
+                    x: xs[i] as f64,
+                    y: ys[i] as f64,
+                    on_curve: flags[i] & ON_CURVE_POINT != 0,
+                })
+                .collect(),
+        );
+        start = end + 1;
+    }
+    Ok(Outline { contours })
+}
+
+/// 複合グリフの各構成要素のレコードの (開始位置, 終了位置)。単純グリフや空のグリフでは空です。
+fn component_records(data: &[u8]) -> Result<Vec<(usize, usize)>, FontExtractorError> {
+    if data.is_empty() || read_i16(data, 0)? >= 0 {
+        return Ok(Vec::new());
+    }
+    let mut records = Vec::new();
+    let mut offset = 10;
+    loop {
+        let flags = read_u16(data, offset)?;
+        let mut len = 4;
+        len += if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+            4
+        } else {
+            2
+        };
+        if flags & WE_HAVE_A_SCALE != 0 {
+            len += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            len += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            len += 8;
+        }
+        if offset + len > data.len() {
+            return Err(corrupt("composite glyph is truncated"));
+        }
+        records.push((offset, offset + len));
+        offset += len;
+        if flags & MORE_COMPONENTS == 0 {
+            return Ok(records);
+        }
+    }
+}
+
+/// 複合グリフが参照する構成要素のグリフ ID を返します。単純グリフや空のグリフでは空です。
+pub fn components(data: &[u8]) -> Result<Vec<u16>, FontExtractorError> {
+    component_records(data)?
+        .into_iter()
+        .map(|(start, _)| read_u16(data, start + 2))
+        .collect()
+}
+
+/// グリフデータからヒンティング命令を取り除き、複合グリフの構成要素のグリフ ID を `remap` で置き換えます。
+///
+/// 命令は元のフォントの fpgm / prep / cvt を前提にしているため、別のフォントへグリフを移すときに使います。
+pub fn strip_and_remap(
+    data: &[u8],
+    remap: impl Fn(u16) -> u16,
+) -> Result<Vec<u8>, FontExtractorError> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    let num_contours = read_i16(data, 0)?;
+    if num_contours >= 0 {
+        let instructions = 10 + num_contours as usize * 2;
+        let instructions_len = read_u16(data, instructions)? as usize;
+        let rest = data
+            .get(instructions + 2 + instructions_len..)
+            .ok_or_else(|| corrupt("simple glyph is truncated"))?;
+        let mut glyph = data[..instructions].to_vec();
+        glyph.extend_from_slice(&0u16.to_be_bytes());
+        glyph.extend_from_slice(rest);
+        return Ok(glyph);
+    }
+
+    let records = component_records(data)?;
+    let mut glyph = data[..10].to_vec();
+    for (start, end) in records {
+        let flags = read_u16(data, start)? & !WE_HAVE_INSTRUCTIONS;
+        glyph.extend_from_slice(&flags.to_be_bytes());
+        glyph.extend_from_slice(&remap(read_u16(data, start + 2)?).to_be_bytes());
+        glyph.extend_from_slice(&data[start + 4..end]);
+    }
+    Ok(glyph)
+}
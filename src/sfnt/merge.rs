@@ -0,0 +1,330 @@
+//! 複数のフォントのグリフを 1 つのフォントにまとめる (TrueType アウトラインのみ)
+//!
+//! 最初のフォント (ベース) のグリフとテーブルはグリフ ID を変えずにそのまま残し、
+//! 2 つ目以降のフォントからは cmap で採用した文字のグリフ (と複合グリフの構成要素) だけを末尾に追加します。
+//! ベースの GSUB / GPOS などはベースのグリフ ID のまま有効ですが、追加したフォントのレイアウト機能は引き継ぎません。
+
+use std::collections::{BTreeMap, HashMap};
+
+use clap::ValueEnum;
+
+use super::glyf::{self, Glyf};
+use super::rebuild::Rebuild;
+use super::{Sfnt, Tag, cmap, corrupt, read_i16, read_u16, read_u32};
+use crate::FontExtractorError;
+
+/// 書き出さないテーブル (グリフ数に依存する、または変更で無効になる)
+const DROPPED_TABLES: &[&Tag] = &[
+    b"DSIG", b"hdmx", b"LTSH", b"VDMX", b"sbix", b"vhea", b"vmtx",
+];
+
+/// まとめたフォントで作り直すテーブル
+const REBUILT_TABLES: &[&Tag] = &[
+    b"cmap", b"glyf", b"head", b"hhea", b"hmtx", b"loca", b"maxp", b"OS/2", b"post",
+];
+
+/// 複数のフォントに同じ文字がある場合にどのフォントのグリフを使うか
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overlap {
+    /// The font listed first wins
+    #[default]
+    First,
+    /// The font listed last wins
+    Last,
+}
+
+/// まとめた結果
+pub struct Merged {
+    pub data: Vec<u8>,
+    /// フォントごとの (採用した文字数, 追加したグリフ数)。ベースのグリフはすべて残るため、追加数はベースでは 0 です。
+    pub contributions: Vec<(usize, usize)>,
+    /// ベースにあったが書き出さなかったテーブル
+    pub dropped_tables: Vec<Tag>,
+}
+
+/// まとめる元のフォント
+struct Source<'a> {
+    font: Sfnt<'a>,
+    glyf: Glyf<'a>,
+    hmtx: &'a [u8],
+    num_h_metrics: u16,
+    map: BTreeMap<u32, u16>,
+}
+
+impl<'a> Source<'a> {
+    fn parse(data: &'a [u8], index: usize) -> Result<Self, FontExtractorError> {
+        let font = Sfnt::parse_face(data, 0)?;
+        if font.table(b"fvar").is_some() {
+            return Err(corrupt(format!(
+                "font #{} is a variable font, which cannot be merged",
+                index + 1
+            )));
+        }
+        let glyf = Glyf::from_font(&font)?.ok_or_else(|| {
+            corrupt(format!(
+                "font #{} has no TrueType outlines ('glyf'); only TrueType fonts can be merged",
+                index + 1
+            ))
+        })?;
+        let require = |tag: &Tag| {
+            font.table(tag).ok_or_else(|| {
+                corrupt(format!(
+                    "font #{} has no '{}' table",
+                    index + 1,
+                    super::tag_to_string(tag)
+                ))
+            })
+        };
+        let hmtx = require(b"hmtx")?;
+        let num_h_metrics = read_u16(require(b"hhea")?, 34)?;
+        let map = cmap::parse(require(b"cmap")?)?;
+        Ok(Self {
+            font,
+            glyf,
+            hmtx,
+            num_h_metrics,
+            map,
+        })
+    }
+
+    fn table(&self, tag: &Tag) -> &'a [u8] {
+        self.font.table(tag).unwrap_or_default()
+    }
+
+    /// グリフの (送り幅, 左サイドベアリング)
+    fn metrics(&self, glyph: u16) -> Result<(u16, i16), FontExtractorError> {
+        let long = self.num_h_metrics.max(1);
+        if glyph < long {
+            let at = glyph as usize * 4;
+            Ok((read_u16(self.hmtx, at)?, read_i16(self.hmtx, at + 2)?))
+        } else {
+            // 最後の送り幅が以降のグリフにも使われる
+            let advance = read_u16(self.hmtx, (long as usize - 1) * 4)?;
+            let at = long as usize * 4 + (glyph - long) as usize * 2;
+            Ok((advance, read_i16(self.hmtx, at)?))
+        }
+    }
+}
+
+/// `fonts` を 1 つのフォントにまとめます。最初のフォントがベースになります。
+pub fn merge(fonts: &[&[u8]], overlap: Overlap) -> Result<Merged, FontExtractorError> {
+    let sources = fonts
+        .iter()
+        .enumerate()
+        .map(|(i, data)| Source::parse(data, i))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(base) = sources.first() else {
+        return Err(corrupt("no fonts to merge"));
+    };
+    let units_per_em = read_u16(base.table(b"head"), 18)?;
+    for (i, source) in sources.iter().enumerate().skip(1) {
+        let other = read_u16(source.table(b"head"), 18)?;
+        if other != units_per_em {
+            return Err(corrupt(format!(
+                "font #{} has {other} units per em but the base font has {units_per_em}",
+                i + 1
+            )));
+        }
+    }
+
+    // --- 文字ごとにどのフォントのグリフを使うかを決める ---
+    let mut assigned: BTreeMap<u32, (usize, u16)> = BTreeMap::new();
+    for (i, source) in sources.iter().enumerate() {
+        for (&codepoint, &glyph) in &source.map {
+            match overlap {
+                Overlap::First => {
+                    assigned.entry(codepoint).or_insert((i, glyph));
+                }
+                Overlap::Last => {
+                    assigned.insert(codepoint, (i, glyph));
+                }
+            }
+        }
+    }
+
+    // --- グリフ ID の割り当て (ベースはそのまま、他のフォントのグリフは末尾に追加) ---
+    let mut glyphs: Vec<(usize, u16)> = (0..base.glyf.num_glyphs()).map(|g| (0, g)).collect();
+    let mut ids: Vec<HashMap<u16, u16>> = vec![HashMap::new(); sources.len()];
+    let mut add = |source: usize, glyph: u16, glyphs: &mut Vec<(usize, u16)>| {
+        if source == 0 {
+            return Ok(glyph);
+        }
+        if let Some(&id) = ids[source].get(&glyph) {
+            return Ok(id);
+        }
+        let id = u16::try_from(glyphs.len())
+            .map_err(|_| corrupt("the merged font would have more than 65535 glyphs"))?;
+        ids[source].insert(glyph, id);
+        glyphs.push((source, glyph));
+        Ok::<_, FontExtractorError>(id)
+    };
+    let mut map = BTreeMap::new();
+    for (&codepoint, &(source, glyph)) in &assigned {
+        map.insert(codepoint, add(source, glyph, &mut glyphs)?);
+    }
+    // 追加したグリフが参照する構成要素も追加する (追加した構成要素の構成要素も、この走査で順に処理される)
+    let mut next = base.glyf.num_glyphs() as usize;
+    while next < glyphs.len() {
+        let (source, glyph) = glyphs[next];
+        for component in glyf::components(sources[source].glyf.glyph_data(glyph)?)? {
+            add(source, component, &mut glyphs)?;
+        }
+        next += 1;
+    }
+    let num_glyphs = glyphs.len() as u16;
+
+    // --- glyf / loca / hmtx ---
+    let mut glyf_table = Vec::new();
+    let mut loca = Vec::with_capacity((glyphs.len() + 1) * 4);
+    let mut hmtx = Vec::with_capacity(glyphs.len() * 4);
+    for &(source, glyph) in &glyphs {
+        loca.extend_from_slice(&(glyf_table.len() as u32).to_be_bytes());
+        let data = sources[source].glyf.glyph_data(glyph)?;
+        if source == 0 {
+            glyf_table.extend_from_slice(data);
+        } else {
+            let remap = |component: u16| ids[source].get(&component).copied().unwrap_or(0);
+            glyf_table.extend_from_slice(&glyf::strip_and_remap(data, remap)?);
+        }
+        glyf_table.resize(glyf_table.len().next_multiple_of(4), 0);
+        let (advance, lsb) = sources[source].metrics(glyph)?;
+        hmtx.extend_from_slice(&advance.to_be_bytes());
+        hmtx.extend_from_slice(&lsb.to_be_bytes());
+    }
+    loca.extend_from_slice(&(glyf_table.len() as u32).to_be_bytes());
+
+    // --- 他のテーブルはベースの値を元に、全フォントの最大・最小を反映する ---
+    let mut maxp = base.table(b"maxp").to_vec();
+    set_u16(&mut maxp, 4, num_glyphs)?;
+    // maxPoints, maxContours, maxCompositePoints, maxCompositeContours, maxComponentElements, maxComponentDepth
+    for offset in [6, 8, 10, 12, 28, 30] {
+        let max = max_u16(&sources, b"maxp", offset)?;
+        set_u16(&mut maxp, offset, max)?;
+    }
+
+    let mut hhea = base.table(b"hhea").to_vec();
+    set_u16(&mut hhea, 10, max_u16(&sources, b"hhea", 10)?)?; // advanceWidthMax
+    set_i16(&mut hhea, 12, min_i16(&sources, b"hhea", 12)?)?; // minLeftSideBearing
+    set_i16(&mut hhea, 14, min_i16(&sources, b"hhea", 14)?)?; // minRightSideBearing
+    set_i16(&mut hhea, 16, max_i16(&sources, b"hhea", 16)?)?; // xMaxExtent
+    set_u16(&mut hhea, 34, num_glyphs)?; // numberOfHMetrics
+
+    let mut head = base.table(b"head").to_vec();
+    set_i16(&mut head, 36, min_i16(&sources, b"head", 36)?)?; // xMin
+    set_i16(&mut head, 38, min_i16(&sources, b"head", 38)?)?; // yMin
+    set_i16(&mut head, 40, max_i16(&sources, b"head", 40)?)?; // xMax
+    set_i16(&mut head, 42, max_i16(&sources, b"head", 42)?)?; // yMax
+    set_i16(&mut head, 50, 1)?; // indexToLocFormat (long)
+
+    // グリフ数が変わるためグリフ名は持たない (version 3.0)
+    let mut post = base
+        .table(b"post")
+        .get(..32)
+        .ok_or_else(|| corrupt("the base font's 'post' table is missing or truncated"))?
+        .to_vec();
+    post[..4].copy_from_slice(&0x0003_0000u32.to_be_bytes());
+
+    let cmap_table = cmap::build(&map, cmap::variation_subtable(base.table(b"cmap"))?, &[])?;
+    let os2 = match base.font.table(b"OS/2") {
+        Some(os2) => Some(merge_os2(os2, &sources, &map)?),
+        None => None,
+    };
+
+    let mut rebuild = Rebuild::from_font(&base.font);
+    rebuild.retain(|tag| !REBUILT_TABLES.contains(&tag) && !DROPPED_TABLES.contains(&tag));
+    for (tag, data) in [
+        (*b"cmap", cmap_table.as_slice()),
+        (*b"glyf", &glyf_table),
+        (*b"head", &head),
+        (*b"hhea", &hhea),
+        (*b"hmtx", &hmtx),
+        (*b"loca", &loca),
+        (*b"maxp", &maxp),
+        (*b"post", &post),
+    ] {
+        rebuild.replace(tag, data);
+    }
+    if let Some(os2) = &os2 {
+        rebuild.replace(*b"OS/2", os2);
+    }
+
+    let contributions = (0..sources.len())
+        .map(|i| {
+            (
+                assigned
+                    .values()
+                    .filter(|&&(source, _)| source == i)
+                    .count(),
+                ids[i].len(),
+            )
+        })
+        .collect();
+    let dropped_tables = base
+        .font
+        .tables
+        .iter()
+        .filter(|t| DROPPED_TABLES.contains(&&t.tag))
+        .map(|t| t.tag)
+        .collect();
+    Ok(Merged {
+        data: rebuild.finish()?,
+        contributions,
+        dropped_tables,
+    })
+}
+
+/// ベースの OS/2 に、全フォントの ulUnicodeRange / ulCodePageRange と、まとめた cmap の文字の範囲を反映します。
+fn merge_os2(
+    os2: &[u8],
+    sources: &[Source<'_>],
+    map: &BTreeMap<u32, u16>,
+) -> Result<Vec<u8>, FontExtractorError> {
+    let mut merged = os2.to_vec();
+    // ulUnicodeRange1..4 と (バージョン 1 以降の) ulCodePageRange1..2
+    let mut fields = vec![42, 46, 50, 54];
+    if os2.len() >= 86 {
+        fields.extend([78, 82]);
+    }
+    for offset in fields {
+        let mut bits = read_u32(os2, offset)?;
+        for source in &sources[1..] {
+            bits |= read_u32(source.table(b"OS/2"), offset).unwrap_or(0);
+        }
+        merged[offset..offset + 4].copy_from_slice(&bits.to_be_bytes());
+    }
+    let first = map.keys().next().copied().unwrap_or(0).min(0xFFFF) as u16;
+    let last = map.keys().next_back().copied().unwrap_or(0).min(0xFFFF) as u16;
+    set_u16(&mut merged, 64, first)?; // usFirstCharIndex
+    set_u16(&mut merged, 66, last)?; // usLastCharIndex
+    Ok(merged)
+}
+
+fn max_u16(sources: &[Source<'_>], tag: &Tag, offset: usize) -> Result<u16, FontExtractorError> {
+    sources.iter().try_fold(0, |max, source| {
+        Ok(max.max(read_u16(source.table(tag), offset)?))
+    })
+}
+
+fn max_i16(sources: &[Source<'_>], tag: &Tag, offset: usize) -> Result<i16, FontExtractorError> {
+    sources.iter().try_fold(i16::MIN, |max, source| {
+        Ok(max.max(read_i16(source.table(tag), offset)?))
+    })
+}
+
+fn min_i16(sources: &[Source<'_>], tag: &Tag, offset: usize) -> Result<i16, FontExtractorError> {
+    sources.iter().try_fold(i16::MAX, |min, source| {
+        Ok(min.min(read_i16(source.table(tag), offset)?))
+    })
+}
+
+fn set_u16(table: &mut [u8], offset: usize, value: u16) -> Result<(), FontExtractorError> {
+    table
+        .get_mut(offset..offset + 2)
+        .ok_or_else(|| corrupt(format!("table is too short to set offset {offset}")))?
+        .copy_from_slice(&value.to_be_bytes());
+    Ok(())
+}
+
+fn set_i16(table: &mut [u8], offset: usize, value: i16) -> Result<(), FontExtractorError> {
+    set_u16(table, offset, value as u16)
+}
@@ -0,0 +1,329 @@
+//! name テーブルのパーサ
+
+use super::{corrupt, read_u16};
+use crate::FontExtractorError;
+
+/// Windows プラットフォームの英語 (米国) 言語 ID
+const LANGUAGE_EN_US: u16 = 0x0409;
+
+/// name テーブルの 1 レコード
+#[derive(Debug, Clone)]
+pub struct NameRecord<'a> {
+    pub platform_id: u16,
+    pub encoding_id: u16,
+    pub language_id: u16,
+    pub name_id: u16,
+    pub bytes: &'a [u8],
+}
+
+impl NameRecord<'_> {
+    /// レコードの文字列をデコードします。未対応のエンコーディングの場合は `None` を返します。
+    pub fn decode(&self) -> Option<String> {
+        match (self.platform_id, self.encoding_id) {
+            // Unicode / Windows (Symbol, BMP, Full) は UTF-16BE
+            (0, _) | (3, 0 | 1 | 10) => {
+                let units: Vec<u16> = self
+                    .bytes
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                Some(String::from_utf16_lossy(&units))
+            }
+            // Macintosh Roman は 1 バイト文字コード
+            (1, 0) => Some(
+                self.bytes
+                    .iter()
+                    .map(|&b| {
+                        if b < 0x80 {
+                            b as char
+                        } else {
+                            MAC_ROMAN_HIGH[b as usize - 0x80]
+                        }
+                    })
+                    .collect(),
+            ),
+            // その他の Macintosh スクリプトは対応する Windows コードページで変換
+            (1, encoding) => {
+                mac_code_page(encoding).and_then(|cp| decode_code_page(self.bytes, cp))
+            }
+            _ => None,
+        }
+    }
+
+    /// プラットフォーム ID の表示名
+    pub fn platform_label(&self) -> &'static str {
+        match self.platform_id {
+            0 => "Unicode",
+            1 => "Macintosh",
+            2 => "ISO",
+            3 => "Windows",
+            4 => "Custom",
+            _ => "Unknown",
+        }
+    }
+
+    /// エンコーディング ID の表示名
+    pub fn encoding_label(&self) -> &'static str {
+        match (self.platform_id, self.encoding_id) {
+            (0, 0) => "Unicode 1.0",
+            (0, 1) => "Unicode 1.1",
+            (0, 2) => "ISO/IEC 10646",
+            (0, 3) => "Unicode BMP",
+            (0, 4) => "Unicode full",
+            (1, 0) => "Roman",
+            (1, 1) => "Japanese",
+            (1, 2) => "Chinese (Traditional)",
+            (1, 3) => "Korean",
+            (1, 4) => "Arabic",
+            (1, 5) => "Hebrew",
+            (1, 6) => "Greek",
+            (1, 7) => "Russian",
+            (1, 21) => "Thai",
+            (1, 25) => "Chinese (Simplified)",
+            (3, 0) => "Symbol",
+            (3, 1) => "Unicode BMP",
+            (3, 2) => "ShiftJIS",
+            (3, 3) => "PRC",
+            (3, 4) => "Big5",
+            (3, 5) => "Wansung",
+            (3, 6) => "Johab",
+            (3, 10) => "Unicode full",
+            _ => "Unknown",
+        }
+    }
+
+    /// 言語 ID の表示名 (主要な言語のみ。それ以外は `None`)
+    pub fn language_label(&self) -> Option<&'static str> {
+        let label = match (self.platform_id, self.language_id) {
+            (0, _) => "-",
+            (3, 0x0401) => "ar-SA",
+            (3, 0x0404) => "zh-TW",
+            (3, 0x0405) => "cs-CZ",
+            (3, 0x0406) => "da-DK",
+            (3, 0x0407) => "de-DE",
+            (3, 0x0408) => "el-GR",
+            (3, 0x0409) => "en-US",
+            (3, 0x040A | 0x0C0A) => "es-ES",
+            (3, 0x040B) => "fi-FI",
+            (3, 0x040C) => "fr-FR",
+            (3, 0x040D) => "he-IL",
+            (3, 0x040E) => "hu-HU",
+            (3, 0x0410) => "it-IT",
+            (3, 0x0411) => "ja-JP",
+            (3, 0x0412) => "ko-KR",
+            (3, 0x0413) => "nl-NL",
+            (3, 0x0414) => "nb-NO",
+            (3, 0x0415) => "pl-PL",
+            (3, 0x0416) => "pt-BR",
+            (3, 0x0419) => "ru-RU",
+            (3, 0x041D) => "sv-SE",
+            (3, 0x041E) => "th-TH",
+            (3, 0x041F) => "tr-TR",
+            (3, 0x042A) => "vi-VN",
+            (3, 0x0804) => "zh-CN",
+            (3, 0x0809) => "en-GB",
+            (3, 0x0816) => "pt-PT",
+            (3, 0x0C04) => "zh-HK",
+            (3, 0x1004) => "zh-SG",
+            (1, 0) => "English",
+            (1, 1) => "French",
+            (1, 2) => "German",
+            (1, 3) => "Italian",
+            (1, 4) => "Dutch",
+            (1, 5) => "Swedish",
+            (1, 6) => "Spanish",
+            (1, 7) => "Danish",
+            (1, 8) => "Portuguese",
+            (1, 9) => "Norwegian",
+            (1, 10) => "Hebrew",
+            (1, 11) => "Japanese",
+            (1, 12) => "Arabic",
+            (1, 13) => "Finnish",
+            (1, 14) => "Greek",
+            (1, 19) => "Chinese (Traditional)",
+            (1, 23) => "Korean",
+            (1, 32) => "Russian",
+            (1, 33) => "Chinese (Simplified)",
+            _ => return None,
+        };
+        Some(label)
+    }
+}
+
+/// Macintosh Roman の 0x80..0xFF に対応する文字
+#[rustfmt::skip]
+const MAC_ROMAN_HIGH: [char; 128] = [
+    'Ä', 'Å', 'Ç', 'É', 'Ñ', 'Ö', 'Ü', 'á', 'à', 'â', 'ä', 'ã', 'å', 'ç', 'é', 'è',
+    'ê', 'ë', 'í', 'ì', 'î', 'ï', 'ñ', 'ó', 'ò', 'ô', 'ö', 'õ', 'ú', 'ù', 'û', 'ü',
+    '†', '°', '¢', '£', '§', '•', '¶', 'ß', '®', '©', '™', '´', '¨', '≠', 'Æ', 'Ø',
+    '∞', '±', '≤', '≥', '¥', 'µ', '∂', '∑', '∏', 'π', '∫', 'ª', 'º', 'Ω', 'æ', 'ø',
+    '¿', '¡', '¬', '√', 'ƒ', '≈', '∆', '«', '»', '…', '\u{A0}', 'À', 'Ã', 'Õ', 'Œ', 'œ',
+    '–', '—', '“', '”', '‘', '’', '÷', '◊', 'ÿ', 'Ÿ', '⁄', '€', '‹', '›', 'ﬁ', 'ﬂ',
+    '‡', '·', '‚', '„', '‰', 'Â', 'Ê', 'Á', 'Ë', 'È', 'Í', 'Î', 'Ï', 'Ì', 'Ó', 'Ô',
+    '\u{F8FF}', 'Ò', 'Ú', 'Û', 'Ù', 'ı', 'ˆ', '˜', '¯', '˘', '˙', '˚', '¸', '˝', '˛', 'ˇ',
+];
+
+/// Macintosh のエンコーディング ID に対応する Windows のコードページ
+fn mac_code_page(encoding_id: u16) -> Option<u32> {
+    match encoding_id {
+        1 => Some(10001),  // Japanese
+        2 => Some(10002),  // Chinese (Traditional)
+        3 => Some(10003),  // Korean
+        4 => Some(10004),  // Arabic
+        5 => Some(10005),  // Hebrew
+        6 => Some(10006),  // Greek
+        7 => Some(10007),  // Russian
+        21 => Some(10021), // Thai
+        25 => Some(10008), // Chinese (Simplified)
+        _ => None,
+    }
+}
+
+/// マルチバイト文字列を Windows のコードページ変換で UTF-16 に変換します。
+#[cfg(windows)]
+fn decode_code_page(bytes: &[u8], code_page: u32) -> Option<String> {
+    use windows::Win32::Globalization::{MULTI_BYTE_TO_WIDE_CHAR_FLAGS, MultiByteToWideChar};
+
+    if bytes.is_empty() {
+        return Some(String::new());
+    }
+    let flags = MULTI_BYTE_TO_WIDE_CHAR_FLAGS(0);
+    let len = unsafe { MultiByteToWideChar(code_page, flags, bytes, None) };
+    if len <= 0 {
+        return None;
+    }
+    let mut wide = vec![0u16; len as usize];
+    let written = unsafe { MultiByteToWideChar(code_page, flags, bytes, Some(&mut wide)) };
+    (written > 0).then(|| String::from_utf16_lossy(&wide[..written as usize]))
+}
+
+/// Windows 以外ではコードページの変換が無いため、Macintosh の (Roman 以外の) スクリプトの名前は読み取りません。
+#[cfg(not(windows))]
+fn decode_code_page(_bytes: &[u8], _code_page: u32) -> Option<String> {
+    None
+}
+
+/// name テーブルの全レコードをパースします。
+pub fn parse(name: &[u8]) -> Result<Vec<NameRecord<'_>>, FontExtractorError> {
+    let count = read_u16(name, 2)? as usize;
+    let storage = read_u16(name, 4)? as usize;
+    let mut records = Vec::with_capacity(count);
+    for i in 0..count {
+        let record = 6 + i * 12;
+        let length = read_u16(name, record + 8)? as usize;
+        let offset = storage + read_u16(name, record + 10)? as usize;
+        let bytes = name
+            .get(offset..offset + length)
+            .ok_or_else(|| corrupt("name record points outside the name table"))?;
+        records.push(NameRecord {
+            platform_id: read_u16(name, record)?,
+            encoding_id: read_u16(name, record + 2)?,
+            language_id: read_u16(name, record + 4)?,
+            name_id: read_u16(name, record + 6)?,
+            bytes,
+        });
+    }
+    Ok(records)
+}
+
+/// 指定した name ID の文字列を、Windows 英語 → Windows の任意言語 → その他の順で探します。
+pub fn find(records: &[NameRecord<'_>], name_id: u16) -> Option<String> {
+    let candidates = || records.iter().filter(move |r| r.name_id == name_id);
+    candidates()
+        .find(|r| r.platform_id == 3 && r.language_id == LANGUAGE_EN_US)
+        .or_else(|| candidates().find(|r| r.platform_id == 3))
+        .or_else(|| candidates().find(|r| r.decode().is_some()))
+        .and_then(NameRecord::decode)
+}
+
+/// 指定した name ID の文字列を、`languages` (`ja`、`en-US` などの言語タグ) の順に Windows プラットフォームのレコードから探します。
+///
+/// `ja` のように地域を省いたタグはその言語のすべての地域に一致します。
+/// どの言語のレコードも無ければ [`find`] と同じ順で探します。
+pub fn find_preferred(
+    records: &[NameRecord<'_>],
+    name_id: u16,
+    languages: &[String],
+) -> Option<String> {
+    languages
+        .iter()
+        .find_map(|language| {
+            records
+                .iter()
+                .filter(|r| r.name_id == name_id && r.platform_id == 3)
+                .find(|r| {
+                    r.language_label()
+                        .is_some_and(|label| language_matches(label, language))
+                })
+                .and_then(NameRecord::decode)
+        })
+        .or_else(|| find(records, name_id))
+}
+
+/// `label` ("ja-JP" など) が言語タグ `language` ("ja" や "ja-JP") に一致するかを返します。
+fn language_matches(label: &str, language: &str) -> bool {
+    label.eq_ignore_ascii_case(language)
+        || label
+            .split_once('-')
+            .is_some_and(|(primary, _)| primary.eq_ignore_ascii_case(language))
+}
+
+/// `--name-languages` の値 (`ja`、`en-US` のような言語タグ) を検査します。
+pub fn parse_language(value: &str) -> Result<String, String> {
+    let valid = match value.split_once('-') {
+        Some((primary, region)) => {
+            primary.len() == 2
+                && primary.chars().all(|c| c.is_ascii_alphabetic())
+                && region.len() == 2
+                && region.chars().all(|c| c.is_ascii_alphabetic())
+        }
+        None => value.len() == 2 && value.chars().all(|c| c.is_ascii_alphabetic()),
+    };
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(format!(
+            "'{value}' is not a language tag; use a language such as \"ja\" or a language and region such as \"en-US\""
+        ))
+    }
+}
+
+/// ファミリー名 (name ID 1 / 16) かフルネーム (name ID 4) のいずれかの言語の文字列が `family` と一致するかを返します。
+/// GDI と同じく大文字と小文字は区別しません。
+pub fn has_family(records: &[NameRecord<'_>], family: &str) -> bool {
+    records
+        .iter()
+        .filter(|r| matches!(r.name_id, 1 | 4 | 16))
+        .filter_map(NameRecord::decode)
+        .any(|name| name.to_lowercase() == family.to_lowercase())
+}
+
+/// name ID の表示名を返します。
+pub fn label(name_id: u16) -> &'static str {
+    match name_id {
+        0 => "Copyright",
+        1 => "Family",
+        2 => "Subfamily",
+        3 => "Unique ID",
+        4 => "Full name",
+        5 => "Version",
+        6 => "PostScript name",
+        7 => "Trademark",
+        8 => "Manufacturer",
+        9 => "Designer",
+        10 => "Description",
+        11 => "Vendor URL",
+        12 => "Designer URL",
+        13 => "License",
+        14 => "License URL",
+        16 => "Typographic family",
+        17 => "Typographic subfamily",
+        18 => "Compatible full name",
+        19 => "Sample text",
+        20 => "PostScript CID name",
+        21 => "WWS family",
+        22 => "WWS subfamily",
+        25 => "Variations PostScript prefix",
+        _ => "Other",
+    }
+}
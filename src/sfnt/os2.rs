@@ -0,0 +1,270 @@
+//! OS/2 テーブルのパーサと fsType / ulUnicodeRange / ulCodePageRange のビット定義
+
+use super::{read_u16, read_u32};
+use crate::FontExtractorError;
+
+/// OS/2 テーブルから読み取った値
+#[derive(Debug, Clone)]
+pub struct Os2 {
+    /// fsType (埋め込みの許可)
+    pub fs_type: u16,
+    /// ulUnicodeRange1..4 (ビット 0..127)
+    pub unicode_range: [u32; 4],
+    /// ulCodePageRange1..2 (OS/2 バージョン 1 以降のみ)
+    pub code_page_range: Option<[u32; 2]>,
+}
+
+impl Os2 {
+    pub fn parse(os2: &[u8]) -> Result<Self, FontExtractorError> {
+        let unicode_range = [
+            read_u32(os2, 42)?,
+            read_u32(os2, 46)?,
+            read_u32(os2, 50)?,
+            read_u32(os2, 54)?,
+        ];
+        // バージョン 0 のテーブルには ulCodePageRange が存在しない
+        let code_page_range = match (read_u32(os2, 78), read_u32(os2, 82)) {
+            (Ok(first), Ok(second)) => Some([first, second]),
+            _ => None,
+        };
+        Ok(Self {
+            fs_type: read_u16(os2, 8)?,
+            unicode_range,
+            code_page_range,
+        })
+    }
+
+    pub fn embedding(&self) -> Embedding {
+        Embedding::from_fs_type(self.fs_type)
+    }
+
+    pub fn has_unicode_bit(&self, bit: u8) -> bool {
+        self.unicode_range[bit as usize / 32] & (1 << (bit % 32)) != 0
+    }
+
+    pub fn has_code_page_bit(&self, bit: u8) -> bool {
+        self.code_page_range
+            .is_some_and(|range| range[bit as usize / 32] & (1 << (bit % 32)) != 0)
+    }
+}
+
+/// fsType のビット 0-3 が表す埋め込みの許可
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Embedding {
+    /// 0: Installable (制限なし)
+    Installable,
+    /// ビット 1: Restricted License (埋め込みも、ライセンスを持たない相手への複製もできない)
+    Restricted,
+    /// ビット 2: Preview & Print (閲覧と印刷の間だけ一時的に埋め込める)
+    PreviewAndPrint,
+    /// ビット 3: Editable (編集する文書にも埋め込める)
+    Editable,
+}
+
+impl Embedding {
+    /// 複数のビットが立っている場合は、最も制限の弱いものが適用されます。
+    pub fn from_fs_type(fs_type: u16) -> Self {
+        if fs_type & 0x0008 != 0 {
+            Self::Editable
+        } else if fs_type & 0x0004 != 0 {
+            Self::PreviewAndPrint
+        } else if fs_type & 0x0002 != 0 {
+            Self::Restricted
+        } else {
+            Self::Installable
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Installable => "Installable",
+            Self::Restricted => "Restricted License",
+            Self::PreviewAndPrint => "Preview & Print",
+            Self::Editable => "Editable",
+        }
+    }
+}
+
+/// ulUnicodeRange の 1 ビット
+pub struct UnicodeRangeBit {
+    pub bit: u8,
+    pub name: &'static str,
+    pub ranges: &'static [(u32, u32)],
+}
+
+/// OpenType 仕様 (OS/2 バージョン 4 以降) の ulUnicodeRange ビット定義
+#[rustfmt::skip]
+pub const UNICODE_RANGES: &[UnicodeRangeBit] = &[
+    UnicodeRangeBit { bit: 0, name: "Basic Latin", ranges: &[(0x0000, 0x007F)] },
+    UnicodeRangeBit { bit: 1, name: "Latin-1 Supplement", ranges: &[(0x0080, 0x00FF)] },
+    UnicodeRangeBit { bit: 2, name: "Latin Extended-A", ranges: &[(0x0100, 0x017F)] },
+    UnicodeRangeBit { bit: 3, name: "Latin Extended-B", ranges: &[(0x0180, 0x024F)] },
+    UnicodeRangeBit { bit: 4, name: "IPA Extensions", ranges: &[(0x0250, 0x02AF), (0x1D00, 0x1DBF)] },
+    UnicodeRangeBit { bit: 5, name: "Spacing Modifier Letters", ranges: &[(0x02B0, 0x02FF), (0xA700, 0xA71F)] },
+    UnicodeRangeBit { bit: 6, name: "Combining Diacritical Marks", ranges: &[(0x0300, 0x036F), (0x1DC0, 0x1DFF)] },
+    UnicodeRangeBit { bit: 7, name: "Greek and Coptic", ranges: &[(0x0370, 0x03FF)] },
+    UnicodeRangeBit { bit: 8, name: "Coptic", ranges: &[(0x2C80, 0x2CFF)] },
+    UnicodeRangeBit { bit: 9, name: "Cyrillic", ranges: &[(0x0400, 0x052F), (0x2DE0, 0x2DFF), (0xA640, 0xA69F)] },
+    UnicodeRangeBit { bit: 10, name: "Armenian", ranges: &[(0x0530, 0x058F)] },
+    UnicodeRangeBit { bit: 11, name: "Hebrew", ranges: &[(0x0590, 0x05FF)] },
+    UnicodeRangeBit { bit: 12, name: "Vai", ranges: &[(0xA500, 0xA63F)] },
+    UnicodeRangeBit { bit: 13, name: "Arabic", ranges: &[(0x0600, 0x06FF), (0x0750, 0x077F)] },
+    UnicodeRangeBit { bit: 14, name: "NKo", ranges: &[(0x07C0, 0x07FF)] },
+    UnicodeRangeBit { bit: 15, name: "Devanagari", ranges: &[(0x0900, 0x097F)] },
+    UnicodeRangeBit { bit: 16, name: "Bengali", ranges: &[(0x0980, 0x09FF)] },
+    UnicodeRangeBit { bit: 17, name: "Gurmukhi", ranges: &[(0x0A00, 0x0A7F)] },
+    UnicodeRangeBit { bit: 18, name: "Gujarati", ranges: &[(0x0A80, 0x0AFF)] },
+    UnicodeRangeBit { bit: 19, name: "Oriya", ranges: &[(0x0B00, 0x0B7F)] },
+    UnicodeRangeBit { bit: 20, name: "Tamil", ranges: &[(0x0B80, 0x0BFF)] },
+    UnicodeRangeBit { bit: 21, name: "Telugu", ranges: &[(0x0C00, 0x0C7F)] },
+    UnicodeRangeBit { bit: 22, name: "Kannada", ranges: &[(0x0C80, 0x0CFF)] },
+    UnicodeRangeBit { bit: 23, name: "Malayalam", ranges: &[(0x0D00, 0x0D7F)] },
+    UnicodeRangeBit { bit: 24, name: "Thai", ranges: &[(0x0E00, 0x0E7F)] },
+    UnicodeRangeBit { bit: 25, name: "Lao", ranges: &[(0x0E80, 0x0EFF)] },
+    UnicodeRangeBit { bit: 26, name: "Georgian", ranges: &[(0x10A0, 0x10FF), (0x2D00, 0x2D2F)] },
+    UnicodeRangeBit { bit: 27, name: "Balinese", ranges: &[(0x1B00, 0x1B7F)] },
+    UnicodeRangeBit { bit: 28, name: "Hangul Jamo", ranges: &[(0x1100, 0x11FF)] },
+    UnicodeRangeBit { bit: 29, name: "Latin Extended Additional", ranges: &[(0x1E00, 0x1EFF), (0x2C60, 0x2C7F), (0xA720, 0xA7FF)] },
+    UnicodeRangeBit { bit: 30, name: "Greek Extended", ranges: &[(0x1F00, 0x1FFF)] },
+    UnicodeRangeBit { bit: 31, name: "General Punctuation", ranges: &[(0x2000, 0x206F), (0x2E00, 0x2E7F)] },
+    UnicodeRangeBit { bit: 32, name: "Superscripts And Subscripts", ranges: &[(0x2070, 0x209F)] },
+    UnicodeRangeBit { bit: 33, name: "Currency Symbols", ranges: &[(0x20A0, 0x20CF)] },
+    UnicodeRangeBit { bit: 34, name: "Combining Diacritical Marks For Symbols", ranges: &[(0x20D0, 0x20FF)] },
+    UnicodeRangeBit { bit: 35, name: "Letterlike Symbols", ranges: &[(0x2100, 0x214F)] },
+    UnicodeRangeBit { bit: 36, name: "Number Forms", ranges: &[(0x2150, 0x218F)] },
+    UnicodeRangeBit { bit: 37, name: "Arrows", ranges: &[(0x2190, 0x21FF), (0x27F0, 0x27FF), (0x2900, 0x297F), (0x2B00, 0x2BFF)] },
+    UnicodeRangeBit { bit: 38, name: "Mathematical Operators", ranges: &[(0x2200, 0x22FF), (0x2A00, 0x2AFF), (0x27C0, 0x27EF), (0x2980, 0x29FF)] },
+    UnicodeRangeBit { bit: 39, name: "Miscellaneous Technical", ranges: &[(0x2300, 0x23FF)] },
+    UnicodeRangeBit { bit: 40, name: "Control Pictures", ranges: &[(0x2400, 0x243F)] },
+    UnicodeRangeBit { bit: 41, name: "Optical Character Recognition", ranges: &[(0x2440, 0x245F)] },
+    UnicodeRangeBit { bit: 42, name: "Enclosed Alphanumerics", ranges: &[(0x2460, 0x24FF)] },
+    UnicodeRangeBit { bit: 43, name: "Box Drawing", ranges: &[(0x2500, 0x257F)] },
+    UnicodeRangeBit { bit: 44, name: "Block Elements", ranges: &[(0x2580, 0x259F)] },
+    UnicodeRangeBit { bit: 45, name: "Geometric Shapes", ranges: &[(0x25A0, 0x25FF)] },
+    UnicodeRangeBit { bit: 46, name: "Miscellaneous Symbols", ranges: &[(0x2600, 0x26FF)] },
+    UnicodeRangeBit { bit: 47, name: "Dingbats", ranges: &[(0x2700, 0x27BF)] },
+    UnicodeRangeBit { bit: 48, name: "CJK Symbols And Punctuation", ranges: &[(0x3000, 0x303F)] },
+    UnicodeRangeBit { bit: 49, name: "Hiragana", ranges: &[(0x3040, 0x309F)] },
+    UnicodeRangeBit { bit: 50, name: "Katakana", ranges: &[(0x30A0, 0x30FF), (0x31F0, 0x31FF)] },
+    UnicodeRangeBit { bit: 51, name: "Bopomofo", ranges: &[(0x3100, 0x312F), (0x31A0, 0x31BF)] },
+    UnicodeRangeBit { bit: 52, name: "Hangul Compatibility Jamo", ranges: &[(0x3130, 0x318F)] },
+    UnicodeRangeBit { bit: 53, name: "Phags-pa", ranges: &[(0xA840, 0xA87F)] },
+    UnicodeRangeBit { bit: 54, name: "Enclosed CJK Letters And Months", ranges: &[(0x3200, 0x32FF)] },
+    UnicodeRangeBit { bit: 55, name: "CJK Compatibility", ranges: &[(0x3300, 0x33FF)] },
+    UnicodeRangeBit { bit: 56, name: "Hangul Syllables", ranges: &[(0xAC00, 0xD7AF)] },
+    UnicodeRangeBit { bit: 57, name: "Non-Plane 0", ranges: &[(0x10000, 0x10FFFF)] },
+    UnicodeRangeBit { bit: 58, name: "Phoenician", ranges: &[(0x10900, 0x1091F)] },
+    UnicodeRangeBit { bit: 59, name: "CJK Unified Ideographs", ranges: &[(0x4E00, 0x9FFF), (0x2E80, 0x2FFF), (0x3400, 0x4DBF), (0x20000, 0x2A6DF), (0x3190, 0x319F)] },
+    UnicodeRangeBit { bit: 60, name: "Private Use Area (plane 0)", ranges: &[(0xE000, 0xF8FF)] },
+    UnicodeRangeBit { bit: 61, name: "CJK Strokes", ranges: &[(0x31C0, 0x31EF), (0xF900, 0xFAFF), (0x2F800, 0x2FA1F)] },
+    UnicodeRangeBit { bit: 62, name: "Alphabetic Presentation Forms", ranges: &[(0xFB00, 0xFB4F)] },
+    UnicodeRangeBit { bit: 63, name: "Arabic Presentation Forms-A", ranges: &[(0xFB50, 0xFDFF)] },
+    UnicodeRangeBit { bit: 64, name: "Combining Half Marks", ranges: &[(0xFE20, 0xFE2F)] },
+    UnicodeRangeBit { bit: 65, name: "Vertical Forms", ranges: &[(0xFE10, 0xFE1F), (0xFE30, 0xFE4F)] },
+    UnicodeRangeBit { bit: 66, name: "Small Form Variants", ranges: &[(0xFE50, 0xFE6F)] },
+    UnicodeRangeBit { bit: 67, name: "Arabic Presentation Forms-B", ranges: &[(0xFE70, 0xFEFF)] },
+    UnicodeRangeBit { bit: 68, name: "Halfwidth And Fullwidth Forms", ranges: &[(0xFF00, 0xFFEF)] },
+    UnicodeRangeBit { bit: 69, name: "Specials", ranges: &[(0xFFF0, 0xFFFF)] },
+    UnicodeRangeBit { bit: 70, name: "Tibetan", ranges: &[(0x0F00, 0x0FFF)] },
+    UnicodeRangeBit { bit: 71, name: "Syriac", ranges: &[(0x0700, 0x074F)] },
+    UnicodeRangeBit { bit: 72, name: "Thaana", ranges: &[(0x0780, 0x07BF)] },
+    UnicodeRangeBit { bit: 73, name: "Sinhala", ranges: &[(0x0D80, 0x0DFF)] },
+    UnicodeRangeBit { bit: 74, name: "Myanmar", ranges: &[(0x1000, 0x109F)] },
+    UnicodeRangeBit { bit: 75, name: "Ethiopic", ranges: &[(0x1200, 0x139F), (0x2D80, 0x2DDF)] },
+    UnicodeRangeBit { bit: 76, name: "Cherokee", ranges: &[(0x13A0, 0x13FF)] },
+    UnicodeRangeBit { bit: 77, name: "Unified Canadian Aboriginal Syllabics", ranges: &[(0x1400, 0x167F)] },
+    UnicodeRangeBit { bit: 78, name: "Ogham", ranges: &[(0x1680, 0x169F)] },
+    UnicodeRangeBit { bit: 79, name: "Runic", ranges: &[(0x16A0, 0x16FF)] },
+    UnicodeRangeBit { bit: 80, name: "Khmer", ranges: &[(0x1780, 0x17FF), (0x19E0, 0x19FF)] },
+    UnicodeRangeBit { bit: 81, name: "Mongolian", ranges: &[(0x1800, 0x18AF)] },
+    UnicodeRangeBit { bit: 82, name: "Braille Patterns", ranges: &[(0x2800, 0x28FF)] },
+    UnicodeRangeBit { bit: 83, name: "Yi Syllables", ranges: &[(0xA000, 0xA4CF)] },
+    UnicodeRangeBit { bit: 84, name: "Tagalog", ranges: &[(0x1700, 0x177F)] },
+    UnicodeRangeBit { bit: 85, name: "Old Italic", ranges: &[(0x10300, 0x1032F)] },
+    UnicodeRangeBit { bit: 86, name: "Gothic", ranges: &[(0x10330, 0x1034F)] },
+    UnicodeRangeBit { bit: 87, name: "Deseret", ranges: &[(0x10400, 0x1044F)] },
+    UnicodeRangeBit { bit: 88, name: "Byzantine Musical Symbols", ranges: &[(0x1D000, 0x1D24F)] },
+    UnicodeRangeBit { bit: 89, name: "Mathematical Alphanumeric Symbols", ranges: &[(0x1D400, 0x1D7FF)] },
+    UnicodeRangeBit { bit: 90, name: "Private Use (plane 15)", ranges: &[(0xF0000, 0xFFFFD), (0x100000, 0x10FFFD)] },
+    UnicodeRangeBit { bit: 91, name: "Variation Selectors", ranges: &[(0xFE00, 0xFE0F), (0xE0100, 0xE01EF)] },
+    UnicodeRangeBit { bit: 92, name: "Tags", ranges: &[(0xE0000, 0xE007F)] },
+    UnicodeRangeBit { bit: 93, name: "Limbu", ranges: &[(0x1900, 0x194F)] },
+    UnicodeRangeBit { bit: 94, name: "Tai Le", ranges: &[(0x1950, 0x197F)] },
+    UnicodeRangeBit { bit: 95, name: "New Tai Lue", ranges: &[(0x1980, 0x19DF)] },
+    UnicodeRangeBit { bit: 96, name: "Buginese", ranges: &[(0x1A00, 0x1A1F)] },
+    UnicodeRangeBit { bit: 97, name: "Glagolitic", ranges: &[(0x2C00, 0x2C5F)] },
+    UnicodeRangeBit { bit: 98, name: "Tifinagh", ranges: &[(0x2D30, 0x2D7F)] },
+    UnicodeRangeBit { bit: 99, name: "Yijing Hexagram Symbols", ranges: &[(0x4DC0, 0x4DFF)] },
+    UnicodeRangeBit { bit: 100, name: "Syloti Nagri", ranges: &[(0xA800, 0xA82F)] },
+    UnicodeRangeBit { bit: 101, name: "Linear B Syllabary", ranges: &[(0x10000, 0x1013F)] },
+    UnicodeRangeBit { bit: 102, name: "Ancient Greek Numbers", ranges: &[(0x10140, 0x1018F)] },
+    UnicodeRangeBit { bit: 103, name: "Ugaritic", ranges: &[(0x10380, 0x1039F)] },
+    UnicodeRangeBit { bit: 104, name: "Old Persian", ranges: &[(0x103A0, 0x103DF)] },
+    UnicodeRangeBit { bit: 105, name: "Shavian", ranges: &[(0x10450, 0x1047F)] },
+    UnicodeRangeBit { bit: 106, name: "Osmanya", ranges: &[(0x10480, 0x104AF)] },
+    UnicodeRangeBit { bit: 107, name: "Cypriot Syllabary", ranges: &[(0x10800, 0x1083F)] },
+    UnicodeRangeBit { bit: 108, name: "Kharoshthi", ranges: &[(0x10A00, 0x10A5F)] },
+    UnicodeRangeBit { bit: 109, name: "Tai Xuan Jing Symbols", ranges: &[(0x1D300, 0x1D35F)] },
+    UnicodeRangeBit { bit: 110, name: "Cuneiform", ranges: &[(0x12000, 0x1247F)] },
+    UnicodeRangeBit { bit: 111, name: "Counting Rod Numerals", ranges: &[(0x1D360, 0x1D37F)] },
+    UnicodeRangeBit { bit: 112, name: "Sundanese", ranges: &[(0x1B80, 0x1BBF)] },
+    UnicodeRangeBit { bit: 113, name: "Lepcha", ranges: &[(0x1C00, 0x1C4F)] },
+    UnicodeRangeBit { bit: 114, name: "Ol Chiki", ranges: &[(0x1C50, 0x1C7F)] },
+    UnicodeRangeBit { bit: 115, name: "Saurashtra", ranges: &[(0xA880, 0xA8DF)] },
+    UnicodeRangeBit { bit: 116, name: "Kayah Li", ranges: &[(0xA900, 0xA92F)] },
+    UnicodeRangeBit { bit: 117, name: "Rejang", ranges: &[(0xA930, 0xA95F)] },
+    UnicodeRangeBit { bit: 118, name: "Cham", ranges: &[(0xAA00, 0xAA5F)] },
+    UnicodeRangeBit { bit: 119, name: "Ancient Symbols", ranges: &[(0x10190, 0x101CF)] },
+    UnicodeRangeBit { bit: 120, name: "Phaistos Disc", ranges: &[(0x101D0, 0x101FF)] },
+    UnicodeRangeBit { bit: 121, name: "Carian, Lycian, Lydian", ranges: &[(0x10280, 0x102DF), (0x10920, 0x1093F)] },
+    UnicodeRangeBit { bit: 122, name: "Domino and Mahjong Tiles", ranges: &[(0x1F000, 0x1F09F)] },
+];
+
+/// ulCodePageRange の 1 ビット
+pub struct CodePageBit {
+    pub bit: u8,
+    pub name: &'static str,
+    /// そのコードページに対応するフォントなら必ず持っているはずの代表文字
+    pub probes: &'static [u32],
+}
+
+/// ANSI コードページの最後のビット (これより上は OEM / 特殊なコードページ)
+pub const LAST_ANSI_CODE_PAGE_BIT: u8 = 21;
+
+#[rustfmt::skip]
+pub const CODE_PAGES: &[CodePageBit] = &[
+    CodePageBit { bit: 0, name: "1252 Latin 1", probes: &[0x00E9, 0x00FC] },
+    CodePageBit { bit: 1, name: "1250 Latin 2: Eastern Europe", probes: &[0x0150, 0x0159] },
+    CodePageBit { bit: 2, name: "1251 Cyrillic", probes: &[0x0416, 0x044F] },
+    CodePageBit { bit: 3, name: "1253 Greek", probes: &[0x0391, 0x03C9] },
+    CodePageBit { bit: 4, name: "1254 Turkish", probes: &[0x011F, 0x0130] },
+    CodePageBit { bit: 5, name: "1255 Hebrew", probes: &[0x05D0, 0x05EA] },
+    CodePageBit { bit: 6, name: "1256 Arabic", probes: &[0x0627, 0x064A] },
+    CodePageBit { bit: 7, name: "1257 Windows Baltic", probes: &[0x0117, 0x0173] },
+    CodePageBit { bit: 8, name: "1258 Vietnamese", probes: &[0x01A1, 0x20AB] },
+    CodePageBit { bit: 16, name: "874 Thai", probes: &[0x0E01, 0x0E3F] },
+    CodePageBit { bit: 17, name: "932 JIS/Japan", probes: &[0x3042, 0x30A2, 0x65E5] },
+    CodePageBit { bit: 18, name: "936 Chinese: Simplified", probes: &[0x4E2D, 0x8FD9] },
+    CodePageBit { bit: 19, name: "949 Korean Wansung", probes: &[0xAC00, 0xD55C] },
+    CodePageBit { bit: 20, name: "950 Chinese: Traditional", probes: &[0x4E2D, 0x9019] },
+    CodePageBit { bit: 21, name: "1361 Korean Johab", probes: &[0xAC00, 0xD7A3] },
+    CodePageBit { bit: 29, name: "Macintosh Character Set (US Roman)", probes: &[] },
+    CodePageBit { bit: 30, name: "OEM Character Set", probes: &[] },
+    CodePageBit { bit: 31, name: "Symbol Character Set", probes: &[] },
+    CodePageBit { bit: 48, name: "869 IBM Greek", probes: &[0x0391] },
+    CodePageBit { bit: 49, name: "866 MS-DOS Russian", probes: &[0x0416] },
+    CodePageBit { bit: 50, name: "865 MS-DOS Nordic", probes: &[0x00F8] },
+    CodePageBit { bit: 51, name: "864 Arabic", probes: &[0x0627] },
+    CodePageBit { bit: 52, name: "863 MS-DOS Canadian French", probes: &[0x00E9] },
+    CodePageBit { bit: 53, name: "862 Hebrew", probes: &[0x05D0] },
+    CodePageBit { bit: 54, name: "861 MS-DOS Icelandic", probes: &[0x00F0] },
+    CodePageBit { bit: 55, name: "860 MS-DOS Portuguese", probes: &[0x00E3] },
+    CodePageBit { bit: 56, name: "857 IBM Turkish", probes: &[0x011F] },
+    CodePageBit { bit: 57, name: "855 IBM Cyrillic", probes: &[0x0416] },
+    CodePageBit { bit: 58, name: "852 Latin 2", probes: &[0x0150] },
+    CodePageBit { bit: 59, name: "775 MS-DOS Baltic", probes: &[0x0173] },
+    CodePageBit { bit: 60, name: "737 Greek; former 437 G", probes: &[0x0391] },
+    CodePageBit { bit: 61, name: "708 Arabic; ASMO 708", probes: &[0x0627] },
+    CodePageBit { bit: 62, name: "850 WE/Latin 1", probes: &[0x00E9, 0x2500] },
+    CodePageBit { bit: 63, name: "437 US", probes: &[0x00E9, 0x2500] },
+];
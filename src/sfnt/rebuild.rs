@@ -0,0 +1,116 @@
+//! 書き換えたフォントの組み立て直し
+//!
+//! コレクションの分割、テーブルの削除、縦書きメトリクスの合成、cmap やスタイルの書き換え、グリフの統合など、
+//! フォントを書き換える機能はすべてここで組み立て直します。テーブルディレクトリとオフセットを作り直し、
+//! 各テーブルのチェックサムと head の checkSumAdjustment を計算し直すため、書き換えたフォントも検証を通ります。
+
+use super::{Sfnt, Tag, checksum, corrupt};
+use crate::FontExtractorError;
+
+/// ファイル全体のチェックサムと checkSumAdjustment の和 (OpenType の仕様で決まった値)
+pub const CHECKSUM_MAGIC: u32 = 0xB1B0_AFBA;
+
+/// 既存のフェイスのテーブルを差し替え・追加・削除して組み立て直すための一覧
+///
+/// 変更しないテーブルは元のフェイスのバイト列をそのまま使います。
+#[derive(Debug, Clone)]
+pub struct Rebuild<'a> {
+    sfnt_version: u32,
+    tables: Vec<(Tag, &'a [u8])>,
+}
+
+impl<'a> Rebuild<'a> {
+    /// `font` のすべてのテーブルから始めます (sfnt バージョンも引き継ぎます)。
+    pub fn from_font(font: &Sfnt<'a>) -> Self {
+        Self {
+            sfnt_version: font.sfnt_version,
+            tables: font
+                .tables
+                .iter()
+                .filter_map(|record| Some((record.tag, font.table(&record.tag)?)))
+                .collect(),
+        }
+    }
+
+    /// `tag` のテーブルを `data` に差し替えます。無ければ追加します。
+    pub fn replace(&mut self, tag: Tag, data: &'a [u8]) -> &mut Self {
+        match self.tables.iter_mut().find(|(t, _)| *t == tag) {
+            Some(table) => table.1 = data,
+            None => self.tables.push((tag, data)),
+        }
+        self
+    }
+
+    /// `keep` が `false` を返すテーブルを取り除きます。
+    pub fn retain(&mut self, mut keep: impl FnMut(&Tag) -> bool) -> &mut Self {
+        self.tables.retain(|(tag, _)| keep(tag));
+        self
+    }
+
+    /// 単体の sfnt フォントに組み立てます ([`build`])。
+    pub fn finish(&self) -> Result<Vec<u8>, FontExtractorError> {
+        build(self.sfnt_version, &self.tables)
+    }
+}
+
+/// テーブルの一覧から単体の sfnt フォントを組み立てます。
+///
+/// テーブルはタグ順に 4 バイト境界で並べ、各テーブルのチェックサムと head の checkSumAdjustment を計算し直します。
+/// テーブル数がテーブルディレクトリに書ける数 (65535) を超える場合はエラーです。
+pub fn build(sfnt_version: u32, tables: &[(Tag, &[u8])]) -> Result<Vec<u8>, FontExtractorError> {
+    let num_tables = u16::try_from(tables.len()).map_err(|_| {
+        corrupt(format!(
+            "{} tables do not fit in an sfnt table directory (at most {})",
+            tables.len(),
+            u16::MAX
+        ))
+    })?;
+    let mut tables = tables.to_vec();
+    tables.sort_by_key(|(tag, _)| *tag);
+
+    // searchRange と rangeShift は 4096 テーブル以上で u16 に収まらないため u32 で計算し、上限で頭打ちにする
+    // (どちらも二分探索のための補助の値で、テーブルの読み取りには使われない)
+    let entry_selector = if num_tables == 0 {
+        0
+    } else {
+        15 - num_tables.leading_zeros()
+    };
+    let search_range = (1u32 << entry_selector) * 16;
+    let range_shift = num_tables as u32 * 16 - search_range;
+    let mut font = Vec::new();
+    font.extend_from_slice(&sfnt_version.to_be_bytes());
+    font.extend_from_slice(&num_tables.to_be_bytes());
+    font.extend_from_slice(&(search_range.min(u16::MAX as u32) as u16).to_be_bytes());
+    font.extend_from_slice(&(entry_selector as u16).to_be_bytes());
+    font.extend_from_slice(&(range_shift.min(u16::MAX as u32) as u16).to_be_bytes());
+
+    let mut offset = 12 + 16 * tables.len();
+    let mut head_offset = None;
+    for (tag, data) in &tables {
+        let mut checksum_data = data.to_vec();
+        if tag == b"head" && checksum_data.len() >= 12 {
+            // head のチェックサムは checkSumAdjustment を 0 として計算する
+            checksum_data[8..12].fill(0);
+            head_offset = Some(offset);
+        }
+        font.extend_from_slice(tag);
+        font.extend_from_slice(&checksum(&checksum_data).to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        offset += data.len().next_multiple_of(4);
+    }
+    for (tag, data) in &tables {
+        let start = font.len();
+        font.extend_from_slice(data);
+        if tag == b"head" && data.len() >= 12 {
+            font[start + 8..start + 12].fill(0);
+        }
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+
+    if let Some(head) = head_offset {
+        let adjustment = CHECKSUM_MAGIC.wrapping_sub(checksum(&font));
+        font[head + 8..head + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    Ok(font)
+}
@@ -0,0 +1,245 @@
+//! スタイルリンク (Regular / Bold / Italic / Bold Italic の 4 フェイスを 1 ファミリーとして扱う仕組み) の検査と修正
+//!
+//! Windows や Office は name ID 1 (ファミリー名) が同じフォントを 1 つのファミリーにまとめ、
+//! OS/2 の fsSelection (と head の macStyle) の太字・斜体のビットで 4 つのスタイルを区別します。
+//! name ID 2 (サブファミリー名) とこれらのビットが食い違うと、太字ボタンで別のフェイスに切り替わらなかったり
+//! 擬似的な太字・斜体が合成されたりします。
+
+use super::rebuild::Rebuild;
+use super::{Sfnt, Tag, corrupt, name, read_u16};
+use crate::FontExtractorError;
+
+// OS/2 fsSelection のビット
+const FS_ITALIC: u16 = 1 << 0;
+const FS_BOLD: u16 = 1 << 5;
+const FS_REGULAR: u16 = 1 << 6;
+
+// head macStyle のビット
+const MAC_BOLD: u16 = 1 << 0;
+const MAC_ITALIC: u16 = 1 << 1;
+
+/// OS/2 の fsSelection と head の macStyle のオフセット
+const FS_SELECTION_OFFSET: usize = 62;
+const MAC_STYLE_OFFSET: usize = 44;
+
+/// サブファミリー名で Regular の意味で使われる語
+const REGULAR_NAMES: &[&str] = &["regular", "book", "normal", "roman", "plain", "standard"];
+
+/// Windows が太字として扱う最小の usWeightClass
+const MIN_BOLD_WEIGHT: u16 = 600;
+
+/// スタイルリンクで区別される 4 つのスタイル
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+}
+
+impl Style {
+    /// name ID 2 の名前から読み取ります。"Book" や "Oblique" など、よく使われる別名も受け付けます。
+    /// 4 つのスタイルのどれとも読めない名前 ("Light" など) は `None` です。
+    fn from_subfamily(subfamily: &str) -> Option<Self> {
+        if subfamily.trim().is_empty() {
+            return None;
+        }
+        let mut style = Self {
+            bold: false,
+            italic: false,
+        };
+        for word in subfamily.to_ascii_lowercase().split_whitespace() {
+            match word {
+                "bold" => style.bold = true,
+                "italic" | "oblique" => style.italic = true,
+                _ if REGULAR_NAMES.contains(&word) => {}
+                _ => return None,
+            }
+        }
+        Some(style)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match (self.bold, self.italic) {
+            (false, false) => "Regular",
+            (true, false) => "Bold",
+            (false, true) => "Italic",
+            (true, true) => "Bold Italic",
+        }
+    }
+}
+
+/// スタイルリンクに関わるフォントの値
+#[derive(Debug, Clone)]
+pub struct StyleLinking {
+    /// name ID 1
+    pub family: String,
+    /// name ID 2
+    pub subfamily: String,
+    pub fs_selection: u16,
+    pub mac_style: u16,
+    pub weight_class: u16,
+}
+
+impl StyleLinking {
+    /// フォントデータ (TTC の場合は最初のフェイス) から読み取ります。
+    pub fn inspect(data: &[u8]) -> Result<Self, FontExtractorError> {
+        let font = Sfnt::parse_face(data, 0)?;
+        let (os2, head) = require_tables(&font)?;
+        let records = match font.table(b"name") {
+            Some(table) => name::parse(table)?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            family: name::find(&records, 1).unwrap_or_default(),
+            subfamily: name::find(&records, 2).unwrap_or_default(),
+            fs_selection: read_u16(os2, FS_SELECTION_OFFSET)?,
+            mac_style: read_u16(head, MAC_STYLE_OFFSET)?,
+            weight_class: read_u16(os2, 4)?,
+        })
+    }
+
+    /// fsSelection か macStyle の斜体のビットが立っているかどうか
+    pub fn is_italic(&self) -> bool {
+        self.fs_selection & FS_ITALIC != 0 || self.mac_style & MAC_ITALIC != 0
+    }
+
+    /// name ID 2 が示すスタイル (4 つのスタイル以外の名前なら `None`)
+    pub fn style(&self) -> Option<Style> {
+        Style::from_subfamily(&self.subfamily)
+    }
+
+    /// name ID 2 と fsSelection / macStyle / usWeightClass の食い違いを説明する文字列を返します。
+    pub fn problems(&self) -> Vec<String> {
+        let Some(style) = self.style() else {
+            return vec![format!(
+                "subfamily (name ID 2) '{}' is not Regular, Bold, Italic or Bold Italic; style-linked faces of '{}' should use one of these",
+                self.subfamily, self.family
+            )];
+        };
+        let mut problems = Vec::new();
+        let fs_style = Style {
+            bold: self.fs_selection & FS_BOLD != 0,
+            italic: self.fs_selection & FS_ITALIC != 0,
+        };
+        if fs_style != style {
+            problems.push(format!(
+                "OS/2 fsSelection says {} but the subfamily is {}",
+                fs_style.label(),
+                style.label()
+            ));
+        }
+        if (self.fs_selection & FS_REGULAR != 0) != (!style.bold && !style.italic) {
+            problems.push(format!(
+                "OS/2 fsSelection REGULAR bit is {} for a {} face",
+                if self.fs_selection & FS_REGULAR != 0 {
+                    "set"
+                } else {
+                    "clear"
+                },
+                style.label()
+            ));
+        }
+        let mac_style = Style {
+            bold: self.mac_style & MAC_BOLD != 0,
+            italic: self.mac_style & MAC_ITALIC != 0,
+        };
+        if mac_style != style {
+            problems.push(format!(
+                "head macStyle says {} but the subfamily is {}",
+                mac_style.label(),
+                style.label()
+            ));
+        }
+        if style.bold && self.weight_class < MIN_BOLD_WEIGHT {
+            problems.push(format!(
+                "OS/2 usWeightClass is {} for a Bold face; applications may embolden it again",
+                self.weight_class
+            ));
+        }
+        problems
+    }
+}
+
+/// fsSelection と macStyle の太字・斜体のビットを name ID 2 に合わせたフォントデータを返します。
+///
+/// name ID 2 が 4 つのスタイルのいずれでもない場合や、既に一致している場合は `None` を返します。
+/// name テーブルと usWeightClass は変更しません。
+pub fn fix(data: &[u8]) -> Result<Option<Vec<u8>>, FontExtractorError> {
+    if data.get(..4) == Some(b"ttcf") {
+        return Err(corrupt(
+            "style linking bits cannot be fixed in a TrueType Collection",
+        ));
+    }
+    let linking = StyleLinking::inspect(data)?;
+    let Some(style) = linking.style() else {
+        return Ok(None);
+    };
+
+    let mut fs_selection = linking.fs_selection & !(FS_ITALIC | FS_BOLD | FS_REGULAR);
+    let mut mac_style = linking.mac_style & !(MAC_BOLD | MAC_ITALIC);
+    if style.bold {
+        fs_selection |= FS_BOLD;
+        mac_style |= MAC_BOLD;
+    }
+    if style.italic {
+        fs_selection |= FS_ITALIC;
+        mac_style |= MAC_ITALIC;
+    }
+    if !style.bold && !style.italic {
+        fs_selection |= FS_REGULAR;
+    }
+    if fs_selection == linking.fs_selection && mac_style == linking.mac_style {
+        return Ok(None);
+    }
+
+    let font = Sfnt::parse_face(data, 0)?;
+    let (os2, head) = require_tables(&font)?;
+    let mut os2 = os2.to_vec();
+    let mut head = head.to_vec();
+    os2[FS_SELECTION_OFFSET..FS_SELECTION_OFFSET + 2].copy_from_slice(&fs_selection.to_be_bytes());
+    head[MAC_STYLE_OFFSET..MAC_STYLE_OFFSET + 2].copy_from_slice(&mac_style.to_be_bytes());
+
+    Ok(Some(
+        Rebuild::from_font(&font)
+            .replace(*b"OS/2", &os2)
+            .replace(*b"head", &head)
+            .finish()?,
+    ))
+}
+
+/// 同じファミリー名 (name ID 1) で同じスタイルを名乗るフォントの組を説明する文字列を返します。
+/// このような組は一方のフェイスが他方を隠してしまいます。
+pub fn family_conflicts(fonts: &[(&str, &StyleLinking)]) -> Vec<String> {
+    let mut conflicts = Vec::new();
+    for (i, (first, a)) in fonts.iter().enumerate() {
+        let Some(style) = a.style() else {
+            continue;
+        };
+        for (second, b) in &fonts[i + 1..] {
+            if a.family.eq_ignore_ascii_case(&b.family) && b.style() == Some(style) {
+                conflicts.push(format!(
+                    "'{first}' and '{second}' are both '{}' {}",
+                    a.family,
+                    style.label()
+                ));
+            }
+        }
+    }
+    conflicts
+}
+
+fn require_tables<'a>(font: &Sfnt<'a>) -> Result<(&'a [u8], &'a [u8]), FontExtractorError> {
+    let require = |tag: &Tag| {
+        font.table(tag).ok_or_else(|| {
+            corrupt(format!(
+                "style linking requires a '{}' table",
+                super::tag_to_string(tag)
+            ))
+        })
+    };
+    let os2 = require(b"OS/2")?;
+    let head = require(b"head")?;
+    if os2.len() < FS_SELECTION_OFFSET + 2 || head.len() < MAC_STYLE_OFFSET + 2 {
+        return Err(corrupt("OS/2 or head table is truncated"));
+    }
+    Ok((os2, head))
+}
@@ -0,0 +1,210 @@
+//! 縦書き用テーブル (vhea / vmtx / VORG / BASE) のパーサと縦書きメトリクスの合成
+
+use super::rebuild::Rebuild;
+use super::{Sfnt, Tag, corrupt, read_i16, read_u16, read_u32};
+use crate::FontExtractorError;
+
+/// vhea テーブルから読み取った値
+#[derive(Debug, Clone)]
+pub struct Vhea {
+    pub version: u32,
+    pub ascender: i16,
+    pub descender: i16,
+    pub line_gap: i16,
+    pub advance_height_max: u16,
+    pub num_long_metrics: u16,
+}
+
+impl Vhea {
+    pub fn parse(vhea: &[u8]) -> Result<Self, FontExtractorError> {
+        Ok(Self {
+            version: read_u32(vhea, 0)?,
+            ascender: read_i16(vhea, 4)?,
+            descender: read_i16(vhea, 6)?,
+            line_gap: read_i16(vhea, 8)?,
+            advance_height_max: read_u16(vhea, 10)?,
+            num_long_metrics: read_u16(vhea, 34)?,
+        })
+    }
+
+    /// `num_glyphs` 個のグリフに必要な vmtx の最小バイト数
+    pub fn vmtx_len(&self, num_glyphs: u16) -> usize {
+        let long = self.num_long_metrics.min(num_glyphs) as usize;
+        long * 4 + (num_glyphs as usize - long) * 2
+    }
+}
+
+/// VORG テーブル (CFF フォントの縦書き原点)
+#[derive(Debug, Clone)]
+pub struct Vorg {
+    pub default_origin_y: i16,
+    pub num_overrides: u16,
+}
+
+impl Vorg {
+    pub fn parse(vorg: &[u8]) -> Result<Self, FontExtractorError> {
+        Ok(Self {
+            default_origin_y: read_i16(vorg, 4)?,
+            num_overrides: read_u16(vorg, 6)?,
+        })
+    }
+}
+
+/// BASE テーブルの 1 軸 (横書きまたは縦書き)
+#[derive(Debug, Clone)]
+pub struct BaseAxis {
+    /// ベースラインのタグ (`ideo`, `romn` など)
+    pub baselines: Vec<Tag>,
+    /// ベースライン値を持つスクリプトのタグ
+    pub scripts: Vec<Tag>,
+}
+
+/// BASE テーブルから読み取った軸
+#[derive(Debug, Clone)]
+pub struct Base {
+    pub horizontal: Option<BaseAxis>,
+    pub vertical: Option<BaseAxis>,
+}
+
+impl Base {
+    pub fn parse(base: &[u8]) -> Result<Self, FontExtractorError> {
+        Ok(Self {
+            horizontal: parse_axis(base, read_u16(base, 4)? as usize)?,
+            vertical: parse_axis(base, read_u16(base, 6)? as usize)?,
+        })
+    }
+}
+
+/// `offset` にある Axis テーブルを読み取ります (オフセット 0 は軸が無いことを表します)。
+fn parse_axis(base: &[u8], offset: usize) -> Result<Option<BaseAxis>, FontExtractorError> {
+    if offset == 0 {
+        return Ok(None);
+    }
+    // リストのオフセットは Axis テーブルの先頭からの相対値 (0 はリストが無いことを表す)
+    let read_tags = |list: usize, stride: usize| -> Result<Vec<Tag>, FontExtractorError> {
+        if list == 0 {
+            return Ok(Vec::new());
+        }
+        let list = offset + list;
+        (0..read_u16(base, list)? as usize)
+            .map(|i| {
+                let start = list + 2 + i * stride;
+                base.get(start..start + 4)
+                    .map(|b| [b[0], b[1], b[2], b[3]])
+                    .ok_or_else(|| corrupt("BASE table is truncated"))
+            })
+            .collect()
+    };
+    Ok(Some(BaseAxis {
+        baselines: read_tags(read_u16(base, offset)? as usize, 4)?,
+        // BaseScriptRecord はタグ (4 バイト) + オフセット (2 バイト)
+        scripts: read_tags(read_u16(base, offset + 2)? as usize, 6)?,
+    }))
+}
+
+/// フォントに vhea / vmtx が無ければ合成して追加したフォントデータを返します。
+/// 既に両方とも存在する場合は `None` を返します。
+///
+/// 全グリフの縦の送りを 1 em とし、em ボックスを (OS/2 の typo 値、無ければ hhea の) アセンダー・ディセンダーの
+/// 中央に置きます。上側のサイドベアリングは glyf のバウンディングボックスから求めるため、
+/// TrueType アウトラインのフォントのみ対応します。
+pub fn add_vertical_metrics(data: &[u8]) -> Result<Option<Vec<u8>>, FontExtractorError> {
+    let font = Sfnt::parse_face(data, 0)?;
+    if data.get(..4) == Some(b"ttcf") {
+        return Err(corrupt(
+            "vertical metrics cannot be synthesized for a TrueType Collection",
+        ));
+    }
+    if font.table(b"vhea").is_some() && font.table(b"vmtx").is_some() {
+        return Ok(None);
+    }
+    let require = |tag: &Tag| {
+        font.table(tag).ok_or_else(|| {
+            corrupt(format!(
+                "synthesizing vertical metrics requires a '{}' table (TrueType outlines only)",
+                super::tag_to_string(tag)
+            ))
+        })
+    };
+    let head = require(b"head")?;
+    let hhea = require(b"hhea")?;
+    let loca = require(b"loca")?;
+    let glyf = require(b"glyf")?;
+    let num_glyphs = read_u16(require(b"maxp")?, 4)?;
+
+    let units_per_em = read_u16(head, 18)? as i32;
+    let long_loca = read_i16(head, 50)? != 0;
+    let (ascender, descender) = match font.table(b"OS/2") {
+        Some(os2) => (read_i16(os2, 68)? as i32, read_i16(os2, 70)? as i32),
+        None => (read_i16(hhea, 4)? as i32, read_i16(hhea, 6)? as i32),
+    };
+    // em ボックスの上端 (縦書きの原点の Y 座標)
+    let top = ascender + (units_per_em - (ascender - descender)) / 2;
+
+    let mut side_bearings = Vec::with_capacity(num_glyphs as usize);
+    let mut min_top = i32::MAX;
+    let mut min_bottom = i32::MAX;
+    let mut max_extent = i32::MIN;
+    for glyph in 0..num_glyphs as usize {
+        let (start, end) = if long_loca {
+            (
+                read_u32(loca, glyph * 4)? as usize,
+                read_u32(loca, glyph * 4 + 4)? as usize,
+            )
+        } else {
+            (
+                read_u16(loca, glyph * 2)? as usize * 2,
+                read_u16(loca, glyph * 2 + 2)? as usize * 2,
+            )
+        };
+        if start >= end {
+            // 輪郭を持たないグリフ (スペースなど)
+            side_bearings.push(0);
+            continue;
+        }
+        let y_min = read_i16(glyf, start + 4)? as i32;
+        let y_max = read_i16(glyf, start + 8)? as i32;
+        let tsb = top - y_max;
+        side_bearings.push(tsb);
+        min_top = min_top.min(tsb);
+        min_bottom = min_bottom.min(units_per_em - tsb - (y_max - y_min));
+        max_extent = max_extent.max(tsb + (y_max - y_min));
+    }
+    if min_top == i32::MAX {
+        (min_top, min_bottom, max_extent) = (0, 0, 0);
+    }
+
+    let clamp = |v: i32| v.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+    let mut vhea = Vec::with_capacity(36);
+    vhea.extend_from_slice(&0x0001_1000u32.to_be_bytes()); // version 1.1
+    for value in [
+        clamp(units_per_em / 2),  // vertTypoAscender (中心線から em の半分)
+        clamp(-units_per_em / 2), // vertTypoDescender
+        0,                        // vertTypoLineGap
+        clamp(units_per_em),      // advanceHeightMax
+        clamp(min_top),           // minTopSideBearing
+        clamp(min_bottom),        // minBottomSideBearing
+        clamp(max_extent),        // yMaxExtent
+        0,                        // caretSlopeRise
+        1,                        // caretSlopeRun
+        0,                        // caretOffset
+    ] {
+        vhea.extend_from_slice(&value.to_be_bytes());
+    }
+    vhea.extend_from_slice(&[0; 10]); // reserved x4, metricDataFormat
+    vhea.extend_from_slice(&1u16.to_be_bytes()); // numOfLongVerMetrics (送りは全グリフ共通)
+
+    // 最初のグリフだけ (送り, サイドベアリング) の組、以降はサイドベアリングのみ
+    let mut vmtx = Vec::with_capacity(2 + side_bearings.len() * 2);
+    vmtx.extend_from_slice(&(units_per_em as u16).to_be_bytes());
+    for tsb in side_bearings {
+        vmtx.extend_from_slice(&clamp(tsb).to_be_bytes());
+    }
+
+    Ok(Some(
+        Rebuild::from_font(&font)
+            .replace(*b"vhea", &vhea)
+            .replace(*b"vmtx", &vmtx)
+            .finish()?,
+    ))
+}
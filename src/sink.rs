@@ -0,0 +1,200 @@
+//! 抽出したフォントデータの書き込み先 (シンク)
+//!
+//! `--sink` の指定に応じて、ローカルディレクトリ・zip/tar アーカイブ・HTTP(S) PUT・
+//! S3 互換オブジェクトストレージ・標準出力のいずれかに書き込みます。
+//! アーカイブは `archive` フィーチャー、HTTP(S) と S3 は `network` フィーチャーが有効な場合のみ使えます。
+
+#[cfg(feature = "archive")]
+mod archive;
+mod directory;
+#[cfg(feature = "network")]
+mod http;
+#[cfg(feature = "network")]
+mod s3;
+mod stdout;
+#[cfg(feature = "network")]
+mod transfer;
+
+use std::path::{Path, PathBuf};
+#[cfg(feature = "network")]
+use std::sync::Arc;
+
+use crate::FontExtractorError;
+use crate::output::ConflictStrategy;
+use crate::zone::Marking;
+
+#[cfg(feature = "network")]
+pub use transfer::{TransferOptions, parse_rate};
+
+/// 書き込み先の共通インターフェース
+pub trait Sink {
+    /// `file_name` という名前で `data` を書き込み、実際の書き込み先を返します。
+    /// 書き込みをスキップした場合は `None` を返します。
+    fn write(&mut self, file_name: &str, data: &[u8])
+    -> Result<Option<String>, FontExtractorError>;
+
+    /// `write` が返した書き込み先のファイルの `Zone.Identifier` を設定または削除します。
+    /// ローカルのファイルに書き込まないシンクでは何もせず `false` を返します。
+    fn mark_zone(
+        &mut self,
+        _location: &str,
+        _marking: &Marking,
+    ) -> Result<bool, FontExtractorError> {
+        Ok(false)
+    }
+
+    /// 書き込みを確定します (アーカイブの終端の書き込みなど)。
+    fn finish(self: Box<Self>) -> Result<(), FontExtractorError> {
+        Ok(())
+    }
+}
+
+/// シンクの生成に使うコマンドラインオプション
+pub struct SinkOptions<'a> {
+    pub output_dir: &'a Path,
+    pub on_conflict: ConflictStrategy,
+    #[cfg(feature = "network")]
+    pub headers: &'a [String],
+    #[cfg(feature = "network")]
+    pub s3_endpoint: Option<&'a str>,
+    #[cfg(feature = "network")]
+    pub transfer: TransferOptions,
+}
+
+/// `--sink` の指定からシンクを生成します。指定が無い場合は `--output-dir` に書き込みます。
+///
+/// - `dir:PATH` : ローカルディレクトリ
+/// - `zip:PATH` / `*.zip` : zip アーカイブ
+/// - `tar:PATH` / `*.tar` : tar アーカイブ
+/// - `http://...` / `https://...` : URL の下に HTTP PUT
+/// - `s3://bucket/prefix` : S3 互換オブジェクトストレージ
+/// - `-` (`--sink -` または `--output-dir -`) : 標準出力
+pub fn open(
+    spec: Option<&str>,
+    options: &SinkOptions<'_>,
+) -> Result<Box<dyn Sink>, FontExtractorError> {
+    if is_stdout(spec, options.output_dir) {
+        return Ok(Box::<stdout::StdoutSink>::default());
+    }
+    let Some(spec) = spec else {
+        return Ok(Box::new(directory::DirectorySink::new(
+            options.output_dir.to_path_buf(),
+            options.on_conflict,
+        )));
+    };
+    if let Some(path) = spec.strip_prefix("dir:") {
+        Ok(Box::new(directory::DirectorySink::new(
+            PathBuf::from(path),
+            options.on_conflict,
+        )))
+    } else if spec.starts_with("http://")
+        || spec.starts_with("https://")
+        || spec.starts_with("s3://")
+    {
+        open_network(spec, options)
+    } else if let Some(path) = spec.strip_prefix("zip:") {
+        open_archive(ArchiveFormat::Zip, Path::new(path))
+    } else if let Some(path) = spec.strip_prefix("tar:") {
+        open_archive(ArchiveFormat::Tar, Path::new(path))
+    } else if spec.to_ascii_lowercase().ends_with(".zip") {
+        open_archive(ArchiveFormat::Zip, Path::new(spec))
+    } else if spec.to_ascii_lowercase().ends_with(".tar") {
+        open_archive(ArchiveFormat::Tar, Path::new(spec))
+    } else {
+        Err(FontExtractorError::InvalidSink {
+            spec: spec.to_string(),
+            reason:
+                "expected dir:PATH, zip:PATH, tar:PATH, an http(s):// URL or s3://bucket/prefix"
+                    .to_string(),
+        })
+    }
+}
+
+/// 書き込み先が標準出力 (`-`) かどうか
+pub fn is_stdout(spec: Option<&str>, output_dir: &Path) -> bool {
+    match spec {
+        Some(spec) => spec == "-",
+        None => output_dir == Path::new("-"),
+    }
+}
+
+/// アーカイブの形式
+enum ArchiveFormat {
+    Zip,
+    Tar,
+}
+
+#[cfg(feature = "archive")]
+fn open_archive(format: ArchiveFormat, path: &Path) -> Result<Box<dyn Sink>, FontExtractorError> {
+    Ok(match format {
+        ArchiveFormat::Zip => Box::new(archive::ZipSink::create(path)?),
+        ArchiveFormat::Tar => Box::new(archive::TarSink::create(path)?),
+    })
+}
+
+#[cfg(not(feature = "archive"))]
+fn open_archive(_format: ArchiveFormat, path: &Path) -> Result<Box<dyn Sink>, FontExtractorError> {
+    Err(not_compiled(&path.display().to_string(), "archive"))
+}
+
+#[cfg(feature = "network")]
+fn open_network(
+    spec: &str,
+    options: &SinkOptions<'_>,
+) -> Result<Box<dyn Sink>, FontExtractorError> {
+    match spec.strip_prefix("s3://") {
+        Some(location) => Ok(Box::new(s3::S3Sink::from_env(
+            location,
+            options.s3_endpoint,
+            options.transfer,
+        )?)),
+        None => Ok(Box::new(http::HttpSink::new(
+            spec,
+            options.headers,
+            options.transfer,
+        )?)),
+    }
+}
+
+#[cfg(not(feature = "network"))]
+fn open_network(
+    spec: &str,
+    _options: &SinkOptions<'_>,
+) -> Result<Box<dyn Sink>, FontExtractorError> {
+    Err(not_compiled(spec, "network"))
+}
+
+/// このビルドで無効になっているフィーチャーのシンクを指定された場合のエラー
+#[cfg(not(all(feature = "archive", feature = "network")))]
+fn not_compiled(spec: &str, feature: &str) -> FontExtractorError {
+    FontExtractorError::InvalidSink {
+        spec: spec.to_string(),
+        reason: format!("this build of font-export does not include the '{feature}' feature"),
+    }
+}
+
+/// HTTP 系シンクで共有する ureq エージェント (TLS は OS のネイティブ実装を使用)
+#[cfg(feature = "network")]
+pub fn http_agent() -> Result<ureq::Agent, FontExtractorError> {
+    let connector =
+        native_tls::TlsConnector::new().map_err(|e| FontExtractorError::InvalidSink {
+            spec: "https".to_string(),
+            reason: format!("failed to initialize TLS: {e}"),
+        })?;
+    Ok(ureq::AgentBuilder::new()
+        .tls_connector(Arc::new(connector))
+        .build())
+}
+
+/// URL パスに使えるように文字列をパーセントエンコードします (`/` はそのまま残します)。
+#[cfg(feature = "network")]
+fn encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
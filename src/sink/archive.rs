@@ -0,0 +1,125 @@
+//! zip / tar アーカイブへの書き込み
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::Sink;
+use crate::FontExtractorError;
+
+/// アーカイブファイルを作成します (親ディレクトリも作成します)。
+fn create_archive_file(path: &Path) -> Result<File, FontExtractorError> {
+    if let Some(parent_dir) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent_dir).map_err(|e| FontExtractorError::FileCreate {
+            path: parent_dir.display().to_string(),
+            source: e,
+        })?;
+    }
+    File::create(path).map_err(|e| FontExtractorError::FileCreate {
+        path: path.display().to_string(),
+        source: e,
+    })
+}
+
+/// --- zip アーカイブ ---
+pub struct ZipSink {
+    path: PathBuf,
+    writer: ZipWriter<File>,
+}
+
+impl ZipSink {
+    pub fn create(path: &Path) -> Result<Self, FontExtractorError> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: ZipWriter::new(create_archive_file(path)?),
+        })
+    }
+
+    fn write_error(&self, source: std::io::Error) -> FontExtractorError {
+        FontExtractorError::FileWrite {
+            path: self.path.display().to_string(),
+            source,
+        }
+    }
+}
+
+impl Sink for ZipSink {
+    fn write(
+        &mut self,
+        file_name: &str,
+        data: &[u8],
+    ) -> Result<Option<String>, FontExtractorError> {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .large_file(data.len() as u64 >= u32::MAX as u64);
+        self.writer
+            .start_file(file_name, options)
+            .map_err(|e| self.write_error(std::io::Error::other(e)))?;
+        self.writer
+            .write_all(data)
+            .map_err(|e| self.write_error(e))?;
+        Ok(Some(format!("{}:{file_name}", self.path.display())))
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), FontExtractorError> {
+        let path = self.path.display().to_string();
+        self.writer
+            .finish()
+            .map(|_| ())
+            .map_err(|e| FontExtractorError::FileWrite {
+                path,
+                source: std::io::Error::other(e),
+            })
+    }
+}
+
+/// --- tar アーカイブ ---
+pub struct TarSink {
+    path: PathBuf,
+    builder: tar::Builder<File>,
+}
+
+impl TarSink {
+    pub fn create(path: &Path) -> Result<Self, FontExtractorError> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            builder: tar::Builder::new(create_archive_file(path)?),
+        })
+    }
+}
+
+impl Sink for TarSink {
+    fn write(
+        &mut self,
+        file_name: &str,
+        data: &[u8],
+    ) -> Result<Option<String>, FontExtractorError> {
+        let mtime = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        header.set_cksum();
+        self.builder
+            .append_data(&mut header, file_name, data)
+            .map_err(|e| FontExtractorError::FileWrite {
+                path: self.path.display().to_string(),
+                source: e,
+            })?;
+        Ok(Some(format!("{}:{file_name}", self.path.display())))
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), FontExtractorError> {
+        let path = self.path.display().to_string();
+        self.builder
+            .into_inner()
+            .and_then(|mut file| file.flush())
+            .map_err(|e| FontExtractorError::FileWrite { path, source: e })
+    }
+}
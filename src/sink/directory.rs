@@ -0,0 +1,63 @@
+//! ローカルディレクトリへの書き込み
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::Sink;
+use crate::FontExtractorError;
+use crate::output::{self, ConflictStrategy};
+use crate::zone::{self, Marking};
+
+pub struct DirectorySink {
+    dir: PathBuf,
+    on_conflict: ConflictStrategy,
+}
+
+impl DirectorySink {
+    pub fn new(dir: PathBuf, on_conflict: ConflictStrategy) -> Self {
+        Self { dir, on_conflict }
+    }
+}
+
+impl Sink for DirectorySink {
+    fn write(
+        &mut self,
+        file_name: &str,
+        data: &[u8],
+    ) -> Result<Option<String>, FontExtractorError> {
+        // --- 既存ファイルとの衝突処理 ---
+        let output_path = self.dir.join(file_name);
+        let Some(output_path) = output::resolve_conflict(&output_path, data, self.on_conflict)?
+        else {
+            return Ok(None);
+        };
+        let output_path_str = output_path.display().to_string();
+
+        // --- 保存先ディレクトリの作成 ---
+        if let Some(parent_dir) = output_path.parent() {
+            fs::create_dir_all(parent_dir).map_err(|e| FontExtractorError::FileCreate {
+                path: parent_dir.display().to_string(),
+                source: e,
+            })?;
+        }
+
+        // --- ファイルへの書き込み ---
+        let mut file =
+            fs::File::create(&output_path).map_err(|e| FontExtractorError::FileCreate {
+                path: output_path_str.clone(),
+                source: e,
+            })?;
+        file.write_all(data)
+            .map_err(|e| FontExtractorError::FileWrite {
+                path: output_path_str.clone(),
+                source: e,
+            })?;
+        Ok(Some(output_path_str))
+    }
+
+    fn mark_zone(&mut self, location: &str, marking: &Marking) -> Result<bool, FontExtractorError> {
+        zone::apply(Path::new(location), marking)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,61 @@
+//! HTTP(S) PUT によるアップロード
+
+use super::{Sink, TransferOptions, encode_path, http_agent};
+use crate::FontExtractorError;
+
+pub struct HttpSink {
+    base_url: String,
+    headers: Vec<(String, String)>,
+    agent: ureq::Agent,
+    transfer: TransferOptions,
+}
+
+impl HttpSink {
+    /// `headers` は `--sink-header` で指定された "Name: value" 形式の文字列です。
+    pub fn new(
+        base_url: &str,
+        headers: &[String],
+        transfer: TransferOptions,
+    ) -> Result<Self, FontExtractorError> {
+        let headers = headers
+            .iter()
+            .map(|header| {
+                header
+                    .split_once(':')
+                    .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                    .ok_or_else(|| FontExtractorError::InvalidSink {
+                        spec: base_url.to_string(),
+                        reason: format!("header '{header}' is not in 'Name: value' form"),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            headers,
+            agent: http_agent()?,
+            transfer,
+        })
+    }
+}
+
+impl Sink for HttpSink {
+    fn write(
+        &mut self,
+        file_name: &str,
+        data: &[u8],
+    ) -> Result<Option<String>, FontExtractorError> {
+        let url = format!("{}/{}", self.base_url, encode_path(file_name));
+        self.transfer.with_retry(&url, || {
+            let mut request = self
+                .agent
+                .put(&url)
+                .set("Content-Type", "application/octet-stream")
+                .set("Content-Length", &data.len().to_string());
+            for (name, value) in &self.headers {
+                request = request.set(name, value);
+            }
+            request.send(self.transfer.body(data)).map_err(Box::new)
+        })?;
+        Ok(Some(url))
+    }
+}
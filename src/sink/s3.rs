@@ -0,0 +1,332 @@
+//! S3 互換オブジェクトストレージへのアップロード (AWS Signature Version 4)
+//!
+//! 認証情報は環境変数 `AWS_ACCESS_KEY_ID` / `AWS_SECRET_ACCESS_KEY`
+//! (必要なら `AWS_SESSION_TOKEN`)、リージョンは `AWS_REGION` / `AWS_DEFAULT_REGION` から読み取ります。
+//! バケットはパス形式 (`{endpoint}/{bucket}/{key}`) で指定するため、MinIO などの互換実装でも動作します。
+//!
+//! [`PART_SIZE`] を超えるデータはマルチパートアップロードで送信し、失敗したパートだけを再送します。
+
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::{Sink, TransferOptions, encode_path, http_agent};
+use crate::FontExtractorError;
+use crate::digest::sha256_hex;
+
+/// リージョンが指定されていない場合の既定値
+const DEFAULT_REGION: &str = "us-east-1";
+/// マルチパートアップロードの 1 パートの大きさ (S3 が認める最小値。最後のパートを除く)
+const PART_SIZE: usize = 5 * 1024 * 1024;
+
+pub struct S3Sink {
+    endpoint: String,
+    host: String,
+    bucket: String,
+    prefix: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+    agent: ureq::Agent,
+    transfer: TransferOptions,
+}
+
+impl S3Sink {
+    /// `location` は `s3://` を除いた `bucket/prefix` 部分です。
+    pub fn from_env(
+        location: &str,
+        endpoint: Option<&str>,
+        transfer: TransferOptions,
+    ) -> Result<Self, FontExtractorError> {
+        let spec = format!("s3://{location}");
+        let invalid = |reason: &str| FontExtractorError::InvalidSink {
+            spec: spec.clone(),
+            reason: reason.to_string(),
+        };
+
+        let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+        if bucket.is_empty() {
+            return Err(invalid("bucket name is empty"));
+        }
+        let access_key =
+            env::var("AWS_ACCESS_KEY_ID").map_err(|_| invalid("AWS_ACCESS_KEY_ID is not set"))?;
+        let secret_key = env::var("AWS_SECRET_ACCESS_KEY")
+            .map_err(|_| invalid("AWS_SECRET_ACCESS_KEY is not set"))?;
+        let region = env::var("AWS_REGION")
+            .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|_| DEFAULT_REGION.to_string());
+
+        let endpoint = endpoint
+            .map(|e| e.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| format!("https://s3.{region}.amazonaws.com"));
+        let host = endpoint
+            .split_once("://")
+            .map_or(endpoint.as_str(), |(_, rest)| rest)
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        if host.is_empty() {
+            return Err(invalid("S3 endpoint has no host"));
+        }
+
+        Ok(Self {
+            endpoint,
+            host,
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+            region,
+            access_key,
+            secret_key,
+            session_token: env::var("AWS_SESSION_TOKEN").ok(),
+            agent: http_agent()?,
+            transfer,
+        })
+    }
+
+    fn object_key(&self, file_name: &str) -> String {
+        if self.prefix.is_empty() {
+            file_name.to_string()
+        } else {
+            format!("{}/{file_name}", self.prefix)
+        }
+    }
+
+    /// SigV4 の Authorization ヘッダーを計算します。
+    /// `query` は正規化済み (キーの昇順、値はエンコード済み) のクエリ文字列です。
+    fn authorization(
+        &self,
+        method: &str,
+        path: &str,
+        query: &str,
+        payload_hash: &str,
+        amz_date: &str,
+    ) -> String {
+        let date = &amz_date[..8];
+        let mut canonical_headers = format!(
+            "host:{}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n",
+            self.host
+        );
+        let mut signed_headers = "host;x-amz-content-sha256;x-amz-date".to_string();
+        if let Some(token) = &self.session_token {
+            canonical_headers.push_str(&format!("x-amz-security-token:{token}\n"));
+            signed_headers.push_str(";x-amz-security-token");
+        }
+        let canonical_request = format!(
+            "{method}\n{path}\n{query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}"
+        );
+
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            sha256_hex(canonical_request.as_bytes())
+        );
+
+        let mut key = hmac_sha256(
+            format!("AWS4{}", self.secret_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part.as_bytes());
+        }
+        let signature: String = hmac_sha256(&key, string_to_sign.as_bytes())
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            self.access_key
+        )
+    }
+
+    /// 署名済みのリクエストを送信します。`query` は (キー, 値) の組をキーの昇順に並べたものです。
+    fn send(
+        &self,
+        method: &str,
+        path: &str,
+        query: &[(&str, &str)],
+        payload: &[u8],
+    ) -> Result<ureq::Response, Box<ureq::Error>> {
+        let query = query
+            .iter()
+            .map(|(key, value)| format!("{key}={}", encode_path(value).replace('/', "%2F")))
+            .collect::<Vec<_>>()
+            .join("&");
+        let url = if query.is_empty() {
+            format!("{}{path}", self.endpoint)
+        } else {
+            format!("{}{path}?{query}", self.endpoint)
+        };
+        let payload_hash = sha256_hex(payload);
+        let amz_date = amz_date(SystemTime::now());
+
+        let mut request = self
+            .agent
+            .request(method, &url)
+            .set("x-amz-content-sha256", &payload_hash)
+            .set("x-amz-date", &amz_date)
+            .set(
+                "Authorization",
+                &self.authorization(method, path, &query, &payload_hash, &amz_date),
+            )
+            .set("Content-Length", &payload.len().to_string());
+        if let Some(token) = &self.session_token {
+            request = request.set("x-amz-security-token", token);
+        }
+        request.send(self.transfer.body(payload)).map_err(Box::new)
+    }
+
+    /// `data` をマルチパートアップロードで送信します。途中で失敗した場合はアップロードを中止します。
+    fn upload_multipart(&self, path: &str, data: &[u8]) -> Result<(), FontExtractorError> {
+        let url = format!("{}{path}", self.endpoint);
+        let upload_error = |reason: String| FontExtractorError::Upload {
+            url: url.clone(),
+            reason,
+        };
+
+        let response = self
+            .transfer
+            .with_retry(&url, || self.send("POST", path, &[("uploads", "")], &[]))?;
+        let body = response
+            .into_string()
+            .map_err(|e| upload_error(e.to_string()))?;
+        let upload_id = xml_element(&body, "UploadId")
+            .ok_or_else(|| upload_error("CreateMultipartUpload returned no UploadId".to_string()))?
+            .to_string();
+
+        let result = self.upload_parts(path, &upload_id, data).and_then(|etags| {
+            let mut complete = String::from("<CompleteMultipartUpload>");
+            for (i, etag) in etags.iter().enumerate() {
+                complete.push_str(&format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{etag}</ETag></Part>",
+                    i + 1
+                ));
+            }
+            complete.push_str("</CompleteMultipartUpload>");
+            let response = self.transfer.with_retry(&url, || {
+                self.send(
+                    "POST",
+                    path,
+                    &[("uploadId", &upload_id)],
+                    complete.as_bytes(),
+                )
+            })?;
+            // CompleteMultipartUpload は 200 応答の本文でエラーを返すことがある
+            let body = response
+                .into_string()
+                .map_err(|e| upload_error(e.to_string()))?;
+            match xml_element(&body, "Error") {
+                Some(error) => Err(upload_error(format!(
+                    "CompleteMultipartUpload failed: {}",
+                    xml_element(error, "Message").unwrap_or(error)
+                ))),
+                None => Ok(()),
+            }
+        });
+        if result.is_err() {
+            // 中止しないと送信済みのパートがバケットに残り課金される (中止の失敗は元のエラーを優先して無視)
+            let _ = self.send("DELETE", path, &[("uploadId", &upload_id)], &[]);
+        }
+        result
+    }
+
+    /// 各パートを送信し、ETag をパート番号順に返します。
+    fn upload_parts(
+        &self,
+        path: &str,
+        upload_id: &str,
+        data: &[u8],
+    ) -> Result<Vec<String>, FontExtractorError> {
+        data.chunks(PART_SIZE)
+            .enumerate()
+            .map(|(i, part)| {
+                let part_number = (i + 1).to_string();
+                let url = format!("{}{path}?partNumber={part_number}", self.endpoint);
+                let response = self.transfer.with_retry(&url, || {
+                    self.send(
+                        "PUT",
+                        path,
+                        &[("partNumber", &part_number), ("uploadId", upload_id)],
+                        part,
+                    )
+                })?;
+                response.header("ETag").map(str::to_string).ok_or_else(|| {
+                    FontExtractorError::Upload {
+                        url,
+                        reason: "UploadPart returned no ETag".to_string(),
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+impl Sink for S3Sink {
+    fn write(
+        &mut self,
+        file_name: &str,
+        data: &[u8],
+    ) -> Result<Option<String>, FontExtractorError> {
+        let path = format!(
+            "/{}/{}",
+            encode_path(&self.bucket),
+            encode_path(&self.object_key(file_name))
+        );
+        if data.len() > PART_SIZE {
+            self.upload_multipart(&path, data)?;
+        } else {
+            let url = format!("{}{path}", self.endpoint);
+            self.transfer
+                .with_retry(&url, || self.send("PUT", &path, &[], data))?;
+        }
+        Ok(Some(format!(
+            "s3://{}/{}",
+            self.bucket,
+            self.object_key(file_name)
+        )))
+    }
+}
+
+/// XML の本文から最初の `<name>...</name>` の中身を取り出します (S3 の応答は単純なので専用のパーサは使いません)。
+fn xml_element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{name}>"))? + name.len() + 2;
+    let end = start + xml[start..].find(&format!("</{name}>"))?;
+    Some(&xml[start..end])
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// `YYYYMMDDTHHMMSSZ` 形式の UTC 時刻
+fn amz_date(now: SystemTime) -> String {
+    let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time_of_day = secs % 86_400;
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60
+    )
+}
+
+/// 1970-01-01 からの日数をグレゴリオ暦の (年, 月, 日) に変換します。
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
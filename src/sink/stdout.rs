@@ -0,0 +1,44 @@
+//! 標準出力への書き込み (`-o -`)
+//!
+//! パイプでつなぐためのもので、書き込めるファイルは 1 つだけです。
+
+use std::io::Write;
+
+use super::Sink;
+use crate::FontExtractorError;
+
+/// 書き込み先として表示する名前
+const LOCATION: &str = "<stdout>";
+
+#[derive(Default)]
+pub struct StdoutSink {
+    /// 書き込んだファイル名 (2 つ目を書き込もうとしたときのエラーに使う)
+    written: Option<String>,
+}
+
+impl Sink for StdoutSink {
+    fn write(
+        &mut self,
+        file_name: &str,
+        data: &[u8],
+    ) -> Result<Option<String>, FontExtractorError> {
+        if let Some(first) = &self.written {
+            return Err(FontExtractorError::InvalidSink {
+                spec: "-".to_string(),
+                reason: format!(
+                    "only one file can be written to standard output, but '{file_name}' follows '{first}'"
+                ),
+            });
+        }
+        let mut stdout = std::io::stdout().lock();
+        stdout
+            .write_all(data)
+            .and_then(|()| stdout.flush())
+            .map_err(|e| FontExtractorError::FileWrite {
+                path: LOCATION.to_string(),
+                source: e,
+            })?;
+        self.written = Some(file_name.to_string());
+        Ok(Some(LOCATION.to_string()))
+    }
+}
@@ -0,0 +1,145 @@
+//! HTTP 系シンクの転送制御 (再試行と帯域制限)
+//!
+//! 失敗したリクエストは指数バックオフ (1 秒, 2 秒, 4 秒, ... 最大 30 秒) で再試行します。
+//! 再試行するのは接続・通信エラー、タイムアウト (408)、レート制限 (429) とサーバーエラー (5xx) だけで、
+//! 認証エラーなどはすぐに失敗として返します。
+
+use std::io::{self, Read};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::FontExtractorError;
+
+/// 1 回目の再試行までの待ち時間
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// 再試行の待ち時間の上限
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// 帯域制限時に 1 回の read で送るバイト数の上限
+const THROTTLE_CHUNK: usize = 16 * 1024;
+
+/// 転送に関するコマンドラインオプション
+#[derive(Debug, Clone, Copy)]
+pub struct TransferOptions {
+    /// 失敗したリクエストを再試行する回数 (0 なら再試行しない)
+    pub retries: u32,
+    /// 送信速度の上限 (バイト/秒)
+    pub limit: Option<u64>,
+}
+
+impl TransferOptions {
+    /// `send` を成功するか再試行の回数を使い切るまで繰り返します。`send` には試行ごとに新しいリクエストを作らせます。
+    pub fn with_retry<T>(
+        &self,
+        url: &str,
+        mut send: impl FnMut() -> Result<T, Box<ureq::Error>>,
+    ) -> Result<T, FontExtractorError> {
+        let mut attempt = 0;
+        loop {
+            let error = match send() {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            let wait = match &*error {
+                ureq::Error::Status(code, response) if is_retryable(*code) => Some(
+                    // Retry-After (秒数) があればそれに従う
+                    response
+                        .header("Retry-After")
+                        .and_then(|value| value.trim().parse().ok())
+                        .map(Duration::from_secs),
+                ),
+                ureq::Error::Transport(transport) if is_transient(transport.kind()) => Some(None),
+                _ => None,
+            };
+            let Some(retry_after) = wait.filter(|_| attempt < self.retries) else {
+                let reason = if attempt == 0 {
+                    error.to_string()
+                } else {
+                    format!("{error} (gave up after {} attempts)", attempt + 1)
+                };
+                return Err(FontExtractorError::Upload {
+                    url: url.to_string(),
+                    reason,
+                });
+            };
+            let backoff = INITIAL_BACKOFF
+                .saturating_mul(1 << attempt.min(16))
+                .min(MAX_BACKOFF);
+            thread::sleep(retry_after.unwrap_or(backoff).min(MAX_BACKOFF));
+            attempt += 1;
+        }
+    }
+
+    /// `data` を送信するリクエストボディを返します。帯域制限が指定されていれば送信速度を抑えます。
+    /// 呼び出し側は `Content-Length` ヘッダーを設定してください (設定しないと chunked で送信されます)。
+    pub fn body<'a>(&self, data: &'a [u8]) -> Throttled<&'a [u8]> {
+        Throttled {
+            inner: data,
+            limit: self.limit,
+            started: Instant::now(),
+            sent: 0,
+        }
+    }
+}
+
+fn is_retryable(status: u16) -> bool {
+    matches!(status, 408 | 429) || status >= 500
+}
+
+/// 接続の問題による (URL の誤りなどではない) エラーか
+fn is_transient(kind: ureq::ErrorKind) -> bool {
+    matches!(
+        kind,
+        ureq::ErrorKind::Dns
+            | ureq::ErrorKind::ConnectionFailed
+            | ureq::ErrorKind::Io
+            | ureq::ErrorKind::ProxyConnect
+    )
+}
+
+/// 送信速度を `limit` バイト/秒に抑える Reader
+pub struct Throttled<R> {
+    inner: R,
+    limit: Option<u64>,
+    started: Instant,
+    sent: u64,
+}
+
+impl<R: Read> Read for Throttled<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(limit) = self.limit else {
+            return self.inner.read(buf);
+        };
+        let len = buf.len().min(THROTTLE_CHUNK);
+        let read = self.inner.read(&mut buf[..len])?;
+        self.sent += read as u64;
+        // ここまでの送信量を制限速度で送るのにかかる時間より早ければ、その差だけ待つ
+        let due = Duration::from_secs_f64(self.sent as f64 / limit as f64);
+        if let Some(wait) = due.checked_sub(self.started.elapsed()) {
+            thread::sleep(wait);
+        }
+        Ok(read)
+    }
+}
+
+/// `--upload-limit` の値 (`500K`, `2M` など。単位は 1024 倍、省略時はバイト) をバイト/秒に変換します。
+pub fn parse_rate(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '/');
+    let unit = value[digits.len()..].to_ascii_uppercase();
+    let multiplier: u64 = match unit.trim_end_matches("/S").trim_end_matches('B') {
+        "" => 1,
+        "K" | "KI" => 1 << 10,
+        "M" | "MI" => 1 << 20,
+        "G" | "GI" => 1 << 30,
+        _ => return Err(format!("unknown unit '{unit}' (use K, M or G)")),
+    };
+    let number: f64 = digits
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{value}' is not a number of bytes per second"))?;
+    let rate = (number * multiplier as f64) as u64;
+    if rate == 0 {
+        return Err("the rate must be at least 1 byte per second".to_string());
+    }
+    Ok(rate)
+}
@@ -0,0 +1,161 @@
+//! 名前からインストール済みフォントを選んで読み取る方法 (バックエンド) の共通インターフェース
+//!
+//! バックエンドはそれぞれ Cargo のフィーチャーで有効にします。ライブラリの利用者は必要なものだけを有効にでき、
+//! たとえば `default-features = false, features = ["dwrite"]` なら GDI のコードも windows クレートの GDI の部分も取り込みません。
+//!
+//! | フィーチャー | バックエンド | ターゲット |
+//! |---|---|---|
+//! | `gdi` | GDI (CreateFontW と GetFontData) | Windows |
+//! | `dwrite` | DirectWrite | Windows |
+//! | `fontconfig` | fontconfig (libfontconfig) | Windows と macOS 以外 |
+//! | `coretext` | Core Text | macOS |
+//!
+//! ターゲットで使えないフィーチャーは無視されます。複数のバックエンドが有効な場合は表の順に優先し、最初のものが
+//! 名前を解決します ([`native`])。そのバックエンドが読み取れなかったフォントは、次のバックエンド、レジストリに登録された
+//! ファイルの順に試します ([`crate::extract_font_data_with_fallback`])。
+//! バックエンドが 1 つも無くても、フォントファイルの処理とレジストリに登録されたファイルの読み取り (`--bypass-cache`) は使えます。
+
+use crate::{
+    Backend, FontExtractorError, FontFace, FontRequest, FontTables, ReadTimes, SafeDC,
+    TableDirectory, fontfile, sfnt,
+};
+
+/// このターゲットで名前からフォントを選べるバックエンドのフィーチャー (エラーメッセージ用)
+#[cfg(windows)]
+const NATIVE_FEATURES: &str = "gdi or dwrite";
+#[cfg(target_os = "macos")]
+const NATIVE_FEATURES: &str = "coretext";
+#[cfg(not(any(windows, target_os = "macos")))]
+const NATIVE_FEATURES: &str = "fontconfig";
+
+/// PostScript 名を引けるバックエンドのフィーチャー (エラーメッセージ用)
+#[cfg(windows)]
+const POSTSCRIPT_FEATURES: &str = "dwrite";
+#[cfg(not(windows))]
+const POSTSCRIPT_FEATURES: &str = NATIVE_FEATURES;
+
+/// 名前からインストール済みフォントを選んで読み取るバックエンド
+pub trait FontSource {
+    /// バックエンドの種類
+    fn backend(&self) -> Backend;
+
+    /// `request` の名前 (と太さ・斜体など) からフォントを選び、フォントデータ全体を読み取ります。
+    /// TrueType / OpenType Collection のフェイスは単体のフォントに組み立て直します。
+    fn read(&self, request: &FontRequest<'_>) -> Result<(Vec<u8>, ReadTimes), FontExtractorError>;
+
+    /// 選んだフォントがコレクションのフェイスなら、コレクション全体 (他のフェイスを含む) を読み取ります。
+    /// コレクションに含まれないフォントでは `None` を返します。
+    fn collection(&self, request: &FontRequest<'_>) -> Result<Option<Vec<u8>>, FontExtractorError>;
+
+    /// 選んだフォントから `tags` のテーブルだけを読み取ります。
+    fn tables(
+        &self,
+        request: &FontRequest<'_>,
+        tags: &[sfnt::Tag],
+    ) -> Result<FontTables, FontExtractorError> {
+        let (data, _) = self.read(request)?;
+        fontfile::tables_from_data(&data, tags)
+    }
+
+    /// 選んだフォントのテーブルディレクトリを読み取ります。
+    fn directory(&self, request: &FontRequest<'_>) -> Result<TableDirectory, FontExtractorError> {
+        let (data, _) = self.read(request)?;
+        fontfile::face_directory(&data, 0)
+    }
+
+    /// TrueType / OpenType のファミリー名を、重複を除いて名前順に返します。
+    fn families(&self) -> Result<Vec<String>, FontExtractorError>;
+
+    /// `family` の TrueType / OpenType のフェイスを、太さ・斜体の順に重複を除いて返します。
+    fn faces(&self, family: &str) -> Result<Vec<FontFace>, FontExtractorError>;
+
+    /// PostScript 名が `name` のフェイスを探し、名前から選ぶためのファミリー名とフェイスを返します (見つからなければ `Ok(None)`)。
+    /// PostScript 名を引けないバックエンドは `None` を返します。
+    fn find_postscript_name(
+        &self,
+        name: &str,
+    ) -> Option<Result<Option<(String, FontFace)>, FontExtractorError>> {
+        let _ = name;
+        None
+    }
+}
+
+/// 有効なバックエンドを優先する順に返します (GDI は `dc` を使います)。
+pub fn sources(dc: &SafeDC) -> Vec<Box<dyn FontSource + '_>> {
+    #[cfg(not(backend = "gdi"))]
+    let _ = dc;
+    vec![
+        #[cfg(backend = "gdi")]
+        Box::new(Gdi(dc)),
+        #[cfg(backend = "dwrite")]
+        Box::new(crate::directwrite::DirectWrite),
+        #[cfg(backend = "fontconfig")]
+        Box::new(crate::fontconfig::Fontconfig),
+        #[cfg(backend = "coretext")]
+        Box::new(crate::coretext::CoreText),
+    ]
+}
+
+/// 名前からフォントを選ぶバックエンド (有効なもののうち最も優先するもの)
+///
+/// バックエンドが 1 つも有効でなければエラーを返します。
+pub fn native(dc: &SafeDC) -> Result<Box<dyn FontSource + '_>, FontExtractorError> {
+    sources(dc)
+        .into_iter()
+        .next()
+        .ok_or(FontExtractorError::BackendUnavailable {
+            what: "Reading installed fonts by name",
+            features: NATIVE_FEATURES,
+        })
+}
+
+/// PostScript 名を引けるバックエンドで `name` のフェイスを探します。
+pub fn find_postscript_name(name: &str) -> Result<Option<(String, FontFace)>, FontExtractorError> {
+    let dc = SafeDC::new()?;
+    sources(&dc)
+        .iter()
+        .find_map(|source| source.find_postscript_name(name))
+        .unwrap_or(Err(FontExtractorError::BackendUnavailable {
+            what: "Looking up PostScript names",
+            features: POSTSCRIPT_FEATURES,
+        }))
+}
+
+/// GDI のフォントマッパー (CreateFontW で選び、GetFontData で読み取る)
+#[cfg(backend = "gdi")]
+pub struct Gdi<'dc>(pub &'dc SafeDC);
+
+#[cfg(backend = "gdi")]
+impl FontSource for Gdi<'_> {
+    fn backend(&self) -> Backend {
+        Backend::Gdi
+    }
+
+    fn read(&self, request: &FontRequest<'_>) -> Result<(Vec<u8>, ReadTimes), FontExtractorError> {
+        crate::gdi_read(self.0, request)
+    }
+
+    fn collection(&self, request: &FontRequest<'_>) -> Result<Option<Vec<u8>>, FontExtractorError> {
+        crate::gdi_collection(self.0, request)
+    }
+
+    fn tables(
+        &self,
+        request: &FontRequest<'_>,
+        tags: &[sfnt::Tag],
+    ) -> Result<FontTables, FontExtractorError> {
+        crate::gdi_tables(self.0, request, tags)
+    }
+
+    fn directory(&self, request: &FontRequest<'_>) -> Result<TableDirectory, FontExtractorError> {
+        crate::gdi_directory(self.0, request)
+    }
+
+    fn families(&self) -> Result<Vec<String>, FontExtractorError> {
+        Ok(crate::gdi_families(self.0))
+    }
+
+    fn faces(&self, family: &str) -> Result<Vec<FontFace>, FontExtractorError> {
+        Ok(crate::gdi_faces(self.0, family))
+    }
+}
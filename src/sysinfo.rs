@@ -0,0 +1,182 @@
+//! 診断情報 (`--diagnostics`) に載せる OS とフォントキャッシュの情報
+//!
+//! 取得できなかった項目は、失敗の理由を値にして返します (診断情報の収集そのものは失敗させない)。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+#[cfg(windows)]
+use windows::Win32::System::Registry::{
+    HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD, RRF_RT_REG_SZ, RegGetValueW,
+};
+#[cfg(windows)]
+use windows::core::{HSTRING, w};
+
+use crate::fontreg;
+
+/// OS のバージョン、フォントキャッシュの状態、インストールされているフォントの数を (項目名, 値) の一覧で返します。
+pub fn describe() -> Vec<(&'static str, String)> {
+    let mut items = vec![("os", os_version())];
+    #[cfg(backend = "fontconfig")]
+    items.push(("fontconfig", crate::fontconfig::version()));
+    for dir in font_cache_dirs() {
+        items.push(("font cache", describe_dir(&dir)));
+    }
+    items.push((
+        "installed font files",
+        match fontreg::registered_fonts() {
+            Ok(fonts) => fonts.len().to_string(),
+            Err(e) => format!("unavailable ({e})"),
+        },
+    ));
+    items
+}
+
+/// "Windows 10 Pro 22H2 (build 19045.4291)" の形式 (HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion から)
+#[cfg(windows)]
+fn os_version() -> String {
+    let product = registry_string("ProductName").unwrap_or_else(|| "Windows".to_string());
+    let release = registry_string("DisplayVersion").or_else(|| registry_string("ReleaseId"));
+    let build = registry_string("CurrentBuildNumber").unwrap_or_else(|| "?".to_string());
+    let revision = registry_dword("UBR").map_or(String::new(), |ubr| format!(".{ubr}"));
+    match release {
+        Some(release) => format!("{product} {release} (build {build}{revision})"),
+        None => format!("{product} (build {build}{revision})"),
+    }
+}
+
+/// "Linux 6.8.0-45-generic #45-Ubuntu SMP ... x86_64" の形式 (uname から)
+#[cfg(not(windows))]
+fn os_version() -> String {
+    let mut name: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut name) } != 0 {
+        return format!("unavailable ({})", std::io::Error::last_os_error());
+    }
+    let field = |chars: &[libc::c_char]| {
+        let bytes: Vec<u8> = chars
+            .iter()
+            .take_while(|&&c| c != 0)
+            .map(|&c| c as u8)
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+    format!(
+        "{} {} {} {}",
+        field(&name.sysname),
+        field(&name.release),
+        field(&name.version),
+        field(&name.machine)
+    )
+}
+
+#[cfg(windows)]
+const CURRENT_VERSION_KEY: windows::core::PCWSTR =
+    w!(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion");
+
+#[cfg(windows)]
+fn registry_string(value: &str) -> Option<String> {
+    let mut buffer = [0u16; 256];
+    let mut size = std::mem::size_of_val(&buffer) as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            CURRENT_VERSION_KEY,
+            &HSTRING::from(value),
+            RRF_RT_REG_SZ,
+            None,
+            Some(buffer.as_mut_ptr().cast()),
+            Some(&mut size),
+        )
+    };
+    if status.is_err() {
+        return None;
+    }
+    let len = (size as usize / 2).min(buffer.len());
+    let text = String::from_utf16_lossy(&buffer[..len]);
+    Some(text.trim_end_matches('\0').to_string())
+}
+
+#[cfg(windows)]
+fn registry_dword(value: &str) -> Option<u32> {
+    let mut data = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            CURRENT_VERSION_KEY,
+            &HSTRING::from(value),
+            RRF_RT_REG_DWORD,
+            None,
+            Some((&mut data as *mut u32).cast()),
+            Some(&mut size),
+        )
+    };
+    status.is_ok().then_some(data)
+}
+
+/// フォントキャッシュの置き場所
+///
+/// Windows では GDI の FNTCACHE.DAT と Windows Font Cache Service のキャッシュ、
+/// それ以外ではユーザーごとのものとシステムの fontconfig のキャッシュです。
+#[cfg(windows)]
+fn font_cache_dirs() -> Vec<PathBuf> {
+    let windows = fontreg::fonts_dir()
+        .parent()
+        .map_or_else(|| PathBuf::from(r"C:\Windows"), Path::to_path_buf);
+    vec![
+        windows.join(r"System32\FNTCACHE.DAT"),
+        windows.join(r"ServiceProfiles\LocalService\AppData\Local\FontCache"),
+    ]
+}
+
+#[cfg(not(windows))]
+fn font_cache_dirs() -> Vec<PathBuf> {
+    let user = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|cache| cache.join("fontconfig"));
+    user.into_iter()
+        .chain([PathBuf::from("/var/cache/fontconfig")])
+        .collect()
+}
+
+/// "C:\Windows\System32\FNTCACHE.DAT: 1 file(s), 43520 bytes, newest modified 2 day(s) ago" の形式
+fn describe_dir(path: &Path) -> String {
+    let files: Vec<fs::Metadata> = match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => vec![metadata],
+        Ok(_) => match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok()?.metadata().ok())
+                .filter(|metadata| metadata.is_file())
+                .collect(),
+            Err(e) => return format!("{}: not readable ({e})", path.display()),
+        },
+        Err(e) => return format!("{}: not readable ({e})", path.display()),
+    };
+    let bytes: u64 = files.iter().map(fs::Metadata::len).sum();
+    let newest = files
+        .iter()
+        .filter_map(|metadata| metadata.modified().ok())
+        .max()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    format!(
+        "{}: {} file(s), {bytes} bytes{}",
+        path.display(),
+        files.len(),
+        newest.map_or(String::new(), |age| format!(
+            ", newest modified {} ago",
+            describe_age(age)
+        ))
+    )
+}
+
+fn describe_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..3600 => format!("{} minute(s)", secs / 60),
+        3600..86_400 => format!("{} hour(s)", secs / 3600),
+        _ => format!("{} day(s)", secs / 86_400),
+    }
+}
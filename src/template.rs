@@ -0,0 +1,135 @@
+//! `--output-dir` / `--sink` のテンプレート変数の展開
+//!
+//! | 変数 | 展開結果 |
+//! |------|----------|
+//! | `{hostname}` | コンピューター名 (`COMPUTERNAME`、Windows 以外では gethostname) |
+//! | `{username}` | ユーザー名 (`USERNAME`、Windows 以外では `USER` か `LOGNAME`) |
+//! | `{date}` | ローカル日付 `YYYY-MM-DD` |
+//! | `{time}` | ローカル時刻 `HHMMSS` |
+//! | `{env:NAME}` | 環境変数 `NAME` |
+//!
+//! `{{` と `}}` はそれぞれ `{` と `}` になります。
+
+use std::env;
+
+#[cfg(windows)]
+use windows::Win32::System::SystemInformation::GetLocalTime;
+
+use crate::FontExtractorError;
+
+/// テンプレート中の変数を展開します。
+pub fn expand(template: &str) -> Result<String, FontExtractorError> {
+    let invalid = |reason: String| FontExtractorError::InvalidTemplate {
+        template: template.to_string(),
+        reason,
+    };
+
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(pos) = rest.find(['{', '}']) {
+        expanded.push_str(&rest[..pos]);
+        let brace = &rest[pos..];
+        if let Some(after) = brace
+            .strip_prefix("{{")
+            .or_else(|| brace.strip_prefix("}}"))
+        {
+            expanded.push_str(&brace[..1]);
+            rest = after;
+            continue;
+        }
+        if brace.starts_with('}') {
+            return Err(invalid(
+                "unmatched '}' (write '}}' for a literal brace)".to_string(),
+            ));
+        }
+        let end = brace
+            .find('}')
+            .ok_or_else(|| invalid("unclosed '{' (write '{{' for a literal brace)".to_string()))?;
+        expanded.push_str(&variable(&brace[1..end]).map_err(invalid)?);
+        rest = &brace[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// 変数 1 つの値を返します。
+fn variable(name: &str) -> Result<String, String> {
+    let env_var =
+        |key: &str| env::var(key).map_err(|_| format!("environment variable {key} is not set"));
+    match name {
+        #[cfg(windows)]
+        "hostname" => env_var("COMPUTERNAME"),
+        #[cfg(not(windows))]
+        "hostname" => hostname(),
+        #[cfg(windows)]
+        "username" => env_var("USERNAME"),
+        #[cfg(not(windows))]
+        "username" => env_var("USER").or_else(|_| env_var("LOGNAME")),
+        "date" => {
+            let now = local_time();
+            Ok(format!("{:04}-{:02}-{:02}", now.year, now.month, now.day))
+        }
+        "time" => {
+            let now = local_time();
+            Ok(format!("{:02}{:02}{:02}", now.hour, now.minute, now.second))
+        }
+        _ => match name.strip_prefix("env:") {
+            Some(key) => env_var(key),
+            None => Err(format!(
+                "unknown variable {{{name}}} (expected hostname, username, date, time or env:NAME)"
+            )),
+        },
+    }
+}
+
+/// ローカル時刻
+struct LocalTime {
+    year: i32,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+}
+
+#[cfg(windows)]
+fn local_time() -> LocalTime {
+    let now = unsafe { GetLocalTime() };
+    LocalTime {
+        year: now.wYear.into(),
+        month: now.wMonth.into(),
+        day: now.wDay.into(),
+        hour: now.wHour.into(),
+        minute: now.wMinute.into(),
+        second: now.wSecond.into(),
+    }
+}
+
+#[cfg(not(windows))]
+fn local_time() -> LocalTime {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&now, &mut tm) };
+    LocalTime {
+        year: tm.tm_year + 1900,
+        month: (tm.tm_mon + 1) as u32,
+        day: tm.tm_mday as u32,
+        hour: tm.tm_hour as u32,
+        minute: tm.tm_min as u32,
+        second: tm.tm_sec as u32,
+    }
+}
+
+/// gethostname で取得したコンピューター名
+#[cfg(not(windows))]
+fn hostname() -> Result<String, String> {
+    let mut buffer = [0u8; 256];
+    if unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) } != 0 {
+        return Err(format!(
+            "cannot get the host name: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    let len = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    Ok(String::from_utf8_lossy(&buffer[..len]).into_owned())
+}
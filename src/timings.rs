@@ -0,0 +1,176 @@
+//! 段階ごとの所要時間とスループット (`--timings`)
+//!
+//! 抽出の各段階にかかった時間を実行全体で合計し、実行の最後に表示します。
+//! どの段階が遅いか (GDI の読み取りか、書き込み先への転送か) を見分けるためのものです。
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::events::{Event, Reporter};
+
+/// 抽出の段階
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// ファミリーの列挙と `*` パターンの展開、`--from-file` の読み込み
+    Enumeration,
+    /// GDI のフォントマッパーによる名前の解決 (CreateFontW と SelectObject)
+    Resolution,
+    /// GetFontData やフォントファイルの読み取り
+    Read,
+    /// 代替フォントの検出とカタログ・DirectWrite との照合
+    Checks,
+    /// 縦書きメトリクスの合成などの加工と追加のファイルの生成
+    Conversion,
+    /// 書き込み先への書き込み (アップロードやアーカイブの完成を含む)
+    Io,
+}
+
+impl Stage {
+    const ALL: [Self; 6] = [
+        Self::Enumeration,
+        Self::Resolution,
+        Self::Read,
+        Self::Checks,
+        Self::Conversion,
+        Self::Io,
+    ];
+
+    /// 表示名
+    fn label(self) -> &'static str {
+        match self {
+            Self::Enumeration => "Enumeration",
+            Self::Resolution => "Resolution",
+            Self::Read => "GDI reads",
+            Self::Checks => "Checks",
+            Self::Conversion => "Conversion",
+            Self::Io => "IO",
+        }
+    }
+
+    /// イベントでのキー
+    fn key(self) -> &'static str {
+        match self {
+            Self::Enumeration => "enumeration",
+            Self::Resolution => "resolution",
+            Self::Read => "read",
+            Self::Checks => "checks",
+            Self::Conversion => "conversion",
+            Self::Io => "io",
+        }
+    }
+}
+
+/// 実行全体での段階ごとの所要時間と読み書きした量
+#[derive(Debug)]
+pub struct Timings {
+    started: Instant,
+    spent: [Duration; Stage::ALL.len()],
+    fonts: usize,
+    bytes_read: u64,
+    bytes_written: u64,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            spent: [Duration::ZERO; Stage::ALL.len()],
+            fonts: 0,
+            bytes_read: 0,
+            bytes_written: 0,
+        }
+    }
+}
+
+impl Timings {
+    /// `stage` に `elapsed` を加えます。
+    pub fn add(&mut self, stage: Stage, elapsed: Duration) {
+        self.spent[stage as usize] += elapsed;
+    }
+
+    /// `started` からの経過時間を `stage` に加えます。
+    pub fn since(&mut self, stage: Stage, started: Instant) {
+        self.add(stage, started.elapsed());
+    }
+
+    /// 処理したフォントを 1 件数えます。
+    pub fn font(&mut self) {
+        self.fonts += 1;
+    }
+
+    /// 読み取った量を加えます。
+    pub fn read(&mut self, bytes: usize) {
+        self.bytes_read += bytes as u64;
+    }
+
+    /// 書き込んだ量を加えます。
+    pub fn written(&mut self, bytes: usize) {
+        self.bytes_written += bytes as u64;
+    }
+
+    /// 段階ごとの所要時間とスループットを表示します (イベント出力中は `timings` イベントにします)。
+    pub fn report(&self, reporter: &Reporter) {
+        let total = self.started.elapsed();
+        reporter.emit(Event::Timings {
+            total_ms: total.as_millis(),
+            stages_ms: Stage::ALL
+                .iter()
+                .map(|&stage| (stage.key(), self.spent[stage as usize].as_millis()))
+                .collect::<BTreeMap<_, _>>(),
+            fonts: self.fonts,
+            bytes_read: self.bytes_read,
+            bytes_written: self.bytes_written,
+        });
+
+        reporter.say("");
+        reporter.say(format!(
+            "Timings: {:.3} s for {} font(s)",
+            total.as_secs_f64(),
+            self.fonts
+        ));
+        let share = |spent: Duration| {
+            if total.is_zero() {
+                0.0
+            } else {
+                spent.as_secs_f64() / total.as_secs_f64() * 100.0
+            }
+        };
+        for stage in Stage::ALL {
+            let spent = self.spent[stage as usize];
+            let throughput = match stage {
+                Stage::Read => throughput(self.bytes_read, spent),
+                Stage::Io => throughput(self.bytes_written, spent),
+                _ => String::new(),
+            };
+            reporter.say(format!(
+                "  {:<12} {:>9.3} s {:>6.1}%{throughput}",
+                stage.label(),
+                spent.as_secs_f64(),
+                share(spent)
+            ));
+        }
+        let other = total.saturating_sub(self.spent.iter().sum());
+        reporter.say(format!(
+            "  {:<12} {:>9.3} s {:>6.1}%",
+            "Other",
+            other.as_secs_f64(),
+            share(other)
+        ));
+        if !total.is_zero() {
+            reporter.say(format!(
+                "  Throughput: {:.2} font(s)/s",
+                self.fonts as f64 / total.as_secs_f64()
+            ));
+        }
+    }
+}
+
+/// "  12.3 MiB at 45.6 MiB/s" の形式で量と速度を表します (時間が 0 なら量だけ)。
+fn throughput(bytes: u64, spent: Duration) -> String {
+    let mib = bytes as f64 / (1024.0 * 1024.0);
+    if spent.is_zero() {
+        format!("  {mib:.1} MiB")
+    } else {
+        format!("  {mib:.1} MiB at {:.1} MiB/s", mib / spent.as_secs_f64())
+    }
+}
@@ -0,0 +1,73 @@
+//! 抽出したフォントデータの構造の検証
+
+use crate::sfnt::{self, Sfnt, Tag, cmap, name};
+
+/// どのフォントにも存在するはずのテーブル
+const REQUIRED_TABLES: &[&Tag] = &[
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post",
+];
+
+/// 検証結果
+#[derive(Debug, Default)]
+pub struct Report {
+    /// フォントとして正しく扱えない問題 (テーブルの欠落、パースできないテーブルなど)
+    pub errors: Vec<String>,
+    /// 多くの環境では問題にならないが仕様に反している点 (チェックサムの不一致など)
+    pub warnings: Vec<String>,
+    /// cmap に含まれるコードポイント数
+    pub codepoints: usize,
+}
+
+impl Report {
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty() && self.warnings.is_empty()
+    }
+}
+
+/// フォントデータ (TTC の場合は `face` 番目のフェイス) の構造を検証します。
+pub fn check(data: &[u8], face: u32) -> Report {
+    let mut report = Report::default();
+    let font = match Sfnt::parse_face(data, face) {
+        Ok(font) => font,
+        Err(e) => {
+            report.errors.push(e.to_string());
+            return report;
+        }
+    };
+
+    let missing: Vec<String> = REQUIRED_TABLES
+        .iter()
+        .filter(|tag| font.table(tag).is_none())
+        .map(|tag| sfnt::tag_to_string(tag))
+        .collect();
+    if !missing.is_empty() {
+        report
+            .errors
+            .push(format!("missing table(s) {}", missing.join(", ")));
+    }
+
+    if let Some(table) = font.table(b"cmap") {
+        match cmap::parse(table) {
+            Ok(map) if map.is_empty() => report.errors.push("cmap maps no codepoints".to_string()),
+            Ok(map) => report.codepoints = map.len(),
+            Err(e) => report.errors.push(format!("cmap: {e}")),
+        }
+    }
+    if let Some(table) = font.table(b"name")
+        && let Err(e) = name::parse(table)
+    {
+        report.errors.push(format!("name: {e}"));
+    }
+
+    let mismatches: Vec<String> = font
+        .checksum_mismatches()
+        .iter()
+        .map(sfnt::tag_to_string)
+        .collect();
+    if !mismatches.is_empty() {
+        report
+            .warnings
+            .push(format!("checksum mismatch in {}", mismatches.join(", ")));
+    }
+    report
+}
@@ -0,0 +1,155 @@
+//! 既知の正しいハッシュを記録したカタログ (JSON) との照合
+//!
+//! カタログの形式:
+//!
+//! ```json
+//! {
+//!   "fonts": [
+//!     { "family": "Meiryo", "version": "6.40", "sha256": "3f1c..." }
+//!   ]
+//! }
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::FontExtractorError;
+use crate::digest::sha256_hex;
+use crate::sfnt::{Sfnt, name};
+
+/// カタログ全体
+#[derive(Deserialize, Debug)]
+pub struct Catalog {
+    fonts: Vec<CatalogEntry>,
+}
+
+/// カタログの 1 エントリ (ファミリー名 + バージョン → ハッシュ)
+#[derive(Deserialize, Debug)]
+struct CatalogEntry {
+    family: String,
+    version: String,
+    sha256: String,
+}
+
+/// 照合結果
+#[derive(Debug)]
+pub enum Verification {
+    /// ファミリー名・バージョン・ハッシュがすべて一致した
+    Trusted,
+    /// カタログにファミリー名とバージョンの組が存在しない
+    Unknown { family: String, version: String },
+}
+
+impl Catalog {
+    pub fn load(path: &Path) -> Result<Self, FontExtractorError> {
+        let text = fs::read_to_string(path).map_err(|e| FontExtractorError::FileRead {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        serde_json::from_str(&text).map_err(|e| FontExtractorError::CatalogParse {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })
+    }
+
+    /// フォントデータの name テーブルからファミリー名とバージョンを読み取り、カタログのハッシュと照合します。
+    ///
+    /// 同じファミリー名・バージョンのエントリが存在するのにハッシュが一致しない場合はエラーを返します。
+    pub fn verify(&self, data: &[u8]) -> Result<Verification, FontExtractorError> {
+        let (family, version) = identify(data)?;
+        let actual = sha256_hex(data);
+
+        let candidates: Vec<&CatalogEntry> = self
+            .fonts
+            .iter()
+            .filter(|entry| entry.matches(&family, &version))
+            .collect();
+        if candidates.is_empty() {
+            return Ok(Verification::Unknown { family, version });
+        }
+        if candidates
+            .iter()
+            .any(|entry| entry.sha256.eq_ignore_ascii_case(&actual))
+        {
+            Ok(Verification::Trusted)
+        } else {
+            Err(FontExtractorError::UntrustedFontData {
+                family,
+                version,
+                expected: candidates
+                    .iter()
+                    .map(|entry| entry.sha256.to_ascii_lowercase())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                actual,
+            })
+        }
+    }
+
+    /// `seen` (ファミリー名, バージョン) のどれにも該当しないカタログのエントリを (ファミリー名, バージョン) で返します。
+    pub fn missing<'a>(&'a self, seen: &[(String, String)]) -> Vec<(&'a str, &'a str)> {
+        self.fonts
+            .iter()
+            .filter(|entry| {
+                !seen
+                    .iter()
+                    .any(|(family, version)| entry.matches(family, version))
+            })
+            .map(|entry| (entry.family.as_str(), entry.version.as_str()))
+            .collect()
+    }
+}
+
+impl CatalogEntry {
+    fn matches(&self, family: &str, version: &str) -> bool {
+        self.family.eq_ignore_ascii_case(family)
+            && version_number(&self.version) == version_number(version)
+    }
+}
+
+/// フォントデータの name テーブルから、カタログの照合に使う (ファミリー名, バージョン) を読み取ります。
+pub fn identify(data: &[u8]) -> Result<(String, String), FontExtractorError> {
+    let font = Sfnt::parse_face(data, 0)?;
+    let records = match font.table(b"name") {
+        Some(table) => name::parse(table)?,
+        None => Vec::new(),
+    };
+    // タイポグラフィックファミリー名 (ID 16) があればそちらを優先
+    let family = name::find(&records, 16)
+        .or_else(|| name::find(&records, 1))
+        .unwrap_or_default();
+    let version = name::find(&records, 5).unwrap_or_default();
+    Ok((family, version))
+}
+
+/// `version` ("Version 2.004; ..." など) が `minimum` 以上かどうか。
+///
+/// head の fontRevision と同じく小数として比べます (2.1 = 2.100 > 2.004)。3 つ目以降の区切りは整数として比べます。
+pub fn version_at_least(version: &str, minimum: &str) -> bool {
+    version_key(version) >= version_key(minimum)
+}
+
+/// 比較用のバージョン: (整数部, 末尾の 0 を除いた小数部の各桁, 3 つ目以降の区切り)
+fn version_key(version: &str) -> (u64, Vec<u8>, Vec<u64>) {
+    let mut parts = version_number(version).split('.');
+    let major = parts.next().and_then(|part| part.parse().ok()).unwrap_or(0);
+    let fraction = parts
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('0')
+        .bytes()
+        .map(|digit| digit - b'0')
+        .collect();
+    let rest = parts.map(|part| part.parse().unwrap_or(0)).collect();
+    (major, fraction, rest)
+}
+
+/// "Version 6.40; ..." や "6.40" から比較用のバージョン番号部分 ("6.40") を取り出します。
+fn version_number(version: &str) -> &str {
+    version
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .find(|token| token.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or(version.trim())
+}
@@ -0,0 +1,234 @@
+//! `verify-archive` サブコマンド: バックアップのアーカイブ (zip / tar) や出力先ディレクトリのフォントを読み直し、
+//! 壊れているファイルやカタログに対して欠けているフォントを報告します。
+
+use std::fs;
+#[cfg(feature = "archive")]
+use std::fs::File;
+#[cfg(feature = "archive")]
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use font_export::verify::{self, Catalog, Verification};
+use font_export::{FontExtractorError, validate};
+
+/// フォントファイルとして検証する拡張子
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "otc"];
+
+/// --- `verify-archive` サブコマンドの引数 ---
+#[derive(clap::Args, Debug)]
+pub struct VerifyArchiveArgs {
+    /// Archive (.zip / .tar) or output directory to check
+    path: PathBuf,
+
+    /// JSON catalog of known-good SHA-256 hashes (the --verify-against format); fonts it lists but the archive lacks are reported as missing
+    #[arg(long, value_name = "CATALOG")]
+    catalog: Option<PathBuf>,
+
+    /// Also list files that passed the check
+    #[arg(long, short)]
+    verbose: bool,
+}
+
+/// アーカイブ内のファイル名と、その内容 (読み取れなかった場合は理由)
+pub(crate) type Entry = (String, Result<Vec<u8>, String>);
+
+pub fn run(args: &VerifyArchiveArgs) -> Result<(), FontExtractorError> {
+    let catalog = args.catalog.as_deref().map(Catalog::load).transpose()?;
+    let entries = read_entries(&args.path)?;
+    println!(
+        "Checking {} font file(s) in '{}'...",
+        entries.len(),
+        args.path.display()
+    );
+
+    let mut problems = 0;
+    let mut seen = Vec::new();
+    for (file_name, data) in &entries {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        match data {
+            Ok(data) => {
+                let report = validate::check(data, 0);
+                errors.extend(report.errors);
+                warnings.extend(report.warnings);
+                if let Ok(identity) = verify::identify(data) {
+                    seen.push(identity);
+                }
+                if let Some(catalog) = &catalog {
+                    match catalog.verify(data) {
+                        Ok(Verification::Trusted) => {}
+                        Ok(Verification::Unknown { family, version }) => {
+                            warnings.push(format!("'{family}' ({version}) is not in the catalog"))
+                        }
+                        Err(e) => errors.push(e.to_string()),
+                    }
+                }
+            }
+            Err(reason) => errors.push(reason.clone()),
+        }
+
+        if !errors.is_empty() {
+            problems += 1;
+            println!("FAIL  {file_name}");
+        } else if !warnings.is_empty() {
+            println!("WARN  {file_name}");
+        } else if args.verbose {
+            println!("OK    {file_name}");
+        }
+        for problem in errors.iter().chain(&warnings) {
+            println!("        {problem}");
+        }
+    }
+
+    if let Some(catalog) = &catalog {
+        for (family, version) in catalog.missing(&seen) {
+            problems += 1;
+            println!("MISSING  {family} ({version})");
+        }
+    }
+    if entries.is_empty() {
+        problems += 1;
+        println!("No font files found.");
+    }
+
+    println!();
+    if problems > 0 {
+        return Err(FontExtractorError::ArchiveVerificationFailed {
+            path: args.path.display().to_string(),
+            problems,
+        });
+    }
+    println!("All {} font file(s) are intact.", entries.len());
+    Ok(())
+}
+
+/// `path` (ディレクトリ、zip または tar) に含まれるフォントファイルを読み取ります。
+pub(crate) fn read_entries(path: &Path) -> Result<Vec<Entry>, FontExtractorError> {
+    let read_error = |source: std::io::Error| FontExtractorError::FileRead {
+        path: path.display().to_string(),
+        source,
+    };
+    if path.is_dir() {
+        let mut entries = Vec::new();
+        read_directory(path, path, &mut entries).map_err(read_error)?;
+        return Ok(entries);
+    }
+
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        #[cfg(feature = "archive")]
+        Some("zip") => read_zip(File::open(path).map_err(read_error)?).map_err(read_error),
+        #[cfg(feature = "archive")]
+        Some("tar") => Ok(read_tar(File::open(path).map_err(read_error)?)),
+        #[cfg(not(feature = "archive"))]
+        Some("zip" | "tar") => Err(read_error(std::io::Error::other(
+            "this build of font-export does not include the 'archive' feature",
+        ))),
+        _ => Err(read_error(std::io::Error::other(
+            "expected a directory, a .zip or a .tar archive",
+        ))),
+    }
+}
+
+fn is_font_file(name: &str) -> bool {
+    Path::new(name).extension().is_some_and(|e| {
+        FONT_EXTENSIONS.contains(&e.to_string_lossy().to_ascii_lowercase().as_str())
+    })
+}
+
+/// ディレクトリを再帰的にたどります (出力先テンプレートで作られたサブディレクトリも対象にするため)。
+fn read_directory(root: &Path, dir: &Path, entries: &mut Vec<Entry>) -> std::io::Result<()> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            read_directory(root, &path, entries)?;
+            continue;
+        }
+        let name = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        if is_font_file(&name) {
+            entries.push((name, fs::read(&path).map_err(|e| e.to_string())));
+        }
+    }
+    Ok(())
+}
+
+/// zip の各エントリを読み取ります。読み取り時に CRC-32 も照合されます。
+#[cfg(feature = "archive")]
+fn read_zip(file: File) -> std::io::Result<Vec<Entry>> {
+    let mut archive = zip::ZipArchive::new(file).map_err(std::io::Error::other)?;
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = match archive.by_index(i) {
+            Ok(entry) => entry,
+            Err(e) => {
+                entries.push((format!("entry #{i}"), Err(e.to_string())));
+                continue;
+            }
+        };
+        let name = entry.name().to_string();
+        if entry.is_dir() || !is_font_file(&name) {
+            continue;
+        }
+        // ヘッダーのサイズは壊れたアーカイブでは信用できないので、読み取りに合わせて伸ばす
+        let mut data = Vec::new();
+        let data = entry
+            .read_to_end(&mut data)
+            .map(|_| data)
+            .map_err(|e| e.to_string());
+        entries.push((name, data));
+    }
+    Ok(entries)
+}
+
+/// tar の各エントリを読み取ります。途中でヘッダーが壊れている場合はそれ以降を読み取れないため、その旨をエントリとして返します。
+#[cfg(feature = "archive")]
+fn read_tar(file: File) -> Vec<Entry> {
+    let mut archive = tar::Archive::new(file);
+    let mut entries = Vec::new();
+    let iter = match archive.entries() {
+        Ok(iter) => iter,
+        Err(e) => return vec![("(archive)".to_string(), Err(e.to_string()))],
+    };
+    for entry in iter {
+        let mut entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                entries.push((
+                    "(archive)".to_string(),
+                    Err(format!("unreadable after the last listed entry: {e}")),
+                ));
+                break;
+            }
+        };
+        let name = entry
+            .path()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        if !entry.header().entry_type().is_file() || !is_font_file(&name) {
+            continue;
+        }
+        let mut data = Vec::new();
+        let data = entry
+            .read_to_end(&mut data)
+            .map_err(|e| e.to_string())
+            .and_then(|read| {
+                // 末尾が切り詰められたアーカイブは短いデータを返すことがある
+                if read as u64 == entry.header().size().unwrap_or(read as u64) {
+                    Ok(data)
+                } else {
+                    Err("entry is truncated".to_string())
+                }
+            });
+        entries.push((name, data));
+    }
+    entries
+}
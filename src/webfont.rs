@@ -0,0 +1,189 @@
+//! 書き出したフォントを Web ページで使うための `@font-face` CSS と preload の HTML 断片
+//!
+//! どちらも書き出したフォントファイルを相対 URL で参照するため、フォントと同じ場所に置くか、
+//! ページに合わせてパスを書き換えて使います。
+//! ファミリー単位の CSS は、ファミリーのすべてのフェイスの `@font-face` に加えて、
+//! 代替フォントを含めた `font-family` のカスタムプロパティと太さごとのユーティリティクラスを持ちます。
+
+use clap::ValueEnum;
+
+use crate::sfnt::style::StyleLinking;
+use crate::sfnt::{self, Sfnt};
+
+/// 等幅フォントの代替フォント
+const MONOSPACE_FALLBACK: &str = "ui-monospace, monospace";
+/// それ以外のフォントの代替フォント
+const DEFAULT_FALLBACK: &str = "system-ui, sans-serif";
+
+/// `font-display` の値 (Web フォントを読み込むまでの文字の表示方法)
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FontDisplay {
+    /// Leave it to the browser (usually the same as block)
+    Auto,
+    /// Hide the text for up to about 3 s while the font loads
+    Block,
+    /// Show a fallback font at once and swap when the font has loaded
+    Swap,
+    /// Hide the text for about 100 ms, then use a fallback if the font has not loaded within about 3 s
+    Fallback,
+    /// Hide the text for about 100 ms and use the font only if it has loaded by then (or is cached)
+    Optional,
+}
+
+impl FontDisplay {
+    fn keyword(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Block => "block",
+            Self::Swap => "swap",
+            Self::Fallback => "fallback",
+            Self::Optional => "optional",
+        }
+    }
+}
+
+/// `data` のフォントを `font_file` から読み込む `@font-face` 規則を返します。
+///
+/// `font-weight` と `font-style` は OS/2 の usWeightClass と fsSelection / head の macStyle から決めます。
+/// OS/2 か head が無ければ標準の太さの立体とします。
+pub fn font_face_css(data: &[u8], font_file: &str, family: &str, display: FontDisplay) -> String {
+    let (weight, italic) = face_style(data);
+    format!(
+        "@font-face {{\n  font-family: \"{}\";\n  src: url(\"{}\") format(\"{}\");\n  font-weight: {};\n  font-style: {};\n  font-display: {};\n}}\n",
+        css_string(family),
+        url_escape(font_file),
+        format(data).0,
+        weight,
+        if italic { "italic" } else { "normal" },
+        display.keyword()
+    )
+}
+
+/// フォントの太さ (usWeightClass) と斜体かどうか (OS/2 か head が無ければ標準の太さの立体)
+pub fn face_style(data: &[u8]) -> (u16, bool) {
+    StyleLinking::inspect(data).map_or((400, false), |linking| {
+        (linking.weight_class, linking.is_italic())
+    })
+}
+
+/// フォントに合う代替フォントの並び (post の isFixedPitch が立っていれば等幅フォント)
+pub fn default_fallback(data: &[u8]) -> &'static str {
+    let fixed_pitch = Sfnt::parse_face(data, 0)
+        .ok()
+        .and_then(|font| sfnt::read_u32(font.table(b"post")?, 12).ok())
+        .is_some_and(|is_fixed_pitch| is_fixed_pitch != 0);
+    if fixed_pitch {
+        MONOSPACE_FALLBACK
+    } else {
+        DEFAULT_FALLBACK
+    }
+}
+
+/// ファミリー CSS に含める 1 つのフェイス
+#[derive(Debug, Clone)]
+pub struct FamilyFace {
+    pub weight: u16,
+    pub italic: bool,
+    /// [`font_face_css`] で作った `@font-face` 規則
+    pub font_face: String,
+}
+
+/// ファミリーのすべてのフェイスの `@font-face` と、`--font-<ファミリー>` カスタムプロパティ、
+/// 太さ (と斜体) ごとのユーティリティクラスを持つ CSS を返します。
+///
+/// ```css
+/// :root {
+///   --font-noto-sans-jp: "Noto Sans JP", system-ui, sans-serif;
+/// }
+/// .font-noto-sans-jp-700 { font-family: var(--font-noto-sans-jp); font-weight: 700; }
+/// ```
+pub fn family_css(family: &str, faces: &[FamilyFace], fallback: &str) -> String {
+    let name = css_identifier(family);
+    let mut css = format!(
+        "/* {}: {} face(s) */\n\n",
+        family.replace("*/", "* /"),
+        faces.len()
+    );
+    for face in faces {
+        css.push_str(&face.font_face);
+        css.push('\n');
+    }
+    css.push_str(&format!(
+        ":root {{\n  --font-{name}: \"{}\", {fallback};\n}}\n\n.font-{name} {{ font-family: var(--font-{name}); }}\n",
+        css_string(family)
+    ));
+    let mut styles: Vec<(u16, bool)> = faces.iter().map(|f| (f.weight, f.italic)).collect();
+    styles.sort_unstable();
+    styles.dedup();
+    for (weight, italic) in styles {
+        if italic {
+            css.push_str(&format!(
+                ".font-{name}-{weight}-italic {{ font-family: var(--font-{name}); font-weight: {weight}; font-style: italic; }}\n"
+            ));
+        } else {
+            css.push_str(&format!(
+                ".font-{name}-{weight} {{ font-family: var(--font-{name}); font-weight: {weight}; }}\n"
+            ));
+        }
+    }
+    css
+}
+
+/// ファミリー名を CSS の識別子の一部にします ("Noto Sans JP" → "noto-sans-jp")。
+///
+/// 英数字 (ASCII 以外も含む) は小文字にして残し、それ以外の連続は `-` 1 つにします。
+pub fn css_identifier(family: &str) -> String {
+    let mut identifier = String::new();
+    for c in family.chars() {
+        if c.is_alphanumeric() {
+            identifier.extend(c.to_lowercase());
+        } else if !identifier.is_empty() && !identifier.ends_with('-') {
+            identifier.push('-');
+        }
+    }
+    let identifier = identifier.trim_end_matches('-');
+    match identifier.chars().next() {
+        // 数字で始まる識別子は使えないため接頭辞を付ける
+        Some(c) if c.is_ascii_digit() => format!("f{identifier}"),
+        Some(_) => identifier.to_string(),
+        None => "family".to_string(),
+    }
+}
+
+/// `font_file` を先読みさせる `<link rel="preload">` 要素を返します。
+///
+/// フォントの preload は CORS モードで取得されるため、同じオリジンでも `crossorigin` が必要です。
+pub fn preload_html(data: &[u8], font_file: &str) -> String {
+    format!(
+        "<link rel=\"preload\" href=\"{}\" as=\"font\" type=\"{}\" crossorigin>\n",
+        url_escape(font_file),
+        format(data).1
+    )
+}
+
+/// CSS の `format()` の値と MIME タイプ
+fn format(data: &[u8]) -> (&'static str, &'static str) {
+    match data.get(..4) {
+        Some(b"OTTO") => ("opentype", "font/otf"),
+        Some(b"ttcf") => ("collection", "font/collection"),
+        _ => ("truetype", "font/ttf"),
+    }
+}
+
+/// URL の非予約文字 (英数字と `-._~`) 以外を UTF-8 のパーセントエンコーディングにします。
+fn url_escape(text: &str) -> String {
+    text.bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+                (b as char).to_string()
+            } else {
+                format!("%{b:02X}")
+            }
+        })
+        .collect()
+}
+
+/// CSS の二重引用符の文字列の中で `"` と `\` をエスケープします。
+fn css_string(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,64 @@
+//! Windows のカタログファイル (%WINDIR%\System32\CatRoot) に記録されたハッシュとの照合
+//!
+//! Windows に同梱されているファイルのハッシュはシステムのカタログに署名付きで記録されています。
+//! 抽出したデータの SHA-256 をカタログから検索し、見つかればそのカタログファイルのパスを返します。
+
+use windows::Win32::Security::Cryptography::BCRYPT_SHA256_ALGORITHM;
+use windows::Win32::Security::Cryptography::Catalog::{
+    CATALOG_INFO, CryptCATAdminAcquireContext2, CryptCATAdminEnumCatalogFromHash,
+    CryptCATAdminReleaseCatalogContext, CryptCATAdminReleaseContext,
+    CryptCATCatalogInfoFromContext,
+};
+use windows::core::Error as WinError;
+
+use crate::FontExtractorError;
+use crate::digest::sha256;
+
+/// --- RAII ラッパー: カタログ管理コンテキスト ---
+struct CatalogAdmin(isize);
+impl CatalogAdmin {
+    fn acquire() -> Result<Self, FontExtractorError> {
+        let mut handle = 0;
+        unsafe {
+            CryptCATAdminAcquireContext2(&mut handle, None, BCRYPT_SHA256_ALGORITHM, None, 0)
+        }
+        .map_err(|e| FontExtractorError::WinApi {
+            api_name: "CryptCATAdminAcquireContext2".to_string(),
+            source: e,
+        })?;
+        Ok(Self(handle))
+    }
+}
+impl Drop for CatalogAdmin {
+    fn drop(&mut self) {
+        let _ = unsafe { CryptCATAdminReleaseContext(self.0, 0) };
+    }
+}
+
+/// `data` の SHA-256 を記録している Windows のカタログファイルを返します。どのカタログにも無ければ `None` です。
+pub fn find_catalog(data: &[u8]) -> Result<Option<String>, FontExtractorError> {
+    let admin = CatalogAdmin::acquire()?;
+    let hash = sha256(data);
+
+    let catalog = unsafe { CryptCATAdminEnumCatalogFromHash(admin.0, &hash, 0, None) };
+    if catalog == 0 {
+        return Ok(None);
+    }
+    let mut info = CATALOG_INFO {
+        cbStruct: size_of::<CATALOG_INFO>() as u32,
+        ..Default::default()
+    };
+    let result = unsafe { CryptCATCatalogInfoFromContext(catalog, &mut info, 0) };
+    let _ = unsafe { CryptCATAdminReleaseCatalogContext(admin.0, catalog, 0) };
+    result.map_err(|e: WinError| FontExtractorError::WinApi {
+        api_name: "CryptCATCatalogInfoFromContext".to_string(),
+        source: e,
+    })?;
+
+    let len = info
+        .wszCatalogFile
+        .iter()
+        .position(|&c| c == 0)
+        .unwrap_or(info.wszCatalogFile.len());
+    Ok(Some(String::from_utf16_lossy(&info.wszCatalogFile[..len])))
+}
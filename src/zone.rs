@@ -0,0 +1,121 @@
+//! NTFS の代替データストリーム `Zone.Identifier` (Mark of the Web)
+//!
+//! Windows はダウンロードしたファイルに `ファイル名:Zone.Identifier` というストリームを付け、
+//! 開くときの警告や Office の保護ビューの判断に使います。内容は INI 形式です。
+//!
+//! ```ini
+//! [ZoneTransfer]
+//! ZoneId=3
+//! HostUrl=https://example.com/fonts/
+//! ```
+//!
+//! 代替データストリームは Windows (NTFS) にしか無いため、Windows 以外では読み取らず、書き込みはエラーにします。
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::FontExtractorError;
+
+/// URL セキュリティゾーン (`ZoneId` の値)
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Zone {
+    /// The local computer (ZoneId=0)
+    Local,
+    /// The local intranet (ZoneId=1)
+    Intranet,
+    /// Trusted sites (ZoneId=2)
+    Trusted,
+    /// The Internet (ZoneId=3), as for a downloaded file
+    Internet,
+    /// Restricted sites (ZoneId=4)
+    Restricted,
+}
+
+impl Zone {
+    fn id(self) -> u8 {
+        match self {
+            Self::Local => 0,
+            Self::Intranet => 1,
+            Self::Trusted => 2,
+            Self::Internet => 3,
+            Self::Restricted => 4,
+        }
+    }
+}
+
+/// 書き込んだファイルの `Zone.Identifier` の扱い
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Marking {
+    /// この内容で書き込む
+    Write(String),
+    /// 削除する
+    Strip,
+}
+
+/// `zone` と、分かっていれば取得元の URL (`HostUrl`) を記録する `Zone.Identifier` の内容を作ります。
+pub fn contents(zone: Zone, host_url: Option<&str>) -> String {
+    let mut contents = format!("[ZoneTransfer]\r\nZoneId={}\r\n", zone.id());
+    if let Some(url) = host_url {
+        contents.push_str(&format!("HostUrl={url}\r\n"));
+    }
+    contents
+}
+
+/// `path` の `Zone.Identifier` を読み取ります。無ければ `None` です。
+///
+/// 代替データストリームの無いファイルシステムではストリーム名が不正な名前になるため、これも `None` とします。
+pub fn read(path: &Path) -> Result<Option<String>, FontExtractorError> {
+    if cfg!(not(windows)) {
+        return Ok(None);
+    }
+    let stream = stream_path(path);
+    match fs::read(&stream) {
+        Ok(data) => Ok(Some(String::from_utf8_lossy(&data).into_owned())),
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::InvalidFilename) => Ok(None),
+        Err(e) => Err(FontExtractorError::FileRead {
+            path: stream.display().to_string(),
+            source: e,
+        }),
+    }
+}
+
+/// `path` の `Zone.Identifier` を `marking` のとおりに書き込むか削除します。
+///
+/// NTFS 以外のファイルシステム (FAT32、exFAT など) には代替データストリームが無いため、書き込みは失敗します。
+pub fn apply(path: &Path, marking: &Marking) -> Result<(), FontExtractorError> {
+    let stream = stream_path(path);
+    if cfg!(not(windows)) {
+        return Err(FontExtractorError::FileWrite {
+            path: stream.display().to_string(),
+            source: std::io::Error::new(
+                ErrorKind::Unsupported,
+                "alternate data streams only exist on Windows (NTFS)",
+            ),
+        });
+    }
+    match marking {
+        Marking::Write(contents) => {
+            fs::write(&stream, contents).map_err(|e| FontExtractorError::FileWrite {
+                path: stream.display().to_string(),
+                source: e,
+            })
+        }
+        Marking::Strip => match fs::remove_file(&stream) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(FontExtractorError::FileWrite {
+                path: stream.display().to_string(),
+                source: e,
+            }),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// `C:\fonts\Arial.ttf` → `C:\fonts\Arial.ttf:Zone.Identifier`
+fn stream_path(path: &Path) -> PathBuf {
+    let mut stream = path.as_os_str().to_owned();
+    stream.push(":Zone.Identifier");
+    PathBuf::from(stream)
+}